@@ -0,0 +1,29 @@
+//! Where OpenVR expects to find the vrclient library for a given target.
+//! This is shared between the build script and its tests.
+
+/// Returns the vrclient library name and the platform directory it lives in, relative to the
+/// runtime root.
+///
+/// Object name and platform directory logic is generally based on a couple of pieces of openvr_api code:
+///  - platform directory names: https://github.com/ValveSoftware/openvr/blob/ae46a8dd0172580648c8922658a100439115d3eb/src/vrcore/pathtools_public.h#L127-L157
+///  - general logic and special cases: https://github.com/ValveSoftware/openvr/blob/ae46a8dd0172580648c8922658a100439115d3eb/src/openvr_api_public.cpp#L128-L144
+///
+/// The android platform has been omitted, since we are currently uninterested in supporting it.
+/// macOS is only mapped so that the build can be tested there, it is not a supported runtime.
+pub fn platform_for(os: &str, arch: &str) -> Result<(&'static str, &'static str), String> {
+    let vrclient_name = match (os, arch) {
+        ("windows", "x86_64") => "vrclient_x64",
+        _ => "vrclient",
+    };
+
+    let platform_location = match (os, arch) {
+        ("windows", "x86") | ("windows", "x86_64") => "bin/",
+        ("linux", "x86") => "bin/",
+        ("linux", "x86_64") => "bin/linux64/",
+        ("linux", "aarch64") => "bin/linuxarm64/",
+        ("macos", _) => "bin/osx64/",
+        _ => return Err(format!("Unsupported platform: {os} ({arch})")),
+    };
+
+    Ok((vrclient_name, platform_location))
+}
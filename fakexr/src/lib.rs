@@ -7,7 +7,7 @@ use slotmap::{DefaultKey, Key, KeyData, SlotMap};
 use std::collections::{HashMap, HashSet};
 use std::ffi::{c_char, CStr, CString};
 use std::sync::{
-    atomic::{AtomicBool, AtomicU64, Ordering},
+    atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
     mpsc, Arc, LazyLock, Mutex, MutexGuard, OnceLock, RwLock, Weak,
 };
 
@@ -45,6 +45,22 @@ pub fn set_action_state(action: xr::Action, state: ActionState, hand: UserPath)
     action.active.store(true, Ordering::Relaxed);
 }
 
+/// The last vibration applied to the given haptic action for the given hand.
+pub fn last_haptic_feedback(action: xr::Action, hand: UserPath) -> Option<HapticFeedback> {
+    let action = action.to_handle().unwrap();
+    match hand {
+        UserPath::LeftHand => action.haptics.left.load(),
+        UserPath::RightHand => action.haptics.right.load(),
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct HapticFeedback {
+    pub duration: xr::Duration,
+    pub frequency: f32,
+    pub amplitude: f32,
+}
+
 pub fn deactivate_action(action: xr::Action) {
     let action = action.to_handle().unwrap();
     action.active.store(false, Ordering::Relaxed);
@@ -92,6 +108,22 @@ pub fn set_grip(session: xr::Session, path: UserPath, pose: xr::Posef) {
     get_hand_data(path, &session).grip_pose.store(pose);
 }
 
+pub fn set_display_period(session: xr::Session, period: xr::Duration) {
+    let session = session.to_handle().unwrap();
+    session.display_period.store(period);
+}
+
+/// Makes subsequent swapchain creation on this session fail with the given error.
+pub fn set_swapchain_error(session: xr::Session, error: Option<xr::Result>) {
+    let session = session.to_handle().unwrap();
+    session.swapchain_error.store(error);
+}
+
+pub fn set_view_pose(session: xr::Session, pose: xr::Posef) {
+    let session = session.to_handle().unwrap();
+    session.view_pose.store(pose);
+}
+
 pub fn set_aim(session: xr::Session, path: UserPath, pose: xr::Posef) {
     let session = session.to_handle().unwrap();
     get_hand_data(path, &session).aim_pose.store(pose);
@@ -117,11 +149,22 @@ pub fn get_suggested_bindings(action: xr::Action, profile: xr::Path) -> Vec<Stri
         .collect()
 }
 
+pub fn reference_spaces_created(session: xr::Session) -> u32 {
+    let session = session.to_handle().unwrap();
+    session.reference_spaces_created.load(Ordering::Relaxed)
+}
+
 pub fn session_frame_state(session: xr::Session) -> FrameState {
     let session = session.to_handle().unwrap();
     session.frame_state.load()
 }
 
+/// The types of the layers submitted with the last ended frame.
+pub fn last_frame_layers(session: xr::Session) -> Vec<xr::StructureType> {
+    let session = session.to_handle().unwrap();
+    session.frame_layers.lock().unwrap().clone()
+}
+
 macro_rules! fn_unimplemented_impl {
     ($($param:ident),+) => {
         fn_unimplemented_impl!($($param),+  -> []);
@@ -233,7 +276,7 @@ pub unsafe extern "system" fn get_instance_proc_addr(
                 RequestExitSession,
                 (ResultToString),
                 (StructureTypeToString),
-                (GetInstanceProperties),
+                GetInstanceProperties,
                 (GetSystemProperties),
                 CreateSwapchain,
                 DestroySwapchain,
@@ -252,7 +295,7 @@ pub unsafe extern "system" fn get_instance_proc_addr(
                 BeginFrame,
                 EndFrame,
                 WaitFrame,
-                (ApplyHapticFeedback),
+                ApplyHapticFeedback,
                 (StopHapticFeedback),
                 (PollEvent),
                 StringToPath,
@@ -271,7 +314,10 @@ pub unsafe extern "system" fn get_instance_proc_addr(
                 GetCurrentInteractionProfile,
                 SyncActions,
                 (EnumerateBoundSourcesForAction),
-                (GetInputSourceLocalizedName)
+                (GetInputSourceLocalizedName),
+                EnumerateDisplayRefreshRatesFB,
+                GetDisplayRefreshRateFB,
+                RequestDisplayRefreshRateFB
                 ]
 
                 other => {
@@ -292,20 +338,25 @@ extern "system" fn enumerate_instance_extension_properties(
     properties: *mut xr::ExtensionProperties,
 ) -> xr::Result {
     assert!(layer_name.is_null());
-    unsafe { *property_count_output = 1 };
+    const EXTENSIONS: &[&[u8]] = &[
+        xr::KHR_VULKAN_ENABLE_EXTENSION_NAME,
+        xr::FB_DISPLAY_REFRESH_RATE_EXTENSION_NAME,
+    ];
+    unsafe { *property_count_output = EXTENSIONS.len() as u32 };
     if property_capacity_input > 0 {
         let props =
             unsafe { std::slice::from_raw_parts_mut(properties, property_capacity_input as usize) };
-        props[0] = xr::ExtensionProperties {
-            ty: xr::ExtensionProperties::TYPE,
-            next: std::ptr::null_mut(),
-            extension_name: [0 as c_char; xr::MAX_EXTENSION_NAME_SIZE],
-            extension_version: 1,
-        };
-        let name = xr::KHR_VULKAN_ENABLE_EXTENSION_NAME;
-        let name =
-            unsafe { std::slice::from_raw_parts(name.as_ptr() as *const c_char, name.len()) };
-        props[0].extension_name[..name.len()].copy_from_slice(name);
+        for (prop, name) in props.iter_mut().zip(EXTENSIONS) {
+            *prop = xr::ExtensionProperties {
+                ty: xr::ExtensionProperties::TYPE,
+                next: std::ptr::null_mut(),
+                extension_name: [0 as c_char; xr::MAX_EXTENSION_NAME_SIZE],
+                extension_version: 1,
+            };
+            let name =
+                unsafe { std::slice::from_raw_parts(name.as_ptr() as *const c_char, name.len()) };
+            prop.extension_name[..name.len()].copy_from_slice(name);
+        }
     }
     xr::Result::SUCCESS
 }
@@ -422,10 +473,18 @@ struct Session {
     left_hand: HandData,
     right_hand: HandData,
     spaces: Mutex<HashSet<DefaultKey>>,
+    reference_spaces_created: AtomicU32,
     state: AtomicCell<xr::SessionState>,
     state_synced: AtomicBool,
     should_render: AtomicBool,
+    display_period: AtomicCell<xr::Duration>,
+    /// The HMD pose, relative to the local/stage origin
+    view_pose: AtomicCell<xr::Posef>,
+    swapchain_error: AtomicCell<Option<xr::Result>>,
     frame_state: AtomicCell<FrameState>,
+    refresh_rate: AtomicCell<f32>,
+    /// Types of the layers passed to the last xrEndFrame
+    frame_layers: Mutex<Vec<xr::StructureType>>,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -507,6 +566,9 @@ static LOCATION_FLAGS_TRACKED: LazyLock<xr::SpaceLocationFlags> = LazyLock::new(
 
 struct Space {
     hand: Option<UserPath>,
+    /// Set for view spaces and reference spaces with an offset. Plain local and stage spaces are
+    /// the static LOCAL and STAGE handles instead.
+    reference: Option<xr::ReferenceSpaceType>,
     offset: xr::Posef,
     session: Weak<Session>,
     action: Weak<Action>,
@@ -525,6 +587,19 @@ impl Space {
             .upgrade()
             .ok_or(xr::Result::ERROR_SESSION_LOST)?;
 
+        if let Some(ty) = self.reference {
+            let base = if ty == xr::ReferenceSpaceType::VIEW {
+                session.view_pose.load()
+            } else {
+                xr::Posef::IDENTITY
+            };
+            return Ok(xr::SpaceLocation {
+                location_flags: *LOCATION_FLAGS_TRACKED,
+                pose: mat_to_pose(pose_to_mat(base) * pose_to_mat(self.offset)),
+                ..default()
+            });
+        }
+
         // Check if this hand has an interaction profile
         let hand = self.hand.unwrap_or(UserPath::LeftHand);
         let hand_data = match hand {
@@ -609,6 +684,7 @@ struct Action {
     localized_name: CString,
     state: LeftRight<AtomicCell<ActionStateData>>,
     pending_state: AtomicCell<LeftRight<Option<ActionState>>>,
+    haptics: LeftRight<AtomicCell<Option<HapticFeedback>>>,
     suggested: Mutex<HashMap<xr::Path, Vec<xr::Path>>>,
 }
 
@@ -707,10 +783,16 @@ extern "system" fn create_session(
         left_hand: Default::default(),
         right_hand: Default::default(),
         spaces: Default::default(),
+        reference_spaces_created: 0.into(),
         state: xr::SessionState::READY.into(),
         state_synced: true.into(),
         should_render: false.into(),
+        display_period: xr::Duration::from_nanos(1).into(),
+        view_pose: xr::Posef::IDENTITY.into(),
+        swapchain_error: None.into(),
         frame_state: FrameState::Ended.into(),
+        refresh_rate: 90.0.into(),
+        frame_layers: Default::default(),
     });
 
     let tx = sess.event_sender.clone();
@@ -865,6 +947,7 @@ extern "system" fn create_action(
             right: data.into(),
         },
         pending_state: Default::default(),
+        haptics: Default::default(),
         suggested: Mutex::default(),
     });
 
@@ -899,6 +982,7 @@ extern "system" fn create_action_space(
     };
     let s = Arc::new(Space {
         hand,
+        reference: None,
         offset: info.pose_in_action_space,
         session: Arc::downgrade(&session),
         action: Arc::downgrade(&action),
@@ -910,6 +994,23 @@ extern "system" fn create_action_space(
     xr::Result::SUCCESS
 }
 
+pub const RUNTIME_NAME: &str = "fakexr";
+pub const RUNTIME_VERSION: xr::Version = xr::Version::new(1, 2, 3);
+
+extern "system" fn get_instance_properties(
+    instance: xr::Instance,
+    properties: *mut xr::InstanceProperties,
+) -> xr::Result {
+    get_handle!(instance);
+    let properties = unsafe { properties.as_mut() }.unwrap();
+    properties.runtime_version = RUNTIME_VERSION;
+    properties.runtime_name = [0; xr::MAX_RUNTIME_NAME_SIZE];
+    for (dst, src) in properties.runtime_name.iter_mut().zip(RUNTIME_NAME.bytes()) {
+        *dst = src as c_char;
+    }
+    xr::Result::SUCCESS
+}
+
 extern "system" fn get_system(
     _: xr::Instance,
     _: *const xr::SystemGetInfo,
@@ -943,28 +1044,69 @@ extern "system" fn destroy_space(space: xr::Space) -> xr::Result {
     destroy_handle(space)
 }
 
-static VIEW: LazyLock<xr::Space> = LazyLock::new(|| xr::Space::from_raw(1));
 static LOCAL: LazyLock<xr::Space> = LazyLock::new(|| xr::Space::from_raw(2));
 static STAGE: LazyLock<xr::Space> = LazyLock::new(|| xr::Space::from_raw(3));
 
 extern "system" fn create_reference_space(
-    _: xr::Session,
+    session: xr::Session,
     create_info: *const xr::ReferenceSpaceCreateInfo,
     space: *mut xr::Space,
 ) -> xr::Result {
+    let session = get_handle!(session);
+    session
+        .reference_spaces_created
+        .fetch_add(1, Ordering::Relaxed);
     let info = unsafe { create_info.as_ref().unwrap() };
-    assert_eq!(info.pose_in_reference_space, xr::Posef::IDENTITY);
-    unsafe {
-        *space = match info.reference_space_type {
-            xr::ReferenceSpaceType::VIEW => *VIEW,
-            xr::ReferenceSpaceType::LOCAL => *LOCAL,
-            xr::ReferenceSpaceType::STAGE => *STAGE,
-            other => panic!("unimplemented reference space type: {other:?}"),
+    let ty = info.reference_space_type;
+    assert!(
+        matches!(
+            ty,
+            xr::ReferenceSpaceType::VIEW
+                | xr::ReferenceSpaceType::LOCAL
+                | xr::ReferenceSpaceType::STAGE
+        ),
+        "unimplemented reference space type: {ty:?}"
+    );
+    if ty != xr::ReferenceSpaceType::VIEW && info.pose_in_reference_space == xr::Posef::IDENTITY {
+        // Local and stage are the same in fakexr.
+        let origin = if ty == xr::ReferenceSpaceType::LOCAL {
+            *LOCAL
+        } else {
+            *STAGE
         };
+        unsafe { space.write(origin) };
+        return xr::Result::SUCCESS;
     }
+
+    let s = Arc::new(Space {
+        hand: None,
+        reference: Some(ty),
+        offset: info.pose_in_reference_space,
+        session: Arc::downgrade(&session),
+        action: Weak::new(),
+    });
+    let key = Space::instances().insert(s);
+    session.spaces.lock().unwrap().insert(key);
+    unsafe { space.write(xr::Space::from_raw(key.data().as_ffi())) };
     xr::Result::SUCCESS
 }
 
+/// Locates a space relative to the origin shared by the local and stage spaces.
+fn locate_in_origin(space: xr::Space) -> Result<xr::SpaceLocation, xr::Result> {
+    if space == *LOCAL || space == *STAGE {
+        return Ok(xr::SpaceLocation {
+            ty: xr::SpaceLocation::TYPE,
+            next: std::ptr::null_mut(),
+            location_flags: *LOCATION_FLAGS_TRACKED,
+            pose: xr::Posef::IDENTITY,
+        });
+    }
+    space
+        .to_handle()
+        .ok_or(xr::Result::ERROR_HANDLE_INVALID)?
+        .get_pose_relative_to_local()
+}
+
 extern "system" fn poll_event(
     instance: xr::Instance,
     buffer: *mut xr::EventDataBuffer,
@@ -1181,9 +1323,16 @@ extern "system" fn sync_actions(
 fn get_action_if_attached(
     session: &Session,
     info: *const xr::ActionStateGetInfo,
+) -> Option<(Arc<ActionSet>, Arc<Action>)> {
+    find_attached_action(session, unsafe { (*info).action })
+}
+
+fn find_attached_action(
+    session: &Session,
+    action: xr::Action,
 ) -> Option<(Arc<ActionSet>, Arc<Action>)> {
     let sets = session.attached_sets.get()?;
-    let action = xr::Action::to_handle(unsafe { (*info).action })?;
+    let action = xr::Action::to_handle(action)?;
     sets.into_iter().find_map(|set| {
         let set = xr::ActionSet::to_handle(*set)?;
         for a in set.actions.get().unwrap() {
@@ -1195,6 +1344,43 @@ fn get_action_if_attached(
     })
 }
 
+extern "system" fn apply_haptic_feedback(
+    session: xr::Session,
+    info: *const xr::HapticActionInfo,
+    feedback: *const xr::HapticBaseHeader,
+) -> xr::Result {
+    let session = get_handle!(session);
+    let info = unsafe { info.as_ref().unwrap() };
+    let Some((_, action)) = find_attached_action(&session, info.action) else {
+        return xr::Result::ERROR_ACTIONSET_NOT_ATTACHED;
+    };
+    if !matches!(action.state.left.load().state, ActionState::Haptic) {
+        return xr::Result::ERROR_ACTION_TYPE_MISMATCH;
+    }
+
+    assert_eq!(
+        unsafe { feedback.as_ref().unwrap() }.ty,
+        xr::HapticVibration::TYPE
+    );
+    let vibration = unsafe { &*feedback.cast::<xr::HapticVibration>() };
+    let feedback = Some(HapticFeedback {
+        duration: vibration.duration,
+        frequency: vibration.frequency,
+        amplitude: vibration.amplitude,
+    });
+
+    let instance = session.instance.upgrade().unwrap();
+    match instance.get_user_path(info.subaction_path).unwrap() {
+        Some(UserPath::LeftHand) => action.haptics.left.store(feedback),
+        Some(UserPath::RightHand) => action.haptics.right.store(feedback),
+        None => {
+            action.haptics.left.store(feedback);
+            action.haptics.right.store(feedback);
+        }
+    }
+    xr::Result::SUCCESS
+}
+
 extern "system" fn get_action_state_boolean(
     session: xr::Session,
     info: *const xr::ActionStateGetInfo,
@@ -1340,13 +1526,6 @@ extern "system" fn locate_space(
     _time: xr::Time,
     location: *mut xr::SpaceLocation,
 ) -> xr::Result {
-    assert!(
-        base_space != *STAGE && base_space != *VIEW,
-        "stage/view locate unimplemented"
-    );
-    assert_ne!(space, *LOCAL);
-
-    let space = get_handle!(space);
     let next = unsafe { (*location).next };
     let mut out_loc = xr::SpaceLocation {
         ty: xr::SpaceLocation::TYPE,
@@ -1371,21 +1550,20 @@ extern "system" fn locate_space(
             }
         }
     }
-    if base_space == *LOCAL {
-        match space.get_pose_relative_to_local() {
+    if base_space == *LOCAL || base_space == *STAGE {
+        match locate_in_origin(space) {
             Ok(loc) => {
                 out_loc = loc;
             }
             Err(e) => return e,
         };
     } else {
-        let base_space = get_handle!(base_space);
-        let base_loc = match base_space.get_pose_relative_to_local() {
+        let base_loc = match locate_in_origin(base_space) {
             Ok(loc) => loc,
             Err(e) => return e,
         };
 
-        let target_loc = match space.get_pose_relative_to_local() {
+        let target_loc = match locate_in_origin(space) {
             Ok(loc) => loc,
             Err(e) => return e,
         };
@@ -1407,10 +1585,14 @@ extern "system" fn locate_space(
     xr::Result::SUCCESS
 }
 extern "system" fn create_swapchain(
-    _session: xr::Session,
+    session: xr::Session,
     info: *const xr::SwapchainCreateInfo,
     swapchain: *mut xr::Swapchain,
 ) -> xr::Result {
+    let session = get_handle!(session);
+    if let Some(err) = session.swapchain_error.load() {
+        return err;
+    }
     let info = unsafe { info.as_ref() }.unwrap();
     if info.width == 0 || info.height == 0 {
         return xr::Result::ERROR_VALIDATION_FAILURE;
@@ -1448,14 +1630,77 @@ extern "system" fn enumerate_swapchain_formats(
     xr::Result::SUCCESS
 }
 
+pub const DISPLAY_REFRESH_RATES: [f32; 3] = [72.0, 90.0, 120.0];
+
+extern "system" fn enumerate_display_refresh_rates_f_b(
+    session: xr::Session,
+    capacity: u32,
+    output: *mut u32,
+    rates: *mut f32,
+) -> xr::Result {
+    get_handle!(session);
+    unsafe {
+        output.write(DISPLAY_REFRESH_RATES.len() as u32);
+    }
+    if capacity == 0 {
+        return xr::Result::SUCCESS;
+    }
+    if (capacity as usize) < DISPLAY_REFRESH_RATES.len() {
+        return xr::Result::ERROR_SIZE_INSUFFICIENT;
+    }
+    let rates = unsafe { std::slice::from_raw_parts_mut(rates, capacity as usize) };
+    rates[..DISPLAY_REFRESH_RATES.len()].copy_from_slice(&DISPLAY_REFRESH_RATES);
+    xr::Result::SUCCESS
+}
+
+extern "system" fn get_display_refresh_rate_f_b(
+    session: xr::Session,
+    rate: *mut f32,
+) -> xr::Result {
+    let session = get_handle!(session);
+    unsafe {
+        rate.write(session.refresh_rate.load());
+    }
+    xr::Result::SUCCESS
+}
+
+extern "system" fn request_display_refresh_rate_f_b(session: xr::Session, rate: f32) -> xr::Result {
+    let session = get_handle!(session);
+    // Per the spec, 0 lets the runtime pick; we just keep whatever we have.
+    if rate == 0.0 {
+        return xr::Result::SUCCESS;
+    }
+    if !DISPLAY_REFRESH_RATES.contains(&rate) {
+        return xr::Result::ERROR_DISPLAY_REFRESH_RATE_UNSUPPORTED_FB;
+    }
+    session.refresh_rate.store(rate);
+    xr::Result::SUCCESS
+}
+
 extern "system" fn enumerate_swapchain_images(
-    _swapchain: xr::Swapchain,
-    _: u32,
+    swapchain: xr::Swapchain,
+    capacity: u32,
     output: *mut u32,
-    _: *mut xr::SwapchainImageBaseHeader,
+    images: *mut xr::SwapchainImageBaseHeader,
 ) -> xr::Result {
+    // A single image, with the swapchain's handle standing in for the native image.
     if let Some(output) = unsafe { output.as_mut() } {
-        *output = 0;
+        *output = 1;
+    }
+    if capacity == 0 {
+        return xr::Result::SUCCESS;
+    }
+    let Some(header) = (unsafe { images.as_ref() }) else {
+        return xr::Result::ERROR_VALIDATION_FAILURE;
+    };
+    match header.ty {
+        xr::StructureType::SWAPCHAIN_IMAGE_VULKAN_KHR => unsafe {
+            (*images.cast::<xr::SwapchainImageVulkanKHR>()).image = swapchain.into_raw() as _;
+        },
+        xr::StructureType::SWAPCHAIN_IMAGE_OPENGL_KHR => unsafe {
+            (*images.cast::<xr::SwapchainImageOpenGLKHR>()).image = swapchain.into_raw() as _;
+        },
+        _ => return xr::Result::ERROR_VALIDATION_FAILURE,
     }
     xr::Result::SUCCESS
 }
@@ -1507,7 +1752,7 @@ extern "system" fn wait_frame(
             ty: xr::FrameState::TYPE,
             next: std::ptr::null_mut(),
             predicted_display_time: xr::Time::from_nanos(1),
-            predicted_display_period: xr::Duration::from_nanos(1),
+            predicted_display_period: session.display_period.load(),
             should_render: session.should_render.load(Ordering::Relaxed).into(),
         })
     }
@@ -1525,11 +1770,19 @@ extern "system" fn begin_frame(
     xr::Result::SUCCESS
 }
 
-extern "system" fn end_frame(session: xr::Session, _info: *const xr::FrameEndInfo) -> xr::Result {
+extern "system" fn end_frame(session: xr::Session, info: *const xr::FrameEndInfo) -> xr::Result {
     let session = get_handle!(session);
     if let Err(e) = transition_frame_state(&session.frame_state, FrameState::Ended) {
         return e;
     }
+    let info = unsafe { info.as_ref() }.unwrap();
+    let layers = if info.layer_count == 0 {
+        &[]
+    } else {
+        unsafe { std::slice::from_raw_parts(info.layers, info.layer_count as usize) }
+    };
+    *session.frame_layers.lock().unwrap() =
+        layers.iter().map(|&layer| unsafe { (*layer).ty }).collect();
     if session.state.load() == xr::SessionState::READY {
         session.synchronized();
     }
@@ -171,6 +171,10 @@ pub fn main() -> Result<(), Box<dyn std::error::Error>> {
         .derive_default(true)
         .no_default("vr.*::IVR.*")
         .bitfield_enum("vr.*::EVRSubmitFlags")
+        // Apps can pass any value for these through the C API, so they're checked as integers.
+        .newtype_enum("vr.*::EOverlayDirection")
+        .newtype_enum("vr.*::EVROverlayIntersectionMaskPrimitiveType")
+        .newtype_enum("vr.*::VROverlayInputMethod")
         .rustified_enum(".*")
         .vtable_generation(true)
         .generate_cstr(true)
@@ -72,4 +72,9 @@ impl VRTextureBounds_t {
     pub fn vertically_flipped(&self) -> bool {
         self.vMin > self.vMax
     }
+
+    #[inline]
+    pub fn horizontally_flipped(&self) -> bool {
+        self.uMin > self.uMax
+    }
 }
@@ -2,7 +2,23 @@
 layout(set = 0, binding = 0) uniform sampler2D overlay;
 layout(location = 0) in vec2 texCoord;
 layout(location = 0) out vec4 color;
+layout(push_constant, std430) uniform pc {
+	layout(offset = 16) uint srgbConversion; // 0: none, 1: decode, 2: encode
+};
+
+vec3 srgbToLinear(vec3 c) {
+	return mix(c / 12.92, pow((c + 0.055) / 1.055, vec3(2.4)), greaterThan(c, vec3(0.04045)));
+}
+
+vec3 linearToSrgb(vec3 c) {
+	return mix(c * 12.92, 1.055 * pow(c, vec3(1.0 / 2.4)) - 0.055, greaterThan(c, vec3(0.0031308)));
+}
 
 void main() {
 	color = texture(overlay, texCoord);
+	if (srgbConversion == 1) {
+		color.rgb = srgbToLinear(color.rgb);
+	} else if (srgbConversion == 2) {
+		color.rgb = linearToSrgb(color.rgb);
+	}
 }
@@ -224,8 +224,12 @@ impl IVRClientCore003_Interface for ClientCore {
             .or_else(|| self.try_interface(interface, |_| Chaperone::new(openxr.clone())))
             .or_else(|| self.try_interface(interface, |_| Applications::default()))
             .or_else(|| self.try_interface(interface, |_| OverlayView::default()))
-            .or_else(|| self.try_interface(interface, |_| Screenshots::default()))
-            .or_else(|| self.try_interface(interface, |_| Settings::default()))
+            .or_else(|| self.try_interface(interface, Screenshots::new))
+            .or_else(|| {
+                self.try_interface(interface, |injector| {
+                    Settings::new(openxr.clone(), injector)
+                })
+            })
             .or_else(|| self.try_interface(interface, |_| UnknownInterfaces::default()))
             .unwrap_or_else(|| {
                 warn!("app requested unknown interface {interface:?}");
@@ -2,26 +2,34 @@ use crate::{
     clientcore::{Injected, Injector},
     graphics_backends::{supported_backends_enum, GraphicsBackend, SupportedBackend},
     input::Input,
-    openxr_data::{self, FrameStream, OpenXrData, SessionCreateInfo, SessionData},
-    overlay::OverlayMan,
+    openxr_data::{self, FrameStream, Hand, OpenXrData, SessionCreateInfo, SessionData},
+    overlay::{OverlayMan, SubmittedTexture},
     system::System,
-    tracy_span, AtomicF64,
+    tracy_span,
 };
 
-use log::{debug, info, trace};
+use log::{debug, info, trace, warn};
 use openvr as vr;
 use openxr as xr;
 use std::mem::offset_of;
 use std::sync::{
-    atomic::{AtomicU32, Ordering},
+    atomic::{AtomicBool, AtomicU32, Ordering},
     Arc, Mutex, Once,
 };
-use std::time::Instant;
-use std::{ffi::c_char, ops::Deref};
+use std::time::{Duration, Instant};
+use std::{collections::VecDeque, ffi::c_char, ops::Deref};
 
 #[derive(Default)]
 pub struct CompositorSessionData(Mutex<Option<DynFrameController>>);
 
+impl CompositorSessionData {
+    /// Keeps the frame controller off the app's graphics queue until the returned guard is
+    /// dropped. Frames are submitted while reading overlays, so this has to be locked first.
+    pub fn lock_queue(&self) -> impl Sized + '_ {
+        self.0.lock().unwrap()
+    }
+}
+
 #[derive(macros::InterfaceImpl)]
 #[interface = "IVRCompositor"]
 #[versions(028, 027, 026, 022, 021, 020, 019, 018, 016, 009)]
@@ -32,11 +40,67 @@ pub struct Compositor {
     system: Injected<System>,
     /// Stores the backend data in between session restarts.
     tmp_backend: Mutex<Option<AnyTempBackendData>>,
-    overlays: Injected<OverlayMan>,
+    /// should only be externally accessed for testing
+    pub(crate) overlays: Injected<OverlayMan>,
     metrics: FrameMetrics,
     timing_mode: Mutex<vr::EVRCompositorTimingMode>,
     frame_state: Mutex<FrameState>,
     focused: Once,
+    /// Scene and background fades, indexed by FadeToColor's bBackground
+    fades: Mutex<[Fade; 2]>,
+    /// FadeGrid's transition, only the alpha is used. The skybox stands in for the grid.
+    grid_fade: Mutex<Fade>,
+    /// Set by ForceInterleavedReprojectionOn. OpenXR has no control for it, so it's only reported
+    /// back through frame timing.
+    force_interleaved_reprojection: AtomicBool,
+}
+
+/// A FadeToColor transition, interpolated linearly between two colors.
+#[derive(Copy, Clone)]
+struct Fade {
+    from: vr::HmdColor_t,
+    to: vr::HmdColor_t,
+    start: Instant,
+    duration: Duration,
+}
+
+impl Default for Fade {
+    fn default() -> Self {
+        Self {
+            from: Default::default(),
+            to: Default::default(),
+            start: Instant::now(),
+            duration: Duration::ZERO,
+        }
+    }
+}
+
+impl Fade {
+    fn color_at(&self, now: Instant) -> vr::HmdColor_t {
+        let t = if self.duration.is_zero() {
+            1.0
+        } else {
+            (now.saturating_duration_since(self.start).as_secs_f32() / self.duration.as_secs_f32())
+                .min(1.0)
+        };
+        let lerp = |from: f32, to: f32| from + (to - from) * t;
+        vr::HmdColor_t {
+            r: lerp(self.from.r, self.to.r),
+            g: lerp(self.from.g, self.to.g),
+            b: lerp(self.from.b, self.to.b),
+            a: lerp(self.from.a, self.to.a),
+        }
+    }
+
+    /// Starts fading from the current color to `to`.
+    fn restart(&mut self, to: vr::HmdColor_t, duration: Duration, now: Instant) {
+        *self = Self {
+            from: self.color_at(now),
+            to,
+            start: now,
+            duration,
+        };
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -68,10 +132,118 @@ impl FrameState {
     }
 }
 
+/// Number of frames GetFrameTiming can look back on.
+const FRAME_TIMING_HISTORY: usize = 128;
+
 struct FrameMetrics {
     system_start: Instant,
     index: AtomicU32,
-    time: AtomicF64,
+    /// Most recent frame last. Frames are recorded as WaitGetPoses starts them.
+    history: Mutex<VecDeque<FrameTiming>>,
+    /// Counted since the session was created.
+    stats: Mutex<CumulativeStats>,
+}
+
+/// Frame counts for GetCumulativeStats.
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+struct CumulativeStats {
+    /// Includes reprojected frames.
+    presents: u32,
+    /// Frames the app waited for but never submitted.
+    dropped: u32,
+    /// Vsyncs the app missed, during which the runtime reprojected the previous frame.
+    reprojected: u32,
+}
+
+impl FrameMetrics {
+    fn start_frame(&self, frame_state: Option<xr::FrameState>, throttled: bool) {
+        let now = Instant::now();
+        let mut history = self.history.lock().unwrap();
+        let interval = history.back().map(|prev| now.duration_since(prev.start));
+        if history.len() == FRAME_TIMING_HISTORY {
+            history.pop_front();
+        }
+        let frame = FrameTiming {
+            index: self.index.load(Ordering::Relaxed),
+            start: now,
+            interval,
+            display_period: frame_state
+                .map(|s| Duration::from_nanos(s.predicted_display_period.as_nanos().max(0) as u64)),
+            submitted: None,
+            presented: None,
+            throttled,
+        };
+        let missed_vsyncs = frame.missed_vsyncs();
+        let mut stats = self.stats.lock().unwrap();
+        stats.presents += missed_vsyncs;
+        stats.reprojected += missed_vsyncs;
+        history.push_back(frame);
+    }
+
+    /// How long it's been since the previous frame, for anything that shouldn't depend on the
+    /// frame rate. The first frame goes by the display period instead.
+    fn frame_time(&self) -> Duration {
+        self.history
+            .lock()
+            .unwrap()
+            .back()
+            .and_then(|frame| frame.interval.or(frame.display_period))
+            .unwrap_or_default()
+    }
+
+    fn with_current(&self, f: impl FnOnce(&mut FrameTiming)) {
+        if let Some(frame) = self.history.lock().unwrap().back_mut() {
+            f(frame);
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+struct FrameTiming {
+    index: u32,
+    /// When WaitGetPoses started this frame.
+    start: Instant,
+    /// Time since the previous frame's WaitGetPoses.
+    interval: Option<Duration>,
+    display_period: Option<Duration>,
+    /// When the last eye was submitted, relative to start.
+    submitted: Option<Duration>,
+    /// Start and end of presenting this frame, relative to start.
+    presented: Option<(Duration, Duration)>,
+    /// Whether the app asked for interleaved reprojection, which has it render every other frame.
+    throttled: bool,
+}
+
+impl FrameTiming {
+    /// Number of vsyncs the app missed before coming back to WaitGetPoses for this frame,
+    /// during which the runtime had to show the previous frame again.
+    fn missed_vsyncs(&self) -> u32 {
+        match (self.interval, self.display_period) {
+            (Some(interval), Some(period)) if !period.is_zero() => {
+                ((interval.as_secs_f64() / period.as_secs_f64()).ceil() as u32).saturating_sub(1)
+            }
+            _ => 0,
+        }
+    }
+
+    /// Missed vsyncs are reported as CPU reprojection, and interleaved reprojection as being
+    /// throttled by one frame.
+    fn reprojection_flags(&self) -> u32 {
+        let mut flags = 0;
+        if self.missed_vsyncs() > 0 {
+            flags |= vr::VRCompositor_ReprojectionReason_Cpu;
+        }
+        if self.throttled {
+            flags |= 1 << vr::VRCompositor_ThrottleMask.trailing_zeros();
+        }
+        flags
+    }
+
+    /// Seconds left until this frame's display period is up, negative once the app runs late.
+    fn time_remaining(&self, now: Instant) -> Option<f32> {
+        let period = self.display_period?;
+        Some(period.as_secs_f32() - now.duration_since(self.start).as_secs_f32())
+    }
 }
 
 struct TempBackendData<G: GraphicsBackend> {
@@ -92,15 +264,38 @@ impl Compositor {
             metrics: FrameMetrics {
                 system_start: Instant::now(),
                 index: 0.into(),
-                time: 0.0.into(),
+                history: Mutex::default(),
+                stats: Mutex::default(),
             },
             timing_mode: vr::EVRCompositorTimingMode::Implicit.into(),
             frame_state: FrameState::Submitted.into(),
             focused: Once::new(),
+            fades: Default::default(),
+            grid_fade: Default::default(),
+            force_interleaved_reprojection: false.into(),
+        }
+    }
+
+    /// The scene fade to apply this frame, if any.
+    fn scene_fade(&self) -> Option<vr::HmdColor_t> {
+        let color = self.fades.lock().unwrap()[0].color_at(Instant::now());
+        if color.a <= 0.0 {
+            return None;
+        }
+        if !self
+            .openxr
+            .enabled_extensions
+            .khr_composition_layer_color_scale_bias
+        {
+            crate::warn_once!(
+                "Cannot FadeToColor: Runtime does not support KHR_composition_layer_color_scale_bias"
+            );
+            return None;
         }
+        Some(color)
     }
 
-    fn maybe_wait_frame(&self, session_data: &SessionData) {
+    fn maybe_wait_frame(&self, session_data: &SessionData) -> Option<xr::FrameState> {
         tracy_span!();
         let mut frame_lock = { session_data.comp_data.0.lock().unwrap() };
         self.frame_state
@@ -109,17 +304,21 @@ impl Compositor {
             .advance_to(FrameState::Waited);
         let Some(ctrl) = frame_lock.as_mut() else {
             debug!("no frame controller - not starting frame");
-            return;
+            return None;
         };
 
         #[macros::any_graphics(DynFrameController)]
-        fn wait_frame<G: GraphicsBackend + 'static>(ctrl: &mut FrameController<G>) -> xr::Time {
+        fn wait_frame<G: GraphicsBackend + 'static>(
+            ctrl: &mut FrameController<G>,
+        ) -> xr::FrameState {
             ctrl.wait_frame()
         }
 
+        let frame_state = ctrl.with_any_graphics_mut::<wait_frame>(());
         self.openxr
             .display_time
-            .set(ctrl.with_any_graphics_mut::<wait_frame>(()));
+            .set(frame_state.predicted_display_time);
+        Some(frame_state)
     }
 
     fn maybe_begin_frame(&self, session_data: &SessionData) {
@@ -147,7 +346,8 @@ impl Compositor {
         texture: &vr::Texture_t,
         bounds: vr::VRTextureBounds_t,
     ) -> Result<(), vr::EVRCompositorError> {
-        let backend = SupportedBackend::new(texture, bounds);
+        let backend =
+            SupportedBackend::new(texture, bounds).ok_or(vr::EVRCompositorError::InvalidTexture)?;
 
         #[macros::any_graphics(SupportedBackend)]
         fn swapchain_info<G: GraphicsBackend>(
@@ -160,7 +360,9 @@ impl Compositor {
         {
             let b_texture =
                 G::get_texture(texture).ok_or(vr::EVRCompositorError::InvalidTexture)?;
-            let info = backend.swapchain_info_for_texture(b_texture, bounds, texture.eColorSpace);
+            let info = backend
+                .swapchain_info_for_texture(b_texture, bounds, texture.eColorSpace)
+                .ok_or(vr::EVRCompositorError::InvalidTexture)?;
             Ok(TempBackendData {
                 backend,
                 swapchain_create_info: Some(info),
@@ -177,6 +379,115 @@ impl Compositor {
         self.openxr.restart_session();
         Ok(())
     }
+
+    /// Copies the next textures submitted for the left and right eyes and passes them to
+    /// `on_captured` once both have been read back, which happens in the second eye's Submit.
+    /// Fails if the app isn't submitting frames or another screenshot is already pending.
+    pub fn capture_next_eyes(
+        &self,
+        on_captured: impl FnOnce(ScreenshotEyes) + Send + 'static,
+    ) -> Result<(), vr::EVRCompositorError> {
+        let session_data = self.openxr.session_data.get();
+        let mut frame_lock = session_data.comp_data.0.lock().unwrap();
+        let ctrl = frame_lock
+            .as_mut()
+            .ok_or(vr::EVRCompositorError::RequestFailed)?;
+
+        #[macros::any_graphics(DynFrameController)]
+        fn capture<G: GraphicsBackend + 'static>(
+            ctrl: &mut FrameController<G>,
+            on_captured: ScreenshotCallback,
+        ) -> Result<(), vr::EVRCompositorError> {
+            if ctrl.last_submitted.iter().any(Option::is_none) || ctrl.screenshot.is_some() {
+                return Err(vr::EVRCompositorError::RequestFailed);
+            }
+            ctrl.screenshot = Some(PendingScreenshot {
+                copied: [false; 2],
+                on_captured,
+            });
+            Ok(())
+        }
+
+        ctrl.with_any_graphics_mut::<capture>(Box::new(on_captured))
+    }
+
+    /// The native handle of our copy of the last texture submitted for `eye`, for mirroring what
+    /// the user sees to the desktop. It's made with the app's device or context, so it's only
+    /// valid there, and it's kept up to date until it's released.
+    pub fn mirror_texture(
+        &self,
+        eye: vr::EVREye,
+    ) -> Result<(vr::ETextureType, u64), vr::EVRCompositorError> {
+        let session_data = self.openxr.session_data.get();
+        let mut frame_lock = session_data.comp_data.0.lock().unwrap();
+        let ctrl = frame_lock
+            .as_mut()
+            .ok_or(vr::EVRCompositorError::RequestFailed)?;
+
+        #[macros::any_graphics(DynFrameController)]
+        fn mirror<G: GraphicsBackend + 'static>(
+            ctrl: &mut FrameController<G>,
+            eye: vr::EVREye,
+        ) -> Result<(vr::ETextureType, u64), vr::EVRCompositorError> {
+            let (submitted, _, texture_layer) = ctrl.last_submitted[eye as usize]
+                .as_ref()
+                .ok_or(vr::EVRCompositorError::RequestFailed)?;
+            // Eyes are only copied while they're mirrored, so start from the last submitted one.
+            if !ctrl.mirrored[eye as usize] {
+                let texture = G::get_texture(&submitted.texture())
+                    .ok_or(vr::EVRCompositorError::InvalidTexture)?;
+                ctrl.backend
+                    .snapshot_texture(eye as usize, texture, *texture_layer)
+                    .ok_or(vr::EVRCompositorError::InvalidTexture)?;
+                ctrl.mirrored[eye as usize] = true;
+            }
+            let handle = ctrl
+                .backend
+                .snapshot(eye as usize)
+                .map(G::native_handle)
+                .filter(|handle| *handle != 0)
+                .ok_or(vr::EVRCompositorError::InvalidTexture)?;
+            Ok((submitted.texture().eType, handle))
+        }
+
+        ctrl.with_any_graphics_mut::<mirror>(eye)
+    }
+
+    /// Stops keeping the mirror texture with the given native handle up to date.
+    pub fn release_mirror_texture(&self, handle: u64) {
+        let session_data = self.openxr.session_data.get();
+        let mut frame_lock = session_data.comp_data.0.lock().unwrap();
+
+        #[macros::any_graphics(DynFrameController)]
+        fn release<G: GraphicsBackend + 'static>(ctrl: &mut FrameController<G>, handle: u64) {
+            for (eye, mirrored) in ctrl.mirrored.iter_mut().enumerate() {
+                if ctrl.backend.snapshot(eye).map(G::native_handle) == Some(handle) {
+                    *mirrored = false;
+                }
+            }
+        }
+
+        if let Some(ctrl) = frame_lock.as_mut() {
+            ctrl.with_any_graphics_mut::<release>(handle);
+        }
+    }
+
+    /// A new backend for the graphics API the app submits its frames with, created from the last
+    /// submitted left eye texture.
+    pub fn create_backend(&self) -> Option<SupportedBackend> {
+        let session_data = self.openxr.session_data.get();
+        let frame_lock = session_data.comp_data.0.lock().unwrap();
+
+        #[macros::any_graphics(DynFrameController)]
+        fn create<G: GraphicsBackend + 'static>(
+            ctrl: &FrameController<G>,
+        ) -> Option<SupportedBackend> {
+            let (submitted, bounds, _) = ctrl.last_submitted[0].as_ref()?;
+            SupportedBackend::new(&submitted.texture(), *bounds)
+        }
+
+        frame_lock.as_ref()?.with_any_graphics::<create>(())
+    }
 }
 
 fn fill_vk_extensions_buffer(extensions: String, buffer: *mut c_char, buffer_size: u32) -> u32 {
@@ -237,6 +548,8 @@ impl openxr_data::Compositor for Compositor {
         // This function is called while a write lock is called on the session, and as such should
         // not use self.openxr.session_data.get().
 
+        *self.metrics.stats.lock().unwrap() = CumulativeStats::default();
+
         let backend_data = self
             .tmp_backend
             .lock()
@@ -276,6 +589,10 @@ impl openxr_data::Compositor for Compositor {
             )),
         );
 
+        if let Some(overlays) = self.overlays.get() {
+            overlays.post_session_restart();
+        }
+
         let old_state = std::mem::replace(
             &mut *self.frame_state.lock().unwrap(),
             FrameState::Submitted,
@@ -350,7 +667,15 @@ impl vr::IVRCompositor028_Interface for Compositor {
         }
 
         let session_data = self.openxr.session_data.get();
-        self.maybe_begin_frame(&session_data);
+        let frame_state = *self.frame_state.lock().unwrap();
+        match frame_state {
+            FrameState::Waited => self.maybe_begin_frame(&session_data),
+            FrameState::Begun => trace!("frame already begun, ignoring explicit timing data"),
+            FrameState::Submitted => {
+                crate::warn_once!("SubmitExplicitTimingData called before WaitGetPoses, ignoring");
+                return vr::EVRCompositorError::RequestFailed;
+            }
+        }
         vr::EVRCompositorError::None
     }
     fn SetExplicitTimingMode(&self, timing_mode: vr::EVRCompositorTimingMode) {
@@ -364,11 +689,15 @@ impl vr::IVRCompositor028_Interface for Compositor {
         buffer: *mut std::ffi::c_char,
         buffer_size: u32,
     ) -> u32 {
+        // Extensions are per system rather than per physical device in OpenXR.
         let exts = self
             .openxr
             .instance
             .vulkan_legacy_device_extensions(self.openxr.system_id)
-            .unwrap();
+            .unwrap_or_else(|e| {
+                log::error!("Failed to get required Vulkan device extensions: {e}");
+                String::new()
+            });
         log::debug!("required device extensions: {exts}");
         fill_vk_extensions_buffer(exts, buffer, buffer_size)
     }
@@ -382,31 +711,47 @@ impl vr::IVRCompositor028_Interface for Compositor {
             .openxr
             .instance
             .vulkan_legacy_instance_extensions(self.openxr.system_id)
-            .unwrap();
+            .unwrap_or_else(|e| {
+                log::error!("Failed to get required Vulkan instance extensions: {e}");
+                String::new()
+            });
         log::debug!("required instance extensions: {exts}");
         fill_vk_extensions_buffer(exts, buffer, buffer_size)
     }
 
-    fn UnlockGLSharedTextureForAccess(&self, _glSharedTextureHandle: vr::glSharedTextureHandle_t) {
-        todo!()
-    }
-    fn LockGLSharedTextureForAccess(&self, _glSharedTextureHandle: vr::glSharedTextureHandle_t) {
-        todo!()
-    }
+    // Mirror textures are made with the app's own context, so there's nothing to synchronize.
+    fn UnlockGLSharedTextureForAccess(&self, _glSharedTextureHandle: vr::glSharedTextureHandle_t) {}
+    fn LockGLSharedTextureForAccess(&self, _glSharedTextureHandle: vr::glSharedTextureHandle_t) {}
     fn ReleaseSharedGLTexture(
         &self,
-        _glTextureId: vr::glUInt_t,
+        gl_texture_id: vr::glUInt_t,
         _glSharedTextureHandle: vr::glSharedTextureHandle_t,
     ) -> bool {
-        todo!()
+        self.release_mirror_texture(gl_texture_id.into());
+        true
     }
     fn GetMirrorTextureGL(
         &self,
-        _eEye: vr::EVREye,
-        _pglTextureId: *mut vr::glUInt_t,
-        _pglSharedTextureHandle: *mut vr::glSharedTextureHandle_t,
+        eye: vr::EVREye,
+        texture_id: *mut vr::glUInt_t,
+        shared_handle: *mut vr::glSharedTextureHandle_t,
     ) -> vr::EVRCompositorError {
-        todo!()
+        if texture_id.is_null() || shared_handle.is_null() {
+            return vr::EVRCompositorError::RequestFailed;
+        }
+        let (ty, handle) = match self.mirror_texture(eye) {
+            Ok(mirror) => mirror,
+            Err(e) => return e,
+        };
+        if ty != vr::ETextureType::OpenGL {
+            crate::warn_once!("GetMirrorTextureGL called by non-OpenGL app ({ty:?})");
+            return vr::EVRCompositorError::SharedTexturesNotSupported;
+        }
+        unsafe {
+            texture_id.write(handle as vr::glUInt_t);
+            shared_handle.write(handle as vr::glSharedTextureHandle_t);
+        }
+        vr::EVRCompositorError::None
     }
     fn ReleaseMirrorTextureD3D11(&self, _pD3D11ShaderResourceView: *mut std::ffi::c_void) {
         todo!()
@@ -442,8 +787,14 @@ impl vr::IVRCompositor028_Interface for Compositor {
     fn ForceReconnectProcess(&self) {
         todo!()
     }
-    fn ForceInterleavedReprojectionOn(&self, _: bool) {
-        crate::warn_unimplemented!("ForceInterleavedReprojectionOn");
+    fn ForceInterleavedReprojectionOn(&self, force: bool) {
+        if force {
+            crate::warn_once!(
+                "App requested interleaved reprojection, but OpenXR has no control for it - only reporting it in frame timing"
+            );
+        }
+        self.force_interleaved_reprojection
+            .store(force, Ordering::Relaxed);
     }
     fn ShouldAppRenderWithLowResources(&self) -> bool {
         // TODO
@@ -480,7 +831,10 @@ impl vr::IVRCompositor028_Interface for Compositor {
         crate::warn_unimplemented!("CompositorGoToBack");
     }
     fn CompositorBringToFront(&self) {
-        crate::warn_unimplemented!("CompositorBringToFront");
+        // OpenXR runtimes decide which session has focus. The closest we can get is showing the
+        // app's frames again, so the runtime can focus it.
+        debug!("bringing app to front");
+        self.SuspendRendering(false);
     }
     fn ClearSkyboxOverride(&self) {
         if let Some(overlays) = self.overlays.get() {
@@ -500,14 +854,6 @@ impl vr::IVRCompositor028_Interface for Compositor {
         }
         match unTextureCount {
             1..=2 => {
-                if !self
-                    .openxr
-                    .enabled_extensions
-                    .khr_composition_layer_equirect2
-                {
-                    log::info!("Could not set skybox: khr_composition_layer_equirect2 unsupported");
-                    return vr::EVRCompositorError::None;
-                }
                 log::debug!("Setting new equirect skybox");
             }
             6 => {
@@ -527,56 +873,102 @@ impl vr::IVRCompositor028_Interface for Compositor {
         }
     }
     fn GetCurrentGridAlpha(&self) -> f32 {
-        0.0
+        self.grid_fade.lock().unwrap().color_at(Instant::now()).a
     }
-    fn FadeGrid(&self, _fSeconds: f32, bFadeGridIn: bool) {
-        #[macros::any_graphics(DynFrameController)]
-        fn set_fade_grid<G: GraphicsBackend + 'static>(
-            ctrl: &mut FrameController<G>,
-            app_fade_grid: bool,
-        ) {
-            ctrl.app_fade_grid = app_fade_grid;
-        }
-
-        self.openxr
-            .session_data
-            .get()
-            .comp_data
-            .0
-            .lock()
-            .unwrap()
-            .iter_mut()
-            .for_each(|ctrl| ctrl.with_any_graphics_mut::<set_fade_grid>(bFadeGridIn));
+    fn FadeGrid(&self, fSeconds: f32, bFadeGridIn: bool) {
+        debug!(
+            "fading grid {} over {fSeconds}s",
+            if bFadeGridIn { "in" } else { "out" }
+        );
+        let color = vr::HmdColor_t {
+            a: if bFadeGridIn { 1.0 } else { 0.0 },
+            ..Default::default()
+        };
+        self.grid_fade.lock().unwrap().restart(
+            color,
+            Duration::try_from_secs_f32(fSeconds).unwrap_or_default(),
+            Instant::now(),
+        );
     }
-    fn GetCurrentFadeColor(&self, _bBackground: bool) -> vr::HmdColor_t {
-        todo!()
+    fn GetCurrentFadeColor(&self, bBackground: bool) -> vr::HmdColor_t {
+        self.fades.lock().unwrap()[bBackground as usize].color_at(Instant::now())
     }
     fn FadeToColor(
         &self,
-        _fSeconds: f32,
-        _fRed: f32,
-        _fGreen: f32,
-        _fBlue: f32,
-        _fAlpha: f32,
-        _bBackground: bool,
+        fSeconds: f32,
+        fRed: f32,
+        fGreen: f32,
+        fBlue: f32,
+        fAlpha: f32,
+        bBackground: bool,
     ) {
-        crate::warn_unimplemented!("FadeToColor");
+        debug!(
+            "fading {} to ({fRed}, {fGreen}, {fBlue}, {fAlpha}) over {fSeconds}s",
+            if bBackground { "background" } else { "scene" }
+        );
+        let color = vr::HmdColor_t {
+            r: fRed,
+            g: fGreen,
+            b: fBlue,
+            a: fAlpha.clamp(0.0, 1.0),
+        };
+        if bBackground && color.a > 0.0 {
+            // It's still tracked, so GetCurrentFadeColor reports what the app asked for.
+            crate::warn_once!("Background fades are not rendered, only scene fades are");
+        }
+        self.fades.lock().unwrap()[bBackground as usize].restart(
+            color,
+            Duration::try_from_secs_f32(fSeconds).unwrap_or_default(),
+            Instant::now(),
+        );
     }
-    fn GetCumulativeStats(
-        &self,
-        _pStats: *mut vr::Compositor_CumulativeStats,
-        _nStatsSizeInBytes: u32,
-    ) {
-        todo!()
+    fn GetCumulativeStats(&self, stats: *mut vr::Compositor_CumulativeStats, size: u32) {
+        if stats.is_null() {
+            return;
+        }
+        // Older apps pass in smaller structs, we fill in what fits.
+        let size = (size as usize).min(std::mem::size_of::<vr::Compositor_CumulativeStats>());
+        if size
+            < offset_of!(vr::Compositor_CumulativeStats, m_nNumReprojectedFrames)
+                + std::mem::size_of::<u32>()
+        {
+            return;
+        }
+        macro_rules! set {
+            ($member:ident, $value:expr) => {{
+                let ptr = &raw mut (*stats).$member;
+                ptr.write_unaligned($value)
+            }};
+        }
+
+        let counts = *self.metrics.stats.lock().unwrap();
+        unsafe {
+            // Startup, loading and timeout counts aren't tracked.
+            stats.cast::<u8>().write_bytes(0, size);
+            set!(m_nPid, std::process::id());
+            set!(m_nNumFramePresents, counts.presents);
+            set!(m_nNumDroppedFrames, counts.dropped);
+            set!(m_nNumReprojectedFrames, counts.reprojected);
+        }
     }
     fn GetFrameTimeRemaining(&self) -> f32 {
-        crate::warn_unimplemented!("GetFrameTimeRemaining");
-        0.0
+        // Nothing is in flight between presenting a frame and WaitGetPoses starting the next.
+        if *self.frame_state.lock().unwrap() == FrameState::Submitted {
+            return 0.0;
+        }
+        let now = Instant::now();
+        self.metrics
+            .history
+            .lock()
+            .unwrap()
+            .back()
+            .and_then(|frame| frame.time_remaining(now))
+            .unwrap_or_default()
     }
     fn GetFrameTimings(&self, _pTiming: *mut vr::Compositor_FrameTiming, _nFrames: u32) -> u32 {
         todo!()
     }
-    fn GetFrameTiming(&self, timing: *mut vr::Compositor_FrameTiming, _frames_ago: u32) -> bool {
+    fn GetFrameTiming(&self, timing: *mut vr::Compositor_FrameTiming, frames_ago: u32) -> bool {
         if timing.is_null() || !timing.is_aligned() {
             return false;
         }
@@ -601,33 +993,75 @@ impl vr::IVRCompositor028_Interface for Compositor {
             }};
         }
 
+        // 0 is the frame currently in flight.
+        let Some(frame) = self
+            .metrics
+            .history
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .nth(frames_ago as usize)
+            .copied()
+        else {
+            return false;
+        };
+
+        let ms = |d: Duration| d.as_secs_f32() * 1000.0;
+        let missed_vsyncs = frame.missed_vsyncs();
+        let (present_start, present_end) = frame.presented.unzip();
+
         unsafe {
-            // TODO: These values are copy/pasted from OpenComposite, determine if real values are
-            // necessary/better
-            set!(m_nFrameIndex, self.metrics.index.load(Ordering::Relaxed));
-            set!(m_nNumFramePresents, 1);
+            set!(m_nFrameIndex, frame.index);
+            set!(m_nNumFramePresents, frame.presented.is_some() as u32);
             set!(m_nNumMisPresented, 0);
-            set!(m_nReprojectionFlags, 0);
-            set!(m_flSystemTimeInSeconds, self.metrics.time.load());
+            set!(m_nNumDroppedFrames, missed_vsyncs);
+            set!(m_nReprojectionFlags, frame.reprojection_flags());
+            set!(
+                m_flSystemTimeInSeconds,
+                frame
+                    .start
+                    .duration_since(self.metrics.system_start)
+                    .as_secs_f64()
+            );
+            // TODO: GPU times aren't measured, these values are copy/pasted from OpenComposite
             set!(m_flPreSubmitGpuMs, 8.0);
             set!(m_flPostSubmitGpuMs, 1.0);
             set!(m_flTotalRenderGpuMs, 9.0);
-
             set!(m_flCompositorRenderGpuMs, 1.5);
-            set!(m_flCompositorRenderCpuMs, 3.0);
-            set!(m_flCompositorIdleCpuMs, 0.1);
 
-            set!(m_flClientFrameIntervalMs, 11.1);
+            set!(
+                m_flCompositorRenderCpuMs,
+                frame.presented.map(|(s, e)| ms(e - s)).unwrap_or_default()
+            );
+            set!(m_flCompositorIdleCpuMs, 0.0);
+
+            set!(
+                m_flClientFrameIntervalMs,
+                frame.interval.map(ms).unwrap_or_default()
+            );
             set!(m_flPresentCallCpuMs, 0.0);
             set!(m_flWaitForPresentCpuMs, 0.0);
             set!(m_flSubmitFrameMs, 0.0);
 
             set!(m_flWaitGetPosesCalledMs, 0.0);
             set!(m_flNewPosesReadyMs, 0.0);
-            set!(m_flNewFrameReadyMs, 0.0); // second call to IVRCompositor::Submit
-            set!(m_flCompositorUpdateStartMs, 0.0);
-            set!(m_flCompositorUpdateEndMs, 0.0);
-            set!(m_flCompositorRenderStartMs, 0.0);
+            set!(
+                m_flNewFrameReadyMs,
+                frame.submitted.map(ms).unwrap_or_default()
+            ); // second call to IVRCompositor::Submit
+            set!(
+                m_flCompositorUpdateStartMs,
+                present_start.map(ms).unwrap_or_default()
+            );
+            set!(
+                m_flCompositorUpdateEndMs,
+                present_end.map(ms).unwrap_or_default()
+            );
+            set!(
+                m_flCompositorRenderStartMs,
+                present_start.map(ms).unwrap_or_default()
+            );
         }
 
         true
@@ -640,11 +1074,20 @@ impl vr::IVRCompositor028_Interface for Compositor {
             system: &System,
             display_time: xr::Time,
             overlays: Option<&OverlayMan>,
+            fade: Option<vr::HmdColor_t>,
+            grid_alpha: f32,
         ) where
             for<'b> &'b crate::overlay::AnySwapchainMap:
                 TryInto<&'b crate::overlay::SwapchainMap<G::Api>, Error: std::fmt::Display>,
         {
-            ctrl.end_frame(session_data, system, display_time, overlays)
+            ctrl.end_frame(
+                session_data,
+                system,
+                display_time,
+                overlays,
+                fade,
+                grid_alpha,
+            )
         }
 
         let session_data = self.openxr.session_data.get();
@@ -663,11 +1106,14 @@ impl vr::IVRCompositor028_Interface for Compositor {
         let display_time = self.openxr.display_time.get();
         let overlays = self.overlays.get();
 
+        let present_start = Instant::now();
         ctrl.with_any_graphics_mut::<end_frame>((
             &session_data,
             &system,
             display_time,
             overlays.as_deref(),
+            self.scene_fade(),
+            self.GetCurrentGridAlpha(),
         ));
 
         self.frame_state
@@ -675,17 +1121,37 @@ impl vr::IVRCompositor028_Interface for Compositor {
             .unwrap()
             .advance_to(FrameState::Submitted);
 
+        let present_end = Instant::now();
+        self.metrics.with_current(|frame| {
+            frame.presented = Some((
+                present_start.duration_since(frame.start),
+                present_end.duration_since(frame.start),
+            ))
+        });
+        self.metrics.stats.lock().unwrap().presents += 1;
         self.metrics.index.fetch_add(1, Ordering::Relaxed);
-        self.metrics
-            .time
-            .store(self.metrics.system_start.elapsed().as_secs_f64());
         #[cfg(feature = "tracing")]
         {
             tracy_client::frame_mark();
         }
     }
     fn ClearLastSubmittedFrame(&self) {
-        crate::warn_unimplemented!("ClearLastSubmittedFrame");
+        #[macros::any_graphics(DynFrameController)]
+        fn clear_submitted<G: GraphicsBackend + 'static>(ctrl: &mut FrameController<G>) {
+            ctrl.eyes_submitted = [None; 2];
+            ctrl.last_submitted = Default::default();
+        }
+
+        // Without submitted eyes the frame ends without a projection layer.
+        self.openxr
+            .session_data
+            .get()
+            .comp_data
+            .0
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .for_each(|ctrl| ctrl.with_any_graphics_mut::<clear_submitted>(()));
     }
     fn SubmitWithArrayIndex(
         &self,
@@ -728,6 +1194,14 @@ impl vr::IVRCompositor028_Interface for Compositor {
         }
 
         let mut session_lock = self.openxr.session_data.get();
+        if *self.frame_state.lock().unwrap() == FrameState::Waited {
+            // Only possible in explicit timing mode, the app should have begun the frame with
+            // SubmitExplicitTimingData.
+            crate::warn_once!(
+                "App submitted a frame before SubmitExplicitTimingData, beginning it"
+            );
+            self.maybe_begin_frame(&session_lock);
+        }
         let mut frame_lock = session_lock.comp_data.0.lock().unwrap();
 
         let ctrl = match frame_lock.as_mut() {
@@ -755,7 +1229,7 @@ impl vr::IVRCompositor028_Interface for Compositor {
             texture: &vr::Texture_t,
             bounds: vr::VRTextureBounds_t,
             flags: vr::EVRSubmitFlags,
-        ) -> xr::Result<(), vr::EVRCompositorError>
+        ) -> Result<Option<(ScreenshotCallback, ScreenshotEyes)>, vr::EVRCompositorError>
         where
             for<'d> &'d openxr_data::GraphicalSession:
                 TryInto<&'d openxr_data::Session<G::Api>, Error: std::fmt::Display>,
@@ -770,18 +1244,62 @@ impl vr::IVRCompositor028_Interface for Compositor {
                 texture.eColorSpace,
                 bounds,
                 flags,
-            )
+            )?;
+            let texture_layer = if texture.eType == vr::ETextureType::Vulkan
+                && (flags & vr::EVRSubmitFlags::VulkanTextureWithArrayData).0 > 0
+            {
+                unsafe { (*texture.handle.cast::<vr::VRVulkanTextureArrayData_t>()).m_unArrayIndex }
+            } else {
+                0
+            };
+            ctrl.last_submitted[eye as usize] =
+                Some((SubmittedTexture::new(*texture), bounds, texture_layer));
+
+            // Screenshots and mirrors look at the texture after the app has moved on from it,
+            // so it's only copied while one of them needs it.
+            let capturing = ctrl
+                .screenshot
+                .as_ref()
+                .is_some_and(|screenshot| !screenshot.copied[eye as usize]);
+            if !ctrl.mirrored[eye as usize] && !capturing {
+                return Ok(None);
+            }
+            if ctrl
+                .backend
+                .snapshot_texture(eye as usize, real_texture, texture_layer)
+                .is_none()
+            {
+                crate::warn_once!("Failed to copy submitted {:?} texture", texture.eType);
+                if capturing {
+                    warn!("Can't take screenshot: failed to copy {eye:?} texture");
+                    ctrl.screenshot = None;
+                }
+                return Ok(None);
+            }
+            if let Some(screenshot) = ctrl.screenshot.as_mut().filter(|_| capturing) {
+                screenshot.copied[eye as usize] = true;
+            }
+            Ok(ctrl.take_captured_screenshot())
         }
 
-        if let Err(e) = ctrl.with_any_graphics_mut::<submit>((
+        let screenshot = match ctrl.with_any_graphics_mut::<submit>((
             &session_lock,
             eye,
             texture,
             bounds,
             submit_flags,
         )) {
-            return e;
+            Ok(screenshot) => screenshot,
+            Err(e) => return e,
+        };
+        // Screenshots take a while to write out, which shouldn't hold up the other eye.
+        drop(frame_lock);
+        if let Some((on_captured, eyes)) = screenshot {
+            on_captured(eyes);
         }
+        let now = Instant::now();
+        self.metrics
+            .with_current(|frame| frame.submitted = Some(now.duration_since(frame.start)));
         vr::EVRCompositorError::None
     }
 
@@ -850,8 +1368,13 @@ impl vr::IVRCompositor028_Interface for Compositor {
             if *self.frame_state.lock().unwrap() == FrameState::Waited {
                 // discard frame
                 self.maybe_begin_frame(&session_data);
+                self.metrics.stats.lock().unwrap().dropped += 1;
             }
-            self.maybe_wait_frame(&session_data);
+            let frame_state = self.maybe_wait_frame(&session_data);
+            self.metrics.start_frame(
+                frame_state,
+                self.force_interleaved_reprojection.load(Ordering::Relaxed),
+            );
 
             if timing_mode == vr::EVRCompositorTimingMode::Implicit {
                 self.maybe_begin_frame(&session_data);
@@ -860,8 +1383,28 @@ impl vr::IVRCompositor028_Interface for Compositor {
         if let Some(system) = self.system.get() {
             system.reset_views();
         }
+        if let Some(overlays) = self.overlays.get() {
+            overlays.clear_restarted_textures();
+        }
         if let Some(input) = self.input.get() {
             input.frame_start_update();
+            if let Some(overlays) = self
+                .overlays
+                .get()
+                .filter(|overlays| overlays.wants_laser())
+            {
+                // There's no dominant hand setting yet, so the laser comes from the right hand.
+                let laser = input
+                    .get_controller_pose(Hand::Right, None)
+                    .filter(|pose| pose.bPoseIsValid)
+                    .map(|pose| (Hand::Right, pose.mDeviceToAbsoluteTracking));
+                overlays.update_laser(
+                    self.openxr.get_tracking_space(),
+                    laser,
+                    input.legacy_pointer_state(Hand::Right),
+                    self.metrics.frame_time(),
+                );
+            }
         }
 
         self.GetLastPoses(
@@ -915,10 +1458,31 @@ impl vr::IVRCompositor009On016 for Compositor {
     }
 }
 
+/// One eye of a screenshot, read back as it was submitted.
+pub struct EyeImage {
+    pub extent: xr::Extent2Di,
+    /// Tightly packed RGBA8, in the order the backend reads rows back in
+    pub pixels: Vec<u8>,
+    pub bounds: vr::VRTextureBounds_t,
+    pub texture_type: vr::ETextureType,
+}
+
+/// The left and right eye images of a screenshot.
+pub type ScreenshotEyes = [EyeImage; 2];
+type ScreenshotCallback = Box<dyn FnOnce(ScreenshotEyes) + Send>;
+
+/// A screenshot of the next frame the app submits.
+struct PendingScreenshot {
+    /// Eyes that have been copied for the screenshot so far
+    copied: [bool; 2],
+    on_captured: ScreenshotCallback,
+}
+
 #[derive(Copy, Clone, Default)]
 struct SubmittedEye {
     extent: xr::Extent2Di,
     flip_vertically: bool,
+    flip_horizontally: bool,
 }
 
 struct SwapchainData<G: xr::Graphics> {
@@ -935,14 +1499,47 @@ struct FrameController<G: GraphicsBackend> {
     image_acquired: bool,
     should_render: bool,
     app_suspend_render: bool,
-    app_fade_grid: bool,
     eyes_submitted: [Option<SubmittedEye>; 2],
+    /// The last textures submitted for each eye and the layer of them that was used, kept
+    /// across frames for screenshots
+    last_submitted: [Option<(SubmittedTexture, vr::VRTextureBounds_t, u32)>; 2],
+    /// Eyes whose copies have been handed out as mirror textures, so they're copied on every
+    /// submit until released
+    mirrored: [bool; 2],
+    screenshot: Option<PendingScreenshot>,
     submitting_null: bool,
+    /// Incremented on every begin_frame
+    frame_index: u64,
     backend: G,
 }
 supported_backends_enum!(enum DynFrameController: FrameController);
 
 impl<G: GraphicsBackend> FrameController<G> {
+    /// Reads back the pending screenshot once both eyes have been copied for it.
+    fn take_captured_screenshot(&mut self) -> Option<(ScreenshotCallback, ScreenshotEyes)> {
+        if self.screenshot.as_ref()?.copied != [true; 2] {
+            return None;
+        }
+        let screenshot = self.screenshot.take()?;
+        let read = |eye: usize| {
+            let (submitted, bounds, _) = self.last_submitted[eye].as_ref()?;
+            let (extent, pixels) = self
+                .backend
+                .read_texture(self.backend.snapshot(eye)?, *bounds)?;
+            Some(EyeImage {
+                extent,
+                pixels,
+                bounds: *bounds,
+                texture_type: submitted.texture().eType,
+            })
+        };
+        let (Some(left), Some(right)) = (read(0), read(1)) else {
+            warn!("Can't take screenshot: submitted eye textures can't be read back");
+            return None;
+        };
+        Some((screenshot.on_captured, [left, right]))
+    }
+
     fn init_swapchain(
         session_data: &SessionData,
         create_info: &mut xr::SwapchainCreateInfo<G::Api>,
@@ -1024,9 +1621,12 @@ impl<G: GraphicsBackend> FrameController<G> {
             image_acquired: false,
             should_render: false,
             app_suspend_render: false,
-            app_fade_grid: false,
             eyes_submitted: Default::default(),
+            last_submitted: Default::default(),
+            mirrored: [false; 2],
+            screenshot: None,
             submitting_null: false,
+            frame_index: 0,
             backend,
         }
     }
@@ -1074,13 +1674,13 @@ impl<G: GraphicsBackend> FrameController<G> {
         self.image_acquired = true;
     }
 
-    fn wait_frame(&mut self) -> xr::Time {
+    fn wait_frame(&mut self) -> xr::FrameState {
         let frame_state = {
             tracy_span!("wait frame");
             self.waiter.wait().unwrap()
         };
         self.should_render = frame_state.should_render && !self.app_suspend_render;
-        frame_state.predicted_display_time
+        frame_state
     }
 
     fn begin_frame(&mut self) {
@@ -1104,6 +1704,7 @@ impl<G: GraphicsBackend> FrameController<G> {
         }
         self.eyes_submitted = [None; 2];
         self.submitting_null = false;
+        self.frame_index += 1;
         trace!("frame begin");
     }
 
@@ -1129,9 +1730,10 @@ impl<G: GraphicsBackend> FrameController<G> {
 
         self.eyes_submitted[eye as usize] = if self.should_render {
             // Make sure our image dimensions haven't changed.
-            let new_info = self
+            let mut new_info = self
                 .backend
-                .swapchain_info_for_texture(texture, bounds, color_space);
+                .swapchain_info_for_texture(texture, bounds, color_space)
+                .ok_or(vr::EVRCompositorError::InvalidTexture)?;
 
             is_valid_swapchain_info(&new_info)
                 .then(|| {
@@ -1144,20 +1746,33 @@ impl<G: GraphicsBackend> FrameController<G> {
                         is_usable_swapchain(&data.info, data.initial_format, &new_info)
                     }) {
                         info!("recreating swapchain (for {eye:?})");
+                        // Eyes can be submitted with different bounds, so the other eye may
+                        // already have been copied into the old swapchain this frame.
+                        let other_eye = [vr::EVREye::Right, vr::EVREye::Left][eye as usize];
+                        let other_submitted = self.eyes_submitted[other_eye as usize]
+                            .and(self.last_submitted[other_eye as usize].as_ref())
+                            .map(|(submitted, bounds, _)| (submitted.texture(), *bounds));
+                        if let (Some(_), Some(data)) = (&other_submitted, &self.swapchain_data) {
+                            new_info.width = new_info.width.max(data.info.width);
+                            new_info.height = new_info.height.max(data.info.height);
+                        }
                         self.recreate_swapchain(session_data, new_info);
-                    }
 
-                    SubmittedEye {
-                        extent: self.backend.copy_texture_to_swapchain(
-                            eye,
-                            texture,
-                            color_space,
-                            bounds,
-                            self.image_index,
-                            submit_flags,
-                        ),
-                        flip_vertically: bounds.vertically_flipped(),
+                        if let Some((other_texture, other_bounds)) = other_submitted {
+                            trace!("copying {other_eye:?} again");
+                            if let Some(real_texture) = G::get_texture(&other_texture) {
+                                self.eyes_submitted[other_eye as usize] = Some(self.copy_eye(
+                                    other_eye,
+                                    real_texture,
+                                    other_texture.eColorSpace,
+                                    other_bounds,
+                                    submit_flags,
+                                ));
+                            }
+                        }
                     }
+
+                    self.copy_eye(eye, texture, color_space, bounds, submit_flags)
                 })
                 .or_else(|| {
                     trace!("submitting null this frame");
@@ -1181,12 +1796,36 @@ impl<G: GraphicsBackend> FrameController<G> {
         Ok(())
     }
 
+    fn copy_eye(
+        &self,
+        eye: vr::EVREye,
+        texture: G::OpenVrTexture,
+        color_space: vr::EColorSpace,
+        bounds: vr::VRTextureBounds_t,
+        submit_flags: vr::EVRSubmitFlags,
+    ) -> SubmittedEye {
+        SubmittedEye {
+            extent: self.backend.copy_texture_to_swapchain(
+                eye,
+                texture,
+                color_space,
+                bounds,
+                self.image_index,
+                submit_flags,
+            ),
+            flip_vertically: bounds.vertically_flipped(),
+            flip_horizontally: bounds.horizontally_flipped(),
+        }
+    }
+
     fn end_frame(
         &mut self,
         session_data: &SessionData,
         system: &System,
         display_time: xr::Time,
         overlays: Option<&OverlayMan>,
+        fade: Option<vr::HmdColor_t>,
+        grid_alpha: f32,
     ) where
         for<'b> &'b crate::overlay::AnySwapchainMap:
             TryInto<&'b crate::overlay::SwapchainMap<G::Api>, Error: std::fmt::Display>,
@@ -1224,12 +1863,16 @@ impl<G: GraphicsBackend> FrameController<G> {
                     let SubmittedEye {
                         extent,
                         flip_vertically,
+                        flip_horizontally,
                     } = self.eyes_submitted[eye_index]
                         .unwrap_or_else(|| panic!("Eye {eye_index} has not been submitted!"));
                     let mut fov = view.fov;
                     if flip_vertically {
                         std::mem::swap(&mut fov.angle_up, &mut fov.angle_down);
                     }
+                    if flip_horizontally {
+                        std::mem::swap(&mut fov.angle_left, &mut fov.angle_right);
+                    }
 
                     let sub_image = xr::SwapchainSubImage::new()
                         .swapchain(&swapchain_data.swapchain)
@@ -1247,14 +1890,37 @@ impl<G: GraphicsBackend> FrameController<G> {
                 .collect()
         }
 
+        // Blends the scene towards the fade color: scene * (1 - a) + color * a
+        let fade_bias = fade.map(|color| xr::sys::CompositionLayerColorScaleBiasKHR {
+            ty: xr::StructureType::COMPOSITION_LAYER_COLOR_SCALE_BIAS_KHR,
+            next: std::ptr::null(),
+            color_scale: xr::Color4f {
+                r: 1.0 - color.a,
+                g: 1.0 - color.a,
+                b: 1.0 - color.a,
+                a: 1.0,
+            },
+            color_bias: xr::Color4f {
+                r: color.r * color.a,
+                g: color.g * color.a,
+                b: color.b * color.a,
+                a: 0.0,
+            },
+        });
+
         let mut proj_layer = None;
         if !proj_layer_views.is_empty() {
             trace!("projection layer present");
-            proj_layer = Some(
-                xr::CompositionLayerProjection::new()
-                    .space(session_data.tracking_space())
-                    .views(&proj_layer_views),
-            );
+            let mut layer = xr::CompositionLayerProjection::new()
+                .space(session_data.tracking_space())
+                .views(&proj_layer_views);
+            if let Some(bias) = &fade_bias {
+                let mut raw = layer.into_raw();
+                raw.next = bias as *const _ as *const _;
+                // SAFETY: the bias outlives the layer, which is only used for this frame
+                layer = unsafe { xr::CompositionLayerProjection::from_raw(raw) };
+            }
+            proj_layer = Some(layer);
         }
 
         let mut layers: Vec<&xr::CompositionLayerBase<_>> = Vec::new();
@@ -1263,8 +1929,13 @@ impl<G: GraphicsBackend> FrameController<G> {
         }
         let overlay_layers;
         if let Some(overlay_man) = overlays {
-            overlay_layers = overlay_man.get_layers(session_data, self.app_fade_grid);
-            layers.extend(overlay_layers.iter().map(Deref::deref));
+            overlay_layers = overlay_man.get_layers(session_data, grid_alpha, self.frame_index);
+            layers.extend(
+                overlay_layers
+                    .iter()
+                    .filter(|layer| layer.is_for_frame(self.frame_index))
+                    .map(Deref::deref),
+            );
         }
 
         self.stream
@@ -1287,6 +1958,7 @@ where
         && current.width >= new.width
         && current.height >= new.height
         && current.array_size == new.array_size
+        && current.face_count == new.face_count
         && current.sample_count == new.sample_count
 }
 
@@ -1301,7 +1973,8 @@ pub use tests::FakeGraphicsData;
 mod tests {
     use super::*;
     use crate::graphics_backends::{GraphicsBackend, VulkanData};
-    use std::cell::Cell;
+    use std::cell::{Cell, RefCell};
+    use std::collections::HashMap;
     use std::ffi::CStr;
     use std::mem::MaybeUninit;
     use std::thread_local;
@@ -1311,11 +1984,21 @@ mod tests {
     pub struct FakeGraphicsData {
         vk: Arc<VulkanData>,
         swapchain_format: Option<u32>,
+        /// Snapshots are only told apart by their address
+        snapshots: HashMap<usize, Box<u8>>,
     }
     thread_local! {
         static SWAPCHAIN_WIDTH: Cell<u32> = const { Cell::new(10) };
         static SWAPCHAIN_HEIGHT: Cell<u32> = const { Cell::new(10) };
         static SWAPCHAIN_FORMAT: Cell<u32> = const { Cell::new(0) };
+        static TEXTURE_SAMPLE_COUNT: Cell<u32> = const { Cell::new(1) };
+        static LAST_OVERLAY_TEXTURE_LAYER: Cell<u32> = const { Cell::new(0) };
+        static OVERLAY_COPIES: Cell<u32> = const { Cell::new(0) };
+        /// Colors that textures read back as, keyed by texture handle. Defaults to white.
+        static TEXTURE_COLORS: RefCell<HashMap<usize, [u8; 4]>> = RefCell::default();
+        /// Color of the first pixel read back from any texture, to tell which way up it is
+        static FIRST_PIXEL_COLOR: Cell<Option<[u8; 4]>> = const { Cell::new(Option::None) };
+        static FOREIGN_TEXTURES: Cell<bool> = const { Cell::new(false) };
     }
 
     pub enum FakeApi {}
@@ -1363,6 +2046,9 @@ mod tests {
         fn to_nice_format(format: <Self::Api as openxr::Graphics>::Format) -> Self::NiceFormat {
             VulkanData::to_nice_format(format)
         }
+        fn sdr_fallback_format(format: u32) -> Option<u32> {
+            VulkanData::sdr_fallback_format(format)
+        }
         fn session_create_info(&self) -> <Self::Api as openxr::Graphics>::SessionCreateInfo {
             self.vk.session_create_info()
         }
@@ -1371,23 +2057,29 @@ mod tests {
             VulkanData::get_texture(texture)
         }
 
+        fn native_handle(texture: Self::OpenVrTexture) -> u64 {
+            // Fake textures point at the VulkanData rather than at real texture data
+            texture as u64
+        }
+
         fn swapchain_info_for_texture(
             &self,
             _: Self::OpenVrTexture,
-            _: openvr::VRTextureBounds_t,
+            bounds: openvr::VRTextureBounds_t,
             _: openvr::EColorSpace,
-        ) -> openxr::SwapchainCreateInfo<Self::Api> {
-            xr::SwapchainCreateInfo {
+        ) -> Option<openxr::SwapchainCreateInfo<Self::Api>> {
+            let extent = bounded_extent(bounds);
+            Some(xr::SwapchainCreateInfo {
                 create_flags: xr::SwapchainCreateFlags::EMPTY,
                 usage_flags: xr::SwapchainUsageFlags::EMPTY,
                 format: SWAPCHAIN_FORMAT.get(),
-                sample_count: 1,
-                width: SWAPCHAIN_WIDTH.get(),
-                height: SWAPCHAIN_HEIGHT.get(),
+                sample_count: TEXTURE_SAMPLE_COUNT.get(),
+                width: extent.width as u32,
+                height: extent.height as u32,
                 face_count: 1,
                 array_size: 2,
                 mip_count: 1,
-            }
+            })
         }
 
         fn store_swapchain_images(
@@ -1403,25 +2095,106 @@ mod tests {
             _eye: openvr::EVREye,
             _texture: Self::OpenVrTexture,
             _color_space: vr::EColorSpace,
-            _bounds: openvr::VRTextureBounds_t,
+            bounds: openvr::VRTextureBounds_t,
             _image_index: usize,
             _submit_flags: openvr::EVRSubmitFlags,
         ) -> openxr::Extent2Di {
-            xr::Extent2Di::default()
+            bounded_extent(bounds)
         }
 
         fn copy_overlay_to_swapchain(
             &mut self,
             _texture: Self::OpenVrTexture,
+            texture_layer: u32,
+            _color_space: openvr::EColorSpace,
             _bounds: openvr::VRTextureBounds_t,
             _image_index: usize,
+            _layer: u32,
         ) -> openxr::Extent2Di {
-            xr::Extent2Di::default()
+            LAST_OVERLAY_TEXTURE_LAYER.set(texture_layer);
+            OVERLAY_COPIES.set(OVERLAY_COPIES.get() + 1);
+            xr::Extent2Di {
+                width: SWAPCHAIN_WIDTH.get() as i32,
+                height: SWAPCHAIN_HEIGHT.get() as i32,
+            }
+        }
+
+        fn read_texture(
+            &self,
+            texture: Self::OpenVrTexture,
+            _bounds: openvr::VRTextureBounds_t,
+        ) -> Option<(openxr::Extent2Di, Vec<u8>)> {
+            let extent = xr::Extent2Di {
+                width: SWAPCHAIN_WIDTH.get() as i32,
+                height: SWAPCHAIN_HEIGHT.get() as i32,
+            };
+            let color = TEXTURE_COLORS
+                .with_borrow(|colors| colors.get(&(texture as usize)).copied())
+                .unwrap_or([0xff; 4]);
+            let mut pixels = color.repeat(extent.width as usize * extent.height as usize);
+            if let Some(first) = FIRST_PIXEL_COLOR.get() {
+                pixels[..4].copy_from_slice(&first);
+            }
+            Some((extent, pixels))
+        }
+
+        fn snapshot_texture(
+            &mut self,
+            slot: usize,
+            texture: Self::OpenVrTexture,
+            _texture_layer: u32,
+        ) -> Option<Self::OpenVrTexture> {
+            let snapshot = &raw const **self.snapshots.entry(slot).or_default();
+            TEXTURE_COLORS.with_borrow_mut(|colors| {
+                let color = colors.get(&(texture as usize)).copied();
+                match color {
+                    Some(color) => colors.insert(snapshot as usize, color),
+                    None => colors.remove(&(snapshot as usize)),
+                }
+            });
+            Some(snapshot.cast())
+        }
+
+        fn snapshot(&self, slot: usize) -> Option<Self::OpenVrTexture> {
+            let snapshot = self.snapshots.get(&slot)?;
+            Some((&raw const **snapshot).cast())
+        }
+
+        fn swapchain_info_for_pixels(
+            &self,
+            extent: openxr::Extent2Di,
+        ) -> openxr::SwapchainCreateInfo<Self::Api> {
+            xr::SwapchainCreateInfo {
+                create_flags: xr::SwapchainCreateFlags::EMPTY,
+                usage_flags: xr::SwapchainUsageFlags::EMPTY,
+                format: SWAPCHAIN_FORMAT.get(),
+                sample_count: 1,
+                width: extent.width as u32,
+                height: extent.height as u32,
+                face_count: 1,
+                array_size: 1,
+                mip_count: 1,
+            }
+        }
+
+        fn upload_pixels(&mut self, pixels: &[u8], extent: openxr::Extent2Di, _: usize) {
+            assert_eq!(
+                pixels.len(),
+                extent.width as usize * extent.height as usize * 4
+            );
+        }
+    }
+
+    /// The size of the bounded region of a fake texture, which is as big as the swapchain.
+    fn bounded_extent(bounds: vr::VRTextureBounds_t) -> xr::Extent2Di {
+        xr::Extent2Di {
+            width: ((bounds.uMax - bounds.uMin).abs() * SWAPCHAIN_WIDTH.get() as f32) as i32,
+            height: ((bounds.vMax - bounds.vMin).abs() * SWAPCHAIN_HEIGHT.get() as f32) as i32,
         }
     }
 
     impl FakeGraphicsData {
-        fn texture(data: &Arc<VulkanData>) -> vr::Texture_t {
+        pub(crate) fn texture(data: &Arc<VulkanData>) -> vr::Texture_t {
             vr::Texture_t {
                 eType: vr::ETextureType::Reserved,
                 handle: Arc::into_raw(data.clone()) as _,
@@ -1429,6 +2202,46 @@ mod tests {
             }
         }
 
+        /// Sets the sample count of all textures submitted afterwards.
+        pub(crate) fn set_texture_sample_count(count: u32) {
+            TEXTURE_SAMPLE_COUNT.set(count);
+        }
+
+        /// The texture array layer the last overlay copy read from.
+        pub(crate) fn last_overlay_texture_layer() -> u32 {
+            LAST_OVERLAY_TEXTURE_LAYER.get()
+        }
+
+        /// The number of overlay texture layers copied so far.
+        pub(crate) fn overlay_copies() -> u32 {
+            OVERLAY_COPIES.get()
+        }
+
+        /// Sets the size of submitted textures and the swapchains made for them.
+        pub(crate) fn set_swapchain_extent(width: u32, height: u32) {
+            SWAPCHAIN_WIDTH.set(width);
+            SWAPCHAIN_HEIGHT.set(height);
+        }
+
+        /// Makes overlay textures count as drawn with a different graphics API than the
+        /// session's, so they're read back rather than copied.
+        pub(crate) fn set_first_pixel_color(color: [u8; 4]) {
+            FIRST_PIXEL_COLOR.set(Some(color));
+        }
+
+        pub(crate) fn set_foreign_textures(foreign: bool) {
+            FOREIGN_TEXTURES.set(foreign);
+        }
+
+        pub(crate) fn foreign_textures() -> bool {
+            FOREIGN_TEXTURES.get()
+        }
+
+        /// Sets the color that the given texture reads back as.
+        pub(crate) fn set_texture_color(texture: &vr::Texture_t, color: [u8; 4]) {
+            TEXTURE_COLORS.with_borrow_mut(|colors| colors.insert(texture.handle as usize, color));
+        }
+
         pub fn new(texture: &vr::Texture_t) -> Self {
             assert_eq!(texture.eType, vr::ETextureType::Reserved);
             let ptr = texture.handle as *const VulkanData;
@@ -1439,6 +2252,7 @@ mod tests {
             Self {
                 vk,
                 swapchain_format: Option::None,
+                snapshots: HashMap::new(),
             }
         }
     }
@@ -1551,6 +2365,56 @@ mod tests {
         }
     }
 
+    #[test]
+    fn fade_to_color() {
+        let Fixture { comp, .. } = Fixture::new();
+        let rgba = |c: vr::HmdColor_t| [c.r, c.g, c.b, c.a];
+
+        comp.FadeToColor(2.0, 1.0, 0.5, 0.0, 1.0, false);
+        let mut fade = comp.fades.lock().unwrap()[0];
+        let start = fade.start;
+        let second = Duration::from_secs(1);
+        assert_eq!(rgba(fade.color_at(start)), [0.0; 4]);
+        assert_eq!(rgba(fade.color_at(start + second)), [0.5, 0.25, 0.0, 0.5]);
+        assert_eq!(
+            rgba(fade.color_at(start + 3 * second)),
+            [1.0, 0.5, 0.0, 1.0]
+        );
+
+        // the background fades separately
+        assert_eq!(rgba(comp.GetCurrentFadeColor(true)), [0.0; 4]);
+
+        // fading back starts from wherever the previous fade was
+        fade.restart(Default::default(), 2 * second, start + second);
+        assert_eq!(rgba(fade.color_at(start + second)), [0.5, 0.25, 0.0, 0.5]);
+        assert_eq!(
+            rgba(fade.color_at(start + 2 * second)),
+            [0.25, 0.125, 0.0, 0.25]
+        );
+    }
+
+    #[test]
+    fn fade_grid() {
+        let Fixture { comp, .. } = Fixture::new();
+        assert_eq!(comp.GetCurrentGridAlpha(), 0.0);
+
+        comp.FadeGrid(2.0, true);
+        let mut fade = *comp.grid_fade.lock().unwrap();
+        let start = fade.start;
+        let second = Duration::from_secs(1);
+        assert_eq!(fade.color_at(start + second).a, 0.5);
+        assert_eq!(fade.color_at(start + 3 * second).a, 1.0);
+
+        fade.restart(vr::HmdColor_t::default(), 4 * second, start + 3 * second);
+        assert_eq!(fade.color_at(start + 4 * second).a, 0.75);
+        assert_eq!(fade.color_at(start + 7 * second).a, 0.0);
+
+        comp.FadeGrid(0.0, true);
+        assert_eq!(comp.GetCurrentGridAlpha(), 1.0);
+        comp.FadeGrid(0.0, false);
+        assert_eq!(comp.GetCurrentGridAlpha(), 0.0);
+    }
+
     #[test]
     fn allow_flipped_bounds() {
         let Fixture { comp, .. } = Fixture::new();
@@ -1635,6 +2499,7 @@ mod tests {
         assert_eq!(f.wait_get_poses(), None);
         assert_eq!(f.submit(vr::EVREye::Left), None);
         assert_eq!(f.submit(vr::EVREye::Right), None);
+        assert_eq!(f.wait_get_poses(), None);
 
         let mut timing = MaybeUninit::new(vr::Compositor_FrameTiming::default());
         unsafe {
@@ -1654,6 +2519,247 @@ mod tests {
         assert!(!f.comp.GetFrameTiming(timing.as_mut_ptr(), 1));
     }
 
+    #[test]
+    fn mirror_texture() {
+        let f = Fixture::new();
+        assert_eq!(
+            f.comp.mirror_texture(vr::EVREye::Left),
+            Err(vr::EVRCompositorError::RequestFailed)
+        );
+
+        f.ensure_real_session(false);
+        let copied = || {
+            let data = f.comp.openxr.session_data.get();
+            let lock = data.comp_data.0.lock().unwrap();
+            let DynFrameController::Fake(ctrl) = lock.as_ref().unwrap() else {
+                panic!("Frame controller was not set up or not faked!");
+            };
+            ctrl.backend.snapshot(vr::EVREye::Left as usize).is_some()
+        };
+        // Nothing is copied until something looks at the submitted textures
+        assert!(!copied());
+
+        let texture = FakeGraphicsData::texture(&f.vk);
+        let mirror_color = |handle: u64| {
+            TEXTURE_COLORS.with_borrow(|colors| colors.get(&(handle as usize)).copied())
+        };
+        let submit_color = |color| {
+            FakeGraphicsData::set_texture_color(&texture, color);
+            assert_eq!(f.wait_get_poses(), None);
+            assert_eq!(f.submit(vr::EVREye::Left), None);
+            assert_eq!(f.submit(vr::EVREye::Right), None);
+        };
+
+        FakeGraphicsData::set_texture_color(&texture, [1; 4]);
+        let (ty, handle) = f.comp.mirror_texture(vr::EVREye::Left).unwrap();
+        assert_eq!(ty, vr::ETextureType::Reserved);
+        assert_ne!(handle, 0);
+        // The mirror is our own copy, not the app's texture
+        assert_ne!(handle, texture.handle as u64);
+        assert_eq!(mirror_color(handle), Some([1; 4]));
+        assert_eq!(f.comp.mirror_texture(vr::EVREye::Left).unwrap().1, handle);
+
+        // Mirrors follow what's submitted until they're released
+        submit_color([2; 4]);
+        assert_eq!(mirror_color(handle), Some([2; 4]));
+        f.comp.release_mirror_texture(handle);
+        submit_color([3; 4]);
+        assert_eq!(mirror_color(handle), Some([2; 4]));
+
+        // Only OpenGL apps can use the GL mirror
+        let mut id = 0;
+        let mut shared = std::ptr::null_mut();
+        assert_eq!(
+            f.comp
+                .GetMirrorTextureGL(vr::EVREye::Right, &mut id, &mut shared),
+            vr::EVRCompositorError::SharedTexturesNotSupported
+        );
+        assert!(shared.is_null());
+    }
+
+    #[test]
+    fn direct3d_texture_is_invalid() {
+        let f = Fixture::new();
+        assert_eq!(f.wait_get_poses(), None);
+        let mut d3d_texture = 0u8;
+        for ty in [vr::ETextureType::DirectX, vr::ETextureType::DirectX12] {
+            let texture = vr::Texture_t {
+                eType: ty,
+                handle: (&raw mut d3d_texture).cast(),
+                eColorSpace: vr::EColorSpace::Auto,
+            };
+            assert_eq!(
+                f.comp.Submit(
+                    vr::EVREye::Left,
+                    &texture,
+                    std::ptr::null(),
+                    vr::EVRSubmitFlags::Default
+                ),
+                InvalidTexture
+            );
+        }
+        assert!(!f.comp.openxr.session_data.get().is_real_session());
+    }
+
+    #[test]
+    fn frame_timing_history() {
+        let f = Fixture::new();
+        f.ensure_real_session(false);
+        let session = f.comp.openxr.session_data.get().session.as_raw();
+        let period = std::time::Duration::from_millis(50);
+        fakexr::set_display_period(session, xr::Duration::from_nanos(period.as_nanos() as i64));
+
+        let frame = || {
+            assert_eq!(f.wait_get_poses(), None);
+            assert_eq!(f.submit(vr::EVREye::Left), None);
+            assert_eq!(f.submit(vr::EVREye::Right), None);
+        };
+        let timing = |frames_ago| {
+            let mut timing = vr::Compositor_FrameTiming {
+                m_nSize: std::mem::size_of::<vr::Compositor_FrameTiming>() as u32,
+                ..Default::default()
+            };
+            f.comp
+                .GetFrameTiming(&mut timing, frames_ago)
+                .then_some(timing)
+        };
+
+        frame();
+        frame();
+        std::thread::sleep(period * 3);
+        frame();
+
+        let slow = timing(0).unwrap();
+        let fast = timing(1).unwrap();
+
+        // The frame still in flight hasn't been presented yet.
+        assert_eq!(slow.m_nNumFramePresents, 0);
+        assert_eq!(fast.m_nNumFramePresents, 1);
+        assert_eq!(slow.m_nFrameIndex, fast.m_nFrameIndex + 1);
+        assert!(slow.m_flSystemTimeInSeconds > fast.m_flSystemTimeInSeconds);
+        assert!(fast.m_flNewFrameReadyMs > 0.0);
+        assert!(fast.m_flCompositorUpdateEndMs >= fast.m_flCompositorUpdateStartMs);
+
+        assert_eq!(fast.m_nReprojectionFlags, 0);
+        assert_eq!(fast.m_nNumDroppedFrames, 0);
+        assert_eq!(
+            slow.m_nReprojectionFlags,
+            vr::VRCompositor_ReprojectionReason_Cpu
+        );
+        assert!(
+            slow.m_nNumDroppedFrames >= 2,
+            "{}",
+            slow.m_nNumDroppedFrames
+        );
+        assert!(slow.m_flClientFrameIntervalMs >= 150.0);
+
+        assert!(timing(FRAME_TIMING_HISTORY as u32).is_none());
+
+        // Past frames keep whether the app asked for interleaved reprojection back then.
+        let throttled = |timing: vr::Compositor_FrameTiming| {
+            (timing.m_nReprojectionFlags & vr::VRCompositor_ThrottleMask) >> 8
+        };
+        f.comp.ForceInterleavedReprojectionOn(true);
+        frame();
+        f.comp.ForceInterleavedReprojectionOn(false);
+        frame();
+        assert_eq!(throttled(timing(1).unwrap()), 1);
+        assert_eq!(throttled(timing(0).unwrap()), 0);
+    }
+
+    #[test]
+    fn frame_time_remaining() {
+        let start = Instant::now();
+        let frame = FrameTiming {
+            index: 0,
+            start,
+            interval: None,
+            display_period: Some(Duration::from_millis(10)),
+            submitted: None,
+            presented: None,
+            throttled: false,
+        };
+        let remaining = |ms| {
+            frame
+                .time_remaining(start + Duration::from_millis(ms))
+                .unwrap()
+        };
+        assert!((remaining(4) - 0.006).abs() < 1e-6);
+        assert!((remaining(15) + 0.005).abs() < 1e-6);
+
+        let f = Fixture::new();
+        f.ensure_real_session(false);
+        f.comp.PostPresentHandoff();
+        assert_eq!(f.comp.GetFrameTimeRemaining(), 0.0);
+
+        let session = f.comp.openxr.session_data.get().session.as_raw();
+        let period = Duration::from_millis(50);
+        fakexr::set_display_period(session, xr::Duration::from_nanos(period.as_nanos() as i64));
+        assert_eq!(f.wait_get_poses(), None);
+        let remaining = f.comp.GetFrameTimeRemaining();
+        assert!(remaining > 0.0 && remaining <= 0.05, "{remaining}");
+
+        assert_eq!(f.submit(vr::EVREye::Left), None);
+        assert_eq!(f.submit(vr::EVREye::Right), None);
+        f.comp.PostPresentHandoff();
+        assert_eq!(f.comp.GetFrameTimeRemaining(), 0.0);
+    }
+
+    #[test]
+    fn cumulative_stats() {
+        let f = Fixture::new();
+        let get_stats = |size: usize| {
+            let mut stats: vr::Compositor_CumulativeStats = unsafe { std::mem::zeroed() };
+            stats.m_nNumLoading = 7;
+            f.comp.GetCumulativeStats(&mut stats, size as u32);
+            stats
+        };
+        let size = std::mem::size_of::<vr::Compositor_CumulativeStats>();
+
+        f.comp.SetExplicitTimingMode(
+            vr::EVRCompositorTimingMode::Explicit_ApplicationPerformsPostPresentHandoff,
+        );
+        f.ensure_real_session(true);
+        *f.comp.metrics.stats.lock().unwrap() = CumulativeStats {
+            presents: 10,
+            dropped: 2,
+            reprojected: 3,
+        };
+        let stats = get_stats(size);
+        assert_eq!(stats.m_nPid, std::process::id());
+        assert_eq!(stats.m_nNumFramePresents, 10);
+        assert_eq!(stats.m_nNumDroppedFrames, 2);
+        assert_eq!(stats.m_nNumReprojectedFrames, 3);
+        assert_eq!(stats.m_nNumLoading, 0);
+
+        // too small to hold the counts
+        assert_eq!(get_stats(4).m_nNumLoading, 7);
+
+        // waiting again without submitting drops the frame
+        assert_eq!(f.wait_get_poses(), None);
+        assert_eq!(f.wait_get_poses(), None);
+        assert_eq!(get_stats(size).m_nNumDroppedFrames, 3);
+
+        assert_eq!(f.comp.SubmitExplicitTimingData(), None);
+        assert_eq!(f.submit(vr::EVREye::Left), None);
+        assert_eq!(f.submit(vr::EVREye::Right), None);
+        f.comp.PostPresentHandoff();
+        // Missed vsyncs depend on how long the test takes, but are both presented and reprojected.
+        let stats = get_stats(size);
+        assert_eq!(stats.m_nNumFramePresents - stats.m_nNumReprojectedFrames, 8);
+
+        f.comp.openxr.restart_session();
+        let stats = get_stats(size);
+        assert_eq!(
+            (
+                stats.m_nNumFramePresents,
+                stats.m_nNumDroppedFrames,
+                stats.m_nNumReprojectedFrames
+            ),
+            (0, 0, 0)
+        );
+    }
+
     #[test]
     fn zero_dims_texture() {
         let f = Fixture::new();
@@ -1704,6 +2810,138 @@ mod tests {
         }
     }
 
+    #[test]
+    fn bring_to_front() {
+        let f = Fixture::new();
+        f.ensure_real_session(false);
+        let should_render = || {
+            let data = f.comp.openxr.session_data.get();
+            let lock = data.comp_data.0.lock().unwrap();
+            let DynFrameController::Fake(ctrl) = lock.as_ref().unwrap() else {
+                panic!("Frame controller was not set up or not faked!");
+            };
+            ctrl.should_render
+        };
+        while !should_render() {
+            assert_eq!(f.submit(vr::EVREye::Left), None);
+            assert_eq!(f.submit(vr::EVREye::Right), None);
+            assert_eq!(f.wait_get_poses(), None);
+        }
+
+        f.comp.SuspendRendering(true);
+        assert_eq!(f.wait_get_poses(), None);
+        assert!(!should_render());
+
+        f.comp.CompositorBringToFront();
+        assert_eq!(f.wait_get_poses(), None);
+        assert!(should_render());
+    }
+
+    #[test]
+    fn clear_last_submitted_frame() {
+        let f = Fixture::new();
+        f.ensure_real_session(false);
+        assert_eq!(f.submit(vr::EVREye::Left), None);
+        assert_eq!(f.submit(vr::EVREye::Right), None);
+        assert!(f.comp.mirror_texture(vr::EVREye::Left).is_ok());
+
+        f.comp.ClearLastSubmittedFrame();
+        {
+            let data = f.comp.openxr.session_data.get();
+            let lock = data.comp_data.0.lock().unwrap();
+            let DynFrameController::Fake(ctrl) = lock.as_ref().unwrap() else {
+                panic!("Frame controller was not set up or not faked!");
+            };
+            assert!(ctrl.eyes_submitted.iter().all(Option::is_none));
+        }
+        assert_eq!(
+            f.comp.mirror_texture(vr::EVREye::Left),
+            Err(vr::EVRCompositorError::RequestFailed)
+        );
+
+        // the cleared frame can still be presented
+        f.comp.PostPresentHandoff();
+        assert_eq!(f.wait_get_poses(), None);
+    }
+
+    #[test]
+    fn per_eye_bounds() {
+        let f = Fixture::new();
+        f.ensure_real_session(false);
+
+        let submit = |eye, bounds: vr::VRTextureBounds_t| {
+            f.comp.Submit(
+                eye,
+                &FakeGraphicsData::texture(&f.vk),
+                &bounds,
+                vr::EVRSubmitFlags::Default,
+            )
+        };
+        fn with_ctrl<R>(
+            f: &Fixture,
+            func: impl FnOnce(&FrameController<FakeGraphicsData>) -> R,
+        ) -> R {
+            let data = f.comp.openxr.session_data.get();
+            let lock = data.comp_data.0.lock().unwrap();
+            let DynFrameController::Fake(ctrl) = lock.as_ref().unwrap() else {
+                panic!("Frame controller was not set up or not faked!");
+            };
+            func(ctrl)
+        }
+
+        loop {
+            assert_eq!(f.wait_get_poses(), None);
+            if with_ctrl(&f, |ctrl| ctrl.should_render) {
+                break;
+            }
+            assert_eq!(f.submit(vr::EVREye::Left), None);
+            assert_eq!(f.submit(vr::EVREye::Right), None);
+        }
+
+        // The right eye needs a bigger swapchain than the left, which has already been copied.
+        SWAPCHAIN_WIDTH.set(20);
+        let left = vr::VRTextureBounds_t {
+            uMin: 0.0,
+            uMax: 0.5,
+            vMin: 0.0,
+            vMax: 1.0,
+        };
+        let right = vr::VRTextureBounds_t {
+            uMin: 1.0,
+            uMax: 0.0,
+            vMin: 1.0,
+            vMax: 0.0,
+        };
+        assert_eq!(submit(vr::EVREye::Left, left), None);
+        assert_eq!(submit(vr::EVREye::Right, right), None);
+
+        with_ctrl(&f, |ctrl| {
+            assert_eq!(ctrl.swapchain_data.as_ref().unwrap().info.width, 20);
+
+            let left = ctrl.eyes_submitted[0].expect("left eye was dropped");
+            assert_eq!(
+                left.extent,
+                xr::Extent2Di {
+                    width: 10,
+                    height: 10
+                }
+            );
+            assert!(!left.flip_vertically);
+            assert!(!left.flip_horizontally);
+
+            let right = ctrl.eyes_submitted[1].unwrap();
+            assert_eq!(
+                right.extent,
+                xr::Extent2Di {
+                    width: 20,
+                    height: 10
+                }
+            );
+            assert!(right.flip_vertically);
+            assert!(right.flip_horizontally);
+        });
+    }
+
     #[test]
     fn vulkan_extensions() {
         let f = Fixture::new();
@@ -1724,9 +2962,10 @@ mod tests {
             let data = unsafe { CStr::from_ptr(exts.as_ptr()) };
             assert_eq!(data, c"VK_foo VK_bar", "{dbg}");
 
-            // Undersized buffer - should not crash
+            // Undersized buffer - should not crash, and still report the required size
             let mut exts = vec![0];
-            func(exts.as_mut_ptr(), exts.len() as u32);
+            assert_eq!(func(exts.as_mut_ptr(), exts.len() as u32), size, "{dbg}");
+            assert_eq!(exts[0], 0, "{dbg}");
         }
 
         tst(
@@ -1785,6 +3024,58 @@ mod tests {
         f.check_frame_state(fakexr::FrameState::Ended);
     }
 
+    #[test]
+    fn explicit_timing_misordered_calls() {
+        let f = Fixture::new();
+        f.ensure_real_session(false);
+        f.comp.SetExplicitTimingMode(
+            vr::EVRCompositorTimingMode::Explicit_ApplicationPerformsPostPresentHandoff,
+        );
+        f.comp.PostPresentHandoff();
+        f.check_frame_state(fakexr::FrameState::Ended);
+
+        // Explicit timing data has to come after WaitGetPoses.
+        assert_eq!(f.comp.SubmitExplicitTimingData(), RequestFailed);
+        f.check_frame_state(fakexr::FrameState::Ended);
+
+        // Submitting without explicit timing data begins the frame anyway.
+        assert_eq!(f.wait_get_poses(), None);
+        f.check_frame_state(fakexr::FrameState::Waited);
+        assert_eq!(f.submit(vr::EVREye::Left), None);
+        f.check_frame_state(fakexr::FrameState::Begun);
+        assert_eq!(f.comp.SubmitExplicitTimingData(), None);
+        assert_eq!(f.submit(vr::EVREye::Right), None);
+        f.comp.PostPresentHandoff();
+        f.check_frame_state(fakexr::FrameState::Ended);
+    }
+
+    #[test]
+    fn explicit_timing_mode_switches() {
+        let f = Fixture::new();
+        f.ensure_real_session(false);
+        f.check_frame_state(fakexr::FrameState::Begun);
+        assert_eq!(f.comp.SubmitExplicitTimingData(), RequestFailed);
+
+        // The runtime still ends frames, but the app begins them.
+        f.comp.SetExplicitTimingMode(
+            vr::EVRCompositorTimingMode::Explicit_RuntimePerformsPostPresentHandoff,
+        );
+        assert_eq!(f.wait_get_poses(), None);
+        f.check_frame_state(fakexr::FrameState::Waited);
+        assert_eq!(f.comp.SubmitExplicitTimingData(), None);
+        f.check_frame_state(fakexr::FrameState::Begun);
+        assert_eq!(f.submit(vr::EVREye::Left), None);
+        assert_eq!(f.submit(vr::EVREye::Right), None);
+        assert_eq!(f.wait_get_poses(), None);
+        f.check_frame_state(fakexr::FrameState::Waited);
+
+        assert_eq!(f.comp.SubmitExplicitTimingData(), None);
+        f.comp
+            .SetExplicitTimingMode(vr::EVRCompositorTimingMode::Implicit);
+        assert_eq!(f.wait_get_poses(), None);
+        f.check_frame_state(fakexr::FrameState::Begun);
+    }
+
     #[test]
     fn explicit_timing_no_submit() {
         let f = Fixture::new();
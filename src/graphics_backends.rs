@@ -1,4 +1,5 @@
 mod gl;
+mod shaders;
 mod vulkan;
 
 use derive_more::{From, TryInto};
@@ -14,17 +15,27 @@ pub trait GraphicsBackend: Into<SupportedBackend> {
 
     fn to_nice_format(format: <Self::Api as xr::Graphics>::Format) -> Self::NiceFormat;
 
+    /// An 8 bit format to use in place of a 10 bit or floating point (HDR) format that the
+    /// runtime doesn't support. None if the format isn't an HDR one.
+    fn sdr_fallback_format(
+        format: <Self::Api as xr::Graphics>::Format,
+    ) -> Option<<Self::Api as xr::Graphics>::Format>;
+
     fn session_create_info(&self) -> <Self::Api as xr::Graphics>::SessionCreateInfo;
 
     /// Returns None if the texture is invalid.
     fn get_texture(texture: &vr::Texture_t) -> Option<Self::OpenVrTexture>;
 
+    /// The native handle of an app texture: a texture name for OpenGL, a VkImage for Vulkan.
+    fn native_handle(texture: Self::OpenVrTexture) -> u64;
+
+    /// Returns None if the texture's format isn't supported.
     fn swapchain_info_for_texture(
         &self,
         texture: Self::OpenVrTexture,
         bounds: vr::VRTextureBounds_t,
         color_space: vr::EColorSpace,
-    ) -> xr::SwapchainCreateInfo<Self::Api>;
+    ) -> Option<xr::SwapchainCreateInfo<Self::Api>>;
 
     fn store_swapchain_images(
         &mut self,
@@ -42,12 +53,52 @@ pub trait GraphicsBackend: Into<SupportedBackend> {
         submit_flags: vr::EVRSubmitFlags,
     ) -> xr::Extent2Di;
 
+    /// `texture_layer` is the array layer of the app's texture to copy from, and `layer` is the
+    /// array layer (or cube face) of the swapchain image to copy to.
     fn copy_overlay_to_swapchain(
         &mut self,
         texture: Self::OpenVrTexture,
+        texture_layer: u32,
+        color_space: vr::EColorSpace,
         bounds: vr::VRTextureBounds_t,
         image_index: usize,
+        layer: u32,
     ) -> xr::Extent2Di;
+
+    /// Reads the bounded region of an app texture back to the CPU as tightly packed RGBA8.
+    /// Returns None if the texture's format can't be read back.
+    fn read_texture(
+        &self,
+        texture: Self::OpenVrTexture,
+        bounds: vr::VRTextureBounds_t,
+    ) -> Option<(xr::Extent2Di, Vec<u8>)>;
+
+    /// Copies a layer of an app texture into a single sampled texture of our own, replacing the
+    /// copy previously kept in `slot`. Apps may destroy or draw over their textures as soon as
+    /// they've been submitted, so anything looking at a texture afterwards uses the copy.
+    /// Returns None if the texture can't be copied.
+    fn snapshot_texture(
+        &mut self,
+        slot: usize,
+        texture: Self::OpenVrTexture,
+        texture_layer: u32,
+    ) -> Option<Self::OpenVrTexture>;
+
+    /// The copy last made with [`snapshot_texture`](Self::snapshot_texture) in `slot`, which
+    /// covers the whole texture it was made from.
+    fn snapshot(&self, slot: usize) -> Option<Self::OpenVrTexture>;
+
+    /// Swapchain info for an overlay drawn by us rather than the app, whose contents are
+    /// uploaded from sRGB RGBA8 pixels.
+    fn swapchain_info_for_pixels(
+        &self,
+        extent: xr::Extent2Di,
+    ) -> xr::SwapchainCreateInfo<Self::Api>;
+
+    /// Uploads tightly packed RGBA8 pixels, starting at the top row, to a single layer swapchain
+    /// image. The app's queue may be used for this, so callers hold
+    /// [`CompositorSessionData::lock_queue`](crate::compositor::CompositorSessionData::lock_queue).
+    fn upload_pixels(&mut self, pixels: &[u8], extent: xr::Extent2Di, image_index: usize);
 }
 
 #[derive(macros::Backends, TryInto, From)]
@@ -108,18 +159,55 @@ pub trait WithAnyGraphicsOwned<G>: WithAnyGraphicsParams {
 }
 
 impl SupportedBackend {
-    pub fn new(texture: &vr::Texture_t, _bounds: vr::VRTextureBounds_t) -> Self {
+    /// Returns None if there's no backend for the texture's type.
+    pub fn new(texture: &vr::Texture_t, _bounds: vr::VRTextureBounds_t) -> Option<Self> {
         match texture.eType {
             vr::ETextureType::Vulkan => {
-                let vk_texture = unsafe { &*(texture.handle as *const vr::VRVulkanTextureData_t) };
-                Self::Vulkan(VulkanData::new(vk_texture))
+                let vk_texture =
+                    unsafe { (texture.handle as *const vr::VRVulkanTextureData_t).as_ref() }?;
+                Some(Self::Vulkan(VulkanData::new(vk_texture)))
+            }
+            vr::ETextureType::OpenGL => Some(Self::OpenGL(GlData::new())),
+            // xrizer only runs natively on Linux, where there is no D3D12 runtime (or OpenXR
+            // D3D12 binding) to hand textures to. Under Proton, Wine's vrclient translates
+            // D3D11/D3D12 textures into Vulkan ones before they reach us, so seeing one here
+            // means the app is bypassing that layer.
+            vr::ETextureType::DirectX | vr::ETextureType::DirectX12 => {
+                crate::warn_once!(
+                    "Direct3D textures ({:?}) must be translated to Vulkan by Wine's vrclient",
+                    texture.eType
+                );
+                None
             }
-            vr::ETextureType::OpenGL => Self::OpenGL(GlData::new()),
             #[cfg(test)]
-            vr::ETextureType::Reserved => {
-                Self::Fake(crate::compositor::FakeGraphicsData::new(texture))
+            vr::ETextureType::Reserved => Some(Self::Fake(
+                crate::compositor::FakeGraphicsData::new(texture),
+            )),
+            other => {
+                crate::warn_once!("Unsupported texture type: {other:?}");
+                None
             }
-            other => panic!("Unsupported texture type: {other:?}"),
         }
     }
+
+    /// The type of texture this backend was created for.
+    pub fn texture_type(&self) -> vr::ETextureType {
+        match self {
+            Self::Vulkan(_) => vr::ETextureType::Vulkan,
+            Self::OpenGL(_) => vr::ETextureType::OpenGL,
+            #[cfg(test)]
+            Self::Fake(_) => vr::ETextureType::Reserved,
+        }
+    }
+}
+
+/// Reverses the rows of tightly packed RGBA8 pixels. GL images start at the bottom row, while
+/// pixels going to and from apps start at the top one.
+pub fn flip_rows(pixels: &[u8], width: usize) -> Vec<u8> {
+    pixels
+        .chunks_exact(width * 4)
+        .rev()
+        .flatten()
+        .copied()
+        .collect()
 }
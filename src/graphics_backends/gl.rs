@@ -1,4 +1,4 @@
-use super::GraphicsBackend;
+use super::{flip_rows, GraphicsBackend};
 use derive_more::Deref;
 use glutin_glx_sys::{
     glx::{self, Glx},
@@ -8,8 +8,9 @@ use libc::{dlerror, dlopen, dlsym};
 use log::warn;
 use openvr as vr;
 use openxr as xr;
+use std::collections::HashSet;
 use std::ffi::{c_char, c_void, CStr, CString};
-use std::sync::{Arc, LazyLock, Once};
+use std::sync::{Arc, LazyLock, Mutex, Once};
 
 static GLX: LazyLock<Library> = LazyLock::new(|| Library::new(c"libGLX.so.0"));
 
@@ -19,6 +20,8 @@ pub struct GlData {
     format: u32,
     read_fbo: u32,
     draw_fbo: u32,
+    /// Our own textures, see [`GraphicsBackend::snapshot_texture`]. 0 if a slot is empty.
+    snapshots: Vec<gl::types::GLuint>,
 }
 
 #[derive(Deref)]
@@ -115,6 +118,76 @@ impl GlData {
             format: 0,
             read_fbo: fbos[0],
             draw_fbo: fbos[1],
+            snapshots: Vec::new(),
+        }
+    }
+}
+
+impl GlData {
+    /// Blits a region of `texture` to a layer of an array or cubemap texture.
+    fn blit_to_layer(
+        &self,
+        texture: gl::types::GLuint,
+        texture_layer: i32,
+        offset: xr::Offset2Di,
+        extent: xr::Extent2Di,
+        target: gl::types::GLuint,
+        layer: i32,
+        flip_vertically: bool,
+    ) {
+        let [dst_x0, dst_y0, dst_x1, dst_y1] = blit_dst_rect(extent, flip_vertically);
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.read_fbo);
+            if texture_layer == 0 {
+                gl::FramebufferTexture2D(
+                    gl::READ_FRAMEBUFFER,
+                    gl::COLOR_ATTACHMENT0,
+                    gl::TEXTURE_2D,
+                    texture,
+                    0,
+                );
+            } else {
+                gl::FramebufferTextureLayer(
+                    gl::READ_FRAMEBUFFER,
+                    gl::COLOR_ATTACHMENT0,
+                    texture,
+                    0,
+                    texture_layer,
+                );
+            }
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, self.draw_fbo);
+            gl::FramebufferTextureLayer(
+                gl::DRAW_FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                target,
+                0,
+                layer,
+            );
+
+            // The swapchain format already matches the encoding of the texture's data, so copy the
+            // raw values. Apps that render in sRGB commonly leave this enabled, which would make
+            // the blit encode already encoded data again.
+            let srgb_enabled = gl::IsEnabled(gl::FRAMEBUFFER_SRGB) == gl::TRUE;
+            gl::Disable(gl::FRAMEBUFFER_SRGB);
+
+            gl::BlitFramebuffer(
+                offset.x,
+                offset.y,
+                offset.x + extent.width,
+                offset.y + extent.height,
+                dst_x0,
+                dst_y0,
+                dst_x1,
+                dst_y1,
+                gl::COLOR_BUFFER_BIT,
+                gl::NEAREST,
+            );
+
+            if srgb_enabled {
+                gl::Enable(gl::FRAMEBUFFER_SRGB);
+            }
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, 0);
         }
     }
 }
@@ -128,6 +201,15 @@ impl GraphicsBackend for GlData {
         format
     }
 
+    fn sdr_fallback_format(format: u32) -> Option<u32> {
+        match format {
+            gl::RGB10_A2 => Some(gl::RGBA8),
+            // Float data is linear, so it needs to be encoded to fit in 8 bits.
+            gl::RGBA16F => Some(gl::SRGB8_ALPHA8),
+            _ => None,
+        }
+    }
+
     fn session_create_info(&self) -> <Self::Api as openxr::Graphics>::SessionCreateInfo {
         // SAFETY: SessionCreateInfo should be Copy anyway but doesn't work right
         // https://github.com/Ralith/openxrs/issues/183
@@ -139,6 +221,10 @@ impl GraphicsBackend for GlData {
         Some(texture.handle as _)
     }
 
+    fn native_handle(texture: Self::OpenVrTexture) -> u64 {
+        texture as u64
+    }
+
     #[inline]
     fn store_swapchain_images(
         &mut self,
@@ -155,34 +241,34 @@ impl GraphicsBackend for GlData {
         texture: Self::OpenVrTexture,
         bounds: vr::VRTextureBounds_t,
         color_space: vr::EColorSpace,
-    ) -> xr::SwapchainCreateInfo<Self::Api> {
+    ) -> Option<xr::SwapchainCreateInfo<Self::Api>> {
+        static UNSUPPORTED: LazyLock<Mutex<HashSet<gl::types::GLenum>>> =
+            LazyLock::new(Mutex::default);
         let mut fmt = 0;
         unsafe {
             gl::BindTexture(gl::TEXTURE_2D, texture);
             gl::GetTexLevelParameteriv(gl::TEXTURE_2D, 0, gl::TEXTURE_INTERNAL_FORMAT, &mut fmt);
         }
-        let xr::Rect2Di { extent, .. } = texture_rect_from_bounds(texture, bounds);
-
-        let fmt = match color_space {
-            vr::EColorSpace::Linear => fmt,
-            vr::EColorSpace::Gamma | vr::EColorSpace::Auto => match fmt as gl::types::GLenum {
-                gl::RGBA8 => gl::SRGB8_ALPHA8 as gl::types::GLint,
-                gl::RGB8 => gl::SRGB8 as gl::types::GLint,
-                _ => fmt,
-            },
+        let fmt = fmt as gl::types::GLenum;
+        let Some(format) = vr_format_to_xr(fmt, color_space) else {
+            if UNSUPPORTED.lock().unwrap().insert(fmt) {
+                warn!("Unsupported texture format: {fmt:#x}");
+            }
+            return None;
         };
+        let xr::Rect2Di { extent, .. } = texture_rect_from_bounds(texture, bounds);
 
-        xr::SwapchainCreateInfo {
+        Some(xr::SwapchainCreateInfo {
             create_flags: xr::SwapchainCreateFlags::EMPTY,
             usage_flags: xr::SwapchainUsageFlags::TRANSFER_DST,
-            format: fmt as u32,
+            format,
             sample_count: 1,
             width: extent.width as u32,
             height: extent.height as u32,
             face_count: 1,
             array_size: 2,
             mip_count: 1,
-        }
+        })
     }
 
     fn copy_texture_to_swapchain(
@@ -226,40 +312,16 @@ impl GraphicsBackend for GlData {
                 );
             }
         } else {
-            unsafe {
-                gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.read_fbo);
-                gl::FramebufferTexture2D(
-                    gl::READ_FRAMEBUFFER,
-                    gl::COLOR_ATTACHMENT0,
-                    gl::TEXTURE_2D,
-                    texture,
-                    0,
-                );
-                gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, self.draw_fbo);
-                gl::FramebufferTextureLayer(
-                    gl::DRAW_FRAMEBUFFER,
-                    gl::COLOR_ATTACHMENT0,
-                    swapchain_texture,
-                    0,
-                    eye as i32,
-                );
-
-                gl::BlitFramebuffer(
-                    offset.x,
-                    offset.y,
-                    offset.x + extent.width,
-                    offset.y + extent.height,
-                    0,
-                    0,
-                    extent.width,
-                    extent.height,
-                    gl::COLOR_BUFFER_BIT,
-                    gl::NEAREST,
-                );
-
-                gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
-                gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, 0);
-            }
+            // Flipped eyes are handled by the projection layer's fov.
+            self.blit_to_layer(
+                texture,
+                0,
+                offset,
+                extent,
+                swapchain_texture,
+                eye as i32,
+                false,
+            );
         }
 
         extent
@@ -268,20 +330,175 @@ impl GraphicsBackend for GlData {
     fn copy_overlay_to_swapchain(
         &mut self,
         texture: Self::OpenVrTexture,
+        texture_layer: u32,
+        _color_space: openvr::EColorSpace,
         bounds: openvr::VRTextureBounds_t,
         image_index: usize,
+        layer: u32,
     ) -> openxr::Extent2Di {
-        self.copy_texture_to_swapchain(
-            vr::EVREye::Left,
+        // Overlay swapchains may be cubemaps, so we can't use CopyImageSubData with a fixed target.
+        let xr::Rect2Di { extent, offset } = texture_rect_from_bounds(texture, bounds);
+        self.blit_to_layer(
             texture,
-            vr::EColorSpace::Auto,
-            bounds,
-            image_index,
-            vr::EVRSubmitFlags::Default,
-        )
+            texture_layer as i32,
+            offset,
+            extent,
+            self.images[image_index],
+            layer as i32,
+            bounds.vertically_flipped(),
+        );
+        extent
+    }
+
+    fn read_texture(
+        &self,
+        texture: Self::OpenVrTexture,
+        bounds: vr::VRTextureBounds_t,
+    ) -> Option<(xr::Extent2Di, Vec<u8>)> {
+        let xr::Rect2Di { extent, offset } = texture_rect_from_bounds(texture, bounds);
+        let mut pixels = vec![0; extent.width as usize * extent.height as usize * 4];
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.read_fbo);
+            gl::FramebufferTexture2D(
+                gl::READ_FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                texture,
+                0,
+            );
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::ReadPixels(
+                offset.x,
+                offset.y,
+                extent.width,
+                extent.height,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_mut_ptr().cast(),
+            );
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+        }
+
+        Some((extent, pixels))
+    }
+
+    fn snapshot_texture(
+        &mut self,
+        slot: usize,
+        texture: Self::OpenVrTexture,
+        texture_layer: u32,
+    ) -> Option<Self::OpenVrTexture> {
+        let target = if texture_layer == 0 {
+            gl::TEXTURE_2D
+        } else {
+            gl::TEXTURE_2D_ARRAY
+        };
+        let [width, height, format] = level_parameters(target, texture);
+        if width == 0 || height == 0 {
+            return None;
+        }
+        if self.snapshots.len() <= slot {
+            self.snapshots.resize(slot + 1, 0);
+        }
+
+        let snapshot = &mut self.snapshots[slot];
+        if *snapshot == 0 || level_parameters(gl::TEXTURE_2D, *snapshot) != [width, height, format]
+        {
+            unsafe {
+                if *snapshot != 0 {
+                    gl::DeleteTextures(1, snapshot);
+                }
+                gl::GenTextures(1, snapshot);
+                gl::BindTexture(gl::TEXTURE_2D, *snapshot);
+                gl::TexStorage2D(gl::TEXTURE_2D, 1, format as _, width, height);
+                gl::BindTexture(gl::TEXTURE_2D, 0);
+            }
+        }
+        unsafe {
+            gl::CopyImageSubData(
+                texture,
+                target,
+                0, // level
+                0, // x
+                0, // y
+                texture_layer as i32,
+                *snapshot,
+                gl::TEXTURE_2D,
+                0, // level
+                0, // x
+                0, // y
+                0, // z
+                width,
+                height,
+                1,
+            );
+        }
+        Some(*snapshot)
+    }
+
+    fn snapshot(&self, slot: usize) -> Option<Self::OpenVrTexture> {
+        self.snapshots
+            .get(slot)
+            .copied()
+            .filter(|texture| *texture != 0)
+    }
+
+    fn swapchain_info_for_pixels(
+        &self,
+        extent: xr::Extent2Di,
+    ) -> xr::SwapchainCreateInfo<Self::Api> {
+        xr::SwapchainCreateInfo {
+            create_flags: xr::SwapchainCreateFlags::EMPTY,
+            usage_flags: xr::SwapchainUsageFlags::TRANSFER_DST,
+            format: gl::SRGB8_ALPHA8,
+            sample_count: 1,
+            width: extent.width as u32,
+            height: extent.height as u32,
+            face_count: 1,
+            array_size: 1,
+            mip_count: 1,
+        }
+    }
+
+    fn upload_pixels(&mut self, pixels: &[u8], extent: xr::Extent2Di, image_index: usize) {
+        let flipped = flip_rows(pixels, extent.width as usize);
+        unsafe {
+            let mut unpack_buffer = 0;
+            gl::GetIntegerv(gl::PIXEL_UNPACK_BUFFER_BINDING, &mut unpack_buffer);
+            gl::BindBuffer(gl::PIXEL_UNPACK_BUFFER, 0);
+            gl::BindTexture(gl::TEXTURE_2D, self.images[image_index]);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::PixelStorei(gl::UNPACK_ROW_LENGTH, 0);
+            gl::TexSubImage2D(
+                gl::TEXTURE_2D,
+                0,
+                0,
+                0,
+                extent.width,
+                extent.height,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                flipped.as_ptr().cast(),
+            );
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+            gl::BindBuffer(gl::PIXEL_UNPACK_BUFFER, unpack_buffer as u32);
+        }
     }
 }
 
+/// The width, height and internal format of the first level of a texture.
+fn level_parameters(target: gl::types::GLenum, texture: gl::types::GLuint) -> [i32; 3] {
+    let [mut width, mut height, mut format] = [0; 3];
+    unsafe {
+        gl::BindTexture(target, texture);
+        gl::GetTexLevelParameteriv(target, 0, gl::TEXTURE_WIDTH, &mut width);
+        gl::GetTexLevelParameteriv(target, 0, gl::TEXTURE_HEIGHT, &mut height);
+        gl::GetTexLevelParameteriv(target, 0, gl::TEXTURE_INTERNAL_FORMAT, &mut format);
+        gl::BindTexture(target, 0);
+    }
+    [width, height, format]
+}
+
 fn texture_rect_from_bounds(
     texture: glx::types::GLuint,
     bounds: vr::VRTextureBounds_t,
@@ -310,6 +527,36 @@ fn texture_rect_from_bounds(
     }
 }
 
+/// The swapchain format for a texture's internal format, or None if it isn't supported. Unorm
+/// textures in gamma space get an sRGB swapchain, so the runtime decodes them instead of showing
+/// them too bright, and sRGB textures in linear space get a unorm one, like on Vulkan.
+fn vr_format_to_xr(
+    internal_format: gl::types::GLenum,
+    color_space: vr::EColorSpace,
+) -> Option<gl::types::GLenum> {
+    let gamma = color_space != vr::EColorSpace::Linear;
+    Some(match internal_format {
+        // Unsized formats are what the app asked for, drivers store them as 8 bits per channel.
+        gl::RGBA8 | gl::RGBA | gl::SRGB8_ALPHA8 if gamma => gl::SRGB8_ALPHA8,
+        gl::RGB8 | gl::RGB | gl::SRGB8 if gamma => gl::SRGB8,
+        gl::RGBA8 | gl::RGBA | gl::SRGB8_ALPHA8 => gl::RGBA8,
+        gl::RGB8 | gl::RGB | gl::SRGB8 => gl::RGB8,
+        // There are no sRGB variants of these.
+        gl::RGB10_A2 | gl::RGBA16F => internal_format,
+        _ => return None,
+    })
+}
+
+/// The destination rectangle of a blit, as x0, y0, x1, y1. Quad layers can't be flipped like
+/// projection layers, so flipped overlay bounds are handled by mirroring the blit.
+fn blit_dst_rect(extent: xr::Extent2Di, flip_vertically: bool) -> [i32; 4] {
+    if flip_vertically {
+        [0, extent.height, extent.width, 0]
+    } else {
+        [0, 0, extent.width, extent.height]
+    }
+}
+
 extern "system" fn debug_message(
     source: gl::types::GLenum,
     ty: gl::types::GLenum,
@@ -378,3 +625,48 @@ impl Library {
         symbol
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swapchain_formats() {
+        use vr::EColorSpace::*;
+        // (texture format, gamma swapchain format, linear swapchain format)
+        let table = [
+            (gl::RGBA8, gl::SRGB8_ALPHA8, gl::RGBA8),
+            (gl::RGB8, gl::SRGB8, gl::RGB8),
+            (gl::RGBA, gl::SRGB8_ALPHA8, gl::RGBA8),
+            (gl::RGB, gl::SRGB8, gl::RGB8),
+            (gl::SRGB8_ALPHA8, gl::SRGB8_ALPHA8, gl::RGBA8),
+            (gl::SRGB8, gl::SRGB8, gl::RGB8),
+            // There are no sRGB variants of these, so they are used as is.
+            (gl::RGB10_A2, gl::RGB10_A2, gl::RGB10_A2),
+            (gl::RGBA16F, gl::RGBA16F, gl::RGBA16F),
+        ];
+        for (format, gamma, linear) in table {
+            assert_eq!(vr_format_to_xr(format, Auto), Some(gamma), "{format:#x}");
+            assert_eq!(vr_format_to_xr(format, Gamma), Some(gamma), "{format:#x}");
+            assert_eq!(vr_format_to_xr(format, Linear), Some(linear), "{format:#x}");
+        }
+        assert_eq!(vr_format_to_xr(gl::DEPTH_COMPONENT24, Auto), None);
+
+        assert_eq!(GlData::sdr_fallback_format(gl::RGB10_A2), Some(gl::RGBA8));
+        assert_eq!(
+            GlData::sdr_fallback_format(gl::RGBA16F),
+            Some(gl::SRGB8_ALPHA8)
+        );
+        assert_eq!(GlData::sdr_fallback_format(gl::RGBA8), None);
+    }
+
+    #[test]
+    fn flipped_blit_is_mirrored() {
+        let extent = xr::Extent2Di {
+            width: 20,
+            height: 10,
+        };
+        assert_eq!(blit_dst_rect(extent, false), [0, 0, 20, 10]);
+        assert_eq!(blit_dst_rect(extent, true), [0, 10, 20, 0]);
+    }
+}
@@ -0,0 +1,142 @@
+//! SPIR-V for the Vulkan backend's shaders. It's normally baked in at build time, but with the
+//! `shader-hot-reload` feature it's read from the directory in XRIZER_SHADER_DIR instead, so the
+//! shaders can be iterated on without rebuilding xrizer.
+
+use std::borrow::Cow;
+
+pub struct Shader {
+    /// File name of the compiled shader, in OUT_DIR or XRIZER_SHADER_DIR
+    name: &'static str,
+    baked: &'static [u8],
+}
+
+pub static OVERLAY_VERT: Shader = Shader {
+    name: "vert_overlay.spv",
+    baked: include_bytes!(concat!(env!("OUT_DIR"), "/vert_overlay.spv")),
+};
+
+pub static OVERLAY_FRAG: Shader = Shader {
+    name: "frag_overlay.spv",
+    baked: include_bytes!(concat!(env!("OUT_DIR"), "/frag_overlay.spv")),
+};
+
+/// Whether either of the overlay shaders changed since the overlay pipeline loaded them.
+pub fn overlay_changed() -> bool {
+    OVERLAY_VERT.changed() || OVERLAY_FRAG.changed()
+}
+
+#[cfg(not(feature = "shader-hot-reload"))]
+impl Shader {
+    pub fn spirv(&self) -> Cow<'static, [u8]> {
+        Cow::Borrowed(self.baked)
+    }
+
+    fn changed(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(feature = "shader-hot-reload")]
+impl Shader {
+    pub fn spirv(&self) -> Cow<'static, [u8]> {
+        let Some(dir) = hot_reload::dir() else {
+            return Cow::Borrowed(self.baked);
+        };
+        match hot_reload::load(&dir.join(self.name)) {
+            Ok(spirv) => {
+                log::info!("Loaded shader {} from {}", self.name, dir.display());
+                Cow::Owned(spirv)
+            }
+            Err(e) => {
+                log::warn!(
+                    "Couldn't load shader {} from {}, using the built in one: {e}",
+                    self.name,
+                    dir.display()
+                );
+                Cow::Borrowed(self.baked)
+            }
+        }
+    }
+
+    fn changed(&self) -> bool {
+        hot_reload::dir().is_some_and(|dir| hot_reload::changed(&dir.join(self.name)))
+    }
+}
+
+#[cfg(feature = "shader-hot-reload")]
+mod hot_reload {
+    use std::collections::HashMap;
+    use std::path::{Path, PathBuf};
+    use std::sync::Mutex;
+    use std::time::SystemTime;
+
+    /// Modification times of the shaders when they were last loaded
+    static LOADED: Mutex<Option<HashMap<PathBuf, SystemTime>>> = Mutex::new(None);
+
+    pub fn dir() -> Option<PathBuf> {
+        std::env::var_os("XRIZER_SHADER_DIR").map(PathBuf::from)
+    }
+
+    fn modified(path: &Path) -> std::io::Result<SystemTime> {
+        std::fs::metadata(path)?.modified()
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Vec<u8>> {
+        let modified = modified(path)?;
+        let spirv = std::fs::read(path)?;
+        LOADED
+            .lock()
+            .unwrap()
+            .get_or_insert_default()
+            .insert(path.to_path_buf(), modified);
+        Ok(spirv)
+    }
+
+    /// A shader that was never loaded counts as changed once it shows up.
+    pub fn changed(path: &Path) -> bool {
+        let Ok(modified) = modified(path) else {
+            return false;
+        };
+        LOADED
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|loaded| loaded.get(path))
+            != Some(&modified)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::fs::File;
+        use std::time::Duration;
+
+        #[test]
+        fn reload_on_modification() {
+            let dir = std::env::temp_dir().join(format!("xrizer_shaders_{}", std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            let path = dir.join("test.spv");
+            assert!(load(&path).is_err());
+            assert!(!changed(&path));
+
+            std::fs::write(&path, [1, 2, 3, 4]).unwrap();
+            assert!(changed(&path));
+            assert_eq!(load(&path).unwrap(), [1, 2, 3, 4]);
+            assert!(!changed(&path));
+
+            std::fs::write(&path, [5, 6, 7, 8]).unwrap();
+            // Don't rely on the file system's timestamp granularity
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(SystemTime::now() + Duration::from_secs(10))
+                .unwrap();
+            assert!(changed(&path));
+            assert_eq!(load(&path).unwrap(), [5, 6, 7, 8]);
+            assert!(!changed(&path));
+
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+    }
+}
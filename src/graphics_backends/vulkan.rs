@@ -1,6 +1,6 @@
-use super::GraphicsBackend;
+use super::{shaders, GraphicsBackend};
 use ash::vk::{self, Handle};
-use log::warn;
+use log::{debug, warn};
 use openvr as vr;
 use openxr as xr;
 use std::collections::HashSet;
@@ -23,6 +23,52 @@ pub struct VulkanData {
     pub queue: vk::Queue,
     pub queue_family_index: u32,
     real_data: Option<RealSessionData>,
+    /// Single sampled copy of the last multisampled overlay texture
+    overlay_resolve: Option<ResolveTarget>,
+    /// Views and framebuffers of the last copy into each overlay swapchain image. They point at
+    /// the app's image, which can be destroyed and its handle reused at any time, so they're
+    /// never reused - only kept until the image's command buffer is recorded again.
+    overlay_copies: Vec<(usize, OverlayCopyTarget)>,
+    snapshots: Vec<Option<Snapshot>>,
+}
+
+struct OverlayCopyTarget {
+    game_view: vk::ImageView,
+    /// Only views of the swapchain's first layer are kept by the pipeline, other layers (i.e.
+    /// cubemap faces) get their own.
+    layer_view: Option<vk::ImageView>,
+    framebuffer: vk::Framebuffer,
+}
+
+impl OverlayCopyTarget {
+    fn destroy(&self, device: &ash::Device) {
+        unsafe {
+            device.destroy_framebuffer(self.framebuffer, None);
+            device.destroy_image_view(self.game_view, None);
+            if let Some(view) = self.layer_view {
+                device.destroy_image_view(view, None);
+            }
+        }
+    }
+}
+
+/// Our own copy of an app texture, see [`GraphicsBackend::snapshot_texture`].
+struct Snapshot {
+    /// Describes our image the same way the app describes its textures
+    data: Box<vr::VRVulkanTextureData_t>,
+    memory: vk::DeviceMemory,
+    pool: vk::CommandPool,
+    buf: vk::CommandBuffer,
+    /// Signaled once the last copy into the image is done
+    fence: vk::Fence,
+}
+
+struct ResolveTarget {
+    image: vk::Image,
+    memory: vk::DeviceMemory,
+    format: vk::Format,
+    width: u32,
+    height: u32,
 }
 
 impl Drop for VulkanData {
@@ -30,6 +76,15 @@ impl Drop for VulkanData {
         unsafe {
             self.device.device_wait_idle().unwrap();
         }
+        if let Some(target) = self.overlay_resolve.take() {
+            self.destroy_resolve_target(target);
+        }
+        for (_, target) in self.overlay_copies.drain(..) {
+            target.destroy(&self.device);
+        }
+        for snapshot in std::mem::take(&mut self.snapshots).into_iter().flatten() {
+            self.destroy_snapshot(snapshot);
+        }
         match &self.real_data {
             // Temporary session - we created these handles, so let's destroy them
             None => unsafe {
@@ -40,11 +95,7 @@ impl Drop for VulkanData {
             Some(data) => unsafe {
                 self.device.destroy_command_pool(data.pool, None);
                 if let Some(data) = &data.overlay_pipeline {
-                    self.device.destroy_pipeline(data.pipeline, None);
-                    self.device.destroy_pipeline_layout(data.layout, None);
-                    self.device.destroy_render_pass(data.renderpass, None);
-                    self.device.destroy_descriptor_pool(data.pool, None);
-                    self.device.destroy_sampler(data.sampler, None);
+                    data.destroy(&self.device);
                 }
             },
         }
@@ -61,6 +112,18 @@ impl GraphicsBackend for VulkanData {
         vk::Format::from_raw(format as _)
     }
 
+    fn sdr_fallback_format(format: u32) -> Option<u32> {
+        let fallback = match vk::Format::from_raw(format as _) {
+            vk::Format::A2B10G10R10_UNORM_PACK32 | vk::Format::A2R10G10B10_UNORM_PACK32 => {
+                vk::Format::R8G8B8A8_UNORM
+            }
+            // Float data is linear, so it needs to be encoded to fit in 8 bits.
+            vk::Format::R16G16B16A16_SFLOAT => vk::Format::R8G8B8A8_SRGB,
+            _ => return None,
+        };
+        Some(fallback.as_raw() as u32)
+    }
+
     fn session_create_info(&self) -> <Self::Api as openxr::Graphics>::SessionCreateInfo {
         let queue_families = unsafe {
             self.instance
@@ -95,8 +158,14 @@ impl GraphicsBackend for VulkanData {
             None
         }
     }
+
+    fn native_handle(texture: Self::OpenVrTexture) -> u64 {
+        unsafe { (*texture).m_nImage }
+    }
+
     fn store_swapchain_images(&mut self, images: Vec<u64>, format: u32) {
         let images: Vec<vk::Image> = images.into_iter().map(vk::Image::from_raw).collect();
+        self.clear_overlay_copies();
         let pool = unsafe {
             self.device
                 .create_command_pool(
@@ -137,25 +206,29 @@ impl GraphicsBackend for VulkanData {
         texture: *const vr::VRVulkanTextureData_t,
         bounds: vr::VRTextureBounds_t,
         color_space: vr::EColorSpace,
-    ) -> xr::SwapchainCreateInfo<Self::Api> {
+    ) -> Option<xr::SwapchainCreateInfo<Self::Api>> {
+        static UNSUPPORTED: LazyLock<Mutex<HashSet<vk::Format>>> = LazyLock::new(Mutex::default);
         let texture = unsafe { texture.as_ref() }.unwrap();
+        let game_format = vk::Format::from_raw(texture.m_nFormat as _);
+        let Some(format) = vr_format_to_xr(game_format, color_space) else {
+            if UNSUPPORTED.lock().unwrap().insert(game_format) {
+                warn!("Unsupported texture format: {game_format:?}");
+            }
+            return None;
+        };
         let (extent, _) = texture_extent_from_bounds(texture, bounds);
-        xr::SwapchainCreateInfo {
+        Some(xr::SwapchainCreateInfo {
             create_flags: xr::SwapchainCreateFlags::EMPTY,
             usage_flags: xr::SwapchainUsageFlags::COLOR_ATTACHMENT
                 | xr::SwapchainUsageFlags::TRANSFER_DST,
-            format: get_colorspace_corrected_format(
-                vk::Format::from_raw(texture.m_nFormat as _),
-                color_space,
-            )
-            .as_raw() as _,
+            format: format.as_raw() as _,
             sample_count: texture.m_nSampleCount,
             width: extent.width,
             height: extent.height,
             face_count: 1,
             array_size: 2,
             mip_count: 1,
-        }
+        })
     }
 
     fn copy_texture_to_swapchain(
@@ -235,10 +308,9 @@ impl GraphicsBackend for VulkanData {
                 extent,
             };
 
-            let game_format = get_colorspace_corrected_format(
-                vk::Format::from_raw(texture.m_nFormat as _),
-                color_space,
-            );
+            // Unsupported formats never got a swapchain to be copied to.
+            let game_format = vk::Format::from_raw(texture.m_nFormat as _);
+            let game_format = vr_format_to_xr(game_format, color_space).unwrap_or(game_format);
             if texture.m_nSampleCount > 1 {
                 self.device.cmd_resolve_image(
                     buf,
@@ -314,12 +386,21 @@ impl GraphicsBackend for VulkanData {
     fn copy_overlay_to_swapchain(
         &mut self,
         texture: *const vr::VRVulkanTextureData_t,
+        texture_layer: u32,
+        color_space: vr::EColorSpace,
         bounds: vr::VRTextureBounds_t,
         image_index: usize,
+        layer: u32,
     ) -> xr::Extent2Di {
+        let texture = unsafe { texture.as_ref() }.unwrap();
+        // The overlay pipeline samples the texture, which can't be done with a multisampled one.
+        let (source_image, source_layer) = if texture.m_nSampleCount > 1 {
+            (self.overlay_resolve_image(texture), 0)
+        } else {
+            (vk::Image::from_raw(texture.m_nImage), texture_layer)
+        };
         let mut data = self.real_data.as_ref().unwrap();
         let buf = data.bufs[image_index];
-        let texture = unsafe { texture.as_ref() }.unwrap();
         let (extent, offset) = texture_extent_from_bounds(texture, bounds);
         let rect = vk::Rect2D {
             offset: vk::Offset2D {
@@ -332,19 +413,27 @@ impl GraphicsBackend for VulkanData {
             },
         };
         let pipeline_data = match &data.overlay_pipeline {
-            Some(d) => {
+            Some(d) if !shaders::overlay_changed() => {
                 assert_eq!(
                     d.image_format, data.format,
                     "Overlay image format unexpectedly changed"
                 );
                 d
             }
-            None => {
+            _ => {
+                if let Some(old) = &data.overlay_pipeline {
+                    debug!("overlay shaders changed, recreating pipeline");
+                    unsafe { self.device.queue_wait_idle(self.queue).unwrap() };
+                    old.destroy(&self.device);
+                    // The framebuffers use the old render pass.
+                    for (_, target) in self.overlay_copies.drain(..) {
+                        target.destroy(&self.device);
+                    }
+                }
                 self.real_data.as_mut().unwrap().overlay_pipeline = Some(PipelineData::new(
                     &self.device,
                     vk::Format::from_raw(texture.m_nFormat as _),
                     data.format,
-                    texture.m_nSampleCount,
                     &data.images,
                 ));
                 data = self.real_data.as_ref().unwrap();
@@ -352,28 +441,60 @@ impl GraphicsBackend for VulkanData {
             }
         };
 
-        let swapchain_view = pipeline_data.image_views[image_index];
-        let game_view = unsafe {
-            self.device
+        if layer != 0 {
+            // The command buffer for this image may still be in use by the previous layer's copy.
+            unsafe { self.device.queue_wait_idle(self.queue).unwrap() };
+        }
+        let game_format = vk::Format::from_raw(texture.m_nFormat as _);
+        // The last copy into this image is done by now, since its command buffer is reused.
+        self.overlay_copies.retain(|(index, target)| {
+            let done = *index == image_index && layer == 0;
+            if done {
+                target.destroy(&self.device);
+            }
+            !done
+        });
+        let create_target = || unsafe {
+            let layer_view = (layer != 0).then(|| {
+                self.device
+                    .create_image_view(
+                        &vk::ImageViewCreateInfo::default()
+                            .image(data.images[image_index])
+                            .format(data.format)
+                            .view_type(vk::ImageViewType::TYPE_2D)
+                            .components(vk::ComponentMapping::default())
+                            .subresource_range(vk::ImageSubresourceRange {
+                                aspect_mask: vk::ImageAspectFlags::COLOR,
+                                base_mip_level: 0,
+                                level_count: 1,
+                                base_array_layer: layer,
+                                layer_count: 1,
+                            }),
+                        None,
+                    )
+                    .unwrap()
+            });
+            let swapchain_view = layer_view.unwrap_or(pipeline_data.image_views[image_index]);
+            let game_view = self
+                .device
                 .create_image_view(
                     &vk::ImageViewCreateInfo::default()
-                        .image(vk::Image::from_raw(texture.m_nImage))
-                        .format(vk::Format::from_raw(texture.m_nFormat as _))
+                        .image(source_image)
+                        .format(game_format)
                         .view_type(vk::ImageViewType::TYPE_2D)
                         .components(vk::ComponentMapping::default())
                         .subresource_range(vk::ImageSubresourceRange {
                             aspect_mask: vk::ImageAspectFlags::COLOR,
                             base_mip_level: 0,
                             level_count: 1,
-                            base_array_layer: 0,
+                            base_array_layer: source_layer,
                             layer_count: 1,
                         }),
                     None,
                 )
-                .unwrap()
-        };
-        let fb = unsafe {
-            self.device
+                .unwrap();
+            let framebuffer = self
+                .device
                 .create_framebuffer(
                     &vk::FramebufferCreateInfo::default()
                         .render_pass(pipeline_data.renderpass)
@@ -383,8 +504,16 @@ impl GraphicsBackend for VulkanData {
                         .layers(1),
                     None,
                 )
-                .unwrap()
+                .unwrap();
+            OverlayCopyTarget {
+                game_view,
+                layer_view,
+                framebuffer,
+            }
         };
+        let target = create_target();
+        let (game_view, fb) = (target.game_view, target.framebuffer);
+        self.overlay_copies.push((image_index, target));
 
         unsafe {
             self.device.update_descriptor_sets(
@@ -403,6 +532,9 @@ impl GraphicsBackend for VulkanData {
         }
 
         self.record_commands(buf, || unsafe {
+            if texture.m_nSampleCount > 1 {
+                self.record_overlay_resolve(buf, texture, texture_layer, source_image);
+            }
             self.device.cmd_bind_pipeline(
                 buf,
                 vk::PipelineBindPoint::GRAPHICS,
@@ -437,6 +569,15 @@ impl GraphicsBackend for VulkanData {
                 0,
                 pc.align_to().1,
             );
+            let conversion =
+                overlay_srgb_conversion(vk::Format::from_raw(texture.m_nFormat as _), color_space);
+            self.device.cmd_push_constants(
+                buf,
+                pipeline_data.layout,
+                vk::ShaderStageFlags::FRAGMENT,
+                std::mem::size_of_val(&pc) as u32,
+                &(conversion as u32).to_ne_bytes(),
+            );
             self.device.cmd_begin_render_pass(
                 buf,
                 &vk::RenderPassBeginInfo::default()
@@ -450,18 +591,742 @@ impl GraphicsBackend for VulkanData {
             self.device.cmd_end_render_pass(buf);
         });
 
-        unsafe {
-            self.device.destroy_framebuffer(fb, None);
-            self.device.destroy_image_view(game_view, None);
-        }
-
         xr::Extent2Di {
             width: extent.width as _,
             height: extent.height as _,
         }
     }
+
+    fn read_texture(
+        &self,
+        texture: *const vr::VRVulkanTextureData_t,
+        bounds: vr::VRTextureBounds_t,
+    ) -> Option<(xr::Extent2Di, Vec<u8>)> {
+        let texture = unsafe { texture.as_ref() }?;
+        let format = vk::Format::from_raw(texture.m_nFormat as _);
+        let bgra = match format {
+            vk::Format::R8G8B8A8_UNORM | vk::Format::R8G8B8A8_SRGB => false,
+            vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB => true,
+            _ => {
+                warn!("Cannot read back overlay texture with format {format:?}");
+                return None;
+            }
+        };
+        if texture.m_nSampleCount > 1 {
+            warn!("Cannot read back multisampled overlay texture");
+            return None;
+        }
+
+        let (extent, offset) = texture_extent_from_bounds(texture, bounds);
+        let size = extent.width as u64 * extent.height as u64 * 4;
+
+        let buffer = unsafe {
+            self.device
+                .create_buffer(
+                    &vk::BufferCreateInfo::default()
+                        .size(size)
+                        .usage(vk::BufferUsageFlags::TRANSFER_DST)
+                        .sharing_mode(vk::SharingMode::EXCLUSIVE),
+                    None,
+                )
+                .unwrap()
+        };
+        let reqs = unsafe { self.device.get_buffer_memory_requirements(buffer) };
+        let Some(memory_type_index) = self.find_memory_type(
+            reqs.memory_type_bits,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        ) else {
+            warn!("No host visible memory available for overlay readback");
+            unsafe { self.device.destroy_buffer(buffer, None) };
+            return None;
+        };
+
+        let mut pixels = vec![0; size as usize];
+        unsafe {
+            let memory = self
+                .device
+                .allocate_memory(
+                    &vk::MemoryAllocateInfo::default()
+                        .allocation_size(reqs.size)
+                        .memory_type_index(memory_type_index),
+                    None,
+                )
+                .unwrap();
+            self.device.bind_buffer_memory(buffer, memory, 0).unwrap();
+
+            let pool = self
+                .device
+                .create_command_pool(
+                    &vk::CommandPoolCreateInfo::default()
+                        .flags(vk::CommandPoolCreateFlags::TRANSIENT)
+                        .queue_family_index(self.queue_family_index),
+                    None,
+                )
+                .unwrap();
+            let buf = self
+                .device
+                .allocate_command_buffers(
+                    &vk::CommandBufferAllocateInfo::default()
+                        .command_pool(pool)
+                        .level(vk::CommandBufferLevel::PRIMARY)
+                        .command_buffer_count(1),
+                )
+                .unwrap()[0];
+            let fence = self
+                .device
+                .create_fence(&vk::FenceCreateInfo::default(), None)
+                .unwrap();
+
+            self.device
+                .begin_command_buffer(
+                    buf,
+                    &vk::CommandBufferBeginInfo::default()
+                        .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT),
+                )
+                .unwrap();
+            // Like the swapchain copies, we expect the app to have left the image in TRANSFER_SRC_OPTIMAL.
+            self.device.cmd_copy_image_to_buffer(
+                buf,
+                vk::Image::from_raw(texture.m_nImage),
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                buffer,
+                &[vk::BufferImageCopy {
+                    buffer_offset: 0,
+                    buffer_row_length: 0,
+                    buffer_image_height: 0,
+                    image_subresource: vk::ImageSubresourceLayers {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        mip_level: 0,
+                        base_array_layer: 0,
+                        layer_count: 1,
+                    },
+                    image_offset: offset,
+                    image_extent: extent,
+                }],
+            );
+            self.device.end_command_buffer(buf).unwrap();
+            self.device
+                .queue_submit(
+                    self.queue,
+                    &[vk::SubmitInfo::default().command_buffers(&[buf])],
+                    fence,
+                )
+                .unwrap();
+            self.device
+                .wait_for_fences(&[fence], true, u64::MAX)
+                .unwrap();
+
+            let mapped = self
+                .device
+                .map_memory(memory, 0, size, vk::MemoryMapFlags::empty())
+                .unwrap();
+            std::ptr::copy_nonoverlapping(mapped.cast::<u8>(), pixels.as_mut_ptr(), pixels.len());
+            self.device.unmap_memory(memory);
+
+            self.device.destroy_fence(fence, None);
+            self.device.destroy_command_pool(pool, None);
+            self.device.free_memory(memory, None);
+            self.device.destroy_buffer(buffer, None);
+        }
+
+        if bgra {
+            pixels.chunks_exact_mut(4).for_each(|px| px.swap(0, 2));
+        }
+
+        Some((
+            xr::Extent2Di {
+                width: extent.width as _,
+                height: extent.height as _,
+            },
+            pixels,
+        ))
+    }
+
+    fn snapshot_texture(
+        &mut self,
+        slot: usize,
+        texture: *const vr::VRVulkanTextureData_t,
+        texture_layer: u32,
+    ) -> Option<*const vr::VRVulkanTextureData_t> {
+        let texture = unsafe { texture.as_ref() }?;
+        if self.snapshots.len() <= slot {
+            self.snapshots.resize_with(slot + 1, || None);
+        }
+        let fits = self.snapshots[slot].as_ref().is_some_and(|snapshot| {
+            snapshot.data.m_nFormat == texture.m_nFormat
+                && snapshot.data.m_nWidth == texture.m_nWidth
+                && snapshot.data.m_nHeight == texture.m_nHeight
+        });
+        if !fits {
+            if let Some(snapshot) = self.snapshots[slot].take() {
+                self.destroy_snapshot(snapshot);
+            }
+            self.snapshots[slot] = Some(self.create_snapshot(texture)?);
+        }
+
+        let snapshot = self.snapshots[slot].as_ref().unwrap();
+        let image = vk::Image::from_raw(snapshot.data.m_nImage);
+        let buf = snapshot.buf;
+        let range = vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count: 1,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+        let layers = vk::ImageSubresourceLayers {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            mip_level: 0,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+        let copy = vk::ImageResolve {
+            src_subresource: vk::ImageSubresourceLayers {
+                base_array_layer: texture_layer,
+                ..layers
+            },
+            src_offset: vk::Offset3D::default(),
+            dst_subresource: layers,
+            dst_offset: vk::Offset3D::default(),
+            extent: vk::Extent3D {
+                width: texture.m_nWidth,
+                height: texture.m_nHeight,
+                depth: 1,
+            },
+        };
+
+        unsafe {
+            // The command buffer can only be recorded again once the last copy is done.
+            self.device
+                .wait_for_fences(&[snapshot.fence], true, u64::MAX)
+                .unwrap();
+            self.device.reset_fences(&[snapshot.fence]).unwrap();
+            self.device
+                .begin_command_buffer(
+                    buf,
+                    &vk::CommandBufferBeginInfo::default()
+                        .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT),
+                )
+                .unwrap();
+            self.device.cmd_pipeline_barrier(
+                buf,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[vk::ImageMemoryBarrier {
+                    src_access_mask: vk::AccessFlags::TRANSFER_READ,
+                    dst_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+                    old_layout: vk::ImageLayout::UNDEFINED,
+                    new_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    image,
+                    subresource_range: range,
+                    ..Default::default()
+                }],
+            );
+            // Like the swapchain copies, we expect the app to have left the image in TRANSFER_SRC_OPTIMAL.
+            let game_image = vk::Image::from_raw(texture.m_nImage);
+            if texture.m_nSampleCount > 1 {
+                self.device.cmd_resolve_image(
+                    buf,
+                    game_image,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    image,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    &[copy],
+                );
+            } else {
+                self.device.cmd_copy_image(
+                    buf,
+                    game_image,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    image,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    &[vk::ImageCopy {
+                        src_subresource: copy.src_subresource,
+                        src_offset: copy.src_offset,
+                        dst_subresource: copy.dst_subresource,
+                        dst_offset: copy.dst_offset,
+                        extent: copy.extent,
+                    }],
+                );
+            }
+            // Left the same way as the app's textures, so it can be read back like them.
+            self.device.cmd_pipeline_barrier(
+                buf,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[vk::ImageMemoryBarrier {
+                    src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+                    dst_access_mask: vk::AccessFlags::TRANSFER_READ,
+                    old_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    new_layout: vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    image,
+                    subresource_range: range,
+                    ..Default::default()
+                }],
+            );
+            self.device.end_command_buffer(buf).unwrap();
+            self.device
+                .queue_submit(
+                    self.queue,
+                    &[vk::SubmitInfo::default().command_buffers(&[buf])],
+                    snapshot.fence,
+                )
+                .unwrap();
+        }
+
+        Some(&*snapshot.data)
+    }
+
+    fn snapshot(&self, slot: usize) -> Option<*const vr::VRVulkanTextureData_t> {
+        let snapshot = self.snapshots.get(slot)?.as_ref()?;
+        Some(&*snapshot.data)
+    }
+
+    fn swapchain_info_for_pixels(
+        &self,
+        extent: xr::Extent2Di,
+    ) -> xr::SwapchainCreateInfo<Self::Api> {
+        xr::SwapchainCreateInfo {
+            create_flags: xr::SwapchainCreateFlags::EMPTY,
+            usage_flags: xr::SwapchainUsageFlags::COLOR_ATTACHMENT
+                | xr::SwapchainUsageFlags::TRANSFER_DST,
+            format: vk::Format::R8G8B8A8_SRGB.as_raw() as _,
+            sample_count: 1,
+            width: extent.width as u32,
+            height: extent.height as u32,
+            face_count: 1,
+            array_size: 1,
+            mip_count: 1,
+        }
+    }
+
+    fn upload_pixels(&mut self, pixels: &[u8], extent: xr::Extent2Di, image_index: usize) {
+        let data = self.real_data.as_ref().unwrap();
+        let swapchain_image = data.images[image_index];
+        let buf = data.bufs[2 * image_index];
+        let bgra = matches!(
+            data.format,
+            vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB
+        );
+        let size = pixels.len() as u64;
+
+        let buffer = unsafe {
+            self.device
+                .create_buffer(
+                    &vk::BufferCreateInfo::default()
+                        .size(size)
+                        .usage(vk::BufferUsageFlags::TRANSFER_SRC)
+                        .sharing_mode(vk::SharingMode::EXCLUSIVE),
+                    None,
+                )
+                .unwrap()
+        };
+        let reqs = unsafe { self.device.get_buffer_memory_requirements(buffer) };
+        let Some(memory_type_index) = self.find_memory_type(
+            reqs.memory_type_bits,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        ) else {
+            warn!("No host visible memory available for overlay upload");
+            unsafe { self.device.destroy_buffer(buffer, None) };
+            return;
+        };
+
+        let memory = unsafe {
+            let memory = self
+                .device
+                .allocate_memory(
+                    &vk::MemoryAllocateInfo::default()
+                        .allocation_size(reqs.size)
+                        .memory_type_index(memory_type_index),
+                    None,
+                )
+                .unwrap();
+            self.device.bind_buffer_memory(buffer, memory, 0).unwrap();
+            let mapped = self
+                .device
+                .map_memory(memory, 0, size, vk::MemoryMapFlags::empty())
+                .unwrap();
+            let mapped = std::slice::from_raw_parts_mut(mapped.cast::<u8>(), pixels.len());
+            mapped.copy_from_slice(pixels);
+            if bgra {
+                mapped.chunks_exact_mut(4).for_each(|px| px.swap(0, 2));
+            }
+            self.device.unmap_memory(memory);
+            memory
+        };
+
+        let swapchain_res = vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count: 1,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+        self.record_commands(buf, || unsafe {
+            self.device.cmd_pipeline_barrier(
+                buf,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[vk::ImageMemoryBarrier {
+                    src_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                    dst_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+                    old_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                    new_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    image: swapchain_image,
+                    subresource_range: swapchain_res,
+                    ..Default::default()
+                }],
+            );
+            self.device.cmd_copy_buffer_to_image(
+                buf,
+                buffer,
+                swapchain_image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[vk::BufferImageCopy {
+                    buffer_offset: 0,
+                    buffer_row_length: 0,
+                    buffer_image_height: 0,
+                    image_subresource: vk::ImageSubresourceLayers {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        mip_level: 0,
+                        base_array_layer: 0,
+                        layer_count: 1,
+                    },
+                    image_offset: vk::Offset3D::default(),
+                    image_extent: vk::Extent3D {
+                        width: extent.width as u32,
+                        height: extent.height as u32,
+                        depth: 1,
+                    },
+                }],
+            );
+            self.device.cmd_pipeline_barrier(
+                buf,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[vk::ImageMemoryBarrier {
+                    src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+                    dst_access_mask: vk::AccessFlags::empty(),
+                    old_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    new_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                    image: swapchain_image,
+                    subresource_range: swapchain_res,
+                    ..Default::default()
+                }],
+            );
+        });
+
+        // Uploads are rare enough that waiting here beats tracking the staging buffer's lifetime.
+        // The caller holds the queue lock, so the frame controller isn't using the queue.
+        unsafe {
+            self.device.queue_wait_idle(self.queue).unwrap();
+            self.device.free_memory(memory, None);
+            self.device.destroy_buffer(buffer, None);
+        }
+    }
 }
 impl VulkanData {
+    fn find_memory_type(&self, type_bits: u32, flags: vk::MemoryPropertyFlags) -> Option<u32> {
+        let memory_props = unsafe {
+            self.instance
+                .get_physical_device_memory_properties(self.physical_device)
+        };
+        (0..memory_props.memory_type_count).find(|idx| {
+            type_bits & (1 << idx) != 0
+                && memory_props.memory_types[*idx as usize]
+                    .property_flags
+                    .contains(flags)
+        })
+    }
+
+    /// Returns a single sampled image the multisampled texture can be resolved into,
+    /// (re)creating it if the texture doesn't fit in the current one.
+    fn overlay_resolve_image(&mut self, texture: &vr::VRVulkanTextureData_t) -> vk::Image {
+        let format = vk::Format::from_raw(texture.m_nFormat as _);
+        if let Some(target) = &self.overlay_resolve {
+            if target.format == format
+                && target.width >= texture.m_nWidth
+                && target.height >= texture.m_nHeight
+            {
+                return target.image;
+            }
+        }
+
+        if let Some(target) = self.overlay_resolve.take() {
+            // The previous overlay copy may still be using it.
+            unsafe { self.device.queue_wait_idle(self.queue).unwrap() };
+            // Earlier copies have views of it.
+            self.clear_overlay_copies();
+            self.destroy_resolve_target(target);
+        }
+
+        let image = unsafe {
+            self.device
+                .create_image(
+                    &vk::ImageCreateInfo::default()
+                        .image_type(vk::ImageType::TYPE_2D)
+                        .format(format)
+                        .extent(vk::Extent3D {
+                            width: texture.m_nWidth,
+                            height: texture.m_nHeight,
+                            depth: 1,
+                        })
+                        .mip_levels(1)
+                        .array_layers(1)
+                        .samples(vk::SampleCountFlags::TYPE_1)
+                        .tiling(vk::ImageTiling::OPTIMAL)
+                        .usage(
+                            vk::ImageUsageFlags::TRANSFER_DST
+                                | vk::ImageUsageFlags::SAMPLED
+                                | vk::ImageUsageFlags::INPUT_ATTACHMENT,
+                        )
+                        .sharing_mode(vk::SharingMode::EXCLUSIVE)
+                        .initial_layout(vk::ImageLayout::UNDEFINED),
+                    None,
+                )
+                .unwrap()
+        };
+        let reqs = unsafe { self.device.get_image_memory_requirements(image) };
+        let memory_type_index = self
+            .find_memory_type(reqs.memory_type_bits, vk::MemoryPropertyFlags::DEVICE_LOCAL)
+            .expect("No device local memory for overlay resolve image");
+        let memory = unsafe {
+            let memory = self
+                .device
+                .allocate_memory(
+                    &vk::MemoryAllocateInfo::default()
+                        .allocation_size(reqs.size)
+                        .memory_type_index(memory_type_index),
+                    None,
+                )
+                .unwrap();
+            self.device.bind_image_memory(image, memory, 0).unwrap();
+            memory
+        };
+
+        self.overlay_resolve = Some(ResolveTarget {
+            image,
+            memory,
+            format,
+            width: texture.m_nWidth,
+            height: texture.m_nHeight,
+        });
+        image
+    }
+
+    /// Resolves a layer of the texture into the resolve image, leaving it in
+    /// TRANSFER_SRC_OPTIMAL like the app's textures.
+    fn record_overlay_resolve(
+        &self,
+        buf: vk::CommandBuffer,
+        texture: &vr::VRVulkanTextureData_t,
+        texture_layer: u32,
+        target: vk::Image,
+    ) {
+        let range = vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count: 1,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+        let layers = vk::ImageSubresourceLayers {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            mip_level: 0,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+
+        unsafe {
+            self.device.cmd_pipeline_barrier(
+                buf,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[vk::ImageMemoryBarrier {
+                    src_access_mask: vk::AccessFlags::empty(),
+                    dst_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+                    old_layout: vk::ImageLayout::UNDEFINED,
+                    new_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    image: target,
+                    subresource_range: range,
+                    ..Default::default()
+                }],
+            );
+            self.device.cmd_resolve_image(
+                buf,
+                vk::Image::from_raw(texture.m_nImage),
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                target,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[vk::ImageResolve {
+                    src_subresource: vk::ImageSubresourceLayers {
+                        base_array_layer: texture_layer,
+                        ..layers
+                    },
+                    src_offset: vk::Offset3D::default(),
+                    dst_subresource: layers,
+                    dst_offset: vk::Offset3D::default(),
+                    extent: vk::Extent3D {
+                        width: texture.m_nWidth,
+                        height: texture.m_nHeight,
+                        depth: 1,
+                    },
+                }],
+            );
+            self.device.cmd_pipeline_barrier(
+                buf,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[vk::ImageMemoryBarrier {
+                    src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+                    dst_access_mask: vk::AccessFlags::SHADER_READ,
+                    old_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    new_layout: vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    image: target,
+                    subresource_range: range,
+                    ..Default::default()
+                }],
+            );
+        }
+    }
+
+    fn clear_overlay_copies(&mut self) {
+        if self.overlay_copies.is_empty() {
+            return;
+        }
+        unsafe { self.device.queue_wait_idle(self.queue).unwrap() };
+        for (_, target) in self.overlay_copies.drain(..) {
+            target.destroy(&self.device);
+        }
+    }
+
+    /// A single sampled image as big as the texture, with everything needed to copy into it.
+    fn create_snapshot(&self, texture: &vr::VRVulkanTextureData_t) -> Option<Snapshot> {
+        let format = vk::Format::from_raw(texture.m_nFormat as _);
+        let image = unsafe {
+            self.device
+                .create_image(
+                    &vk::ImageCreateInfo::default()
+                        .image_type(vk::ImageType::TYPE_2D)
+                        .format(format)
+                        .extent(vk::Extent3D {
+                            width: texture.m_nWidth,
+                            height: texture.m_nHeight,
+                            depth: 1,
+                        })
+                        .mip_levels(1)
+                        .array_layers(1)
+                        .samples(vk::SampleCountFlags::TYPE_1)
+                        .tiling(vk::ImageTiling::OPTIMAL)
+                        .usage(
+                            vk::ImageUsageFlags::TRANSFER_SRC
+                                | vk::ImageUsageFlags::TRANSFER_DST
+                                | vk::ImageUsageFlags::SAMPLED,
+                        )
+                        .sharing_mode(vk::SharingMode::EXCLUSIVE)
+                        .initial_layout(vk::ImageLayout::UNDEFINED),
+                    None,
+                )
+                .unwrap()
+        };
+        let reqs = unsafe { self.device.get_image_memory_requirements(image) };
+        let Some(memory_type_index) =
+            self.find_memory_type(reqs.memory_type_bits, vk::MemoryPropertyFlags::DEVICE_LOCAL)
+        else {
+            warn!("No device local memory available for texture copy");
+            unsafe { self.device.destroy_image(image, None) };
+            return None;
+        };
+
+        unsafe {
+            let memory = self
+                .device
+                .allocate_memory(
+                    &vk::MemoryAllocateInfo::default()
+                        .allocation_size(reqs.size)
+                        .memory_type_index(memory_type_index),
+                    None,
+                )
+                .unwrap();
+            self.device.bind_image_memory(image, memory, 0).unwrap();
+            let pool = self
+                .device
+                .create_command_pool(
+                    &vk::CommandPoolCreateInfo::default()
+                        .flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER)
+                        .queue_family_index(self.queue_family_index),
+                    None,
+                )
+                .unwrap();
+            let buf = self
+                .device
+                .allocate_command_buffers(
+                    &vk::CommandBufferAllocateInfo::default()
+                        .command_pool(pool)
+                        .level(vk::CommandBufferLevel::PRIMARY)
+                        .command_buffer_count(1),
+                )
+                .unwrap()[0];
+            let fence = self
+                .device
+                .create_fence(
+                    &vk::FenceCreateInfo::default().flags(vk::FenceCreateFlags::SIGNALED),
+                    None,
+                )
+                .unwrap();
+
+            Some(Snapshot {
+                data: Box::new(vr::VRVulkanTextureData_t {
+                    m_nImage: image.as_raw(),
+                    m_nSampleCount: 1,
+                    ..*texture
+                }),
+                memory,
+                pool,
+                buf,
+                fence,
+            })
+        }
+    }
+
+    fn destroy_snapshot(&self, snapshot: Snapshot) {
+        unsafe {
+            self.device
+                .wait_for_fences(&[snapshot.fence], true, u64::MAX)
+                .unwrap();
+            self.device.destroy_fence(snapshot.fence, None);
+            self.device.destroy_command_pool(snapshot.pool, None);
+            self.device
+                .destroy_image(vk::Image::from_raw(snapshot.data.m_nImage), None);
+            self.device.free_memory(snapshot.memory, None);
+        }
+    }
+
+    fn destroy_resolve_target(&self, target: ResolveTarget) {
+        unsafe {
+            self.device.destroy_image(target.image, None);
+            self.device.free_memory(target.memory, None);
+        }
+    }
+
     pub fn record_commands(&self, buf: vk::CommandBuffer, cmds: impl FnOnce()) {
         unsafe {
             self.device
@@ -511,6 +1376,9 @@ impl VulkanData {
             queue: vk::Queue::from_raw(data.m_pQueue as _),
             queue_family_index: data.m_nQueueFamilyIndex,
             real_data: Default::default(),
+            overlay_resolve: None,
+            overlay_copies: Vec::new(),
+            snapshots: Vec::new(),
         }
     }
 
@@ -591,6 +1459,9 @@ impl VulkanData {
             queue,
             queue_family_index,
             real_data: Default::default(),
+            overlay_resolve: None,
+            overlay_copies: Vec::new(),
+            snapshots: Vec::new(),
         }
     }
 }
@@ -607,26 +1478,27 @@ struct PipelineData {
 }
 
 impl PipelineData {
+    fn destroy(&self, device: &ash::Device) {
+        unsafe {
+            for view in &self.image_views {
+                device.destroy_image_view(*view, None);
+            }
+            device.destroy_pipeline(self.pipeline, None);
+            device.destroy_pipeline_layout(self.layout, None);
+            device.destroy_render_pass(self.renderpass, None);
+            device.destroy_descriptor_pool(self.pool, None);
+            device.destroy_sampler(self.sampler, None);
+        }
+    }
+
     fn new(
         device: &ash::Device,
         source_format: vk::Format,
         target_format: vk::Format,
-        sample_count: u32,
         images: &[vk::Image],
     ) -> Self {
-        let samples = match sample_count {
-            1 => vk::SampleCountFlags::TYPE_1,
-            2 => vk::SampleCountFlags::TYPE_2,
-            4 => vk::SampleCountFlags::TYPE_4,
-            8 => vk::SampleCountFlags::TYPE_8,
-            16 => vk::SampleCountFlags::TYPE_16,
-            32 => vk::SampleCountFlags::TYPE_32,
-            64 => vk::SampleCountFlags::TYPE_64,
-            other => {
-                warn!("unexpected sample count {other} for pipeline - using 1");
-                vk::SampleCountFlags::TYPE_1
-            }
-        };
+        // Multisampled textures are resolved before they reach the pipeline.
+        let samples = vk::SampleCountFlags::TYPE_1;
         let attachments = [
             // game image
             vk::AttachmentDescription {
@@ -695,13 +1567,11 @@ impl PipelineData {
             )
         };
 
-        let (_vert_module, vert_stage) = load_module(
-            vk::ShaderStageFlags::VERTEX,
-            &include_bytes!(concat!(env!("OUT_DIR"), "/vert_overlay.spv"))[..],
-        );
+        let (_vert_module, vert_stage) =
+            load_module(vk::ShaderStageFlags::VERTEX, &shaders::OVERLAY_VERT.spirv());
         let (_frag_module, frag_stage) = load_module(
             vk::ShaderStageFlags::FRAGMENT,
-            &include_bytes!(concat!(env!("OUT_DIR"), "/frag_overlay.spv"))[..],
+            &shaders::OVERLAY_FRAG.spirv(),
         );
 
         let binding = vk::DescriptorSetLayoutBinding::default()
@@ -747,12 +1617,17 @@ impl PipelineData {
             offset: 0,
             size: std::mem::size_of::<[f32; 4]>() as u32,
         };
+        let srgb_conversion_pc = vk::PushConstantRange {
+            stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            offset: texture_coordinates_pc.size,
+            size: std::mem::size_of::<SrgbConversion>() as u32,
+        };
         let pipeline_layout = unsafe {
             device
                 .create_pipeline_layout(
                     &vk::PipelineLayoutCreateInfo::default()
                         .set_layouts(std::slice::from_ref(&set_layout))
-                        .push_constant_ranges(&[texture_coordinates_pc]),
+                        .push_constant_ranges(&[texture_coordinates_pc, srgb_conversion_pc]),
                     None,
                 )
                 .unwrap()
@@ -854,23 +1729,65 @@ impl PipelineData {
     }
 }
 
-#[inline]
-fn get_colorspace_corrected_format(format: vk::Format, color_space: vr::EColorSpace) -> vk::Format {
-    static UNSUPPORTED: LazyLock<Mutex<HashSet<vk::Format>>> = LazyLock::new(Mutex::default);
+/// The swapchain format for a texture's format, or None if it isn't supported.
+fn vr_format_to_xr(format: vk::Format, color_space: vr::EColorSpace) -> Option<vk::Format> {
     // https://github.com/ValveSoftware/openvr/wiki/Vulkan#image-formats
-    match color_space {
-        vr::EColorSpace::Auto | vr::EColorSpace::Gamma => match format {
-            vk::Format::R8G8B8A8_UNORM | vk::Format::R8G8B8A8_SRGB => vk::Format::R8G8B8A8_SRGB,
-            vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB => vk::Format::B8G8R8A8_SRGB,
-            vk::Format::BC3_SRGB_BLOCK => format,
-            _ => {
-                if UNSUPPORTED.lock().unwrap().insert(format) {
-                    warn!("Unhandled texture format: {format:?}");
-                }
-                format
-            }
-        },
-        vr::EColorSpace::Linear => todo!("Linear colorspace not implemented yet"),
+    let gamma = color_space != vr::EColorSpace::Linear;
+    Some(match format {
+        vk::Format::R8G8B8A8_UNORM | vk::Format::R8G8B8A8_SRGB if gamma => {
+            vk::Format::R8G8B8A8_SRGB
+        }
+        vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB if gamma => {
+            vk::Format::B8G8R8A8_SRGB
+        }
+        vk::Format::R8G8B8A8_UNORM | vk::Format::R8G8B8A8_SRGB => vk::Format::R8G8B8A8_UNORM,
+        vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB => vk::Format::B8G8R8A8_UNORM,
+        vk::Format::BC3_SRGB_BLOCK => format,
+        // There are no sRGB variants of these.
+        vk::Format::A2B10G10R10_UNORM_PACK32
+        | vk::Format::A2R10G10B10_UNORM_PACK32
+        | vk::Format::R16G16B16A16_SFLOAT => format,
+        _ => return None,
+    })
+}
+
+/// How the overlay shader has to convert sampled values so that it always writes linear values.
+/// Must match the values the overlay fragment shader expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+enum SrgbConversion {
+    None = 0,
+    /// The texture holds sRGB data in a linear format, so sampling doesn't decode it.
+    Decode = 1,
+    /// The texture holds linear data in an sRGB format, so sampling wrongly decodes it.
+    Encode = 2,
+}
+
+fn overlay_srgb_conversion(format: vk::Format, color_space: vr::EColorSpace) -> SrgbConversion {
+    let is_srgb = |format| {
+        matches!(
+            format,
+            vk::Format::R8G8B8A8_SRGB | vk::Format::B8G8R8A8_SRGB | vk::Format::BC3_SRGB_BLOCK
+        )
+    };
+    // Formats without an sRGB variant can't have gamma encoded data decoded by sampling. Auto
+    // means gamma for normalized formats, but linear for float ones.
+    let gamma_without_srgb = match format {
+        vk::Format::A2B10G10R10_UNORM_PACK32 | vk::Format::A2R10G10B10_UNORM_PACK32 => {
+            color_space != vr::EColorSpace::Linear
+        }
+        vk::Format::R16G16B16A16_SFLOAT => color_space == vr::EColorSpace::Gamma,
+        _ => false,
+    };
+    if gamma_without_srgb {
+        return SrgbConversion::Decode;
+    }
+    // The texture's data is encoded the way the swapchain format negotiated for it is.
+    let data_format = vr_format_to_xr(format, color_space).unwrap_or(format);
+    match (is_srgb(format), is_srgb(data_format)) {
+        (false, true) => SrgbConversion::Decode,
+        (true, false) => SrgbConversion::Encode,
+        _ => SrgbConversion::None,
     }
 }
 
@@ -910,3 +1827,154 @@ fn new_entry() -> ash::Entry {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn texture_bounds_select_sub_image() {
+        let texture = vr::VRVulkanTextureData_t {
+            m_nWidth: 200,
+            m_nHeight: 100,
+            ..Default::default()
+        };
+        let bounds = vr::VRTextureBounds_t {
+            uMin: 0.5,
+            vMin: 0.0,
+            uMax: 1.0,
+            vMax: 1.0,
+        };
+        let (extent, offset) = texture_extent_from_bounds(&texture, bounds);
+        assert_eq!((offset.x, offset.y), (100, 0));
+        assert_eq!((extent.width, extent.height), (100, 100));
+
+        // Flipped bounds cover the same region.
+        let flipped = vr::VRTextureBounds_t {
+            vMin: 1.0,
+            vMax: 0.0,
+            ..bounds
+        };
+        let (extent, offset) = texture_extent_from_bounds(&texture, flipped);
+        assert_eq!((offset.x, offset.y), (100, 0));
+        assert_eq!((extent.width, extent.height), (100, 100));
+    }
+
+    #[test]
+    fn swapchain_formats() {
+        use vk::Format;
+        use vr::EColorSpace::*;
+        // (texture format, gamma swapchain format, linear swapchain format)
+        let table = [
+            (
+                Format::R8G8B8A8_UNORM,
+                Format::R8G8B8A8_SRGB,
+                Format::R8G8B8A8_UNORM,
+            ),
+            (
+                Format::R8G8B8A8_SRGB,
+                Format::R8G8B8A8_SRGB,
+                Format::R8G8B8A8_UNORM,
+            ),
+            (
+                Format::B8G8R8A8_UNORM,
+                Format::B8G8R8A8_SRGB,
+                Format::B8G8R8A8_UNORM,
+            ),
+            (
+                Format::B8G8R8A8_SRGB,
+                Format::B8G8R8A8_SRGB,
+                Format::B8G8R8A8_UNORM,
+            ),
+            (
+                Format::BC3_SRGB_BLOCK,
+                Format::BC3_SRGB_BLOCK,
+                Format::BC3_SRGB_BLOCK,
+            ),
+        ];
+        for (format, gamma, linear) in table {
+            assert_eq!(vr_format_to_xr(format, Auto), Some(gamma), "{format:?}");
+            assert_eq!(vr_format_to_xr(format, Gamma), Some(gamma), "{format:?}");
+            assert_eq!(vr_format_to_xr(format, Linear), Some(linear), "{format:?}");
+        }
+        assert_eq!(vr_format_to_xr(Format::D32_SFLOAT, Auto), None);
+    }
+
+    #[test]
+    fn hdr_formats() {
+        use vk::Format;
+        use vr::EColorSpace::*;
+        let hdr = [
+            Format::A2B10G10R10_UNORM_PACK32,
+            Format::A2R10G10B10_UNORM_PACK32,
+            Format::R16G16B16A16_SFLOAT,
+        ];
+        for format in hdr {
+            for color_space in [Auto, Gamma, Linear] {
+                assert_eq!(vr_format_to_xr(format, color_space), Some(format));
+            }
+        }
+
+        for format in &hdr[..2] {
+            assert_eq!(
+                overlay_srgb_conversion(*format, Auto),
+                SrgbConversion::Decode
+            );
+            assert_eq!(
+                overlay_srgb_conversion(*format, Linear),
+                SrgbConversion::None
+            );
+        }
+        assert_eq!(
+            overlay_srgb_conversion(Format::R16G16B16A16_SFLOAT, Auto),
+            SrgbConversion::None
+        );
+        assert_eq!(
+            overlay_srgb_conversion(Format::R16G16B16A16_SFLOAT, Gamma),
+            SrgbConversion::Decode
+        );
+
+        let fallback = |format: Format| {
+            VulkanData::sdr_fallback_format(format.as_raw() as u32)
+                .map(|f| Format::from_raw(f as i32))
+        };
+        assert_eq!(
+            fallback(Format::A2B10G10R10_UNORM_PACK32),
+            Some(Format::R8G8B8A8_UNORM)
+        );
+        assert_eq!(
+            fallback(Format::R16G16B16A16_SFLOAT),
+            Some(Format::R8G8B8A8_SRGB)
+        );
+        assert_eq!(fallback(Format::R8G8B8A8_SRGB), None);
+    }
+
+    #[test]
+    fn overlay_srgb_conversion_matches_color_space() {
+        use vk::Format;
+        use vr::EColorSpace::*;
+        // sRGB data in a linear format would otherwise be gamma encoded twice on the way out
+        for color_space in [Auto, Gamma] {
+            assert_eq!(
+                overlay_srgb_conversion(Format::R8G8B8A8_UNORM, color_space),
+                SrgbConversion::Decode
+            );
+            assert_eq!(
+                overlay_srgb_conversion(Format::B8G8R8A8_SRGB, color_space),
+                SrgbConversion::None
+            );
+        }
+        assert_eq!(
+            overlay_srgb_conversion(Format::R8G8B8A8_UNORM, Linear),
+            SrgbConversion::None
+        );
+        assert_eq!(
+            overlay_srgb_conversion(Format::B8G8R8A8_SRGB, Linear),
+            SrgbConversion::Encode
+        );
+        assert_eq!(
+            overlay_srgb_conversion(Format::R16G16B16A16_SFLOAT, Linear),
+            SrgbConversion::None
+        );
+    }
+}
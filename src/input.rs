@@ -7,6 +7,7 @@ mod skeletal;
 #[cfg(test)]
 mod tests;
 
+pub use legacy::PointerState;
 use profiles::MainAxisType;
 pub use profiles::{InteractionProfile, Profiles};
 use skeletal::FingerState;
@@ -1005,7 +1006,7 @@ impl<C: openxr_data::Compositor> vr::IVRInput010_Interface for Input<C> {
             let skeletal_input = data.input_data.estimated_skeleton_actions.get().unwrap();
             sync_sets.push(xr::ActiveActionSet::new(&legacy.set));
             sync_sets.push(xr::ActiveActionSet::new(&skeletal_input.set));
-            self.legacy_state.on_action_sync();
+            self.legacy_state.on_app_action_sync();
         }
 
         {
@@ -1211,6 +1212,52 @@ impl<C: openxr_data::Compositor> Input<C> {
         )
     }
 
+    /// Vibrates the given controller for the overlay laser. The laser has no haptic action of its
+    /// own, so this goes through one of the app's vibration actions, if it has any.
+    pub fn vibrate_controller(
+        &self,
+        hand: Hand,
+        duration_seconds: f32,
+        frequency: f32,
+        amplitude: f32,
+    ) {
+        let data = self.openxr.session_data.get();
+        let loaded = data.input_data.get_loaded_actions();
+        let Some(action) = loaded.as_ref().and_then(|loaded| {
+            loaded.actions.values().find_map(|action| match action {
+                ActionData::Haptic(action) => Some(action),
+                _ => None,
+            })
+        }) else {
+            debug!("no vibration actions loaded, not vibrating {hand:?} controller");
+            return;
+        };
+        let hand_path = match hand {
+            Hand::Left => self.openxr.left_hand.subaction_path,
+            Hand::Right => self.openxr.right_hand.subaction_path,
+        };
+        if let Err(e) = action.apply_feedback(
+            &data.session,
+            hand_path,
+            &xr::HapticVibration::new()
+                .amplitude(amplitude.clamp(0.0, 1.0))
+                .frequency(frequency)
+                .duration(xr::Duration::from_nanos((duration_seconds * 1e9) as _)),
+        ) {
+            warn!("Failed to vibrate {hand:?} controller: {e:?}");
+        }
+    }
+
+    #[cfg(test)]
+    pub fn haptic_action(&self, handle: vr::VRActionHandle_t) -> Option<xr::sys::Action> {
+        let data = self.openxr.session_data.get();
+        let loaded = data.input_data.get_loaded_actions()?;
+        match loaded.try_get_action(handle).ok()? {
+            ActionData::Haptic(action) => Some(action.as_raw()),
+            _ => None,
+        }
+    }
+
     pub fn frame_start_update(&self) {
         tracy_span!();
         std::mem::take(&mut *self.cached_poses.lock().unwrap());
@@ -1222,11 +1269,26 @@ impl<C: openxr_data::Compositor> Input<C> {
             // and interaction profiles are only updated after xrSyncActions is called. So here, we
             // do an action sync to try and get the runtime to update the interaction profile.
             let loaded = loaded.read().unwrap();
+            let mut sync_sets = Vec::new();
             if !self.openxr.left_hand.connected() || !self.openxr.right_hand.connected() {
                 debug!("no controllers connected - syncing info set");
-                data.session
-                    .sync_actions(&[xr::ActiveActionSet::new(&loaded.info_set)])
-                    .unwrap();
+                sync_sets.push(xr::ActiveActionSet::new(&loaded.info_set));
+            }
+            // The overlay laser reads the legacy actions, which are otherwise only synced with the
+            // app's own. Apps don't always sync theirs, like while they're paused behind an
+            // overlay, so the laser would get stuck.
+            let app_synced = self.legacy_state.take_app_synced();
+            if let Some(legacy) = data
+                .input_data
+                .legacy_actions
+                .get()
+                .filter(|_| !app_synced || !sync_sets.is_empty())
+            {
+                sync_sets.push(xr::ActiveActionSet::new(&legacy.set));
+                self.legacy_state.on_action_sync();
+            }
+            if !sync_sets.is_empty() {
+                data.session.sync_actions(&sync_sets).unwrap();
             }
             return;
         }
@@ -1396,6 +1458,7 @@ impl<C: openxr_data::Compositor> Input<C> {
 struct CachedSpaces {
     seated: CachedPoses,
     standing: CachedPoses,
+    raw: CachedPoses,
 }
 
 #[derive(Default)]
@@ -1418,7 +1481,7 @@ impl CachedSpaces {
         let space = match origin {
             vr::ETrackingUniverseOrigin::Seated => &mut self.seated,
             vr::ETrackingUniverseOrigin::Standing => &mut self.standing,
-            vr::ETrackingUniverseOrigin::RawAndUncalibrated => unreachable!(),
+            vr::ETrackingUniverseOrigin::RawAndUncalibrated => &mut self.raw,
         };
 
         let pose = match hand {
@@ -1,6 +1,6 @@
 use super::{Input, Profiles};
 use crate::openxr_data::{self, Hand, OpenXrData, SessionData};
-use glam::Quat;
+use glam::{Quat, Vec2};
 use log::{debug, trace, warn};
 use openvr as vr;
 use openxr as xr;
@@ -12,10 +12,21 @@ use std::{
     },
 };
 
+/// The controller state used for pointing at overlays
+#[derive(Copy, Clone, Default, PartialEq, Debug)]
+pub struct PointerState {
+    pub trigger: bool,
+    /// Trackpad or thumbstick position
+    pub scroll: Vec2,
+}
+
 #[derive(Default)]
 pub(super) struct LegacyState {
     packet_num: AtomicU32,
     got_state_this_frame: [AtomicBool; 2],
+    /// Whether the app has synced its actions, and the legacy ones with them, since the last
+    /// frame started
+    app_synced: AtomicBool,
 }
 
 impl LegacyState {
@@ -25,6 +36,16 @@ impl LegacyState {
             state.store(false, Ordering::Relaxed);
         }
     }
+
+    pub fn on_app_action_sync(&self) {
+        self.on_action_sync();
+        self.app_synced.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the app has synced its actions since this was last called.
+    pub fn take_app_synced(&self) -> bool {
+        self.app_synced.swap(false, Ordering::Relaxed)
+    }
 }
 
 // Adapted from openvr.h
@@ -155,6 +176,41 @@ impl<C: openxr_data::Compositor> Input<C> {
 
         true
     }
+
+    /// The legacy actions are synced with the app's own once it loads an action manifest, or at
+    /// the start of the frame if it doesn't sync them, so this works either way.
+    pub fn legacy_pointer_state(&self, hand: Hand) -> PointerState {
+        let data = self.openxr.session_data.get();
+        let Some(legacy) = data.input_data.legacy_actions.get() else {
+            return PointerState::default();
+        };
+        let actions = &legacy.actions;
+        let hand_path = match hand {
+            Hand::Left => self.openxr.left_hand.subaction_path,
+            Hand::Right => self.openxr.right_hand.subaction_path,
+        };
+        let trigger = actions
+            .trigger_click
+            .state(&data.session, hand_path)
+            .is_ok_and(|s| s.is_active && s.current_state);
+        let scroll = actions
+            .main_xy
+            .state(&data.session, hand_path)
+            .ok()
+            .filter(|s| s.is_active)
+            .map_or(Vec2::ZERO, |s| {
+                Vec2::new(s.current_state.x, s.current_state.y)
+            });
+        PointerState { trigger, scroll }
+    }
+
+    /// The trigger and scroll actions read by [`legacy_pointer_state`](Self::legacy_pointer_state)
+    #[cfg(test)]
+    pub fn legacy_pointer_actions(&self) -> Option<(xr::sys::Action, xr::sys::Action)> {
+        let data = self.openxr.session_data.get();
+        let actions = &data.input_data.legacy_actions.get()?.actions;
+        Some((actions.trigger_click.as_raw(), actions.main_xy.as_raw()))
+    }
 }
 
 macro_rules! legacy_actions_and_bindings {
@@ -627,6 +683,59 @@ mod tests {
         expect_no_event("RightHand".to_string());
     }
 
+    #[test]
+    fn pointer_state_with_action_manifest() {
+        use crate::input::PointerState;
+        use crate::openxr_data::Hand;
+        use fakexr::UserPath::*;
+        use glam::vec2;
+
+        let f = Fixture::new();
+        let set1 = f.get_action_set_handle(c"/actions/set1");
+        f.load_actions(c"actions.json");
+        f.set_interaction_profile(&Knuckles, LeftHand);
+        f.set_interaction_profile(&Knuckles, RightHand);
+        let app_sync = || {
+            f.sync(vr::VRActiveActionSet_t {
+                ulActionSet: set1,
+                ..Default::default()
+            })
+        };
+        app_sync();
+        f.input.openxr.poll_events();
+
+        let (trigger, scroll) = f.input.legacy_pointer_actions().unwrap();
+        let set_pointer = |pressed: bool, y| {
+            fakexr::set_action_state(trigger, pressed.into(), RightHand);
+            fakexr::set_action_state(scroll, fakexr::ActionState::Vector2(0.0, y), RightHand);
+        };
+        let pointer = |trigger, y| PointerState {
+            trigger,
+            scroll: vec2(0.0, y),
+        };
+
+        // The pointer is synced with the app's actions...
+        set_pointer(true, 0.8);
+        app_sync();
+        f.input.frame_start_update();
+        assert_eq!(
+            f.input.legacy_pointer_state(Hand::Right),
+            pointer(true, 0.8)
+        );
+
+        // ...and on its own once the app stops syncing them.
+        set_pointer(false, -0.5);
+        f.input.frame_start_update();
+        assert_eq!(
+            f.input.legacy_pointer_state(Hand::Right),
+            pointer(false, -0.5)
+        );
+        assert_eq!(
+            f.input.legacy_pointer_state(Hand::Left),
+            pointer(false, 0.0)
+        );
+    }
+
     macro_rules! test_button {
         ($click:ident, $id:path $(| $other_id:path)*) => {
             paste::paste! {
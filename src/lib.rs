@@ -22,7 +22,7 @@ use clientcore::ClientCore;
 use openvr as vr;
 use std::ffi::{c_char, c_void, CStr};
 use std::sync::{
-    atomic::{AtomicU32, AtomicU64, Ordering},
+    atomic::{AtomicU32, Ordering},
     Arc,
 };
 
@@ -96,7 +96,6 @@ macro_rules! atomic_float {
 }
 
 atomic_float!(AtomicF32, f32, AtomicU32);
-atomic_float!(AtomicF64, f64, AtomicU64);
 
 fn init_logging() {
     static ONCE: std::sync::Once = std::sync::Once::new();
@@ -8,6 +8,8 @@ use glam::f32::{Quat, Vec3};
 use log::{info, warn};
 use openvr as vr;
 use openxr as xr;
+use std::collections::HashMap;
+use std::ffi::CStr;
 use std::mem::ManuallyDrop;
 use std::sync::{
     atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering},
@@ -38,6 +40,9 @@ pub struct OpenXrData<C: Compositor> {
     pub left_hand: HandInfo,
     pub right_hand: HandInfo,
     pub enabled_extensions: xr::ExtensionSet,
+    /// Versions of the extensions the runtime supports, by name
+    extension_versions: HashMap<String, u32>,
+    pub runtime: RuntimeInfo,
 
     /// should only be externally accessed for testing
     pub(crate) input: Injected<crate::input::Input<C>>,
@@ -69,6 +74,122 @@ impl From<SessionCreationError> for InitError {
     }
 }
 
+/// The OpenXR runtime xrizer is running on, for diagnostics.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuntimeInfo {
+    pub name: String,
+    pub version: xr::Version,
+}
+
+impl RuntimeInfo {
+    fn query(instance: &xr::Instance) -> Self {
+        match instance.properties() {
+            Ok(props) => Self {
+                name: props.runtime_name,
+                version: props.runtime_version,
+            },
+            Err(e) => {
+                warn!("Couldn't get OpenXR runtime properties: {e}");
+                Self {
+                    name: "unknown".into(),
+                    version: xr::Version::new(0, 0, 0),
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for RuntimeInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let version = self.version;
+        write!(
+            f,
+            "{} {}.{}.{}",
+            self.name,
+            version.major(),
+            version.minor(),
+            version.patch()
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct EnabledExtension {
+    pub name: &'static str,
+    /// The version the runtime reported, None if it didn't report the extension
+    pub version: Option<u32>,
+}
+
+/// Every extension xrizer may enable.
+const KNOWN_EXTENSIONS: &[(&str, fn(&xr::ExtensionSet) -> bool)] = &[
+    ("XR_KHR_vulkan_enable", |e| e.khr_vulkan_enable),
+    ("XR_KHR_opengl_enable", |e| e.khr_opengl_enable),
+    ("XR_EXT_hand_tracking", |e| e.ext_hand_tracking),
+    ("XR_KHR_visibility_mask", |e| e.khr_visibility_mask),
+    ("XR_KHR_composition_layer_cylinder", |e| {
+        e.khr_composition_layer_cylinder
+    }),
+    ("XR_KHR_composition_layer_equirect2", |e| {
+        e.khr_composition_layer_equirect2
+    }),
+    ("XR_KHR_composition_layer_cube", |e| {
+        e.khr_composition_layer_cube
+    }),
+    ("XR_KHR_composition_layer_color_scale_bias", |e| {
+        e.khr_composition_layer_color_scale_bias
+    }),
+    ("XR_FB_display_refresh_rate", |e| e.fb_display_refresh_rate),
+];
+
+fn enabled_extensions(
+    exts: &xr::ExtensionSet,
+    versions: &HashMap<String, u32>,
+) -> Vec<EnabledExtension> {
+    KNOWN_EXTENSIONS
+        .iter()
+        .filter(|(_, enabled)| enabled(exts))
+        .map(|(name, _)| EnabledExtension {
+            name: *name,
+            version: versions.get(*name).copied(),
+        })
+        .collect()
+}
+
+/// ExtensionSet only records whether extensions are supported, so the versions have to be queried
+/// separately.
+fn extension_versions(entry: &xr::Entry) -> xr::Result<HashMap<String, u32>> {
+    let enumerate = entry.fp().enumerate_instance_extension_properties;
+    let check = |result: xr::sys::Result| {
+        if result.into_raw() < 0 {
+            Err(result)
+        } else {
+            Ok(())
+        }
+    };
+
+    let mut count = 0;
+    check(unsafe { enumerate(std::ptr::null(), 0, &mut count, std::ptr::null_mut()) })?;
+    let mut props = vec![
+        xr::sys::ExtensionProperties {
+            ty: xr::sys::ExtensionProperties::TYPE,
+            next: std::ptr::null_mut(),
+            extension_name: [0; xr::sys::MAX_EXTENSION_NAME_SIZE],
+            extension_version: 0,
+        };
+        count as usize
+    ];
+    check(unsafe { enumerate(std::ptr::null(), count, &mut count, props.as_mut_ptr()) })?;
+
+    Ok(props
+        .iter()
+        .take(count as usize)
+        .map(|prop| {
+            let name = unsafe { CStr::from_ptr(prop.extension_name.as_ptr()) };
+            (name.to_string_lossy().into_owned(), prop.extension_version)
+        })
+        .collect())
+}
+
 impl<C: Compositor> OpenXrData<C> {
     pub fn new(injector: &Injector) -> Result<Self, InitError> {
         #[cfg(not(test))]
@@ -82,6 +203,11 @@ impl<C: Compositor> OpenXrData<C> {
         let supported_exts = entry
             .enumerate_extensions()
             .map_err(InitError::EnumeratingExtensionsFailed)?;
+        // Versions are only logged, so missing them isn't worth failing over.
+        let extension_versions = extension_versions(&entry).unwrap_or_else(|e| {
+            warn!("Failed to query OpenXR extension versions: {e}");
+            HashMap::new()
+        });
         let mut exts = xr::ExtensionSet::default();
         exts.khr_vulkan_enable = supported_exts.khr_vulkan_enable;
         exts.khr_opengl_enable = supported_exts.khr_opengl_enable;
@@ -89,8 +215,10 @@ impl<C: Compositor> OpenXrData<C> {
         exts.khr_visibility_mask = supported_exts.khr_visibility_mask;
         exts.khr_composition_layer_cylinder = supported_exts.khr_composition_layer_cylinder;
         exts.khr_composition_layer_equirect2 = supported_exts.khr_composition_layer_equirect2;
+        exts.khr_composition_layer_cube = supported_exts.khr_composition_layer_cube;
         exts.khr_composition_layer_color_scale_bias =
             supported_exts.khr_composition_layer_color_scale_bias;
+        exts.fb_display_refresh_rate = supported_exts.fb_display_refresh_rate;
 
         let instance = entry
             .create_instance(
@@ -118,10 +246,13 @@ impl<C: Compositor> OpenXrData<C> {
             .0,
         )));
 
+        let runtime = RuntimeInfo::query(&instance);
+        info!("OpenXR runtime: {runtime}");
+
         let left_hand = HandInfo::new(&instance, "/user/hand/left");
         let right_hand = HandInfo::new(&instance, "/user/hand/right");
 
-        Ok(Self {
+        let data = Self {
             _entry: entry,
             instance,
             system_id,
@@ -130,9 +261,74 @@ impl<C: Compositor> OpenXrData<C> {
             left_hand,
             right_hand,
             enabled_extensions: exts,
+            extension_versions,
+            runtime,
             input: injector.inject(),
             compositor: injector.inject(),
-        })
+        };
+        info!(
+            "Enabled OpenXR extensions: {}",
+            data.enabled_extension_list()
+                .iter()
+                .map(|ext| match ext.version {
+                    Some(version) => format!("{} (v{version})", ext.name),
+                    None => ext.name.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        Ok(data)
+    }
+
+    /// The OpenXR extensions xrizer is using, for diagnostics.
+    pub fn enabled_extension_list(&self) -> Vec<EnabledExtension> {
+        enabled_extensions(&self.enabled_extensions, &self.extension_versions)
+    }
+
+    /// The refresh rates the display supports, or an empty list if XR_FB_display_refresh_rate is
+    /// unavailable.
+    pub fn display_refresh_rates(&self) -> Vec<f32> {
+        if !self.enabled_extensions.fb_display_refresh_rate {
+            crate::warn_once!("XR_FB_display_refresh_rate unavailable, can't list refresh rates");
+            return Vec::new();
+        }
+        self.session_data
+            .get()
+            .session
+            .enumerate_display_refresh_rates()
+            .inspect_err(|e| warn!("Failed to enumerate display refresh rates: {e}"))
+            .unwrap_or_default()
+    }
+
+    /// The current display refresh rate, if XR_FB_display_refresh_rate is available.
+    pub fn display_refresh_rate(&self) -> Option<f32> {
+        if !self.enabled_extensions.fb_display_refresh_rate {
+            crate::warn_once!("XR_FB_display_refresh_rate unavailable, can't query refresh rate");
+            return None;
+        }
+        self.session_data
+            .get()
+            .session
+            .get_display_refresh_rate()
+            .inspect_err(|e| warn!("Failed to get display refresh rate: {e}"))
+            .ok()
+    }
+
+    /// Asks the runtime to switch to the given refresh rate. Returns whether the request was accepted.
+    pub fn request_display_refresh_rate(&self, rate: f32) -> bool {
+        if !self.enabled_extensions.fb_display_refresh_rate {
+            crate::warn_once!(
+                "XR_FB_display_refresh_rate unavailable, ignoring refresh rate request ({})",
+                rate
+            );
+            return false;
+        }
+        self.session_data
+            .get()
+            .session
+            .request_display_refresh_rate(rate)
+            .inspect_err(|e| warn!("Failed to request display refresh rate {rate}: {e}"))
+            .is_ok()
     }
 
     pub fn poll_events(&self) {
@@ -261,7 +457,9 @@ impl<C: Compositor> OpenXrData<C> {
         };
 
         match origin {
-            vr::ETrackingUniverseOrigin::RawAndUncalibrated => unimplemented!(),
+            vr::ETrackingUniverseOrigin::RawAndUncalibrated => {
+                warn!("Can't reset the raw tracking space, ignoring");
+            }
             vr::ETrackingUniverseOrigin::Standing => reset_space(
                 stage_space_reference,
                 stage_space_adjusted,
@@ -341,7 +539,7 @@ pub struct SessionData {
     // The "reference" space is always equivalent to the reference space with an identity offset.
     // The "adjusted" space may have an offset, set by reset_tracking_space.
     // The adjusted spaces should be used for locating things - the reference spaces are only
-    // needed for reset_tracking_space
+    // needed for reset_tracking_space, and as the raw tracking space (the stage one)
     local_space_reference: xr::Space,
     local_space_adjusted: xr::Space,
     stage_space_reference: xr::Space,
@@ -513,6 +711,14 @@ impl SessionData {
             .create_swapchain(info)
     }
 
+    /// Whether the session was created for the graphics API `G`.
+    pub fn uses_api<G: xr::Graphics>(&self) -> bool
+    where
+        for<'a> &'a GraphicalSession: TryInto<&'a Session<G>>,
+    {
+        TryInto::<&Session<G>>::try_into(&self.session_graphics).is_ok()
+    }
+
     pub fn check_format<G: GraphicsBackend>(&self, info: &mut xr::SwapchainCreateInfo<G::Api>)
     where
         for<'a> &'a GraphicalSession: TryInto<&'a Session<G::Api>, Error: std::fmt::Display>,
@@ -529,6 +735,17 @@ impl SessionData {
             .swapchain_formats;
 
         if !formats.contains(&info.format) {
+            if let Some(fallback) =
+                G::sdr_fallback_format(info.format).filter(|f| formats.contains(f))
+            {
+                crate::warn_once!(
+                    "Runtime doesn't support HDR swapchain format {:?}, falling back to {:?}",
+                    G::to_nice_format(info.format),
+                    G::to_nice_format(fallback)
+                );
+                info.format = fallback;
+                return;
+            }
             let new_format = formats[0];
             warn!(
                 "Requested to init swapchain with unsupported format {:?} - instead using {:?}",
@@ -543,12 +760,14 @@ impl SessionData {
         self.get_space_for_origin(self.current_origin)
     }
 
+    /// The spaces are created along with the session (and recreated on recenter), so this is
+    /// cheap enough to call for every overlay every frame.
     #[inline]
     pub fn get_space_for_origin(&self, origin: vr::ETrackingUniverseOrigin) -> &xr::Space {
         match origin {
             vr::ETrackingUniverseOrigin::Seated => &self.local_space_adjusted,
             vr::ETrackingUniverseOrigin::Standing => &self.stage_space_adjusted,
-            vr::ETrackingUniverseOrigin::RawAndUncalibrated => unreachable!(),
+            vr::ETrackingUniverseOrigin::RawAndUncalibrated => &self.stage_space_reference,
         }
     }
 
@@ -566,8 +785,8 @@ impl SessionData {
     pub fn current_origin_as_reference_space(&self) -> xr::ReferenceSpaceType {
         match self.current_origin {
             vr::ETrackingUniverseOrigin::Seated => xr::ReferenceSpaceType::LOCAL,
-            vr::ETrackingUniverseOrigin::Standing => xr::ReferenceSpaceType::STAGE,
-            vr::ETrackingUniverseOrigin::RawAndUncalibrated => unreachable!(),
+            vr::ETrackingUniverseOrigin::Standing
+            | vr::ETrackingUniverseOrigin::RawAndUncalibrated => xr::ReferenceSpaceType::STAGE,
         }
     }
 
@@ -666,3 +885,79 @@ fn swing_twist_decomposition(rotation: Quat, axis: Vec3) -> Option<(Quat, Quat)>
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runtime_info() {
+        let xr = RealOpenXrData::new(&Injector::default()).unwrap();
+        assert_eq!(
+            xr.runtime,
+            RuntimeInfo {
+                name: fakexr::RUNTIME_NAME.into(),
+                version: fakexr::RUNTIME_VERSION,
+            }
+        );
+        assert_eq!(xr.runtime.to_string(), "fakexr 1.2.3");
+    }
+
+    #[test]
+    fn enabled_extension_list() {
+        let mut xr = RealOpenXrData::new(&Injector::default()).unwrap();
+        // fakexr only supports Vulkan and display refresh rates
+        assert_eq!(
+            xr.enabled_extension_list(),
+            [
+                EnabledExtension {
+                    name: "XR_KHR_vulkan_enable",
+                    version: Some(1),
+                },
+                EnabledExtension {
+                    name: "XR_FB_display_refresh_rate",
+                    version: Some(1),
+                },
+            ]
+        );
+
+        xr.enabled_extensions.khr_composition_layer_cylinder = true;
+        assert_eq!(
+            xr.enabled_extension_list(),
+            [
+                EnabledExtension {
+                    name: "XR_KHR_vulkan_enable",
+                    version: Some(1),
+                },
+                EnabledExtension {
+                    name: "XR_KHR_composition_layer_cylinder",
+                    version: None,
+                },
+                EnabledExtension {
+                    name: "XR_FB_display_refresh_rate",
+                    version: Some(1),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn display_refresh_rate() {
+        let mut xr = RealOpenXrData::new(&Injector::default()).unwrap();
+        assert_eq!(xr.display_refresh_rates(), fakexr::DISPLAY_REFRESH_RATES);
+        assert_eq!(xr.display_refresh_rate(), Some(90.0));
+
+        assert!(xr.request_display_refresh_rate(120.0));
+        assert_eq!(xr.display_refresh_rate(), Some(120.0));
+
+        // Unsupported rates are rejected and leave the current rate alone.
+        assert!(!xr.request_display_refresh_rate(61.0));
+        assert_eq!(xr.display_refresh_rate(), Some(120.0));
+
+        // Without the extension, everything falls back.
+        xr.enabled_extensions.fb_display_refresh_rate = false;
+        assert!(xr.display_refresh_rates().is_empty());
+        assert_eq!(xr.display_refresh_rate(), None);
+        assert!(!xr.request_display_refresh_rate(72.0));
+    }
+}
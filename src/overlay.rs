@@ -1,21 +1,91 @@
 use crate::{
     clientcore::{Injected, Injector},
     compositor::{is_usable_swapchain, Compositor},
-    graphics_backends::{supported_apis_enum, GraphicsBackend, SupportedBackend},
-    openxr_data::{GraphicalSession, OpenXrData, Session, SessionData},
+    graphics_backends::{flip_rows, supported_apis_enum, GraphicsBackend, SupportedBackend},
+    input::{Input, PointerState},
+    openxr_data::{GraphicalSession, Hand, OpenXrData, Session, SessionData},
 };
-use glam::{vec3, Quat, Vec3};
-use log::{debug, trace};
+use glam::{vec2, vec3, Quat, Vec2, Vec3};
+use keyboard::{Key, Keyboard, KeyboardLayout};
+use log::{debug, trace, warn};
+use message::Message;
 use openvr as vr;
 use openxr as xr;
-use slotmap::{new_key_type, Key, KeyData, SecondaryMap, SlotMap};
+use slotmap::{new_key_type, Key as _, KeyData, SecondaryMap, SlotMap};
 use std::f32::consts::{FRAC_1_SQRT_2, PI};
 use std::ffi::{c_char, c_void, CStr, CString};
-use std::sync::{Arc, Mutex, RwLock};
-use std::{collections::HashMap, ops::Deref};
+use std::sync::{
+    atomic::{AtomicBool, AtomicU32, Ordering},
+    Arc, LazyLock, Mutex, RwLock,
+};
+use std::time::{Duration, Instant};
+use std::{
+    collections::{HashMap, VecDeque},
+    ops::Deref,
+};
+
+mod keyboard;
+mod message;
+mod raster;
+#[cfg(test)]
+mod tests;
+
+// OpenVR overlays are allowed to use ≥ 0, and everything else is kept above this (see
+// Overlay::set_sort_order), so the skybox always sorts first.
+pub const SKYBOX_Z_ORDER: i64 = i64::MIN;
+
+/// A second equirect texture makes the skybox stereo, with one texture per eye.
+fn skybox_eyes(textures: &[vr::Texture_t]) -> &'static [xr::EyeVisibility] {
+    if textures.len() == 2 {
+        &[xr::EyeVisibility::LEFT, xr::EyeVisibility::RIGHT]
+    } else {
+        &[xr::EyeVisibility::BOTH]
+    }
+}
+
+/// Number of frames an overlay may be shown without a texture before we warn about it.
+const UNTEXTURED_WARN_FRAMES: u32 = 90;
+/// Overlays narrower than this (1mm) are widened, so their geometry stays well defined.
+const MIN_OVERLAY_WIDTH: f32 = 0.001;
+/// Cylinders flatter than this look flat anyway, and tiny curvatures would otherwise put them at
+/// infinity.
+const MAX_CYLINDER_RADIUS: f32 = 100.0;
+/// Distance in front of the tracking origin for overlays without a transform, unless
+/// XRIZER_OVERLAY_DEFAULT_DISTANCE says otherwise.
+const DEFAULT_OVERLAY_DISTANCE: f32 = 0.5;
+/// SteamVR allows far fewer, this only stops runaway apps. Can be changed with
+/// XRIZER_MAX_OVERLAYS.
+const DEFAULT_MAX_OVERLAYS: usize = 256;
 
-// OpenVR overlays are allowed to use ≥ 0
-pub const SKYBOX_Z_ORDER: i64 = -1;
+const MESSAGE_OVERLAY_KEY: &CStr = c"xrizer.message";
+/// Above any sort order an app can set.
+const MESSAGE_Z_ORDER: i64 = u32::MAX as i64 + 1;
+const MESSAGE_WIDTH: f32 = 1.0;
+const MESSAGE_DISTANCE: f32 = 1.5;
+/// ShowMessageOverlay normally blocks until a button is pressed. We can't take overlay input, so
+/// the message is answered right away and only stays up long enough to be read.
+const MESSAGE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Cursors hover this far in front of their overlay, so they don't z-fight with it.
+const CURSOR_OFFSET: f32 = 0.001;
+
+/// How far the stick has to be pushed for a discrete scroll
+const DISCRETE_SCROLL_THRESHOLD: f32 = 0.5;
+/// How far smooth scrolls go per second with the stick pushed all the way, in discrete scroll
+/// notches
+const SMOOTH_SCROLL_SPEED: f32 = 10.0;
+
+/// Events queued per overlay before the oldest ones are dropped
+const MAX_OVERLAY_EVENTS: usize = 64;
+
+const KEYBOARD_OVERLAY_KEY: &CStr = c"xrizer.keyboard";
+const KEYBOARD_WIDTH: f32 = 1.0;
+/// Below and in front of the user, like a real keyboard.
+const KEYBOARD_POSITION: xr::Vector3f = xr::Vector3f {
+    x: 0.0,
+    y: -0.4,
+    z: -0.8,
+};
 
 #[derive(macros::InterfaceImpl)]
 #[interface = "IVROverlay"]
@@ -25,9 +95,114 @@ pub struct OverlayMan {
     openxr: Arc<OpenXrData<Compositor>>,
     /// should only be externally accessed for testing
     pub(crate) compositor: Injected<Compositor>,
+    input: Injected<Input<Compositor>>,
     overlays: RwLock<SlotMap<OverlayKey, Overlay>>,
+    /// Indexed by [`normalized_key`]
     key_to_overlay: RwLock<HashMap<CString, OverlayKey>>,
     skybox: RwLock<Vec<OverlayKey>>,
+    /// What equirect skyboxes cover, full unless the app says otherwise
+    skybox_coverage: Mutex<EquirectCoverage>,
+    dashboard: RwLock<DashboardState>,
+    /// The overlay showing the current ShowMessageOverlay message, and when it was shown
+    message: Mutex<Option<(OverlayKey, Instant)>>,
+    keyboard: Mutex<Option<KeyboardState>>,
+    /// Events polled through IVRSystem
+    events: Mutex<VecDeque<SystemEvent>>,
+    laser: Mutex<LaserState>,
+    gamepad_focus: Mutex<Option<OverlayKey>>,
+    /// Set when the session is recreated, which drops every overlay swapchain. The next frame
+    /// copies the textures overlays had into the new session.
+    restarted: AtomicBool,
+    /// Sort overlays back to front by distance from the HMD, instead of only by sort order
+    depth_sort: bool,
+    /// Creating overlays fails once this many exist
+    max_overlays: usize,
+}
+
+#[derive(Default)]
+struct DashboardState {
+    visible: bool,
+    active: Option<OverlayKey>,
+}
+
+#[derive(Default)]
+struct LaserState {
+    /// The controller the laser comes from, if it's tracked
+    hand: Option<Hand>,
+    /// The overlay under the laser
+    hovered: Option<OverlayKey>,
+    /// The overlay the trigger was pressed on, and where. It gets the button up even if the
+    /// laser has left it by then.
+    pressed: Option<(OverlayKey, Vec2)>,
+    /// Whether the stick was pushed far enough for a discrete scroll last frame
+    scrolled: bool,
+}
+
+struct KeyboardState {
+    keyboard: Keyboard,
+    /// The overlay showing the keyboard, None once it has been dismissed. The text stays
+    /// around for GetKeyboardText.
+    overlay: Option<OverlayKey>,
+    /// The overlay passed to ShowKeyboardForOverlay, if any
+    target: vr::VROverlayHandle_t,
+    user_value: u64,
+}
+
+/// Events polled through PollNextOverlayEvent
+enum OverlayEvent {
+    Mouse(vr::EVREventType, vr::VREvent_Mouse_t),
+    Scroll(vr::EVREventType, vr::VREvent_Scroll_t),
+    Focus(vr::EVREventType),
+}
+
+impl OverlayEvent {
+    fn mouse(ty: vr::EVREventType, position: Vec2, button: u32) -> Self {
+        Self::Mouse(
+            ty,
+            vr::VREvent_Mouse_t {
+                x: position.x,
+                y: position.y,
+                button,
+                cursorIndex: 0,
+            },
+        )
+    }
+
+    fn scroll(ty: vr::EVREventType, delta: Vec2) -> Self {
+        Self::Scroll(
+            ty,
+            vr::VREvent_Scroll_t {
+                xdelta: delta.x,
+                ydelta: delta.y,
+                unused: 0,
+                viewportscale: 1.0,
+                cursorIndex: 0,
+            },
+        )
+    }
+}
+
+enum SystemEvent {
+    Keyboard(vr::EVREventType, vr::VREvent_Keyboard_t),
+}
+
+/// OpenVR compares overlay keys case-insensitively.
+fn normalized_key(key: &CStr) -> CString {
+    CString::new(key.to_bytes().to_ascii_lowercase()).unwrap()
+}
+
+/// Index into [`Overlay::neighbors`], None for Count or anything else apps pass through the C API.
+fn neighbor_index(direction: vr::EOverlayDirection) -> Option<usize> {
+    usize::try_from(direction.0)
+        .ok()
+        .filter(|&idx| idx < vr::EOverlayDirection::Count.0 as usize)
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum DashboardRole {
+    Main,
+    /// We don't have a dashboard bar to put thumbnails in, so these are never rendered.
+    Thumbnail,
 }
 
 impl OverlayMan {
@@ -36,10 +211,623 @@ impl OverlayMan {
             vtables: Vtables::default(),
             openxr,
             compositor: injector.inject(),
+            input: injector.inject(),
             overlays: Default::default(),
             key_to_overlay: Default::default(),
             skybox: Default::default(),
+            skybox_coverage: Default::default(),
+            dashboard: Default::default(),
+            message: Default::default(),
+            keyboard: Default::default(),
+            events: Default::default(),
+            laser: Default::default(),
+            gamepad_focus: Default::default(),
+            restarted: false.into(),
+            depth_sort: std::env::var("XRIZER_OVERLAY_DEPTH_SORT").is_ok_and(|v| v == "1"),
+            max_overlays: std::env::var("XRIZER_MAX_OVERLAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&max| max > 0)
+                .unwrap_or(DEFAULT_MAX_OVERLAYS),
+        }
+    }
+
+    /// Internal overlays are ones we draw ourselves, which don't count towards the app's limit.
+    fn create_overlay(
+        &self,
+        key: &CStr,
+        name: &CStr,
+        internal: bool,
+    ) -> Result<OverlayKey, vr::EVROverlayError> {
+        let mut overlays = self.overlays.write().unwrap();
+        if key.to_bytes().len() >= vr::k_unVROverlayMaxKeyLength as usize {
+            debug!("overlay key {key:?} is too long");
+            return Err(vr::EVROverlayError::KeyTooLong);
+        }
+        let mut key_to_overlay = self.key_to_overlay.write().unwrap();
+        if key_to_overlay.contains_key(&normalized_key(key)) {
+            debug!("overlay key {key:?} is already in use");
+            return Err(vr::EVROverlayError::KeyInUse);
+        }
+        if !internal && overlays.values().filter(|o| !o.internal).count() >= self.max_overlays {
+            crate::warn_once!(
+                "App tried to create more than {} overlays, refusing",
+                self.max_overlays
+            );
+            return Err(vr::EVROverlayError::OverlayLimitExceeded);
+        }
+        let ret_key = overlays.insert(Overlay {
+            internal,
+            ..Overlay::new(key.into(), name.into())
+        });
+        key_to_overlay.insert(normalized_key(key), ret_key);
+
+        debug!("created overlay {name:?} with key {key:?}");
+        Ok(ret_key)
+    }
+
+    /// A new backend for the session's graphics API, created from the app's last submitted frame
+    /// or another overlay's texture.
+    fn session_backend(&self) -> Option<SupportedBackend> {
+        self.compositor
+            .get()
+            .and_then(|compositor| compositor.create_backend())
+            .or_else(|| {
+                let overlays = self.overlays.read().unwrap();
+                overlays
+                    .values()
+                    .filter(|overlay| overlay.interop.is_none())
+                    .find_map(|overlay| {
+                        let texture = overlay.textures.first()?.texture();
+                        SupportedBackend::new(&texture, overlay.bounds)
+                    })
+            })
+    }
+
+    /// Whether the overlay is about to show its first texture drawn with a different graphics API
+    /// than the session's, so it needs a backend for the session's API.
+    fn needs_session_backend(&self, key: OverlayKey, texture: &vr::Texture_t) -> bool {
+        let (has_backend, has_interop) = {
+            let overlays = self.overlays.read().unwrap();
+            let Some(overlay) = overlays.get(key) else {
+                return false;
+            };
+            (overlay.compositor.is_some(), overlay.interop.is_some())
+        };
+        if has_backend {
+            return false;
+        }
+        let session_data = self.openxr.session_data.get();
+        has_interop
+            || (session_data.is_real_session() && needs_interop(texture.eType, &session_data))
+    }
+
+    /// Shows a texture drawn with a different graphics API than the session's, which only happens
+    /// in a real session. The texture is uploaded on the app's queue, so the frame controller is
+    /// kept off of it first.
+    fn set_interop_overlay_texture(
+        &self,
+        key: OverlayKey,
+        texture: vr::Texture_t,
+        session_backend: Option<Option<SupportedBackend>>,
+    ) -> vr::EVROverlayError {
+        let session_data = self.openxr.session_data.get();
+        let _queue = session_data.comp_data.lock_queue();
+        let mut overlays = self.overlays.write().unwrap();
+        let Some(overlay) = overlays.get_mut(key) else {
+            return vr::EVROverlayError::UnknownOverlay;
+        };
+        let result = match session_backend {
+            Some(None) => {
+                crate::warn_once!(
+                    "Can't show {:?} overlay textures in this session before the app has submitted a frame",
+                    texture.eType
+                );
+                Err(vr::EVROverlayError::InvalidTexture)
+            }
+            Some(Some(backend)) => {
+                overlay.compositor = Some(backend);
+                overlay.set_interop_texture(key, &session_data, texture)
+            }
+            None => overlay.set_interop_texture(key, &session_data, texture),
+        };
+        match result {
+            Ok(()) => {
+                debug!("set overlay texture for {:?}", overlay.name);
+                vr::EVROverlayError::None
+            }
+            Err(e) => e,
+        }
+    }
+
+    /// Creates and shows an overlay drawn by us. This needs a texture from the app to know which
+    /// graphics API to draw with.
+    fn show_internal_overlay(
+        &self,
+        key: &CStr,
+        width: f32,
+        position: xr::Vector3f,
+        z_order: i64,
+        (extent, pixels): (xr::Extent2Di, Vec<u8>),
+    ) -> Result<OverlayKey, vr::EVROverlayError> {
+        let session_data = self.openxr.session_data.get();
+        if !session_data.is_real_session() {
+            debug!("can't draw overlay {key:?} before the app has submitted any textures");
+            return Err(vr::EVROverlayError::RequestFailed);
+        }
+        let backend = self
+            .session_backend()
+            .ok_or(vr::EVROverlayError::RequestFailed)?;
+
+        let overlay_key = self.create_overlay(key, key, true)?;
+        let _queue = session_data.comp_data.lock_queue();
+        let mut overlays = self.overlays.write().unwrap();
+        let overlay = overlays.get_mut(overlay_key).unwrap();
+        overlay.compositor = Some(backend);
+        overlay.set_sort_order(z_order);
+        overlay.width = width;
+        overlay.transform = Some((
+            vr::ETrackingUniverseOrigin::Seated,
+            xr::Posef {
+                position,
+                orientation: xr::Quaternionf::IDENTITY,
+            }
+            .into(),
+        ));
+        if let Err(e) = overlay.set_pixels(overlay_key, &session_data, extent, &pixels) {
+            drop(overlays);
+            <Self as vr::IVROverlay027_Interface>::DestroyOverlay(
+                self,
+                overlay_key.data().as_ffi(),
+            );
+            return Err(e);
+        }
+        overlay.visible = true;
+        Ok(overlay_key)
+    }
+
+    fn redraw_internal_overlay(
+        &self,
+        key: OverlayKey,
+        (extent, pixels): (xr::Extent2Di, Vec<u8>),
+    ) -> Result<(), vr::EVROverlayError> {
+        let session_data = self.openxr.session_data.get();
+        let _queue = session_data.comp_data.lock_queue();
+        let mut overlays = self.overlays.write().unwrap();
+        let overlay = overlays
+            .get_mut(key)
+            .ok_or(vr::EVROverlayError::UnknownOverlay)?;
+        overlay.set_pixels(key, &session_data, extent, &pixels)
+    }
+
+    fn show_keyboard(
+        &self,
+        target: vr::VROverlayHandle_t,
+        input_mode: vr::EGamepadTextInputMode,
+        line_mode: vr::EGamepadTextInputLineMode,
+        max_chars: u32,
+        existing_text: *const c_char,
+        user_value: u64,
+    ) -> vr::EVROverlayError {
+        let mut state = self.keyboard.lock().unwrap();
+        if state.as_ref().is_some_and(|state| state.overlay.is_some()) {
+            return vr::EVROverlayError::KeyboardAlreadyInUse;
+        }
+
+        let existing_text = if existing_text.is_null() {
+            Default::default()
+        } else {
+            unsafe { CStr::from_ptr(existing_text) }.to_string_lossy()
+        };
+        let keyboard = Keyboard::new(
+            KeyboardLayout::configured(),
+            input_mode,
+            line_mode,
+            max_chars,
+            &existing_text,
+        );
+        debug!(
+            "app requested {:?} keyboard ({input_mode:?}, {line_mode:?})",
+            keyboard.layout()
+        );
+
+        let overlay = match self.show_internal_overlay(
+            KEYBOARD_OVERLAY_KEY,
+            KEYBOARD_WIDTH,
+            KEYBOARD_POSITION,
+            MESSAGE_Z_ORDER,
+            keyboard.render(),
+        ) {
+            Ok(overlay) => overlay,
+            Err(e) => {
+                warn!("Failed to show keyboard: {e:?}");
+                return vr::EVROverlayError::RequestFailed;
+            }
+        };
+        *state = Some(KeyboardState {
+            keyboard,
+            overlay: Some(overlay),
+            target,
+            user_value,
+        });
+        vr::EVROverlayError::None
+    }
+
+    /// Presses the key the laser clicked, if it clicked the virtual keyboard.
+    fn click_keyboard(&self, overlay: OverlayKey, uv: Vec2) {
+        let key = self
+            .keyboard
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|state| state.overlay == Some(overlay))
+            .and_then(|state| state.keyboard.key_at(uv));
+        if let Some(key) = key {
+            self.press_key(key);
+        }
+    }
+
+    /// Handles a key press on the virtual keyboard.
+    fn press_key(&self, key: Key) {
+        let mut state = self.keyboard.lock().unwrap();
+        let Some(state) = state.as_mut().filter(|state| state.overlay.is_some()) else {
+            return;
+        };
+        let (ty, input) = match key {
+            Key::Char(c) if state.keyboard.type_char(c) => {
+                (vr::EVREventType::KeyboardCharInput, c.to_string())
+            }
+            Key::Backspace if state.keyboard.backspace() => {
+                (vr::EVREventType::KeyboardCharInput, "\x08".to_string())
+            }
+            Key::Done => (vr::EVREventType::KeyboardDone, String::new()),
+            _ => return,
+        };
+        self.push_keyboard_event(ty, state, &input);
+
+        if key == Key::Done {
+            self.dismiss_keyboard(state);
+        } else if let Err(e) =
+            self.redraw_internal_overlay(state.overlay.unwrap(), state.keyboard.render())
+        {
+            warn!("Failed to redraw keyboard: {e:?}");
+        }
+    }
+
+    fn dismiss_keyboard(&self, state: &mut KeyboardState) {
+        if let Some(overlay) = state.overlay.take() {
+            <Self as vr::IVROverlay027_Interface>::DestroyOverlay(self, overlay.data().as_ffi());
+        }
+    }
+
+    fn push_keyboard_event(&self, ty: vr::EVREventType, state: &KeyboardState, input: &str) {
+        let mut data = vr::VREvent_Keyboard_t {
+            cNewInput: [0; 8],
+            uUserValue: state.user_value,
+            overlayHandle: state.target,
+        };
+        // Leave room for the null terminator
+        let len = input.len().min(data.cNewInput.len() - 1);
+        for (dst, src) in data.cNewInput.iter_mut().zip(&input.as_bytes()[..len]) {
+            *dst = *src as c_char;
+        }
+        self.events
+            .lock()
+            .unwrap()
+            .push_back(SystemEvent::Keyboard(ty, data));
+    }
+
+    /// Intersects a ray, given in the space of the tracking origin, with an overlay.
+    fn compute_intersection(
+        &self,
+        key: OverlayKey,
+        origin: vr::ETrackingUniverseOrigin,
+        source: Vec3,
+        direction: Vec3,
+    ) -> Option<Intersection> {
+        let session = self.openxr.session_data.get();
+        let overlays = self.overlays.read().unwrap();
+        let overlay = overlays.get(key)?;
+        let overlay_origin = overlay
+            .transform
+            .as_ref()
+            .map(|(o, _)| *o)
+            .unwrap_or(session.current_origin);
+        if overlay_origin == origin {
+            return overlay.intersect(source, direction);
+        }
+
+        // Move the ray into the overlay's space, and the hit back out of it.
+        let xr::Posef {
+            position: p,
+            orientation: o,
+        } = session
+            .get_space_for_origin(origin)
+            .locate(
+                session.get_space_for_origin(overlay_origin),
+                self.openxr.display_time.get(),
+            )
+            .ok()?
+            .pose;
+        let position = vec3(p.x, p.y, p.z);
+        let rotation = Quat::from_xyzw(o.x, o.y, o.z, o.w);
+        let hit = overlay.intersect(rotation * source + position, rotation * direction)?;
+        let inverse = rotation.inverse();
+        Some(Intersection {
+            point: inverse * (hit.point - position),
+            normal: inverse * hit.normal,
+            ..hit
+        })
+    }
+
+    /// Whether the laser has anything to do: a visible overlay takes mouse input or is the
+    /// virtual keyboard, or the overlay the laser was on still needs to hear that it left.
+    pub fn wants_laser(&self) -> bool {
+        let keyboard = self
+            .keyboard
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|state| state.overlay);
+        let overlays = self.overlays.read().unwrap();
+        let laser = self.laser.lock().unwrap();
+        laser.hovered.is_some()
+            || laser.pressed.is_some()
+            || overlays.iter().any(|(key, overlay)| {
+                overlay.visible
+                    && (overlay.input_method == vr::VROverlayInputMethod::Mouse
+                        || keyboard == Some(key))
+            })
+    }
+
+    /// Finds the overlay under the controller laser and sends mouse events to it, given the
+    /// controller's pose in the space of the tracking origin and how long the last frame took.
+    pub fn update_laser(
+        &self,
+        origin: vr::ETrackingUniverseOrigin,
+        controller: Option<(Hand, vr::HmdMatrix34_t)>,
+        pointer: PointerState,
+        frame_time: Duration,
+    ) {
+        let hit = controller.and_then(|(_, controller)| {
+            let pose: xr::Posef = controller.into();
+            let o = pose.orientation;
+            let source = vec3(pose.position.x, pose.position.y, pose.position.z);
+            // Controllers point down -Z
+            let direction = Quat::from_xyzw(o.x, o.y, o.z, o.w) * Vec3::NEG_Z;
+
+            let overlays = self.overlays.read().unwrap();
+            // Cursors sit right in front of their overlay, and would block the laser.
+            let cursors: Vec<OverlayKey> = overlays.values().filter_map(|o| o.cursor).collect();
+            let candidates: Vec<OverlayKey> = overlays
+                .iter()
+                .filter(|(key, o)| {
+                    o.visible && o.z_order != SKYBOX_Z_ORDER && !cursors.contains(key)
+                })
+                .map(|(key, _)| key)
+                .collect();
+            drop(overlays);
+            candidates
+                .into_iter()
+                .filter_map(|key| {
+                    self.compute_intersection(key, origin, source, direction)
+                        .map(|hit| (key, hit))
+                })
+                .min_by(|(_, a), (_, b)| a.distance.total_cmp(&b.distance))
+        });
+        let current_origin = self.openxr.session_data.get().current_origin;
+
+        // Events and cursors only need the overlays read, so apps setting up overlays on other
+        // threads aren't held up every frame.
+        let overlays = self.overlays.read().unwrap();
+        let mut laser = self.laser.lock().unwrap();
+        laser.hand = controller.map(|(hand, _)| hand);
+        // The overlay that was hit may have been destroyed since the lock was last held.
+        let mouse =
+            hit.and_then(|(key, hit)| overlays.get(key).map(|o| (key, o.uv_to_mouse(hit.uv))));
+        let hit = hit.filter(|_| mouse.is_some());
+        let hovered = hit.map(|(key, _)| key);
+        let send = |key: OverlayKey, event: OverlayEvent| {
+            let Some(overlay) = overlays
+                .get(key)
+                .filter(|o| o.input_method == vr::VROverlayInputMethod::Mouse)
+            else {
+                return;
+            };
+            let wanted = match &event {
+                OverlayEvent::Scroll(vr::EVREventType::ScrollSmooth, _) => {
+                    overlay.has_flag(vr::VROverlayFlags::SendVRSmoothScrollEvents)
+                }
+                OverlayEvent::Scroll(..) => {
+                    overlay.has_flag(vr::VROverlayFlags::SendVRDiscreteScrollEvents)
+                        || overlay.has_flag(vr::VROverlayFlags::ShowTouchPadScrollWheel)
+                }
+                _ => true,
+            };
+            if wanted {
+                overlay.push_event(event);
+            }
+        };
+
+        if laser.hovered != hovered {
+            trace!(
+                "hovered overlay changed: {:?} -> {hovered:?}",
+                laser.hovered
+            );
+            if let Some(old) = laser.hovered {
+                send(old, OverlayEvent::Focus(vr::EVREventType::FocusLeave));
+            }
+            if let Some(new) = hovered {
+                send(new, OverlayEvent::Focus(vr::EVREventType::FocusEnter));
+                send(
+                    new,
+                    OverlayEvent::Focus(vr::EVREventType::OverlayFocusChanged),
+                );
+            }
+            laser.hovered = hovered;
+        }
+
+        if let Some((key, position)) = mouse {
+            send(
+                key,
+                OverlayEvent::mouse(vr::EVREventType::MouseMove, position, 0),
+            );
+        }
+
+        let button = vr::EVRMouseButton::Left as u32;
+        let mut clicked = None;
+        match (laser.pressed, pointer.trigger) {
+            (None, true) => {
+                clicked = hit.map(|(key, hit)| (key, hit.uv));
+                if let Some((key, position)) = mouse {
+                    send(
+                        key,
+                        OverlayEvent::mouse(vr::EVREventType::MouseButtonDown, position, button),
+                    );
+                    laser.pressed = Some((key, position));
+                }
+            }
+            (Some((key, last)), false) => {
+                let position = mouse
+                    .filter(|(hovered, _)| *hovered == key)
+                    .map_or(last, |(_, position)| position);
+                send(
+                    key,
+                    OverlayEvent::mouse(vr::EVREventType::MouseButtonUp, position, button),
+                );
+                laser.pressed = None;
+            }
+            (Some((key, _)), true) => {
+                if let Some((_, position)) = mouse.filter(|(hovered, _)| *hovered == key) {
+                    laser.pressed = Some((key, position));
+                }
+            }
+            (None, false) => {}
+        }
+
+        // Discrete scrolls act like a mouse wheel: one notch each time the stick is pushed.
+        let scrolled = pointer.scroll.abs().max_element() >= DISCRETE_SCROLL_THRESHOLD;
+        if let Some((key, _)) = mouse {
+            if scrolled && !laser.scrolled {
+                let notch = pointer.scroll.round().clamp(Vec2::NEG_ONE, Vec2::ONE);
+                send(
+                    key,
+                    OverlayEvent::scroll(vr::EVREventType::ScrollDiscrete, notch),
+                );
+            }
+            if pointer.scroll != Vec2::ZERO {
+                // Scroll by how long the stick was held, so the speed doesn't follow the frame rate.
+                let delta = pointer.scroll * SMOOTH_SCROLL_SPEED * frame_time.as_secs_f32();
+                send(
+                    key,
+                    OverlayEvent::scroll(vr::EVREventType::ScrollSmooth, delta),
+                );
+            }
+        }
+        laser.scrolled = scrolled;
+
+        // Put cursors where the laser (or the app) says they are.
+        let hovered_uv = hit.map(|(key, hit)| (key, hit.uv));
+        let cursors: Vec<_> = overlays
+            .iter()
+            .filter_map(|(key, target)| {
+                let cursor = target.cursor?;
+                let uv = target.cursor_override.or_else(|| {
+                    hovered_uv
+                        .filter(|(hovered, _)| *hovered == key)
+                        .map(|(_, uv)| uv)
+                })?;
+                let pose = target.pose_at_uv(uv, CURSOR_OFFSET)?;
+                let origin = target
+                    .transform
+                    .as_ref()
+                    .map_or(current_origin, |(origin, _)| *origin);
+                Some((cursor, origin, pose))
+            })
+            .collect();
+        for (cursor, origin, pose) in cursors {
+            if let Some(cursor) = overlays.get(cursor) {
+                *cursor.cursor_transform.lock().unwrap() = Some((origin, pose.into()));
+            }
+        }
+
+        // Typing redraws or destroys the keyboard overlay.
+        drop(overlays);
+        drop(laser);
+        if let Some((key, uv)) = clicked {
+            self.click_keyboard(key, uv);
+        }
+    }
+
+    /// Moves gamepad focus, telling the overlays that lost and gained it.
+    fn set_gamepad_focus(
+        &self,
+        overlays: &mut SlotMap<OverlayKey, Overlay>,
+        focus: Option<OverlayKey>,
+    ) {
+        let mut current = self.gamepad_focus.lock().unwrap();
+        if *current == focus {
+            return;
+        }
+        debug!("gamepad focus changed: {:?} -> {focus:?}", *current);
+        if let Some(old) = current.and_then(|key| overlays.get(key)) {
+            old.push_event(OverlayEvent::Focus(
+                vr::EVREventType::OverlayGamepadFocusLost,
+            ));
+        }
+        if let Some(new) = focus.and_then(|key| overlays.get(key)) {
+            new.push_event(OverlayEvent::Focus(
+                vr::EVREventType::OverlayGamepadFocusGained,
+            ));
+        }
+        *current = focus;
+    }
+
+    pub fn get_next_event(&self, size: u32, out: *mut vr::VREvent_t) -> bool {
+        const FUNC: &str = "get_next_event";
+        if out.is_null() {
+            warn!("{FUNC}: Got null event pointer.");
+            return false;
         }
+
+        let mut events = self.events.lock().unwrap();
+        let Some(event) = events.front() else {
+            return false;
+        };
+
+        const DATA_OFFSET: usize = std::mem::offset_of!(vr::VREvent_t, data);
+        // Older VREvent_Keyboard_t versions end before the overlay handle.
+        const MIN_KEYBOARD_EVENT_SIZE: usize =
+            DATA_OFFSET + std::mem::offset_of!(vr::VREvent_Keyboard_t, overlayHandle);
+        let min_size = match event {
+            SystemEvent::Keyboard(..) => MIN_KEYBOARD_EVENT_SIZE,
+        };
+        if (size as usize) < min_size {
+            warn!("{FUNC}: Provided event struct size ({size}) is smaller than required ({min_size}).");
+            return false;
+        }
+
+        let event = events.pop_front().unwrap();
+        let ty = match &event {
+            SystemEvent::Keyboard(ty, _) => *ty,
+        };
+        // See Input::get_next_event - VREvent_t may be smaller than ours.
+        unsafe {
+            (&raw mut (*out).eventType).write(ty as u32);
+            (&raw mut (*out).trackedDeviceIndex).write(vr::k_unTrackedDeviceIndex_Hmd);
+            (&raw mut (*out).eventAgeSeconds).write(0.0);
+            match event {
+                SystemEvent::Keyboard(_, keyboard) => {
+                    let data = &raw mut (*out).data.keyboard;
+                    (&raw mut (*data).cNewInput).write(keyboard.cNewInput);
+                    (&raw mut (*data).uUserValue).write(keyboard.uUserValue);
+                    if size as usize >= MIN_KEYBOARD_EVENT_SIZE + std::mem::size_of::<u64>() {
+                        (&raw mut (*data).overlayHandle).write(keyboard.overlayHandle);
+                    }
+                }
+            }
+        }
+        true
     }
 
     pub fn set_skybox(
@@ -57,28 +845,102 @@ impl OverlayMan {
         let mut skybox = self.skybox.write().unwrap();
 
         match textures.len() {
+            1..=2
+                if !self
+                    .openxr
+                    .enabled_extensions
+                    .khr_composition_layer_equirect2 =>
+            {
+                warn!(
+                    "khr_composition_layer_equirect2 unsupported, approximating equirect skybox with quads"
+                );
+                // Without equirect layers, wrap the horizon band of the texture around the
+                // user as four quads. The poles are left out, since a quad can't reasonably
+                // approximate them.
+                for (idx, (texture, eye)) in textures.iter().zip(skybox_eyes(textures)).enumerate()
+                {
+                    for strip in 0..4 {
+                        let name = CString::new(format!("__xrizer_skybox_{idx}_{strip}")).unwrap();
+                        let key = overlays.insert(Overlay::internal(name));
+                        skybox.push(key);
+                        let overlay = overlays.get_mut(key).unwrap();
+                        let u_min = strip as f32 / 4.0;
+                        overlay.bounds = vr::VRTextureBounds_t {
+                            uMin: u_min,
+                            uMax: u_min + 0.25,
+                            vMin: 0.25,
+                            vMax: 0.75,
+                        };
+                        if overlay.set_texture(key, session, *texture).is_err() {
+                            return Err(vr::EVRCompositorError::InvalidTexture);
+                        };
+                        overlay.visible = true;
+                        overlay.width = SKYBOX_SIZE * 2.0;
+                        overlay.kind = OverlayKind::Quad;
+                        overlay.z_order = SKYBOX_Z_ORDER;
+                        overlay.eye_visibility = *eye;
+
+                        // the center of the texture is straight ahead, and u increases to the right
+                        let yaw = (u_min + 0.125 - 0.5) * 2.0 * PI;
+                        let (sin, cos) = yaw.sin_cos();
+                        let (half_sin, half_cos) = (-yaw / 2.0).sin_cos();
+                        let pose = xr::Posef {
+                            position: xr::Vector3f {
+                                x: sin * SKYBOX_SIZE,
+                                y: 0.0,
+                                z: -cos * SKYBOX_SIZE,
+                            },
+                            orientation: xr::Quaternionf {
+                                x: 0.0,
+                                y: half_sin,
+                                z: 0.0,
+                                w: half_cos,
+                            },
+                        };
+                        overlay.transform =
+                            Some((vr::ETrackingUniverseOrigin::Standing, pose.into()));
+                    }
+                }
+            }
             1..=2 => {
-                // only single equirect supported for now, ignore any 2nd one
+                for (idx, (texture, eye)) in textures.iter().zip(skybox_eyes(textures)).enumerate()
+                {
+                    let name = CString::new(format!("__xrizer_skybox_{idx}")).unwrap();
+                    let key = overlays.insert(Overlay::internal(name));
+                    skybox.push(key);
+                    let overlay = overlays.get_mut(key).unwrap();
+                    if overlay.set_texture(key, session, *texture).is_err() {
+                        return Err(vr::EVRCompositorError::InvalidTexture);
+                    };
+                    overlay.visible = true;
+                    overlay.width = SKYBOX_SIZE; // for equirect this becomes radius
+                    overlay.kind = OverlayKind::Sphere(*self.skybox_coverage.lock().unwrap());
+                    overlay.z_order = SKYBOX_Z_ORDER;
+                    overlay.eye_visibility = *eye;
+                }
+            }
+            6 if self.openxr.enabled_extensions.khr_composition_layer_cube => {
+                // OpenVR faces are front, back, left, right, top, bottom
+                // OpenXR faces are +X, -X, +Y, -Y, +Z, -Z
+                const FACE_ORDER: [usize; 6] = [3, 2, 4, 5, 1, 0];
+                let faces = FACE_ORDER.map(|idx| textures[idx]);
+
                 let name = CString::new("__xrizer_skybox").unwrap();
-                let key = overlays.insert(Overlay::new(name.clone(), name));
+                let key = overlays.insert(Overlay::internal(name));
+                skybox.push(key);
                 let overlay = overlays.get_mut(key).unwrap();
-                if overlay
-                    .set_texture(key, session, *textures.first().unwrap())
-                    .is_err()
-                {
+                if overlay.set_textures(key, session, &faces).is_err() {
                     return Err(vr::EVRCompositorError::InvalidTexture);
                 };
                 overlay.visible = true;
-                overlay.width = SKYBOX_SIZE; // for equirect this becomes radius
-                overlay.kind = OverlayKind::Sphere;
+                overlay.kind = OverlayKind::Cube;
                 overlay.z_order = SKYBOX_Z_ORDER;
-                skybox.push(key);
             }
             6 => {
                 for (idx, texture) in textures.iter().enumerate() {
                     // 6 quads forming a cursed box
                     let name = CString::new(format!("__xrizer_skybox_{idx}")).unwrap();
-                    let key = overlays.insert(Overlay::new(name.clone(), name));
+                    let key = overlays.insert(Overlay::internal(name));
                     let overlay = overlays.get_mut(key).unwrap();
                     if overlay.set_texture(key, session, *texture).is_err() {
                         return Err(vr::EVRCompositorError::InvalidTexture);
@@ -88,31 +950,35 @@ impl OverlayMan {
                     overlay.kind = OverlayKind::Quad;
                     overlay.z_order = SKYBOX_Z_ORDER;
 
+                    // Faces are in OpenVR order (front, back, left, right, top, bottom), each
+                    // as a camera in the center looking at it would see it. Quads face +Z, so
+                    // each one is turned towards the center with its up matching the camera's:
+                    // the top face's up points back, and the bottom face's up points forward.
                     #[rustfmt::skip]
                     const QUAD_POSES: [xr::Posef; 6] = [
                         xr::Posef { // front
                             position: xr::Vector3f { x: 0.0, y: 0.0, z: -SKYBOX_SIZE },
-                            orientation: xr::Quaternionf { x: 0.0, y: 0.0, z: 1.0, w: 0.0 },
+                            orientation: xr::Quaternionf { x: 0.0, y: 0.0, z: 0.0, w: 1.0 },
                         },
                         xr::Posef { // back
                             position: xr::Vector3f { x: 0.0, y: 0.0, z: SKYBOX_SIZE },
-                            orientation: xr::Quaternionf { x: 1.0, y: 0.0, z: 0.0, w: 0.0 },
+                            orientation: xr::Quaternionf { x: 0.0, y: 1.0, z: 0.0, w: 0.0 },
                         },
                         xr::Posef { // left
                             position: xr::Vector3f { x: -SKYBOX_SIZE, y: 0.0, z: 0.0 },
-                            orientation: xr::Quaternionf { x: FRAC_1_SQRT_2, y: 0.0, z: FRAC_1_SQRT_2, w: 0.0 },
+                            orientation: xr::Quaternionf { x: 0.0, y: FRAC_1_SQRT_2, z: 0.0, w: FRAC_1_SQRT_2 },
                         },
                         xr::Posef { // right
                             position: xr::Vector3f { x: SKYBOX_SIZE, y: 0.0, z: 0.0 },
-                            orientation: xr::Quaternionf { x: -FRAC_1_SQRT_2, y: 0.0, z: FRAC_1_SQRT_2, w: 0.0 },
+                            orientation: xr::Quaternionf { x: 0.0, y: -FRAC_1_SQRT_2, z: 0.0, w: FRAC_1_SQRT_2 },
                         },
-                        xr::Posef { // up
+                        xr::Posef { // top
                             position: xr::Vector3f { x: 0.0, y: SKYBOX_SIZE, z: 0.0 },
-                            orientation: xr::Quaternionf {x: 0.0, y: -FRAC_1_SQRT_2, z: FRAC_1_SQRT_2, w: 0.0 },
+                            orientation: xr::Quaternionf { x: FRAC_1_SQRT_2, y: 0.0, z: 0.0, w: FRAC_1_SQRT_2 },
                         },
-                        xr::Posef { // down
+                        xr::Posef { // bottom
                             position: xr::Vector3f { x: 0.0, y: -SKYBOX_SIZE, z: 0.0 },
-                            orientation: xr::Quaternionf {x: 0.0, y: FRAC_1_SQRT_2, z: FRAC_1_SQRT_2, w: 0.0 },
+                            orientation: xr::Quaternionf { x: -FRAC_1_SQRT_2, y: 0.0, z: 0.0, w: FRAC_1_SQRT_2 },
                         },
                     ];
 
@@ -130,6 +996,43 @@ impl OverlayMan {
         Ok(())
     }
 
+    /// The new session starts without overlay swapchains. This can run inside an overlay call that
+    /// holds the overlays lock (the first SetOverlayTexture starts the real session), so it only
+    /// takes note and leaves the overlays to [`Self::clear_restarted_textures`].
+    pub fn post_session_restart(&self) {
+        self.restarted.store(true, Ordering::Relaxed);
+    }
+
+    /// Marks overlays that haven't been given a texture since the session was recreated as
+    /// untextured. Apps may destroy their textures as soon as they've been set, so the old ones
+    /// can't be copied again - overlays stay hidden until the app sets their textures again.
+    pub fn clear_restarted_textures(&self) {
+        if !self.restarted.swap(false, Ordering::Relaxed) {
+            return;
+        }
+
+        #[macros::any_graphics(AnySwapchainMap)]
+        fn has_swapchain<G: xr::Graphics>(map: &SwapchainMap<G>, key: OverlayKey) -> bool {
+            map.contains_key(key)
+        }
+
+        let session_data = self.openxr.session_data.get();
+        let mut overlays = self.overlays.write().unwrap();
+        let swapchains = session_data.overlay_data.swapchains.lock().unwrap();
+        for (key, overlay) in overlays.iter_mut() {
+            // Textures set since the restart are already in the new session.
+            if swapchains
+                .as_ref()
+                .is_some_and(|map| map.with_any_graphics::<has_swapchain>(key))
+            {
+                continue;
+            }
+            overlay.rect = None;
+            overlay.textures.clear();
+        }
+        debug!("cleared overlay textures after session restart");
+    }
+
     pub fn clear_skybox(&self) {
         let mut overlays = self.overlays.write().unwrap();
         self.skybox.write().unwrap().drain(..).for_each(|key| {
@@ -140,71 +1043,116 @@ impl OverlayMan {
     pub fn get_layers<'a, G: xr::Graphics>(
         &self,
         session: &'a SessionData,
-        render_skybox: bool,
+        skybox_alpha: f32,
+        frame_index: u64,
     ) -> Vec<OverlayLayer<'a, G>>
     where
         for<'b> &'b AnySwapchainMap: TryInto<&'b SwapchainMap<G>, Error: std::fmt::Display>,
     {
-        let mut overlays = self.overlays.write().unwrap();
+        // Only reads overlays, so apps can keep setting up overlays on other threads.
+        let overlays = self.overlays.read().unwrap();
+        overlays
+            .values()
+            .filter(|overlay| overlay.visible && overlay.rect.is_none())
+            .for_each(|overlay| {
+                overlay.note_untextured_frame();
+            });
+
         let swapchains = session.overlay_data.swapchains.lock().unwrap();
         let Some(swapchains) = swapchains.as_ref() else {
             return Vec::new();
         };
-        let swapchains: &SwapchainMap<G> = swapchains.try_into().unwrap_or_else(|e| {
-            panic!(
-                "Requested layers for API {}, but overlays are using a different API - {e}",
-                std::any::type_name::<G>()
-            )
-        });
+        let swapchains: &SwapchainMap<G> = match swapchains.try_into() {
+            Ok(swapchains) => swapchains,
+            Err(e) => {
+                crate::warn_once!(
+                    "Requested layers for API {}, but overlays are using a different API, skipping them - {e}",
+                    std::any::type_name::<G>()
+                );
+                return Vec::new();
+            }
+        };
 
+        let dashboard = self.dashboard.read().unwrap();
+        let expired_message = self
+            .message
+            .lock()
+            .unwrap()
+            .filter(|(_, shown)| shown.elapsed() >= MESSAGE_TIMEOUT)
+            .map(|(key, _)| key);
         let mut layers = Vec::with_capacity(overlays.len());
-        for (key, overlay) in overlays.iter_mut() {
-            if !overlay.visible {
+        for (key, overlay) in overlays.iter() {
+            let visible = match overlay.dashboard {
+                None => overlay.visible && expired_message != Some(key),
+                Some(DashboardRole::Main) => dashboard.visible && dashboard.active == Some(key),
+                Some(DashboardRole::Thumbnail) => false,
+            };
+            if !visible {
                 continue;
             }
-            if overlay.z_order == SKYBOX_Z_ORDER && !render_skybox {
-                continue;
+            let mut alpha = overlay.alpha;
+            if overlay.z_order == SKYBOX_Z_ORDER {
+                if skybox_alpha <= 0.0 {
+                    continue;
+                }
+                // Without color scale/bias the skybox just pops in.
+                if skybox_alpha < 1.0
+                    && self
+                        .openxr
+                        .enabled_extensions
+                        .khr_composition_layer_color_scale_bias
+                {
+                    alpha = Some(alpha.unwrap_or(1.0) * skybox_alpha);
+                }
             }
             let Some(rect) = overlay.rect else {
                 continue;
             };
 
-            let SwapchainData { swapchain, .. } = swapchains.get(key).unwrap();
-            let space = session.get_space_for_origin(
+            // Overlays only have a swapchain in this session once their texture was copied into it.
+            let Some(SwapchainData { swapchain, .. }) = swapchains.get(key) else {
+                continue;
+            };
+            // The skybox surrounds the app's scene, so it follows the app's tracking space even
+            // when that changes after the skybox was set.
+            let origin = if overlay.z_order == SKYBOX_Z_ORDER {
+                session.current_origin
+            } else {
                 overlay
-                    .transform
-                    .as_ref()
-                    .map(|(o, _)| *o)
-                    .unwrap_or(session.current_origin),
-            );
+                    .placement()
+                    .map_or(session.current_origin, |(o, _)| o)
+            };
+            let space = session.get_space_for_origin(origin);
 
             trace!("overlay rect: {rect:#?}");
 
-            let pose = overlay
-                .transform
-                .as_ref()
-                .map(|(_, t)| (*t).into())
-                .unwrap_or(xr::Posef {
-                    position: xr::Vector3f {
-                        x: 0.0,
-                        y: 0.0,
-                        z: -0.5,
-                    },
-                    orientation: xr::Quaternionf::IDENTITY,
-                });
+            let pose = overlay.pose();
+            let position = vec3(pose.position.x, pose.position.y, pose.position.z);
+            let head = || {
+                session
+                    .get_space_from_type(xr::ReferenceSpaceType::VIEW)
+                    .locate(space, self.openxr.display_time.get())
+                    .ok()
+                    .map(|l| vec3(l.pose.position.x, l.pose.position.y, l.pose.position.z))
+            };
+            // Without a head pose, the overlay's space origin stands in for it.
+            let depth = (self.depth_sort && !overlay.ignores_depth_sort())
+                .then(|| position.distance(head().unwrap_or(Vec3::ZERO)));
+
+            let layer_flags = overlay.layer_flags(alpha);
+            let color = overlay.color_scale(alpha);
+            let ignore_texture_alpha = overlay.has_flag(vr::VROverlayFlags::IgnoreTextureAlpha);
 
             macro_rules! layer_init {
                 ($ty:ident) => {{
                     $ty::new()
                         .space(space)
-                        .layer_flags(
-                            xr::CompositionLayerFlags::BLEND_TEXTURE_SOURCE_ALPHA
-                                | xr::CompositionLayerFlags::UNPREMULTIPLIED_ALPHA,
-                        )
-                        .eye_visibility(xr::EyeVisibility::BOTH)
+                        .layer_flags(layer_flags)
+                        .eye_visibility(eye_visibility)
                         .sub_image(
+                            // overlay textures are always copied into the first layer
                             xr::SwapchainSubImage::new()
-                                .image_array_index(vr::EVREye::Left as u32)
+                                .image_array_index(0)
                                 .swapchain(swapchain)
                                 .image_rect(rect),
                         )
@@ -228,117 +1176,223 @@ impl OverlayMan {
                 }}
             }
 
-            match overlay.kind {
-                OverlayKind::Quad => {
-                    use xr::CompositionLayerQuad;
-                    let layer = layer_init!(CompositionLayerQuad)
-                        .pose(pose)
-                        .size(xr::Extent2Df {
-                            width: overlay.width,
-                            height: rect.extent.height as f32 * overlay.width
-                                / rect.extent.width as f32,
-                        });
-
-                    let layer = lifetime_extend!(CompositionLayerQuad, layer);
-                    let mut layer = OverlayLayer::from(OverlayLayerInner::Quad(layer));
-                    overlay.alpha.iter().for_each(|a| layer.set_alpha(*a));
-                    layers.push((overlay.z_order, layer));
+            let kind = match overlay.kind {
+                OverlayKind::Curved {
+                    curvature,
+                    base_width,
+                } if overlay.auto_curve_range.is_some() => {
+                    let curvature = head().map_or(curvature, |head| {
+                        overlay.auto_curvature(curvature, head.distance(position))
+                    });
+                    if curvature > 0.0 {
+                        OverlayKind::Curved {
+                            curvature,
+                            base_width,
+                        }
+                    } else {
+                        OverlayKind::Quad
+                    }
                 }
-                // SetOverlayCurvature checks for khr_composition_layer_cylinder
-                OverlayKind::Curved { curvature } => {
-                    let radius = overlay.width / (2.0 * PI * curvature);
-                    let pos = vec3(pose.position.x, pose.position.y, pose.position.z);
-                    let rot = Quat::from_xyzw(
-                        pose.orientation.x,
-                        pose.orientation.y,
-                        pose.orientation.z,
-                        pose.orientation.w,
-                    );
+                kind => kind,
+            };
 
-                    let center = pos + rot.mul_vec3(Vec3::Z * radius);
-                    let angle = 2.0 * (overlay.width / (2.0 * radius));
+            for (eye_visibility, rect) in overlay.eye_views(rect) {
+                match kind {
+                    OverlayKind::Quad => {
+                        use xr::CompositionLayerQuad;
+                        let layer = layer_init!(CompositionLayerQuad)
+                            .pose(pose)
+                            .size(overlay.quad_size(rect));
 
-                    use xr::CompositionLayerCylinderKHR;
-                    let layer = layer_init!(CompositionLayerCylinderKHR)
-                        .radius(radius)
-                        .central_angle(angle)
-                        .aspect_ratio(rect.extent.height as f32 / rect.extent.width as f32)
-                        .pose(xr::Posef {
-                            orientation: pose.orientation,
-                            position: xr::Vector3f {
-                                x: center.x,
-                                y: center.y,
-                                z: center.z,
-                            },
-                        });
+                        let layer = lifetime_extend!(CompositionLayerQuad, layer);
+                        let mut layer = OverlayLayer::from(OverlayLayerInner::Quad(layer));
+                        layer.set_color_scale(color, alpha, ignore_texture_alpha);
+                        layers.push((overlay.z_order, depth, layer));
+                    }
+                    // SetOverlayCurvature checks for khr_composition_layer_cylinder
+                    OverlayKind::Curved {
+                        curvature,
+                        base_width,
+                    } => {
+                        let (radius, angle) = overlay.curvature_mode.cylinder_shape(
+                            overlay.width,
+                            base_width,
+                            curvature,
+                        );
+                        let pos = vec3(pose.position.x, pose.position.y, pose.position.z);
+                        let rot = Quat::from_xyzw(
+                            pose.orientation.x,
+                            pose.orientation.y,
+                            pose.orientation.z,
+                            pose.orientation.w,
+                        );
 
-                    let layer = lifetime_extend!(CompositionLayerCylinderKHR, layer);
-                    let mut layer = OverlayLayer::from(OverlayLayerInner::Cylinder(layer));
-                    overlay.alpha.iter().for_each(|a| layer.set_alpha(*a));
-                    layers.push((overlay.z_order, layer));
-                }
-                // SetSkyboxOverride checks for khr_composition_layer_equirect2
-                OverlayKind::Sphere => {
-                    const HORIZONTAL_RAD: f32 = 2.0 * PI;
-                    const VERTICAL_RAD_HIGH: f32 = 0.5 * PI;
-                    const VERTICAL_RAD_LOW: f32 = -0.5 * PI;
-
-                    use xr::CompositionLayerEquirect2KHR;
-                    let layer = layer_init!(CompositionLayerEquirect2KHR)
-                        .radius(overlay.width)
-                        .central_horizontal_angle(HORIZONTAL_RAD)
-                        .upper_vertical_angle(VERTICAL_RAD_HIGH)
-                        .lower_vertical_angle(VERTICAL_RAD_LOW)
-                        .pose(pose);
-
-                    let layer = lifetime_extend!(CompositionLayerEquirect2KHR, layer);
-                    let mut layer = OverlayLayer::from(OverlayLayerInner::Equirect2(layer));
-                    overlay.alpha.iter().for_each(|a| layer.set_alpha(*a));
-                    layers.push((overlay.z_order, layer));
+                        let center = pos + rot.mul_vec3(Vec3::Z * radius);
+
+                        use xr::CompositionLayerCylinderKHR;
+                        let layer = layer_init!(CompositionLayerCylinderKHR)
+                            .radius(radius)
+                            .central_angle(angle)
+                            .aspect_ratio(rect.extent.height as f32 / rect.extent.width as f32)
+                            .pose(xr::Posef {
+                                orientation: pose.orientation,
+                                position: xr::Vector3f {
+                                    x: center.x,
+                                    y: center.y,
+                                    z: center.z,
+                                },
+                            });
+
+                        let layer = lifetime_extend!(CompositionLayerCylinderKHR, layer);
+                        let mut layer = OverlayLayer::from(OverlayLayerInner::Cylinder(layer));
+                        layer.set_color_scale(color, alpha, ignore_texture_alpha);
+                        layers.push((overlay.z_order, depth, layer));
+                    }
+                    // set_skybox checks for khr_composition_layer_equirect2
+                    OverlayKind::Sphere(coverage) => {
+                        use xr::CompositionLayerEquirect2KHR;
+                        let layer = layer_init!(CompositionLayerEquirect2KHR)
+                            .radius(overlay.width)
+                            .central_horizontal_angle(coverage.horizontal)
+                            .upper_vertical_angle(coverage.upper)
+                            .lower_vertical_angle(coverage.lower)
+                            .pose(pose);
+
+                        let layer = lifetime_extend!(CompositionLayerEquirect2KHR, layer);
+                        let mut layer = OverlayLayer::from(OverlayLayerInner::Equirect2(layer));
+                        layer.set_color_scale(color, alpha, ignore_texture_alpha);
+                        layers.push((overlay.z_order, depth, layer));
+                    }
+                    // set_skybox checks for khr_composition_layer_cube
+                    OverlayKind::Cube => {
+                        use xr::CompositionLayerCubeKHR;
+                        let layer = CompositionLayerCubeKHR::new()
+                            .space(space)
+                            .layer_flags(layer_flags)
+                            .eye_visibility(eye_visibility)
+                            .swapchain(swapchain)
+                            .image_array_index(0)
+                            .orientation(pose.orientation);
+
+                        let layer = lifetime_extend!(CompositionLayerCubeKHR, layer);
+                        let mut layer = OverlayLayer::from(OverlayLayerInner::Cube(layer));
+                        layer.set_color_scale(color, alpha, ignore_texture_alpha);
+                        layers.push((overlay.z_order, depth, layer));
+                    }
                 }
             }
         }
 
-        // Sort by z_order asc
-        layers.sort_by(|a, b| a.0.cmp(&b.0));
-
-        let sorted_layers: Vec<OverlayLayer<_>> = layers.into_iter().map(|(_, l)| l).collect();
+        let sorted_layers: Vec<OverlayLayer<_>> = sort_layers(layers)
+            .into_iter()
+            .map(|mut l| {
+                l.frame_index = frame_index;
+                l
+            })
+            .collect();
 
         trace!("returning {} layers", sorted_layers.len());
         sorted_layers
     }
 }
 
+/// Sorts layers by z order ascending. Layers with a depth are additionally sorted back to front
+/// amongst themselves, while layers without one keep their z order position.
+fn sort_layers<T>(mut layers: Vec<(i64, Option<f32>, T)>) -> Vec<T> {
+    layers.sort_by_key(|(z_order, ..)| *z_order);
+    let depth_slots: Vec<bool> = layers.iter().map(|(_, depth, _)| depth.is_some()).collect();
+    let (mut by_depth, fixed): (Vec<_>, Vec<_>) = layers
+        .into_iter()
+        .partition(|(_, depth, _)| depth.is_some());
+    by_depth.sort_by(|(_, a, _), (_, b, _)| b.unwrap().total_cmp(&a.unwrap()));
+
+    let mut by_depth = by_depth.into_iter();
+    let mut fixed = fixed.into_iter();
+    depth_slots
+        .into_iter()
+        .map(|slot| {
+            let (.., layer) = if slot { by_depth.next() } else { fixed.next() }.unwrap();
+            layer
+        })
+        .collect()
+}
+
+/// Overlay layers are always composited on top of the scene. XR_KHR_composition_layer_depth can't
+/// occlude them: its depth info may only be chained to projection views, not to the quad,
+/// cylinder, equirect or cube layers we submit, and OpenVR has no way to give an overlay a depth
+/// texture in the first place.
 pub struct OverlayLayer<'a, G: xr::Graphics> {
     /// Only ever None during next_chain_insert
     layer: Option<OverlayLayerInner<'a, G>>,
     color_bias_khr: Option<Box<xr::sys::CompositionLayerColorScaleBiasKHR>>,
+    /// The frame this layer was built for
+    frame_index: u64,
 }
 
 impl<G: xr::Graphics> OverlayLayer<'_, G> {
+    /// Layers reference the swapchain images of the frame they were built in, which are released
+    /// by the next frame - so layers must never outlive their frame.
+    pub fn is_for_frame(&self, frame_index: u64) -> bool {
+        if self.frame_index != frame_index {
+            warn!(
+                "Rejecting stale overlay layer built for frame {} during frame {frame_index}",
+                self.frame_index
+            );
+            return false;
+        }
+        true
+    }
+
     fn set_alpha(&mut self, alpha: f32) {
-        // only one instance is stored, so this would cause segfault due to UAF
-        debug_assert!(
-            self.color_bias_khr.is_none(),
-            "attempted to set_alpha on the same CompositorLayer twice!"
-        );
+        self.color_scale_bias().color_scale.a = alpha;
+    }
 
-        self.color_bias_khr = {
+    /// Scales each color channel and alpha separately, only chaining a color scale/bias if
+    /// either is set. With `ignore_texture_alpha`, the alpha replaces the texture's instead of
+    /// scaling it.
+    fn set_color_scale(
+        &mut self,
+        color: Option<[f32; 3]>,
+        alpha: Option<f32>,
+        ignore_texture_alpha: bool,
+    ) {
+        if let Some([r, g, b]) = color {
+            let scale = &mut self.color_scale_bias().color_scale;
+            (scale.r, scale.g, scale.b) = (r, g, b);
+        }
+        match alpha {
+            Some(alpha) if ignore_texture_alpha => {
+                let scale_bias = self.color_scale_bias();
+                scale_bias.color_scale.a = 0.0;
+                scale_bias.color_bias.a = alpha;
+            }
+            Some(alpha) => self.set_alpha(alpha),
+            None => {}
+        }
+    }
+
+    /// Returns the color scale/bias struct chained to this layer, inserting it if necessary.
+    /// Only one instance is ever chained, later updates patch it in place.
+    fn color_scale_bias(&mut self) -> &mut xr::sys::CompositionLayerColorScaleBiasKHR {
+        if self.color_bias_khr.is_none() {
             let mut payload = Box::new(xr::sys::CompositionLayerColorScaleBiasKHR {
                 ty: xr::StructureType::COMPOSITION_LAYER_COLOR_SCALE_BIAS_KHR,
                 next: std::ptr::null(),
                 color_bias: Default::default(),
                 color_scale: xr::Color4f {
-                    a: alpha,
-                    ..Default::default()
+                    r: 1.0,
+                    g: 1.0,
+                    b: 1.0,
+                    a: 1.0,
                 },
             });
 
             let payload_ptr = payload.as_mut() as *mut _ as *mut xr::sys::BaseInStructure;
             unsafe { self.next_chain_insert(payload_ptr) };
+            self.color_bias_khr = Some(payload);
+        }
 
-            Some(payload)
-        };
+        self.color_bias_khr.as_mut().unwrap()
     }
 
     /// Insert the given item as the first element in the next chain.
@@ -366,6 +1420,12 @@ impl<G: xr::Graphics> OverlayLayer<'_, G> {
                 raw.next = item as *const _;
                 OverlayLayerInner::Equirect2(xr::CompositionLayerEquirect2KHR::from_raw(raw))
             }
+            OverlayLayerInner::Cube(cube) => {
+                let mut raw = cube.into_raw();
+                new_elem.next = raw.next as _;
+                raw.next = item as *const _;
+                OverlayLayerInner::Cube(xr::CompositionLayerCubeKHR::from_raw(raw))
+            }
         });
     }
 }
@@ -375,6 +1435,7 @@ impl<'a, G: xr::Graphics> From<OverlayLayerInner<'a, G>> for OverlayLayer<'a, G>
         Self {
             layer: Some(value),
             color_bias_khr: None,
+            frame_index: 0,
         }
     }
 }
@@ -392,6 +1453,7 @@ pub enum OverlayLayerInner<'a, G: xr::Graphics> {
     Cylinder(xr::CompositionLayerCylinderKHR<'a, G>),
     // Skybox
     Equirect2(xr::CompositionLayerEquirect2KHR<'a, G>),
+    Cube(xr::CompositionLayerCubeKHR<'a, G>),
 }
 
 impl<'a, G: xr::Graphics> Deref for OverlayLayerInner<'a, G> {
@@ -401,6 +1463,7 @@ impl<'a, G: xr::Graphics> Deref for OverlayLayerInner<'a, G> {
             OverlayLayerInner::Quad(quad) => quad.deref(),
             OverlayLayerInner::Cylinder(cylinder) => cylinder.deref(),
             OverlayLayerInner::Equirect2(equirect2) => equirect2.deref(),
+            OverlayLayerInner::Cube(cube) => cube.deref(),
         }
     }
 }
@@ -413,6 +1476,25 @@ pub(crate) struct SwapchainData<G: xr::Graphics> {
     swapchain: xr::Swapchain<G>,
     info: xr::SwapchainCreateInfo<G>,
     initial_format: G::Format,
+    images: Vec<G::SwapchainImage>,
+    /// Index of the image holding the latest copy of the overlay's texture.
+    released_image: Option<usize>,
+}
+
+/// The runtime's copy of an overlay texture, as handed out by GetOverlayTexture.
+struct NativeTexture {
+    handle: u64,
+    width: u32,
+    height: u32,
+    format: u32,
+    api: vr::ETextureType,
+    color_space: vr::EColorSpace,
+}
+
+/// Logs a failed overlay swapchain call and reports it to the app as an invalid texture.
+fn swapchain_failed(operation: &str, err: xr::sys::Result) -> vr::EVROverlayError {
+    crate::warn_once!("Overlay swapchain {operation} failed: {err}");
+    vr::EVROverlayError::InvalidTexture
 }
 
 pub(crate) type SwapchainMap<G> = SecondaryMap<OverlayKey, SwapchainData<G>>;
@@ -423,10 +1505,152 @@ pub struct OverlaySessionData {
     swapchains: Mutex<Option<AnySwapchainMap>>,
 }
 
+#[derive(Copy, Clone)]
 enum OverlayKind {
     Quad,
-    Curved { curvature: f32 },
-    Sphere,
+    Curved {
+        curvature: f32,
+        /// The overlay width at the time the curvature was set
+        base_width: f32,
+    },
+    Sphere(EquirectCoverage),
+    /// A cubemap skybox, with the faces in cube face order (+X, -X, +Y, -Y, +Z, -Z)
+    Cube,
+}
+
+/// How overlay content is fit into the overlay's physical bounds when their aspect ratios differ.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum ContentFit {
+    #[default]
+    Stretch,
+    /// Keep the aspect ratio of the texture and center it, leaving the rest of the overlay empty.
+    Letterbox,
+}
+
+/// How a curved overlay's cylinder changes when its width changes.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum CurvatureMode {
+    /// The central angle stays the same, so the radius scales with the width.
+    #[default]
+    ConstantAngle,
+    /// The radius stays as it was when the curvature was set, so the central angle scales with the width.
+    ConstantRadius,
+}
+
+/// A region of an overlay that controller rays can hit, in mouse coordinates (texture pixels, with
+/// the origin at the bottom left).
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum MaskPrimitive {
+    Rectangle {
+        top_left: Vec2,
+        width: f32,
+        height: f32,
+    },
+    Circle {
+        center: Vec2,
+        radius: f32,
+    },
+}
+
+impl MaskPrimitive {
+    fn contains(&self, point: Vec2) -> bool {
+        match *self {
+            Self::Rectangle {
+                top_left,
+                width,
+                height,
+            } => {
+                let offset = point - top_left;
+                (0.0..=width).contains(&offset.x) && (0.0..=height).contains(&offset.y)
+            }
+            Self::Circle { center, radius } => point.distance_squared(center) <= radius * radius,
+        }
+    }
+}
+
+/// Where a ray hit an overlay. The point and normal are in the space of the ray's origin.
+#[derive(Copy, Clone, PartialEq, Debug)]
+struct Intersection {
+    point: Vec3,
+    normal: Vec3,
+    /// Origin at the bottom left of the overlay
+    uv: Vec2,
+    distance: f32,
+}
+
+/// The part of the sphere an equirect texture covers, in radians. The horizontal angle is
+/// centered straight ahead, the vertical ones are relative to the horizon.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct EquirectCoverage {
+    horizontal: f32,
+    upper: f32,
+    lower: f32,
+}
+
+impl Default for EquirectCoverage {
+    fn default() -> Self {
+        Self {
+            horizontal: 2.0 * PI,
+            upper: 0.5 * PI,
+            lower: -0.5 * PI,
+        }
+    }
+}
+
+impl EquirectCoverage {
+    /// Parses "horizontal,upper,lower" in degrees, e.g. "180,45,-45" for a half panorama.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut angles = value.split(',').map(|a| a.trim().parse::<f32>().ok());
+        let coverage = Self {
+            horizontal: angles.next()??.to_radians(),
+            upper: angles.next()??.to_radians(),
+            lower: angles.next()??.to_radians(),
+        };
+        let full = Self::default();
+        let valid = angles.next().is_none()
+            && coverage.horizontal > 0.0
+            && coverage.horizontal <= full.horizontal
+            && coverage.upper <= full.upper
+            && coverage.lower >= full.lower
+            && coverage.lower < coverage.upper;
+        valid.then_some(coverage)
+    }
+}
+
+static OVERLAY_DISTANCE: LazyLock<f32> =
+    LazyLock::new(|| match std::env::var("XRIZER_OVERLAY_DEFAULT_DISTANCE") {
+        Err(_) => DEFAULT_OVERLAY_DISTANCE,
+        Ok(value) => parse_overlay_distance(&value).unwrap_or_else(|| {
+            warn!("Invalid default overlay distance {value:?}, using {DEFAULT_OVERLAY_DISTANCE}m");
+            DEFAULT_OVERLAY_DISTANCE
+        }),
+    });
+
+/// Parses a distance in meters, which has to put overlays in front of the origin.
+fn parse_overlay_distance(value: &str) -> Option<f32> {
+    value
+        .trim()
+        .parse()
+        .ok()
+        .filter(|distance: &f32| distance.is_finite() && *distance > 0.0)
+}
+
+impl CurvatureMode {
+    /// Returns the radius and central angle of the cylinder for a curved overlay.
+    fn cylinder_shape(self, width: f32, base_width: f32, curvature: f32) -> (f32, f32) {
+        let curvature = curvature.max(f32::EPSILON);
+        let radius = match self {
+            Self::ConstantAngle => width / (2.0 * PI * curvature),
+            Self::ConstantRadius => base_width / (2.0 * PI * curvature),
+        };
+        // The overlay may not wrap around more than the full circle.
+        let min_radius = width / (2.0 * PI);
+        let clamped = radius.clamp(min_radius, MAX_CYLINDER_RADIUS.max(min_radius));
+        if clamped != radius {
+            crate::warn_once!("Clamping curved overlay radius {radius} to {clamped}");
+        }
+        (clamped, width / clamped)
+    }
 }
 
 struct Overlay {
@@ -434,14 +1658,65 @@ struct Overlay {
     name: CString,
     /// Only allowed to be Some if KHR_composition_layer_color_scale_bias is active
     alpha: Option<f32>,
+    /// Per channel tint, same restriction as alpha
+    color: Option<[f32; 3]>,
     width: f32,
+    eye_visibility: xr::EyeVisibility,
+    /// Width of a texel divided by its height
+    texel_aspect: f32,
+    fit: ContentFit,
     visible: bool,
     kind: OverlayKind,
+    /// VROverlayFlags set by the app
+    flags: u32,
+    curvature_mode: CurvatureMode,
+    /// Distances from the user at which the overlay is fully curved and flat
+    auto_curve_range: Option<(f32, f32)>,
+    /// Sort order exposed to apps is always non-negative, internal overlays (like the skybox) use
+    /// negative values to sort below everything else.
     z_order: i64,
     bounds: vr::VRTextureBounds_t,
+    /// Array layer of the submitted texture to show, for layered (i.e. single pass stereo)
+    /// textures. Unlike Submit, SetOverlayTexture has no flag to mark array textures, so the
+    /// layer is set through [`OverlayMan::set_overlay_texture_layer`].
+    texture_layer: u32,
+    /// Overrides the color space of submitted textures, unless set to Auto
+    color_space: vr::EColorSpace,
     transform: Option<(vr::ETrackingUniverseOrigin, vr::HmdMatrix34_t)>,
+    /// Parts of the overlay controller rays can hit - all of it if empty.
+    intersection_mask: Vec<MaskPrimitive>,
+    /// Set through SetOverlayMouseScale, otherwise the texture size
+    mouse_scale: Option<Vec2>,
+    input_method: vr::VROverlayInputMethod,
+    /// Waiting to be polled through PollNextOverlayEvent
+    events: Mutex<VecDeque<OverlayEvent>>,
+    /// Where gamepad focus moves to, indexed by EOverlayDirection
+    neighbors: [Option<OverlayKey>; 4],
+    /// The overlay shown where the laser hits this one
+    cursor: Option<OverlayKey>,
+    /// Fixed UV for the cursor, instead of following the laser
+    cursor_override: Option<Vec2>,
+    /// Where the laser put this overlay, if it's another overlay's cursor. This wins over
+    /// `transform`, and is set while overlays are only read.
+    cursor_transform: Mutex<Option<(vr::ETrackingUniverseOrigin, vr::HmdMatrix34_t)>>,
     compositor: Option<SupportedBackend>,
+    /// Reads back textures drawn with a different graphics API than the session's, whose pixels
+    /// are then uploaded with `compositor`.
+    interop: Option<SupportedBackend>,
     rect: Option<xr::Rect2Di>,
+    dashboard: Option<DashboardRole>,
+    /// The process whose scene this dashboard overlay belongs to
+    scene_process: u32,
+    /// The textures last set, one per swapchain layer
+    textures: Vec<SubmittedTexture>,
+    /// The app has read this overlay's image back, so textures are copied for the next read as
+    /// they're set. Other overlays don't pay for the copy.
+    read_back: bool,
+    /// Frames this overlay has been shown without having a texture
+    untextured_frames: AtomicU32,
+    warned_untextured: AtomicBool,
+    /// Drawn by us (like the skybox or keyboard), so not counted towards the overlay limit
+    internal: bool,
 }
 
 impl Overlay {
@@ -450,9 +1725,16 @@ impl Overlay {
             key,
             name,
             alpha: None,
+            color: None,
             width: 1.0,
+            eye_visibility: xr::EyeVisibility::BOTH,
+            texel_aspect: 1.0,
+            fit: ContentFit::default(),
             visible: false,
             kind: OverlayKind::Quad,
+            flags: 0,
+            curvature_mode: CurvatureMode::default(),
+            auto_curve_range: None,
             z_order: 0,
             bounds: vr::VRTextureBounds_t {
                 uMin: 0.0,
@@ -460,9 +1742,302 @@ impl Overlay {
                 uMax: 1.0,
                 vMax: 1.0,
             },
+            texture_layer: 0,
+            color_space: vr::EColorSpace::Auto,
             transform: None,
+            intersection_mask: Vec::new(),
+            mouse_scale: None,
+            input_method: vr::VROverlayInputMethod::None,
+            events: Default::default(),
+            neighbors: [None; 4],
+            cursor: None,
+            cursor_override: None,
+            cursor_transform: Default::default(),
             compositor: None,
+            interop: None,
             rect: None,
+            dashboard: None,
+            scene_process: 0,
+            textures: Vec::new(),
+            untextured_frames: 0.into(),
+            warned_untextured: false.into(),
+            read_back: false,
+            internal: false,
+        }
+    }
+
+    fn internal(name: CString) -> Self {
+        Self {
+            internal: true,
+            ..Self::new(name.clone(), name)
+        }
+    }
+
+    /// The size of a quad showing the given texture rect. The physical height of the overlay follows
+    /// from its width and texel aspect; letterboxed content is centered within it.
+    fn quad_size(&self, rect: xr::Rect2Di) -> xr::Extent2Df {
+        let content_aspect = rect.extent.height as f32 / rect.extent.width as f32;
+        let height = self.width * content_aspect / self.texel_aspect;
+        match self.fit {
+            ContentFit::Stretch => xr::Extent2Df {
+                width: self.width,
+                height,
+            },
+            ContentFit::Letterbox => {
+                // The quad is centered on its pose, so shrinking it leaves equal margins.
+                let width = self.width.min(height / content_aspect);
+                xr::Extent2Df {
+                    width,
+                    height: width * content_aspect,
+                }
+            }
+        }
+    }
+
+    /// Overlays with an auto curve range flatten out as they move away from the user: fully
+    /// curved at the near end of the range, flat at the far end.
+    fn auto_curvature(&self, curvature: f32, distance: f32) -> f32 {
+        let Some((min, max)) = self.auto_curve_range else {
+            return curvature;
+        };
+        if max <= min {
+            return if distance <= min { curvature } else { 0.0 };
+        }
+        curvature * ((max - distance) / (max - min)).clamp(0.0, 1.0)
+    }
+
+    /// The overlay's tracking origin and transform, if it has one.
+    fn placement(&self) -> Option<(vr::ETrackingUniverseOrigin, vr::HmdMatrix34_t)> {
+        self.cursor_transform.lock().unwrap().or(self.transform)
+    }
+
+    /// Queues an event for PollNextOverlayEvent. Apps that don't poll only ever see the most
+    /// recent events.
+    fn push_event(&self, event: OverlayEvent) {
+        let mut events = self.events.lock().unwrap();
+        // Only the latest position matters to apps that fall behind.
+        if let OverlayEvent::Mouse(vr::EVREventType::MouseMove, mouse) = &event {
+            if let Some(OverlayEvent::Mouse(vr::EVREventType::MouseMove, last)) = events.back_mut()
+            {
+                *last = *mouse;
+                return;
+            }
+        }
+        if events.len() == MAX_OVERLAY_EVENTS {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    /// Overlays without a transform are shown straight in front of the tracking origin, at the
+    /// configured default distance.
+    fn pose(&self) -> xr::Posef {
+        self.placement()
+            .map(|(_, t)| t.into())
+            .unwrap_or(xr::Posef {
+                position: xr::Vector3f {
+                    x: 0.0,
+                    y: 0.0,
+                    z: -*OVERLAY_DISTANCE,
+                },
+                orientation: xr::Quaternionf::IDENTITY,
+            })
+    }
+
+    fn mouse_scale(&self) -> Vec2 {
+        self.mouse_scale.unwrap_or_else(|| {
+            self.rect.map_or(Vec2::ONE, |rect| {
+                vec2(rect.extent.width as f32, rect.extent.height as f32)
+            })
+        })
+    }
+
+    /// Maps overlay UVs to the app's mouse coordinates.
+    fn uv_to_mouse(&self, uv: Vec2) -> Vec2 {
+        uv * self.mouse_scale()
+    }
+
+    /// The pose of a point on the overlay's surface, facing the same way as the overlay.
+    fn pose_at_uv(&self, uv: Vec2, offset: f32) -> Option<xr::Posef> {
+        let rect = self.rect?;
+        if !matches!(self.kind, OverlayKind::Quad) {
+            return None;
+        }
+        let pose = self.pose();
+        let o = pose.orientation;
+        let size = self.quad_size(rect);
+        let local = vec3(
+            (uv.x - 0.5) * size.width,
+            (uv.y - 0.5) * size.height,
+            offset,
+        );
+        let position = Quat::from_xyzw(o.x, o.y, o.z, o.w) * local
+            + vec3(pose.position.x, pose.position.y, pose.position.z);
+        Some(xr::Posef {
+            position: xr::Vector3f {
+                x: position.x,
+                y: position.y,
+                z: position.z,
+            },
+            orientation: o,
+        })
+    }
+
+    /// Intersects a ray, given in the space of the overlay's origin, with the overlay.
+    fn intersect(&self, source: Vec3, direction: Vec3) -> Option<Intersection> {
+        let rect = self.rect?;
+        if !matches!(self.kind, OverlayKind::Quad) {
+            crate::warn_once!("Ray intersection is only supported for flat overlays");
+            return None;
+        }
+
+        let pose = self.pose();
+        let position = vec3(pose.position.x, pose.position.y, pose.position.z);
+        let o = pose.orientation;
+        let rotation = Quat::from_xyzw(o.x, o.y, o.z, o.w);
+        // Quads face +Z
+        let normal = rotation * Vec3::Z;
+        let denom = direction.dot(normal);
+        if denom.abs() < f32::EPSILON {
+            return None;
+        }
+        let distance = (position - source).dot(normal) / denom;
+        if distance < 0.0 {
+            return None;
+        }
+
+        let point = source + direction * distance;
+        let local = rotation.inverse() * (point - position);
+        let size = self.quad_size(rect);
+        let uv = vec2(local.x / size.width + 0.5, local.y / size.height + 0.5);
+        if !(0.0..=1.0).contains(&uv.x) || !(0.0..=1.0).contains(&uv.y) {
+            return None;
+        }
+
+        let mouse = self.uv_to_mouse(uv);
+        if !self.intersection_mask.is_empty()
+            && !self.intersection_mask.iter().any(|p| p.contains(mouse))
+        {
+            return None;
+        }
+
+        Some(Intersection {
+            point,
+            normal,
+            uv,
+            distance: distance * direction.length(),
+        })
+    }
+
+    fn has_flag(&self, flag: vr::VROverlayFlags) -> bool {
+        self.flags & flag as u32 != 0
+    }
+
+    /// Splits side-by-side stereo textures into one view per eye. Everything else is shown as a
+    /// single view with the overlay's own eye visibility.
+    fn eye_views(&self, rect: xr::Rect2Di) -> Vec<(xr::EyeVisibility, xr::Rect2Di)> {
+        let parallel = self.has_flag(vr::VROverlayFlags::SideBySide_Parallel);
+        let crossed = self.has_flag(vr::VROverlayFlags::SideBySide_Crossed);
+        if !parallel && !crossed {
+            return vec![(self.eye_visibility, rect)];
+        }
+
+        let half_width = rect.extent.width / 2;
+        let extent = xr::Extent2Di {
+            width: half_width,
+            height: rect.extent.height,
+        };
+        let left = xr::Rect2Di {
+            offset: rect.offset,
+            extent,
+        };
+        let right = xr::Rect2Di {
+            offset: xr::Offset2Di {
+                x: rect.offset.x + half_width,
+                y: rect.offset.y,
+            },
+            extent,
+        };
+        if crossed {
+            vec![
+                (xr::EyeVisibility::LEFT, right),
+                (xr::EyeVisibility::RIGHT, left),
+            ]
+        } else {
+            vec![
+                (xr::EyeVisibility::LEFT, left),
+                (xr::EyeVisibility::RIGHT, right),
+            ]
+        }
+    }
+
+    /// Sets the sort order, keeping it above the skybox no matter what is passed in.
+    fn set_sort_order(&mut self, order: i64) {
+        self.z_order = order.max(SKYBOX_Z_ORDER + 1);
+    }
+
+    /// Overlays the app wants sorted with the non-scene overlays keep their sort order position
+    /// even with depth sorting.
+    fn ignores_depth_sort(&self) -> bool {
+        self.z_order == SKYBOX_Z_ORDER
+            || self.has_flag(vr::VROverlayFlags::SortWithNonSceneOverlays)
+    }
+
+    /// IgnoreTextureAlpha draws the texture opaque, which makes IsPremultiplied moot - the
+    /// runtime never reads the alpha channel it would describe. An overlay alpha still has to be
+    /// blended though, so the texture's alpha is replaced with it (see
+    /// [`OverlayLayer::set_color_scale`]) and the color treated as unpremultiplied.
+    fn layer_flags(&self, alpha: Option<f32>) -> xr::CompositionLayerFlags {
+        if self.has_flag(vr::VROverlayFlags::IgnoreTextureAlpha) && alpha.is_none() {
+            xr::CompositionLayerFlags::EMPTY
+        } else if self.has_flag(vr::VROverlayFlags::IsPremultiplied)
+            && !self.has_flag(vr::VROverlayFlags::IgnoreTextureAlpha)
+        {
+            xr::CompositionLayerFlags::BLEND_TEXTURE_SOURCE_ALPHA
+        } else {
+            xr::CompositionLayerFlags::BLEND_TEXTURE_SOURCE_ALPHA
+                | xr::CompositionLayerFlags::UNPREMULTIPLIED_ALPHA
+        }
+    }
+
+    /// Premultiplied textures have their color scaled along with the overlay alpha, otherwise
+    /// fading them out would brighten them instead.
+    fn color_scale(&self, alpha: Option<f32>) -> Option<[f32; 3]> {
+        match alpha {
+            Some(alpha)
+                if self.layer_flags(Some(alpha))
+                    == xr::CompositionLayerFlags::BLEND_TEXTURE_SOURCE_ALPHA =>
+            {
+                Some(self.color.unwrap_or([1.0; 3]).map(|c| c * alpha))
+            }
+            _ => self.color,
+        }
+    }
+
+    /// Returns true if this call emitted the warning.
+    fn note_untextured_frame(&self) -> bool {
+        let frames = self
+            .untextured_frames
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |f| {
+                Some(f.saturating_add(1))
+            })
+            .unwrap()
+            .saturating_add(1);
+        if frames < UNTEXTURED_WARN_FRAMES || self.warned_untextured.swap(true, Ordering::Relaxed) {
+            return false;
+        }
+
+        warn!(
+            "Overlay {:?} (key {:?}) has been shown for {} frames without a texture - did the app forget to call SetOverlayTexture?",
+            self.name, self.key, frames
+        );
+        true
+    }
+
+    fn color_space_for(&self, texture: &vr::Texture_t) -> vr::EColorSpace {
+        match self.color_space {
+            vr::EColorSpace::Auto => texture.eColorSpace,
+            space => space,
         }
     }
 
@@ -472,16 +2047,33 @@ impl Overlay {
         session_data: &SessionData,
         texture: vr::Texture_t,
     ) -> Result<(), vr::EVROverlayError> {
-        let backend = self
-            .compositor
-            .get_or_insert_with(|| SupportedBackend::new(&texture, self.bounds));
+        self.set_textures(key, session_data, &[texture])
+    }
 
-        #[macros::any_graphics(SupportedBackend)]
-        fn create_swapchain_map<G: GraphicsBackend>(_: &G) -> AnySwapchainMap
-        where
-            AnySwapchainMap: From<SwapchainMap<G::Api>>,
-        {
-            SwapchainMap::<G::Api>::default().into()
+    /// Sets one texture per layer of the overlay's swapchain - six textures make a cubemap.
+    fn set_textures(
+        &mut self,
+        key: OverlayKey,
+        session_data: &SessionData,
+        textures: &[vr::Texture_t],
+    ) -> Result<(), vr::EVROverlayError> {
+        let texture = textures[0];
+        if self.compositor.is_none() {
+            self.compositor = Some(
+                SupportedBackend::new(&texture, self.bounds)
+                    .ok_or(vr::EVROverlayError::InvalidTexture)?,
+            );
+        }
+        let backend = self.compositor.as_mut().unwrap();
+        // Converting between APIs isn't supported, but it's no reason to take down the app.
+        if let Some(other) = textures.iter().find(|t| t.eType != backend.texture_type()) {
+            warn!(
+                "Overlay {:?} received a {:?} texture, but was previously given {:?} textures",
+                self.name,
+                other.eType,
+                backend.texture_type()
+            );
+            return Err(vr::EVROverlayError::InvalidTexture);
         }
 
         let mut swapchains = session_data.overlay_data.swapchains.lock().unwrap();
@@ -489,86 +2081,474 @@ impl Overlay {
             swapchains.get_or_insert_with(|| backend.with_any_graphics::<create_swapchain_map>(()));
 
         #[macros::any_graphics(SupportedBackend)]
-        fn set_swapchain_texture<G: GraphicsBackend>(
+        fn set_swapchain_textures<G: GraphicsBackend>(
             backend: &mut G,
             session_data: &SessionData,
             texture_bounds: vr::VRTextureBounds_t,
             map: &mut AnySwapchainMap,
             key: OverlayKey,
-            texture: vr::Texture_t,
+            textures: &[vr::Texture_t],
+            texture_layer: u32,
+            color_space: vr::EColorSpace,
+            read_back: bool,
         ) -> Result<xr::Extent2Di, vr::EVROverlayError>
         where
             for<'a> &'a mut SwapchainMap<G::Api>:
                 TryFrom<&'a mut AnySwapchainMap, Error: std::fmt::Display>,
             for<'a> &'a GraphicalSession: TryInto<&'a Session<G::Api>, Error: std::fmt::Display>,
             <G::Api as xr::Graphics>::Format: Eq,
+            <G::Api as xr::Graphics>::SwapchainImage: Copy,
         {
-            let map: &mut SwapchainMap<G::Api> = map.try_into().unwrap_or_else(|e| {
-                panic!(
-                    "Received different texture type for overlay than current ({}) - {e}",
+            if !session_data.uses_api::<G::Api>() {
+                crate::warn_once!(
+                    "Received overlay texture for API {}, but the session uses a different API",
                     std::any::type_name::<G::Api>()
                 );
-            });
-            let Some(b_texture) = G::get_texture(&texture) else {
+                return Err(vr::EVROverlayError::InvalidTexture);
+            }
+            let map: &mut SwapchainMap<G::Api> = match map.try_into() {
+                Ok(map) => map,
+                Err(e) => {
+                    crate::warn_once!(
+                        "Received different texture type for overlay than current ({}) - {e}",
+                        std::any::type_name::<G::Api>()
+                    );
+                    return Err(vr::EVROverlayError::InvalidTexture);
+                }
+            };
+            let Some(b_textures) = textures
+                .iter()
+                .map(G::get_texture)
+                .collect::<Option<Vec<_>>>()
+            else {
                 debug!("received invalid overlay texture handle");
                 return Err(vr::EVROverlayError::InvalidTexture);
             };
-            let tex_swapchain_info =
-                backend.swapchain_info_for_texture(b_texture, texture_bounds, texture.eColorSpace);
-            let mut create_swapchain = || {
-                let mut info = backend.swapchain_info_for_texture(
-                    b_texture,
-                    texture_bounds,
-                    texture.eColorSpace,
+            let cube = b_textures.len() == 6;
+            let layered_info = |info: xr::SwapchainCreateInfo<G::Api>| {
+                // Backends resolve multisampled overlay textures when copying them.
+                let info = xr::SwapchainCreateInfo {
+                    sample_count: 1,
+                    ..info
+                };
+                if cube {
+                    xr::SwapchainCreateInfo {
+                        face_count: 6,
+                        array_size: 1,
+                        ..info
+                    }
+                } else {
+                    info
+                }
+            };
+            let tex_swapchain_info = layered_info(
+                backend
+                    .swapchain_info_for_texture(b_textures[0], texture_bounds, color_space)
+                    .ok_or(vr::EVROverlayError::InvalidTexture)?,
+            );
+            let mut create_swapchain = || -> Result<_, vr::EVROverlayError> {
+                let mut info = layered_info(
+                    backend
+                        .swapchain_info_for_texture(b_textures[0], texture_bounds, color_space)
+                        .ok_or(vr::EVROverlayError::InvalidTexture)?,
                 );
                 let initial_format = info.format;
                 session_data.check_format::<G>(&mut info);
-                let swapchain = session_data.create_swapchain(&info).unwrap();
+                let swapchain = session_data
+                    .create_swapchain(&info)
+                    .map_err(|e| swapchain_failed("creation", e))?;
                 let images = swapchain
                     .enumerate_images()
-                    .expect("Couldn't enumerate swapchain images");
-                backend.store_swapchain_images(images, info.format);
-                SwapchainData {
+                    .map_err(|e| swapchain_failed("image enumeration", e))?;
+                backend.store_swapchain_images(images.clone(), info.format);
+                Ok(SwapchainData {
                     swapchain,
                     info,
                     initial_format,
-                }
+                    images,
+                    released_image: None,
+                })
             };
-            let swapchain = {
-                let data = map
-                    .entry(key)
-                    .unwrap()
-                    .or_insert_with(&mut create_swapchain);
-                if !is_usable_swapchain(&data.info, data.initial_format, &tex_swapchain_info) {
-                    *data = create_swapchain();
+            if !map.contains_key(key) {
+                map.insert(key, create_swapchain()?);
+            }
+            let data = &mut map[key];
+            if !is_usable_swapchain(&data.info, data.initial_format, &tex_swapchain_info) {
+                *data = create_swapchain()?;
+            }
+            let swapchain = &mut data.swapchain;
+            let idx = swapchain
+                .acquire_image()
+                .map_err(|e| swapchain_failed("image acquisition", e))?;
+            swapchain
+                .wait_image(xr::Duration::INFINITE)
+                .map_err(|e| swapchain_failed("image wait", e))?;
+
+            let mut extent = xr::Extent2Di::default();
+            let first_texture = b_textures[0];
+            for (layer, texture) in b_textures.into_iter().enumerate() {
+                extent = backend.copy_overlay_to_swapchain(
+                    texture,
+                    texture_layer,
+                    color_space,
+                    texture_bounds,
+                    idx as usize,
+                    layer as u32,
+                );
+            }
+            // The app may be done with its texture by the time the overlay's image is read.
+            if read_back
+                && backend
+                    .snapshot_texture(0, first_texture, texture_layer)
+                    .is_none()
+            {
+                debug!("failed to copy overlay texture for reading");
+            }
+            swapchain
+                .release_image()
+                .map_err(|e| swapchain_failed("image release", e))?;
+            data.released_image = Some(idx as usize);
+
+            Ok(extent)
+        }
+
+        let color_space = self.color_space_for(&texture);
+        let backend = self.compositor.as_mut().unwrap();
+        let extent = backend.with_any_graphics_mut::<set_swapchain_textures>((
+            session_data,
+            self.bounds,
+            swapchains,
+            key,
+            textures,
+            self.texture_layer,
+            color_space,
+            self.read_back,
+        ))?;
+        self.rect = Some(xr::Rect2Di {
+            extent,
+            offset: xr::Offset2Di::default(),
+        });
+        self.textures = textures
+            .iter()
+            .copied()
+            .map(SubmittedTexture::new)
+            .collect();
+        Ok(())
+    }
+
+    /// Shows pixels drawn by us rather than a texture from the app. The overlay's backend has to
+    /// be set up already.
+    fn set_pixels(
+        &mut self,
+        key: OverlayKey,
+        session_data: &SessionData,
+        extent: xr::Extent2Di,
+        pixels: &[u8],
+    ) -> Result<(), vr::EVROverlayError> {
+        let backend = self
+            .compositor
+            .as_mut()
+            .ok_or(vr::EVROverlayError::InvalidTexture)?;
+        let mut swapchains = session_data.overlay_data.swapchains.lock().unwrap();
+        let swapchains =
+            swapchains.get_or_insert_with(|| backend.with_any_graphics::<create_swapchain_map>(()));
+
+        #[macros::any_graphics(SupportedBackend)]
+        fn upload_swapchain_pixels<G: GraphicsBackend>(
+            backend: &mut G,
+            session_data: &SessionData,
+            map: &mut AnySwapchainMap,
+            key: OverlayKey,
+            extent: xr::Extent2Di,
+            pixels: &[u8],
+        ) -> Result<(), vr::EVROverlayError>
+        where
+            for<'a> &'a mut SwapchainMap<G::Api>:
+                TryFrom<&'a mut AnySwapchainMap, Error: std::fmt::Display>,
+            for<'a> &'a GraphicalSession: TryInto<&'a Session<G::Api>, Error: std::fmt::Display>,
+            <G::Api as xr::Graphics>::Format: Eq,
+            <G::Api as xr::Graphics>::SwapchainImage: Copy,
+        {
+            if !session_data.uses_api::<G::Api>() {
+                crate::warn_once!(
+                    "Tried to draw an overlay for API {}, but the session uses a different API",
+                    std::any::type_name::<G::Api>()
+                );
+                return Err(vr::EVROverlayError::InvalidTexture);
+            }
+            let map: &mut SwapchainMap<G::Api> = match map.try_into() {
+                Ok(map) => map,
+                Err(e) => {
+                    crate::warn_once!(
+                        "Tried to draw an overlay for a different API than other overlays ({}) - {e}",
+                        std::any::type_name::<G::Api>()
+                    );
+                    return Err(vr::EVROverlayError::InvalidTexture);
                 }
-                &mut data.swapchain
             };
-            let idx = swapchain.acquire_image().unwrap();
-            swapchain.wait_image(xr::Duration::INFINITE).unwrap();
 
-            let extent = backend.copy_overlay_to_swapchain(b_texture, texture_bounds, idx as usize);
-            swapchain.release_image().unwrap();
+            let mut create_swapchain = || -> Result<_, vr::EVROverlayError> {
+                let mut info = backend.swapchain_info_for_pixels(extent);
+                let initial_format = info.format;
+                session_data.check_format::<G>(&mut info);
+                let swapchain = session_data
+                    .create_swapchain(&info)
+                    .map_err(|e| swapchain_failed("creation", e))?;
+                let images = swapchain
+                    .enumerate_images()
+                    .map_err(|e| swapchain_failed("image enumeration", e))?;
+                backend.store_swapchain_images(images.clone(), info.format);
+                Ok(SwapchainData {
+                    swapchain,
+                    info,
+                    initial_format,
+                    images,
+                    released_image: None,
+                })
+            };
+            if !map.contains_key(key) {
+                map.insert(key, create_swapchain()?);
+            }
+            let data = &mut map[key];
+            // Redrawing at the same size can reuse the swapchain.
+            if data.info.width != extent.width as u32 || data.info.height != extent.height as u32 {
+                *data = create_swapchain()?;
+            }
+
+            let swapchain = &mut data.swapchain;
+            let idx = swapchain
+                .acquire_image()
+                .map_err(|e| swapchain_failed("image acquisition", e))?;
+            swapchain
+                .wait_image(xr::Duration::INFINITE)
+                .map_err(|e| swapchain_failed("image wait", e))?;
+            backend.upload_pixels(pixels, extent, idx as usize);
+            swapchain
+                .release_image()
+                .map_err(|e| swapchain_failed("image release", e))?;
+            data.released_image = Some(idx as usize);
+            Ok(())
+        }
+
+        backend.with_any_graphics_mut::<upload_swapchain_pixels>((
+            session_data,
+            swapchains,
+            key,
+            extent,
+            pixels,
+        ))?;
+        self.rect = Some(xr::Rect2Di {
+            extent,
+            offset: xr::Offset2Di::default(),
+        });
+        Ok(())
+    }
+
+    /// The native texture last submitted for this overlay, suitable for handing back to the app.
+    fn shared_texture(&self) -> Option<vr::Texture_t> {
+        self.textures.first().map(SubmittedTexture::texture)
+    }
+
+    /// The swapchain image holding the overlay's current texture.
+    fn native_texture(
+        &self,
+        key: OverlayKey,
+        session_data: &SessionData,
+    ) -> Result<NativeTexture, vr::EVROverlayError> {
+        let (Some(backend), Some(texture)) = (&self.compositor, self.shared_texture()) else {
+            return Err(vr::EVROverlayError::InvalidTexture);
+        };
+
+        #[macros::any_graphics(AnySwapchainMap)]
+        fn released_image<G: xr::Graphics>(
+            map: &SwapchainMap<G>,
+            key: OverlayKey,
+        ) -> Option<(u64, u32, u32, u32)>
+        where
+            G::SwapchainImage: Copy + Into<u64>,
+            G::Format: Into<u32>,
+        {
+            let data = map.get(key)?;
+            let image = *data.images.get(data.released_image?)?;
+            let info = &data.info;
+            Some((image.into(), info.width, info.height, info.format.into()))
+        }
+
+        let swapchains = session_data.overlay_data.swapchains.lock().unwrap();
+        let (handle, width, height, format) = swapchains
+            .as_ref()
+            .and_then(|map| map.with_any_graphics::<released_image>(key))
+            .ok_or(vr::EVROverlayError::InvalidTexture)?;
+        Ok(NativeTexture {
+            handle,
+            width,
+            height,
+            format,
+            api: backend.texture_type(),
+            color_space: self.color_space_for(&texture),
+        })
+    }
+
+    /// Shows a texture drawn with a different graphics API than the session's, by reading it back
+    /// and uploading its pixels with `compositor`, which has to use the session's API. This stalls
+    /// both APIs on every texture, so it's only a fallback until textures can be shared through
+    /// external memory.
+    fn set_interop_texture(
+        &mut self,
+        key: OverlayKey,
+        session_data: &SessionData,
+        texture: vr::Texture_t,
+    ) -> Result<(), vr::EVROverlayError> {
+        if self.interop.is_none() {
+            self.interop = Some(
+                SupportedBackend::new(&texture, self.bounds)
+                    .ok_or(vr::EVROverlayError::InvalidTexture)?,
+            );
+        }
+        let reader = self.interop.as_mut().unwrap();
+        if texture.eType != reader.texture_type() {
+            warn!(
+                "Overlay {:?} received a {:?} texture, but was previously given {:?} textures",
+                self.name,
+                texture.eType,
+                reader.texture_type()
+            );
+            return Err(vr::EVROverlayError::InvalidTexture);
+        }
+
+        if !reader.with_any_graphics_mut::<snapshot_backend_texture>((texture, self.texture_layer))
+        {
+            return Err(vr::EVROverlayError::InvalidTexture);
+        }
+        let (extent, mut pixels) = reader
+            .with_any_graphics::<read_backend_snapshot>(self.bounds)
+            .ok_or(vr::EVROverlayError::InvalidTexture)?;
+        if texture.eType == vr::ETextureType::OpenGL {
+            pixels = flip_rows(&pixels, extent.width as usize);
+        }
+        self.set_pixels(key, session_data, extent, &pixels)?;
+        self.textures = vec![SubmittedTexture::new(texture)];
+        Ok(())
+    }
+
+    /// Reads our copy of the last submitted texture back to the CPU. The backend's queue may be
+    /// used for this, so callers hold
+    /// [`CompositorSessionData::lock_queue`](crate::compositor::CompositorSessionData::lock_queue).
+    fn read_texture(&mut self) -> Result<OverlayImage, vr::EVROverlayError> {
+        let Some(texture) = self.shared_texture() else {
+            return Err(vr::EVROverlayError::InvalidTexture);
+        };
+        // Textures drawn with the session's API are only copied once the app starts reading them,
+        // so the first read has to copy the one that's set now.
+        if self.interop.is_none() && !self.read_back {
+            let backend = self
+                .compositor
+                .as_mut()
+                .ok_or(vr::EVROverlayError::InvalidTexture)?;
+            if !backend
+                .with_any_graphics_mut::<snapshot_backend_texture>((texture, self.texture_layer))
+            {
+                return Err(vr::EVROverlayError::RequestFailed);
+            }
+            self.read_back = true;
+        }
+
+        let backend = self
+            .interop
+            .as_ref()
+            .or(self.compositor.as_ref())
+            .ok_or(vr::EVROverlayError::InvalidTexture)?;
+
+        let (extent, pixels) = backend
+            .with_any_graphics::<read_backend_snapshot>(self.bounds)
+            .ok_or(vr::EVROverlayError::RequestFailed)?;
+        Ok(OverlayImage {
+            width: extent.width as u32,
+            height: extent.height as u32,
+            pixels,
+        })
+    }
+}
+
+#[macros::any_graphics(SupportedBackend)]
+fn snapshot_backend_texture<G: GraphicsBackend>(
+    backend: &mut G,
+    texture: vr::Texture_t,
+    texture_layer: u32,
+) -> bool {
+    G::get_texture(&texture)
+        .and_then(|texture| backend.snapshot_texture(0, texture, texture_layer))
+        .is_some()
+}
+
+#[macros::any_graphics(SupportedBackend)]
+fn read_backend_snapshot<G: GraphicsBackend>(
+    backend: &G,
+    bounds: vr::VRTextureBounds_t,
+) -> Option<(xr::Extent2Di, Vec<u8>)> {
+    backend.read_texture(backend.snapshot(0)?, bounds)
+}
+
+/// Whether a texture has to be read back to be shown in the session. Only GL textures in a Vulkan
+/// session are, since Vulkan uploads work from any thread. Other mismatches are rejected.
+fn needs_interop(texture_type: vr::ETextureType, session_data: &SessionData) -> bool {
+    #[cfg(test)]
+    if texture_type == vr::ETextureType::Reserved {
+        return crate::compositor::FakeGraphicsData::foreign_textures();
+    }
+    texture_type == vr::ETextureType::OpenGL && session_data.uses_api::<xr::Vulkan>()
+}
 
-            Ok(extent)
-        }
+#[macros::any_graphics(SupportedBackend)]
+fn create_swapchain_map<G: GraphicsBackend>(_: &G) -> AnySwapchainMap
+where
+    AnySwapchainMap: From<SwapchainMap<G::Api>>,
+{
+    SwapchainMap::<G::Api>::default().into()
+}
 
-        let backend = self.compositor.as_mut().unwrap();
-        let extent = backend.with_any_graphics_mut::<set_swapchain_texture>((
-            session_data,
-            self.bounds,
-            swapchains,
-            key,
+/// The last texture an app submitted for an overlay or eye.
+pub(crate) struct SubmittedTexture {
+    texture: vr::Texture_t,
+    /// Vulkan texture handles point at app owned memory, so we keep our own copy.
+    vulkan_data: Option<Box<vr::VRVulkanTextureData_t>>,
+}
+
+// SAFETY: The handles are only ever passed back to the app or to the backend that created them.
+unsafe impl Send for SubmittedTexture {}
+unsafe impl Sync for SubmittedTexture {}
+
+impl SubmittedTexture {
+    pub(crate) fn new(texture: vr::Texture_t) -> Self {
+        let vulkan_data = (texture.eType == vr::ETextureType::Vulkan && !texture.handle.is_null())
+            .then(|| {
+                Box::new(unsafe { texture.handle.cast::<vr::VRVulkanTextureData_t>().read() })
+            });
+        Self {
             texture,
-        ))?;
-        self.rect = Some(xr::Rect2Di {
-            extent,
-            offset: xr::Offset2Di::default(),
-        });
-        Ok(())
+            vulkan_data,
+        }
+    }
+
+    pub(crate) fn texture(&self) -> vr::Texture_t {
+        let handle = match &self.vulkan_data {
+            Some(data) => (&raw const **data).cast_mut().cast(),
+            None => self.texture.handle,
+        };
+        vr::Texture_t {
+            handle,
+            ..self.texture
+        }
     }
 }
 
+/// Tightly packed RGBA8 pixels read back from an overlay texture.
+pub(crate) struct OverlayImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
 macro_rules! get_overlay {
     (@impl $self:ident, $handle:expr, $overlay:ident, $lock:ident, $get:ident $(,$mut:ident)?) => {
         let $($mut)? overlays = $self.overlays.$lock().unwrap();
@@ -584,6 +2564,62 @@ macro_rules! get_overlay {
     };
 }
 
+/// Overlay settings OpenVR has no calls for. Apps reach these through IVRSettings, in a section
+/// named after the overlay (see [`crate::settings::Settings`]).
+impl OverlayMan {
+    /// Selects the array layer shown from layered (i.e. single pass stereo) textures, starting
+    /// with the next SetOverlayTexture.
+    pub fn set_overlay_texture_layer(
+        &self,
+        handle: vr::VROverlayHandle_t,
+        layer: u32,
+    ) -> vr::EVROverlayError {
+        get_overlay!(self, handle, mut overlay);
+        debug!("set texture layer for {:?}: {layer}", overlay.name);
+        overlay.texture_layer = layer;
+        vr::EVROverlayError::None
+    }
+
+    /// Selects how the overlay's cylinder follows changes to its width.
+    pub fn set_overlay_curvature_mode(
+        &self,
+        handle: vr::VROverlayHandle_t,
+        mode: CurvatureMode,
+    ) -> vr::EVROverlayError {
+        get_overlay!(self, handle, mut overlay);
+        debug!("set curvature mode for {:?}: {mode:?}", overlay.name);
+        overlay.curvature_mode = mode;
+        vr::EVROverlayError::None
+    }
+
+    /// Selects how the texture is fit into the overlay when their aspect ratios differ.
+    pub fn set_overlay_content_fit(
+        &self,
+        handle: vr::VROverlayHandle_t,
+        fit: ContentFit,
+    ) -> vr::EVROverlayError {
+        get_overlay!(self, handle, mut overlay);
+        debug!("set content fit for {:?}: {fit:?}", overlay.name);
+        overlay.fit = fit;
+        vr::EVROverlayError::None
+    }
+
+    /// Sets the part of the sphere equirect skyboxes cover, for the current skybox and later ones.
+    pub fn set_skybox_coverage(&self, coverage: EquirectCoverage) {
+        let mut overlays = self.overlays.write().unwrap();
+        let skybox = self.skybox.read().unwrap();
+        debug!("set skybox coverage: {coverage:?}");
+        *self.skybox_coverage.lock().unwrap() = coverage;
+        for key in skybox.iter() {
+            if let Some(overlay) = overlays.get_mut(*key) {
+                if let OverlayKind::Sphere(_) = overlay.kind {
+                    overlay.kind = OverlayKind::Sphere(coverage);
+                }
+            }
+        }
+    }
+}
+
 impl vr::IVROverlay027_Interface for OverlayMan {
     fn CreateOverlay(
         &self,
@@ -598,16 +2634,14 @@ impl vr::IVROverlay027_Interface for OverlayMan {
             return vr::EVROverlayError::InvalidParameter;
         }
 
-        let mut overlays = self.overlays.write().unwrap();
-        let ret_key = overlays.insert(Overlay::new(key.into(), name.into()));
-        let mut key_to_overlay = self.key_to_overlay.write().unwrap();
-        key_to_overlay.insert(key.into(), ret_key);
-
+        let ret_key = match self.create_overlay(key, name, false) {
+            Ok(key) => key,
+            Err(e) => return e,
+        };
         unsafe {
             handle.write(ret_key.data().as_ffi());
         }
 
-        debug!("created overlay {name:?} with key {key:?}");
         vr::EVROverlayError::None
     }
 
@@ -621,7 +2655,7 @@ impl vr::IVROverlay027_Interface for OverlayMan {
         }
         let key = unsafe { CStr::from_ptr(key) };
         let map = self.key_to_overlay.read().unwrap();
-        if let Some(key) = map.get(key) {
+        if let Some(key) = map.get(&normalized_key(key)) {
             unsafe {
                 handle.write(key.data().as_ffi());
             }
@@ -644,11 +2678,25 @@ impl vr::IVROverlay027_Interface for OverlayMan {
 
         debug!("hiding overlay {:?}", overlay.name);
         overlay.visible = false;
+        // There's no system dashboard to close, so the app hiding the overlay shown with the
+        // dashboard is what closes it.
+        if overlay.dashboard == Some(DashboardRole::Main) {
+            let mut dashboard = self.dashboard.write().unwrap();
+            let key = OverlayKey::from(KeyData::from_ffi(handle));
+            if dashboard.visible && dashboard.active == Some(key) {
+                debug!("hiding dashboard");
+                dashboard.visible = false;
+            }
+        }
         vr::EVROverlayError::None
     }
 
     fn SetOverlayAlpha(&self, handle: vr::VROverlayHandle_t, alpha: f32) -> vr::EVROverlayError {
         get_overlay!(self, handle, mut overlay);
+        if !alpha.is_finite() {
+            return vr::EVROverlayError::InvalidParameter;
+        }
+        let alpha = alpha.clamp(0.0, 1.0);
         if !self
             .openxr
             .enabled_extensions
@@ -679,8 +2727,13 @@ impl vr::IVROverlay027_Interface for OverlayMan {
     ) -> vr::EVROverlayError {
         get_overlay!(self, handle, mut overlay);
 
+        if !width.is_finite() || width <= 0.0 {
+            warn!("rejecting width {width} for overlay {:?}", overlay.name);
+            return vr::EVROverlayError::InvalidParameter;
+        }
+
         debug!("setting overlay {:?} width to {width}", overlay.name);
-        overlay.width = width;
+        overlay.width = width.max(MIN_OVERLAY_WIDTH);
         vr::EVROverlayError::None
     }
 
@@ -689,6 +2742,26 @@ impl vr::IVROverlay027_Interface for OverlayMan {
         handle: vr::VROverlayHandle_t,
         texture: *const vr::Texture_t,
     ) -> vr::EVROverlayError {
+        let key = OverlayKey::from(KeyData::from_ffi(handle));
+        // Creating a backend can lock the frame controller, which reads overlays while holding its
+        // lock, so it has to happen before the overlay is locked.
+        let session_backend = unsafe { texture.as_ref() }
+            .filter(|texture| self.needs_session_backend(key, texture))
+            .map(|_| self.session_backend());
+        let interop = session_backend.is_some()
+            || self
+                .overlays
+                .read()
+                .unwrap()
+                .get(key)
+                .is_some_and(|overlay| overlay.interop.is_some());
+        if interop && !texture.is_null() {
+            return self.set_interop_overlay_texture(
+                key,
+                unsafe { texture.read() },
+                session_backend,
+            );
+        }
         get_overlay!(self, handle, mut overlay);
         if texture.is_null() {
             vr::EVROverlayError::InvalidParameter
@@ -704,8 +2777,8 @@ impl vr::IVROverlay027_Interface for OverlayMan {
             {
                 return vr::EVROverlayError::InvalidTexture;
             }
-            let key = OverlayKey::from(KeyData::from_ffi(handle));
-            match overlay.set_texture(key, &self.openxr.session_data.get(), texture) {
+            let session_data = self.openxr.session_data.get();
+            match overlay.set_texture(key, &session_data, texture) {
                 Ok(_) => {
                     debug!("set overlay texture for {:?}", overlay.name);
                     vr::EVROverlayError::None
@@ -716,18 +2789,58 @@ impl vr::IVROverlay027_Interface for OverlayMan {
     }
 
     fn CloseMessageOverlay(&self) {
-        todo!()
+        let Some((key, _)) = self.message.lock().unwrap().take() else {
+            return;
+        };
+        <Self as vr::IVROverlay027_Interface>::DestroyOverlay(self, key.data().as_ffi());
+        debug!("closed message overlay");
     }
     fn ShowMessageOverlay(
         &self,
-        _: *const c_char,
-        _: *const c_char,
-        _: *const c_char,
-        _: *const c_char,
-        _: *const c_char,
-        _: *const c_char,
+        text: *const c_char,
+        caption: *const c_char,
+        button0: *const c_char,
+        button1: *const c_char,
+        button2: *const c_char,
+        button3: *const c_char,
     ) -> vr::VRMessageOverlayResponse {
-        todo!()
+        let string = |s: *const c_char| {
+            (!s.is_null()).then(|| unsafe { CStr::from_ptr(s) }.to_string_lossy().into_owned())
+        };
+        let message = Message {
+            text: string(text).unwrap_or_default(),
+            caption: string(caption).unwrap_or_default(),
+            buttons: [button0, button1, button2, button3]
+                .into_iter()
+                .filter_map(string)
+                .collect(),
+        };
+        <Self as vr::IVROverlay027_Interface>::CloseMessageOverlay(self);
+
+        let position = xr::Vector3f {
+            x: 0.0,
+            y: 0.0,
+            z: -MESSAGE_DISTANCE,
+        };
+        let key = match self.show_internal_overlay(
+            MESSAGE_OVERLAY_KEY,
+            MESSAGE_WIDTH,
+            position,
+            MESSAGE_Z_ORDER,
+            message.render(),
+        ) {
+            Ok(key) => key,
+            Err(e) => {
+                warn!("Failed to show message overlay: {e:?}");
+                return vr::VRMessageOverlayResponse::CouldntFindOrCreateClientOverlay;
+            }
+        };
+        *self.message.lock().unwrap() = Some((key, Instant::now()));
+        debug!("showing message overlay: {message:?}");
+
+        // The message stays up, but there's no system overlay that could wait for the user to
+        // press one of its buttons, so don't claim one was pressed.
+        vr::VRMessageOverlayResponse::CouldntFindSystemOverlay
     }
     fn SetKeyboardPositionForOverlay(&self, _: vr::VROverlayHandle_t, _: vr::HmdRect2_t) {
         todo!()
@@ -740,71 +2853,188 @@ impl vr::IVROverlay027_Interface for OverlayMan {
         todo!()
     }
     fn HideKeyboard(&self) {
-        todo!()
+        let mut state = self.keyboard.lock().unwrap();
+        let Some(state) = state.as_mut().filter(|state| state.overlay.is_some()) else {
+            return;
+        };
+        self.dismiss_keyboard(state);
+        self.push_keyboard_event(vr::EVREventType::KeyboardClosed, state, "");
+        debug!("hid keyboard");
     }
-    fn GetKeyboardText(&self, _: *mut c_char, _: u32) -> u32 {
-        todo!()
+    fn GetKeyboardText(&self, text: *mut c_char, len: u32) -> u32 {
+        let state = self.keyboard.lock().unwrap();
+        let Some(state) = state.as_ref() else {
+            return 0;
+        };
+        let bytes = state.keyboard.text().as_bytes();
+        if !text.is_null() && len > 0 {
+            let text = unsafe { std::slice::from_raw_parts_mut(text, len as usize) };
+            // Leave room for the null terminator
+            let copied = bytes.len().min(text.len() - 1);
+            for (dst, src) in text.iter_mut().zip(&bytes[..copied]) {
+                *dst = *src as c_char;
+            }
+            text[copied] = 0;
+        }
+        bytes.len() as u32 + 1
     }
     fn ShowKeyboardForOverlay(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: vr::EGamepadTextInputMode,
-        _: vr::EGamepadTextInputLineMode,
-        _: u32,
-        _: *const c_char,
+        handle: vr::VROverlayHandle_t,
+        input_mode: vr::EGamepadTextInputMode,
+        line_mode: vr::EGamepadTextInputLineMode,
         _: u32,
         _: *const c_char,
-        _: u64,
+        max_chars: u32,
+        existing_text: *const c_char,
+        user_value: u64,
     ) -> vr::EVROverlayError {
-        todo!()
+        let key = OverlayKey::from(KeyData::from_ffi(handle));
+        if !self.overlays.read().unwrap().contains_key(key) {
+            return vr::EVROverlayError::UnknownOverlay;
+        }
+        self.show_keyboard(
+            handle,
+            input_mode,
+            line_mode,
+            max_chars,
+            existing_text,
+            user_value,
+        )
     }
     fn ShowKeyboard(
         &self,
-        _: vr::EGamepadTextInputMode,
-        _: vr::EGamepadTextInputLineMode,
-        _: u32,
-        _: *const c_char,
+        input_mode: vr::EGamepadTextInputMode,
+        line_mode: vr::EGamepadTextInputLineMode,
         _: u32,
         _: *const c_char,
-        _: u64,
+        max_chars: u32,
+        existing_text: *const c_char,
+        user_value: u64,
     ) -> vr::EVROverlayError {
-        crate::warn_unimplemented!("ShowKeyboard");
-        vr::EVROverlayError::RequestFailed
+        self.show_keyboard(
+            0,
+            input_mode,
+            line_mode,
+            max_chars,
+            existing_text,
+            user_value,
+        )
     }
     fn GetPrimaryDashboardDevice(&self) -> vr::TrackedDeviceIndex_t {
-        todo!()
+        if !self.dashboard.read().unwrap().visible {
+            return vr::k_unTrackedDeviceIndexInvalid;
+        }
+        self.laser
+            .lock()
+            .unwrap()
+            .hand
+            .map_or(vr::k_unTrackedDeviceIndexInvalid, |hand| hand as u32)
     }
-    fn ShowDashboard(&self, _: *const c_char) {
-        todo!()
+    fn ShowDashboard(&self, overlay_to_show: *const c_char) {
+        let requested = (!overlay_to_show.is_null()).then(|| {
+            let key = unsafe { CStr::from_ptr(overlay_to_show) };
+            let overlays = self.overlays.read().unwrap();
+            let found = self
+                .key_to_overlay
+                .read()
+                .unwrap()
+                .get(&normalized_key(key))
+                .copied();
+            found
+                .filter(|k| overlays.get(*k).and_then(|o| o.dashboard) == Some(DashboardRole::Main))
+                .ok_or(key)
+        });
+
+        let mut dashboard = self.dashboard.write().unwrap();
+        match requested {
+            Some(Ok(key)) => dashboard.active = Some(key),
+            Some(Err(key)) => warn!("ShowDashboard: {key:?} is not a dashboard overlay"),
+            None => {}
+        }
+
+        debug!("showing dashboard (active overlay: {:?})", dashboard.active);
+        dashboard.visible = true;
     }
     fn GetDashboardOverlaySceneProcess(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: *mut u32,
+        handle: vr::VROverlayHandle_t,
+        process_id: *mut u32,
     ) -> vr::EVROverlayError {
-        todo!()
+        get_overlay!(self, handle, overlay);
+        if overlay.dashboard.is_none() {
+            return vr::EVROverlayError::UnknownOverlay;
+        }
+        let Some(process_id) = (unsafe { process_id.as_mut() }) else {
+            return vr::EVROverlayError::InvalidParameter;
+        };
+        *process_id = overlay.scene_process;
+        vr::EVROverlayError::None
     }
     fn SetDashboardOverlaySceneProcess(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: u32,
+        handle: vr::VROverlayHandle_t,
+        process_id: u32,
     ) -> vr::EVROverlayError {
-        todo!()
+        get_overlay!(self, handle, mut overlay);
+        if overlay.dashboard.is_none() {
+            return vr::EVROverlayError::UnknownOverlay;
+        }
+        overlay.scene_process = process_id;
+        vr::EVROverlayError::None
     }
-    fn IsActiveDashboardOverlay(&self, _: vr::VROverlayHandle_t) -> bool {
-        todo!()
+    fn IsActiveDashboardOverlay(&self, handle: vr::VROverlayHandle_t) -> bool {
+        let dashboard = self.dashboard.read().unwrap();
+        dashboard.visible && dashboard.active == Some(OverlayKey::from(KeyData::from_ffi(handle)))
     }
     fn IsDashboardVisible(&self) -> bool {
-        false
+        self.dashboard.read().unwrap().visible
     }
     fn CreateDashboardOverlay(
         &self,
-        _: *const c_char,
-        _: *const c_char,
-        _: *mut vr::VROverlayHandle_t,
-        _: *mut vr::VROverlayHandle_t,
+        key: *const c_char,
+        name: *const c_char,
+        main_handle: *mut vr::VROverlayHandle_t,
+        thumbnail_handle: *mut vr::VROverlayHandle_t,
     ) -> vr::EVROverlayError {
-        todo!()
+        if key.is_null() || name.is_null() || main_handle.is_null() || thumbnail_handle.is_null() {
+            return vr::EVROverlayError::InvalidParameter;
+        }
+        let key = unsafe { CStr::from_ptr(key) };
+        let name = unsafe { CStr::from_ptr(name) };
+        let thumbnail_key = CString::new([key.to_bytes(), b".thumbnail"].concat()).unwrap();
+        // Checked up front so the main overlay isn't left behind when the thumbnail's key is the
+        // one that's too long.
+        if thumbnail_key.as_bytes().len() >= vr::k_unVROverlayMaxKeyLength as usize {
+            debug!("dashboard overlay key {key:?} is too long");
+            return vr::EVROverlayError::KeyTooLong;
+        }
+
+        let main = match self.create_overlay(key, name, false) {
+            Ok(key) => key,
+            Err(e) => return e,
+        };
+        let thumbnail = match self.create_overlay(&thumbnail_key, name, false) {
+            Ok(key) => key,
+            Err(e) => {
+                <Self as vr::IVROverlay027_Interface>::DestroyOverlay(self, main.data().as_ffi());
+                return e;
+            }
+        };
+        {
+            let mut overlays = self.overlays.write().unwrap();
+            overlays[main].dashboard = Some(DashboardRole::Main);
+            overlays[thumbnail].dashboard = Some(DashboardRole::Thumbnail);
+        }
+
+        // The first dashboard overlay becomes the one shown with the dashboard.
+        self.dashboard.write().unwrap().active.get_or_insert(main);
+
+        unsafe {
+            main_handle.write(main.data().as_ffi());
+            thumbnail_handle.write(thumbnail.data().as_ffi());
+        }
+        vr::EVROverlayError::None
     }
     fn GetOverlayTextureSize(
         &self,
@@ -823,17 +3053,48 @@ impl vr::IVROverlay027_Interface for OverlayMan {
     }
     fn GetOverlayTexture(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: *mut *mut c_void,
-        _: *mut c_void,
-        _: *mut u32,
-        _: *mut u32,
-        _: *mut u32,
-        _: *mut vr::ETextureType,
-        _: *mut vr::EColorSpace,
-        _: *mut vr::VRTextureBounds_t,
+        handle: vr::VROverlayHandle_t,
+        native_handle: *mut *mut c_void,
+        _native_texture_ref: *mut c_void,
+        width: *mut u32,
+        height: *mut u32,
+        native_format: *mut u32,
+        api_type: *mut vr::ETextureType,
+        color_space: *mut vr::EColorSpace,
+        texture_bounds: *mut vr::VRTextureBounds_t,
     ) -> vr::EVROverlayError {
-        todo!()
+        get_overlay!(self, handle, overlay);
+        if native_handle.is_null() {
+            return vr::EVROverlayError::InvalidParameter;
+        }
+        let key = OverlayKey::from(KeyData::from_ffi(handle));
+        let texture = match overlay.native_texture(key, &self.openxr.session_data.get()) {
+            Ok(texture) => texture,
+            Err(e) => return e,
+        };
+
+        unsafe {
+            native_handle.write(texture.handle as usize as *mut c_void);
+            if let Some(width) = width.as_mut() {
+                *width = texture.width;
+            }
+            if let Some(height) = height.as_mut() {
+                *height = texture.height;
+            }
+            if let Some(format) = native_format.as_mut() {
+                *format = texture.format;
+            }
+            if let Some(api_type) = api_type.as_mut() {
+                *api_type = texture.api;
+            }
+            if let Some(color_space) = color_space.as_mut() {
+                *color_space = texture.color_space;
+            }
+            if let Some(bounds) = texture_bounds.as_mut() {
+                *bounds = overlay.bounds;
+            }
+        }
+        vr::EVROverlayError::None
     }
     fn SetOverlayFromFile(
         &self,
@@ -855,93 +3116,302 @@ impl vr::IVROverlay027_Interface for OverlayMan {
     fn ClearOverlayTexture(&self, _: vr::VROverlayHandle_t) -> vr::EVROverlayError {
         todo!()
     }
-    fn ClearOverlayCursorPositionOverride(&self, _: vr::VROverlayHandle_t) -> vr::EVROverlayError {
-        todo!()
+    fn ClearOverlayCursorPositionOverride(
+        &self,
+        handle: vr::VROverlayHandle_t,
+    ) -> vr::EVROverlayError {
+        get_overlay!(self, handle, mut overlay);
+        overlay.cursor_override = None;
+        vr::EVROverlayError::None
     }
     fn SetOverlayCursorPositionOverride(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: *const vr::HmdVector2_t,
+        handle: vr::VROverlayHandle_t,
+        position: *const vr::HmdVector2_t,
     ) -> vr::EVROverlayError {
-        todo!()
+        get_overlay!(self, handle, mut overlay);
+        let Some(position) = (unsafe { position.as_ref() }) else {
+            return vr::EVROverlayError::InvalidParameter;
+        };
+        let scale = overlay.mouse_scale();
+        if scale.cmpeq(Vec2::ZERO).any() {
+            return vr::EVROverlayError::InvalidParameter;
+        }
+        // The position is in mouse coordinates, like the events the overlay gets.
+        overlay.cursor_override = Some(Vec2::from_array(position.v) / scale);
+        vr::EVROverlayError::None
     }
     fn SetOverlayCursor(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: vr::VROverlayHandle_t,
+        handle: vr::VROverlayHandle_t,
+        cursor: vr::VROverlayHandle_t,
     ) -> vr::EVROverlayError {
-        todo!()
+        let mut overlays = self.overlays.write().unwrap();
+        // The invalid handle removes the cursor.
+        let cursor = (cursor != 0).then(|| OverlayKey::from(KeyData::from_ffi(cursor)));
+        if cursor.is_some_and(|cursor| !overlays.contains_key(cursor)) {
+            return vr::EVROverlayError::UnknownOverlay;
+        }
+        let Some(overlay) = overlays.get_mut(OverlayKey::from(KeyData::from_ffi(handle))) else {
+            return vr::EVROverlayError::UnknownOverlay;
+        };
+        debug!("setting cursor for {:?} to {cursor:?}", overlay.name);
+        overlay.cursor = cursor;
+        vr::EVROverlayError::None
     }
     fn TriggerLaserMouseHapticVibration(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: f32,
-        _: f32,
-        _: f32,
+        handle: vr::VROverlayHandle_t,
+        duration_seconds: f32,
+        frequency: f32,
+        amplitude: f32,
     ) -> vr::EVROverlayError {
-        todo!()
+        let key = OverlayKey::from(KeyData::from_ffi(handle));
+        if !self.overlays.read().unwrap().contains_key(key) {
+            return vr::EVROverlayError::UnknownOverlay;
+        }
+        // Only the laser pointing at or clicking on the overlay gets the feedback.
+        let laser = self.laser.lock().unwrap();
+        let on_overlay =
+            laser.hovered == Some(key) || laser.pressed.is_some_and(|(pressed, _)| pressed == key);
+        let Some(hand) = laser.hand.filter(|_| on_overlay) else {
+            return vr::EVROverlayError::None;
+        };
+        drop(laser);
+        if let Some(input) = self.input.get() {
+            input.vibrate_controller(hand, duration_seconds, frequency, amplitude);
+        }
+        vr::EVROverlayError::None
     }
     fn SetOverlayIntersectionMask(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: *mut vr::VROverlayIntersectionMaskPrimitive_t,
-        _: u32,
-        _: u32,
+        handle: vr::VROverlayHandle_t,
+        primitives: *mut vr::VROverlayIntersectionMaskPrimitive_t,
+        count: u32,
+        primitive_size: u32,
     ) -> vr::EVROverlayError {
-        todo!()
+        get_overlay!(self, handle, mut overlay);
+        if primitive_size as usize
+            != std::mem::size_of::<vr::VROverlayIntersectionMaskPrimitive_t>()
+            || (primitives.is_null() && count > 0)
+        {
+            return vr::EVROverlayError::InvalidParameter;
+        }
+
+        let primitives = if count == 0 {
+            &[]
+        } else {
+            unsafe { std::slice::from_raw_parts(primitives, count as usize) }
+        };
+        let mask = primitives
+            .iter()
+            .map(|p| match p.m_nPrimitiveType {
+                vr::EVROverlayIntersectionMaskPrimitiveType::Rectangle => {
+                    let r = unsafe { p.m_Primitive.m_Rectangle };
+                    Ok(MaskPrimitive::Rectangle {
+                        top_left: vec2(r.m_flTopLeftX, r.m_flTopLeftY),
+                        width: r.m_flWidth,
+                        height: r.m_flHeight,
+                    })
+                }
+                vr::EVROverlayIntersectionMaskPrimitiveType::Circle => {
+                    let c = unsafe { p.m_Primitive.m_Circle };
+                    Ok(MaskPrimitive::Circle {
+                        center: vec2(c.m_flCenterX, c.m_flCenterY),
+                        radius: c.m_flRadius,
+                    })
+                }
+                other => Err(other),
+            })
+            .collect::<Result<_, _>>();
+        overlay.intersection_mask = match mask {
+            Ok(mask) => mask,
+            Err(ty) => {
+                warn!("Invalid intersection mask primitive type {}", ty.0);
+                return vr::EVROverlayError::BadMaskPrimitive;
+            }
+        };
+        debug!(
+            "set intersection mask for {:?}: {:?}",
+            overlay.name, overlay.intersection_mask
+        );
+        vr::EVROverlayError::None
     }
-    fn IsHoverTargetOverlay(&self, _: vr::VROverlayHandle_t) -> bool {
-        todo!()
+    fn IsHoverTargetOverlay(&self, handle: vr::VROverlayHandle_t) -> bool {
+        self.laser.lock().unwrap().hovered == Some(OverlayKey::from(KeyData::from_ffi(handle)))
     }
     fn ComputeOverlayIntersection(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: *const vr::VROverlayIntersectionParams_t,
-        _: *mut vr::VROverlayIntersectionResults_t,
+        handle: vr::VROverlayHandle_t,
+        params: *const vr::VROverlayIntersectionParams_t,
+        results: *mut vr::VROverlayIntersectionResults_t,
     ) -> bool {
-        todo!()
+        let (Some(params), Some(results)) =
+            (unsafe { params.as_ref() }, unsafe { results.as_mut() })
+        else {
+            return false;
+        };
+        let [x, y, z] = params.vSource.v;
+        let source = vec3(x, y, z);
+        let [x, y, z] = params.vDirection.v;
+        let direction = vec3(x, y, z);
+
+        let Some(hit) = self.compute_intersection(
+            OverlayKey::from(KeyData::from_ffi(handle)),
+            params.eOrigin,
+            source,
+            direction,
+        ) else {
+            return false;
+        };
+        *results = vr::VROverlayIntersectionResults_t {
+            vPoint: hit.point.into(),
+            vNormal: hit.normal.into(),
+            vUVs: vr::HmdVector2_t {
+                v: hit.uv.to_array(),
+            },
+            fDistance: hit.distance,
+        };
+        true
     }
     fn SetOverlayMouseScale(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: *const vr::HmdVector2_t,
+        handle: vr::VROverlayHandle_t,
+        scale: *const vr::HmdVector2_t,
     ) -> vr::EVROverlayError {
-        crate::warn_unimplemented!("SetOverlayMouseScale");
-        vr::EVROverlayError::RequestFailed
+        get_overlay!(self, handle, mut overlay);
+        let Some(scale) = (unsafe { scale.as_ref() }) else {
+            return vr::EVROverlayError::InvalidParameter;
+        };
+        // The scale maps mouse coordinates back to UVs, so it's divided by.
+        if scale.v.contains(&0.0) {
+            return vr::EVROverlayError::InvalidParameter;
+        }
+        overlay.mouse_scale = Some(Vec2::from_array(scale.v));
+        debug!("set mouse scale for {:?}: {:?}", overlay.name, scale.v);
+        vr::EVROverlayError::None
     }
     fn GetOverlayMouseScale(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: *mut vr::HmdVector2_t,
+        handle: vr::VROverlayHandle_t,
+        scale: *mut vr::HmdVector2_t,
     ) -> vr::EVROverlayError {
-        todo!()
+        get_overlay!(self, handle, overlay);
+        let Some(scale) = (unsafe { scale.as_mut() }) else {
+            return vr::EVROverlayError::InvalidParameter;
+        };
+        scale.v = overlay.mouse_scale().to_array();
+        vr::EVROverlayError::None
     }
     fn SetOverlayInputMethod(
         &self,
-        _: vr::VROverlayHandle_t,
+        handle: vr::VROverlayHandle_t,
         input_method: vr::VROverlayInputMethod,
     ) -> vr::EVROverlayError {
-        if input_method == vr::VROverlayInputMethod::Mouse {
-            crate::warn_unimplemented!("SetOverlayInputMethod::Mouse");
-        } else if input_method == vr::VROverlayInputMethod::None {
-            crate::warn_unimplemented!("SetOverlayInputMethod::None");
+        get_overlay!(self, handle, mut overlay);
+        // DualAnalog (2) is no longer supported, and apps can pass anything through the C API.
+        if !matches!(
+            input_method,
+            vr::VROverlayInputMethod::None | vr::VROverlayInputMethod::Mouse
+        ) {
+            warn!("Invalid input method {}", input_method.0);
+            return vr::EVROverlayError::InvalidParameter;
         }
-        vr::EVROverlayError::RequestFailed
+        debug!("set input method for {:?}: {input_method:?}", overlay.name);
+        overlay.input_method = input_method;
+        vr::EVROverlayError::None
     }
     fn GetOverlayInputMethod(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: *mut vr::VROverlayInputMethod,
+        handle: vr::VROverlayHandle_t,
+        input_method: *mut vr::VROverlayInputMethod,
     ) -> vr::EVROverlayError {
-        todo!()
+        get_overlay!(self, handle, overlay);
+        let Some(input_method) = (unsafe { input_method.as_mut() }) else {
+            return vr::EVROverlayError::InvalidParameter;
+        };
+        *input_method = overlay.input_method;
+        vr::EVROverlayError::None
     }
     fn PollNextOverlayEvent(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: *mut vr::VREvent_t,
-        _: u32,
+        handle: vr::VROverlayHandle_t,
+        out: *mut vr::VREvent_t,
+        size: u32,
     ) -> bool {
-        todo!()
+        const FUNC: &str = "PollNextOverlayEvent";
+        if out.is_null() {
+            warn!("{FUNC}: Got null event pointer.");
+            return false;
+        }
+        let overlays = self.overlays.read().unwrap();
+        let Some(overlay) = overlays.get(OverlayKey::from(KeyData::from_ffi(handle))) else {
+            return false;
+        };
+        let mut events = overlay.events.lock().unwrap();
+        let Some(event) = events.front() else {
+            return false;
+        };
+
+        const DATA_OFFSET: usize = std::mem::offset_of!(vr::VREvent_t, data);
+        // Older VREvent_Mouse_t versions end before the cursor index.
+        const MIN_MOUSE_EVENT_SIZE: usize =
+            DATA_OFFSET + std::mem::offset_of!(vr::VREvent_Mouse_t, cursorIndex);
+        // Older VREvent_Scroll_t versions end before the viewport scale.
+        const MIN_SCROLL_EVENT_SIZE: usize =
+            DATA_OFFSET + std::mem::offset_of!(vr::VREvent_Scroll_t, viewportscale);
+        const MIN_OVERLAY_EVENT_SIZE: usize = DATA_OFFSET + std::mem::size_of::<u64>();
+        let min_size = match event {
+            OverlayEvent::Mouse(..) => MIN_MOUSE_EVENT_SIZE,
+            OverlayEvent::Scroll(..) => MIN_SCROLL_EVENT_SIZE,
+            OverlayEvent::Focus(_) => MIN_OVERLAY_EVENT_SIZE,
+        };
+        if (size as usize) < min_size {
+            warn!("{FUNC}: Provided event struct size ({size}) is smaller than required ({min_size}).");
+            return false;
+        }
+
+        let event = events.pop_front().unwrap();
+        let ty = match &event {
+            OverlayEvent::Mouse(ty, _) | OverlayEvent::Scroll(ty, _) | OverlayEvent::Focus(ty) => {
+                *ty
+            }
+        };
+        // See Input::get_next_event - VREvent_t may be smaller than ours.
+        unsafe {
+            (&raw mut (*out).eventType).write(ty as u32);
+            (&raw mut (*out).trackedDeviceIndex).write(Hand::Right as u32);
+            (&raw mut (*out).eventAgeSeconds).write(0.0);
+            match event {
+                OverlayEvent::Mouse(_, mouse) => {
+                    let data = &raw mut (*out).data.mouse;
+                    (&raw mut (*data).x).write(mouse.x);
+                    (&raw mut (*data).y).write(mouse.y);
+                    (&raw mut (*data).button).write(mouse.button);
+                    if size as usize >= MIN_MOUSE_EVENT_SIZE + std::mem::size_of::<u32>() {
+                        (&raw mut (*data).cursorIndex).write(mouse.cursorIndex);
+                    }
+                }
+                OverlayEvent::Scroll(_, scroll) => {
+                    let data = &raw mut (*out).data.scroll;
+                    (&raw mut (*data).xdelta).write(scroll.xdelta);
+                    (&raw mut (*data).ydelta).write(scroll.ydelta);
+                    (&raw mut (*data).unused).write(scroll.unused);
+                    let end = |field| MIN_SCROLL_EVENT_SIZE + field * std::mem::size_of::<u32>();
+                    if size as usize >= end(1) {
+                        (&raw mut (*data).viewportscale).write(scroll.viewportscale);
+                    }
+                    if size as usize >= end(2) {
+                        (&raw mut (*data).cursorIndex).write(scroll.cursorIndex);
+                    }
+                }
+                OverlayEvent::Focus(_) => {
+                    let data = &raw mut (*out).data.overlay;
+                    (&raw mut (*data).overlayHandle).write(handle);
+                }
+            }
+        }
+        true
     }
     fn WaitFrameSync(&self, _: u32) -> vr::EVROverlayError {
         todo!()
@@ -1018,11 +3488,22 @@ impl vr::IVROverlay027_Interface for OverlayMan {
     }
     fn GetOverlayTransformAbsolute(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: *mut vr::ETrackingUniverseOrigin,
-        _: *mut vr::HmdMatrix34_t,
+        handle: vr::VROverlayHandle_t,
+        origin: *mut vr::ETrackingUniverseOrigin,
+        transform: *mut vr::HmdMatrix34_t,
     ) -> vr::EVROverlayError {
-        todo!()
+        let current_origin = self.openxr.session_data.get().current_origin;
+        get_overlay!(self, handle, overlay);
+        if origin.is_null() || transform.is_null() {
+            return vr::EVROverlayError::InvalidParameter;
+        }
+        // Report the pose we render with, which has a normalized rotation.
+        let overlay_origin = overlay.placement().map_or(current_origin, |(o, _)| o);
+        unsafe {
+            origin.write(overlay_origin);
+            transform.write(overlay.pose().into());
+        }
+        vr::EVROverlayError::None
     }
     fn SetOverlayTransformAbsolute(
         &self,
@@ -1091,17 +3572,26 @@ impl vr::IVROverlay027_Interface for OverlayMan {
     }
     fn GetOverlayTextureColorSpace(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: *mut vr::EColorSpace,
+        handle: vr::VROverlayHandle_t,
+        color_space: *mut vr::EColorSpace,
     ) -> vr::EVROverlayError {
-        todo!()
+        get_overlay!(self, handle, overlay);
+        if color_space.is_null() {
+            vr::EVROverlayError::InvalidParameter
+        } else {
+            unsafe { color_space.write(overlay.color_space) };
+            vr::EVROverlayError::None
+        }
     }
     fn SetOverlayTextureColorSpace(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: vr::EColorSpace,
+        handle: vr::VROverlayHandle_t,
+        color_space: vr::EColorSpace,
     ) -> vr::EVROverlayError {
-        todo!()
+        get_overlay!(self, handle, mut overlay);
+        debug!("overlay {:?} color space → {color_space:?}", overlay.name);
+        overlay.color_space = color_space;
+        vr::EVROverlayError::None
     }
     fn GetOverlayPreCurvePitch(
         &self,
@@ -1119,12 +3609,13 @@ impl vr::IVROverlay027_Interface for OverlayMan {
         value: *mut f32,
     ) -> vr::EVROverlayError {
         get_overlay!(self, handle, overlay);
-        unsafe {
-            *value = match overlay.kind {
-                OverlayKind::Curved { curvature } => curvature,
-                _ => 0.0,
-            }
-        }
+        let Some(value) = (unsafe { value.as_mut() }) else {
+            return vr::EVROverlayError::InvalidParameter;
+        };
+        *value = match overlay.kind {
+            OverlayKind::Curved { curvature, .. } => curvature,
+            _ => 0.0,
+        };
         vr::EVROverlayError::None
     }
     fn SetOverlayCurvature(
@@ -1141,6 +3632,7 @@ impl vr::IVROverlay027_Interface for OverlayMan {
             get_overlay!(self, handle, mut overlay);
             overlay.kind = OverlayKind::Curved {
                 curvature: value.clamp(0.0, 1.0),
+                base_width: overlay.width,
             };
         }
         vr::EVROverlayError::None
@@ -1151,9 +3643,10 @@ impl vr::IVROverlay027_Interface for OverlayMan {
         value: *mut f32,
     ) -> vr::EVROverlayError {
         get_overlay!(self, handle, overlay);
-        unsafe {
-            *value = overlay.width;
-        }
+        let Some(value) = (unsafe { value.as_mut() }) else {
+            return vr::EVROverlayError::InvalidParameter;
+        };
+        *value = overlay.width;
         vr::EVROverlayError::None
     }
     fn GetOverlaySortOrder(
@@ -1162,7 +3655,10 @@ impl vr::IVROverlay027_Interface for OverlayMan {
         value: *mut u32,
     ) -> vr::EVROverlayError {
         get_overlay!(self, handle, overlay);
-        unsafe { *value = overlay.z_order as _ };
+        let Some(value) = (unsafe { value.as_mut() }) else {
+            return vr::EVROverlayError::InvalidParameter;
+        };
+        *value = u32::try_from(overlay.z_order.max(0)).unwrap_or(u32::MAX);
         vr::EVROverlayError::None
     }
     fn SetOverlaySortOrder(
@@ -1175,14 +3671,32 @@ impl vr::IVROverlay027_Interface for OverlayMan {
             "overlay {:?} sort order {} → {}",
             overlay.name, overlay.z_order, value
         );
-        overlay.z_order = value as _;
+        overlay.set_sort_order(value.into());
         vr::EVROverlayError::None
     }
-    fn GetOverlayTexelAspect(&self, _: vr::VROverlayHandle_t, _: *mut f32) -> vr::EVROverlayError {
-        todo!()
+    fn GetOverlayTexelAspect(
+        &self,
+        handle: vr::VROverlayHandle_t,
+        value: *mut f32,
+    ) -> vr::EVROverlayError {
+        get_overlay!(self, handle, overlay);
+        if value.is_null() {
+            return vr::EVROverlayError::InvalidParameter;
+        }
+        unsafe { *value = overlay.texel_aspect };
+        vr::EVROverlayError::None
     }
-    fn SetOverlayTexelAspect(&self, _: vr::VROverlayHandle_t, _: f32) -> vr::EVROverlayError {
-        crate::warn_unimplemented!("SetOverlayTexelAspect");
+    fn SetOverlayTexelAspect(
+        &self,
+        handle: vr::VROverlayHandle_t,
+        value: f32,
+    ) -> vr::EVROverlayError {
+        if !(value.is_finite() && value > 0.0) {
+            return vr::EVROverlayError::InvalidParameter;
+        }
+        get_overlay!(self, handle, mut overlay);
+        debug!("overlay {:?} texel aspect → {value}", overlay.name);
+        overlay.texel_aspect = value;
         vr::EVROverlayError::None
     }
     fn GetOverlayAlpha(
@@ -1191,47 +3705,98 @@ impl vr::IVROverlay027_Interface for OverlayMan {
         value: *mut f32,
     ) -> vr::EVROverlayError {
         get_overlay!(self, handle, overlay);
-        unsafe { *value = overlay.alpha.unwrap_or(1.0) };
+        let Some(value) = (unsafe { value.as_mut() }) else {
+            return vr::EVROverlayError::InvalidParameter;
+        };
+        *value = overlay.alpha.unwrap_or(1.0);
         vr::EVROverlayError::None
     }
 
     fn GetOverlayColor(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: *mut f32,
-        _: *mut f32,
-        _: *mut f32,
+        handle: vr::VROverlayHandle_t,
+        red: *mut f32,
+        green: *mut f32,
+        blue: *mut f32,
     ) -> vr::EVROverlayError {
-        todo!()
+        get_overlay!(self, handle, overlay);
+        if red.is_null() || green.is_null() || blue.is_null() {
+            return vr::EVROverlayError::InvalidParameter;
+        }
+        let [r, g, b] = overlay.color.unwrap_or([1.0; 3]);
+        unsafe {
+            red.write(r);
+            green.write(g);
+            blue.write(b);
+        }
+        vr::EVROverlayError::None
     }
     fn SetOverlayColor(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: f32,
-        _: f32,
-        _: f32,
+        handle: vr::VROverlayHandle_t,
+        red: f32,
+        green: f32,
+        blue: f32,
     ) -> vr::EVROverlayError {
-        crate::warn_unimplemented!("SetOverlayColor");
+        get_overlay!(self, handle, mut overlay);
+        let color = [red, green, blue];
+        if !color.iter().all(|c| c.is_finite()) {
+            return vr::EVROverlayError::InvalidParameter;
+        }
+        let color = color.map(|c| c.clamp(0.0, 1.0));
+        if !self
+            .openxr
+            .enabled_extensions
+            .khr_composition_layer_color_scale_bias
+        {
+            crate::warn_once!("Cannot SetOverlayColor on {:?}: Runtime does not support KHR_composition_layer_color_scale_bias", overlay.name);
+            return vr::EVROverlayError::None;
+        }
+
+        debug!("overlay {:?} color {color:?}", overlay.name);
+        overlay.color = (color != [1.0; 3]).then_some(color);
         vr::EVROverlayError::None
     }
-    fn GetOverlayFlags(&self, _: vr::VROverlayHandle_t, _: *mut u32) -> vr::EVROverlayError {
-        todo!()
+    fn GetOverlayFlags(
+        &self,
+        handle: vr::VROverlayHandle_t,
+        flags: *mut u32,
+    ) -> vr::EVROverlayError {
+        get_overlay!(self, handle, overlay);
+        if flags.is_null() {
+            vr::EVROverlayError::InvalidParameter
+        } else {
+            unsafe { flags.write(overlay.flags) };
+            vr::EVROverlayError::None
+        }
     }
     fn GetOverlayFlag(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: vr::VROverlayFlags,
-        _: *mut bool,
+        handle: vr::VROverlayHandle_t,
+        flag: vr::VROverlayFlags,
+        enabled: *mut bool,
     ) -> vr::EVROverlayError {
-        todo!()
+        get_overlay!(self, handle, overlay);
+        if enabled.is_null() {
+            vr::EVROverlayError::InvalidParameter
+        } else {
+            unsafe { enabled.write(overlay.has_flag(flag)) };
+            vr::EVROverlayError::None
+        }
     }
     fn SetOverlayFlag(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: vr::VROverlayFlags,
-        _: bool,
+        handle: vr::VROverlayHandle_t,
+        flag: vr::VROverlayFlags,
+        enabled: bool,
     ) -> vr::EVROverlayError {
-        crate::warn_unimplemented!("SetOverlayFlag");
+        get_overlay!(self, handle, mut overlay);
+        debug!("overlay {:?} flag {flag:?} → {enabled}", overlay.name);
+        if enabled {
+            overlay.flags |= flag as u32;
+        } else {
+            overlay.flags &= !(flag as u32);
+        }
         vr::EVROverlayError::None
     }
     fn GetOverlayRenderingPid(&self, _: vr::VROverlayHandle_t) -> u32 {
@@ -1245,13 +3810,34 @@ impl vr::IVROverlay027_Interface for OverlayMan {
     }
     fn GetOverlayImageData(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: *mut c_void,
-        _: u32,
-        _: *mut u32,
-        _: *mut u32,
+        handle: vr::VROverlayHandle_t,
+        buffer: *mut c_void,
+        buffer_size: u32,
+        width: *mut u32,
+        height: *mut u32,
     ) -> vr::EVROverlayError {
-        todo!()
+        let session_data = self.openxr.session_data.get();
+        let _queue = session_data.comp_data.lock_queue();
+        get_overlay!(self, handle, mut overlay);
+        let image = match overlay.read_texture() {
+            Ok(image) => image,
+            Err(e) => return e,
+        };
+
+        if let Some(width) = unsafe { width.as_mut() } {
+            *width = image.width;
+        }
+        if let Some(height) = unsafe { height.as_mut() } {
+            *height = image.height;
+        }
+        if buffer.is_null() || (buffer_size as usize) < image.pixels.len() {
+            return vr::EVROverlayError::InvalidParameter;
+        }
+
+        let buffer =
+            unsafe { std::slice::from_raw_parts_mut(buffer.cast::<u8>(), image.pixels.len()) };
+        buffer.copy_from_slice(&image.pixels);
+        vr::EVROverlayError::None
     }
     fn SetOverlayName(&self, _: vr::VROverlayHandle_t, _: *const c_char) -> vr::EVROverlayError {
         todo!()
@@ -1280,7 +3866,36 @@ impl vr::IVROverlay027_Interface for OverlayMan {
         let mut overlays = self.overlays.write().unwrap();
         if let Some(overlay) = overlays.remove(key) {
             let mut map = self.key_to_overlay.write().unwrap();
-            map.remove(&overlay.key);
+            map.remove(&normalized_key(&overlay.key));
+            self.skybox.write().unwrap().retain(|k| *k != key);
+
+            // Handles to the overlay must not be handed out or acted on after this.
+            for other in overlays.values_mut() {
+                if other.cursor == Some(key) {
+                    other.cursor = None;
+                }
+                for neighbor in &mut other.neighbors {
+                    if *neighbor == Some(key) {
+                        *neighbor = None;
+                    }
+                }
+            }
+            let mut focus = self.gamepad_focus.lock().unwrap();
+            if *focus == Some(key) {
+                *focus = None;
+            }
+            let mut laser = self.laser.lock().unwrap();
+            if laser.hovered == Some(key) {
+                laser.hovered = None;
+            }
+            if laser.pressed.is_some_and(|(pressed, _)| pressed == key) {
+                laser.pressed = None;
+            }
+            let mut dashboard = self.dashboard.write().unwrap();
+            if dashboard.active == Some(key) {
+                dashboard.active = None;
+                dashboard.visible = false;
+            }
         }
         vr::EVROverlayError::None
     }
@@ -1308,28 +3923,47 @@ impl vr::IVROverlay025On027 for OverlayMan {
 impl vr::IVROverlay021On024 for OverlayMan {
     fn ShowKeyboardForOverlay(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: vr::EGamepadTextInputMode,
-        _: vr::EGamepadTextInputLineMode,
-        _: *const c_char,
-        _: u32,
-        _: *const c_char,
+        handle: vr::VROverlayHandle_t,
+        input_mode: vr::EGamepadTextInputMode,
+        line_mode: vr::EGamepadTextInputLineMode,
+        description: *const c_char,
+        max_chars: u32,
+        existing_text: *const c_char,
         _: bool,
-        _: u64,
+        user_value: u64,
     ) -> vr::EVROverlayError {
-        todo!()
+        <Self as vr::IVROverlay027_Interface>::ShowKeyboardForOverlay(
+            self,
+            handle,
+            input_mode,
+            line_mode,
+            0,
+            description,
+            max_chars,
+            existing_text,
+            user_value,
+        )
     }
     fn ShowKeyboard(
         &self,
-        _: vr::EGamepadTextInputMode,
-        _: vr::EGamepadTextInputLineMode,
-        _: *const c_char,
-        _: u32,
-        _: *const c_char,
+        input_mode: vr::EGamepadTextInputMode,
+        line_mode: vr::EGamepadTextInputLineMode,
+        description: *const c_char,
+        max_chars: u32,
+        existing_text: *const c_char,
         _: bool,
-        _: u64,
+        user_value: u64,
     ) -> vr::EVROverlayError {
-        todo!()
+        <Self as vr::IVROverlay027_Interface>::ShowKeyboard(
+            self,
+            input_mode,
+            line_mode,
+            0,
+            description,
+            max_chars,
+            existing_text,
+            user_value,
+        )
     }
     fn GetOverlayDualAnalogTransform(
         &self,
@@ -1351,61 +3985,144 @@ impl vr::IVROverlay021On024 for OverlayMan {
     }
     fn SetOverlayRenderModel(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: *const c_char,
+        handle: vr::VROverlayHandle_t,
+        render_model: *const c_char,
         _: *const vr::HmdColor_t,
     ) -> vr::EVROverlayError {
-        todo!()
+        get_overlay!(self, handle, overlay);
+        // There's nothing to draw models with, so only clearing the model works.
+        let name = (!render_model.is_null()).then(|| unsafe { CStr::from_ptr(render_model) });
+        match name.filter(|name| !name.is_empty()) {
+            Some(name) => {
+                crate::warn_once!(
+                    "Overlay render models aren't supported (requested {name:?} for {:?})",
+                    overlay.name
+                );
+                vr::EVROverlayError::RequestFailed
+            }
+            None => vr::EVROverlayError::None,
+        }
     }
     fn GetOverlayRenderModel(
         &self,
-        _: vr::VROverlayHandle_t,
+        handle: vr::VROverlayHandle_t,
         _: *mut c_char,
         _: u32,
         _: *mut vr::HmdColor_t,
-        _: *mut vr::EVROverlayError,
+        error: *mut vr::EVROverlayError,
     ) -> u32 {
-        todo!()
+        let exists = self
+            .overlays
+            .read()
+            .unwrap()
+            .contains_key(OverlayKey::from(KeyData::from_ffi(handle)));
+        if let Some(error) = unsafe { error.as_mut() } {
+            *error = if exists {
+                vr::EVROverlayError::None
+            } else {
+                vr::EVROverlayError::UnknownOverlay
+            };
+        }
+        // Overlays never have a render model.
+        0
     }
 }
 
 impl vr::IVROverlay020On021 for OverlayMan {
     fn MoveGamepadFocusToNeighbor(
         &self,
-        _: vr::EOverlayDirection,
-        _: vr::VROverlayHandle_t,
+        direction: vr::EOverlayDirection,
+        from: vr::VROverlayHandle_t,
     ) -> vr::EVROverlayError {
-        todo!()
+        let Some(direction) = neighbor_index(direction) else {
+            return vr::EVROverlayError::InvalidParameter;
+        };
+        let mut overlays = self.overlays.write().unwrap();
+        let Some(from) = overlays.get(OverlayKey::from(KeyData::from_ffi(from))) else {
+            return vr::EVROverlayError::UnknownOverlay;
+        };
+        // DestroyOverlay unlinks destroyed neighbors.
+        let Some(to) = from.neighbors[direction] else {
+            return vr::EVROverlayError::RequestFailed;
+        };
+        self.set_gamepad_focus(&mut overlays, Some(to));
+        vr::EVROverlayError::None
     }
     fn SetOverlayNeighbor(
         &self,
-        _: vr::EOverlayDirection,
-        _: vr::VROverlayHandle_t,
-        _: vr::VROverlayHandle_t,
+        direction: vr::EOverlayDirection,
+        from: vr::VROverlayHandle_t,
+        to: vr::VROverlayHandle_t,
     ) -> vr::EVROverlayError {
-        todo!()
+        let Some(direction) = neighbor_index(direction) else {
+            return vr::EVROverlayError::InvalidParameter;
+        };
+        let mut overlays = self.overlays.write().unwrap();
+        // Linking to the invalid handle removes the neighbor.
+        let to = (to != 0).then(|| OverlayKey::from(KeyData::from_ffi(to)));
+        if to.is_some_and(|to| !overlays.contains_key(to)) {
+            return vr::EVROverlayError::UnknownOverlay;
+        }
+        let Some(from) = overlays.get_mut(OverlayKey::from(KeyData::from_ffi(from))) else {
+            return vr::EVROverlayError::UnknownOverlay;
+        };
+        from.neighbors[direction] = to;
+        vr::EVROverlayError::None
     }
-    fn SetGamepadFocusOverlay(&self, _: vr::VROverlayHandle_t) -> vr::EVROverlayError {
-        todo!()
+    fn SetGamepadFocusOverlay(&self, handle: vr::VROverlayHandle_t) -> vr::EVROverlayError {
+        let mut overlays = self.overlays.write().unwrap();
+        let key = (handle != 0).then(|| OverlayKey::from(KeyData::from_ffi(handle)));
+        if key.is_some_and(|key| !overlays.contains_key(key)) {
+            return vr::EVROverlayError::UnknownOverlay;
+        }
+        self.set_gamepad_focus(&mut overlays, key);
+        vr::EVROverlayError::None
     }
     fn GetGamepadFocusOverlay(&self) -> vr::VROverlayHandle_t {
-        todo!()
+        self.gamepad_focus
+            .lock()
+            .unwrap()
+            .map_or(0, |key| key.data().as_ffi())
     }
     fn GetOverlayAutoCurveDistanceRangeInMeters(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: *mut f32,
-        _: *mut f32,
+        handle: vr::VROverlayHandle_t,
+        min_distance: *mut f32,
+        max_distance: *mut f32,
     ) -> vr::EVROverlayError {
-        todo!()
+        get_overlay!(self, handle, overlay);
+        let (Some(min_distance), Some(max_distance)) = (unsafe { min_distance.as_mut() }, unsafe {
+            max_distance.as_mut()
+        }) else {
+            return vr::EVROverlayError::InvalidParameter;
+        };
+        (*min_distance, *max_distance) = overlay.auto_curve_range.unwrap_or_default();
+        vr::EVROverlayError::None
     }
     fn SetOverlayAutoCurveDistanceRangeInMeters(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: f32,
-        _: f32,
+        handle: vr::VROverlayHandle_t,
+        min_distance: f32,
+        max_distance: f32,
     ) -> vr::EVROverlayError {
-        todo!()
+        get_overlay!(self, handle, mut overlay);
+        if !min_distance.is_finite()
+            || !max_distance.is_finite()
+            || min_distance < 0.0
+            || max_distance < min_distance
+        {
+            warn!(
+                "rejecting auto curve range {min_distance}..{max_distance} for overlay {:?}",
+                overlay.name
+            );
+            return vr::EVROverlayError::InvalidParameter;
+        }
+        debug!(
+            "setting overlay {:?} auto curve range to {min_distance}..{max_distance}",
+            overlay.name
+        );
+        overlay.auto_curve_range = Some((min_distance, max_distance));
+        vr::EVROverlayError::None
     }
 }
 
@@ -1434,16 +4151,39 @@ impl vr::IVROverlay016On018 for OverlayMan {
 impl vr::IVROverlay013On014 for OverlayMan {
     fn GetOverlayTexture(
         &self,
-        _: vr::VROverlayHandle_t,
-        _: *mut *mut c_void,
-        _: *mut c_void,
-        _: *mut u32,
-        _: *mut u32,
-        _: *mut u32,
-        _: *mut vr::EGraphicsAPIConvention,
-        _: *mut vr::EColorSpace,
+        handle: vr::VROverlayHandle_t,
+        native_handle: *mut *mut c_void,
+        native_texture_ref: *mut c_void,
+        width: *mut u32,
+        height: *mut u32,
+        native_format: *mut u32,
+        api: *mut vr::EGraphicsAPIConvention,
+        color_space: *mut vr::EColorSpace,
     ) -> vr::EVROverlayError {
-        todo!()
+        let mut api_type = vr::ETextureType::Invalid;
+        let ret = <Self as vr::IVROverlay027_Interface>::GetOverlayTexture(
+            self,
+            handle,
+            native_handle,
+            native_texture_ref,
+            width,
+            height,
+            native_format,
+            &mut api_type,
+            color_space,
+            std::ptr::null_mut(),
+        );
+        if ret != vr::EVROverlayError::None {
+            return ret;
+        }
+        if let Some(api) = unsafe { api.as_mut() } {
+            // This interface predates Vulkan, so only GL textures can be described.
+            *api = match api_type {
+                vr::ETextureType::OpenGL => vr::EGraphicsAPIConvention::OpenGL,
+                _ => return vr::EVROverlayError::InvalidTexture,
+            };
+        }
+        vr::EVROverlayError::None
     }
 }
 
@@ -0,0 +1,186 @@
+use super::raster;
+use glam::Vec2;
+use log::warn;
+use openvr as vr;
+use openxr as xr;
+
+/// Size of the rendered keyboard, in pixels.
+const WIDTH: usize = 1000;
+const HEIGHT: usize = 400;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum KeyboardLayout {
+    Qwerty,
+    Azerty,
+    Numeric,
+}
+
+impl KeyboardLayout {
+    /// The layout set by XRIZER_KEYBOARD_LAYOUT, or the one matching the system locale.
+    pub fn configured() -> Self {
+        match std::env::var("XRIZER_KEYBOARD_LAYOUT").as_deref() {
+            Ok("qwerty") => Self::Qwerty,
+            Ok("azerty") => Self::Azerty,
+            Ok("numeric") => Self::Numeric,
+            Ok(other) => {
+                warn!("Unknown keyboard layout {other:?}, using system locale");
+                Self::from_locale()
+            }
+            Err(_) => Self::from_locale(),
+        }
+    }
+
+    fn from_locale() -> Self {
+        let locale = std::env::var("LC_ALL")
+            .or_else(|_| std::env::var("LANG"))
+            .unwrap_or_default();
+        if locale.starts_with("fr") {
+            Self::Azerty
+        } else {
+            Self::Qwerty
+        }
+    }
+
+    pub fn rows(self) -> &'static [&'static str] {
+        match self {
+            Self::Qwerty => &["1234567890", "qwertyuiop", "asdfghjkl", "zxcvbnm"],
+            Self::Azerty => &["1234567890", "azertyuiop", "qsdfghjklm", "wxcvbn"],
+            Self::Numeric => &["123", "456", "789", "0"],
+        }
+    }
+}
+
+/// A key on the virtual keyboard.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Key {
+    Char(char),
+    Backspace,
+    Done,
+}
+
+/// State of the software keyboard requested through ShowKeyboard.
+pub struct Keyboard {
+    layout: KeyboardLayout,
+    password: bool,
+    multiline: bool,
+    max_chars: usize,
+    text: String,
+}
+
+impl Keyboard {
+    pub fn new(
+        layout: KeyboardLayout,
+        mode: vr::EGamepadTextInputMode,
+        line_mode: vr::EGamepadTextInputLineMode,
+        max_chars: u32,
+        existing_text: &str,
+    ) -> Self {
+        // The app's text is kept as is, even if it has characters the keyboard can't type.
+        Self {
+            layout,
+            password: mode == vr::EGamepadTextInputMode::Password,
+            multiline: line_mode == vr::EGamepadTextInputLineMode::MultipleLines,
+            max_chars: max_chars as usize,
+            text: existing_text.chars().take(max_chars as usize).collect(),
+        }
+    }
+
+    pub fn layout(&self) -> KeyboardLayout {
+        self.layout
+    }
+
+    /// All characters that can be typed with this keyboard.
+    pub fn keys(&self) -> impl Iterator<Item = char> + '_ {
+        let numeric = self.layout == KeyboardLayout::Numeric;
+        let space = (!numeric).then_some(' ');
+        let newline = (!numeric && self.multiline).then_some('\n');
+        self.layout
+            .rows()
+            .iter()
+            .flat_map(|row| row.chars())
+            .chain(space)
+            .chain(newline)
+    }
+
+    fn accepts(&self, c: char) -> bool {
+        let c = match self.layout {
+            KeyboardLayout::Numeric => c,
+            // letters can be shifted
+            _ => c.to_ascii_lowercase(),
+        };
+        self.keys().any(|key| key == c)
+    }
+
+    /// Returns false if the character was rejected.
+    pub fn type_char(&mut self, c: char) -> bool {
+        if !self.accepts(c) || self.text.chars().count() >= self.max_chars {
+            return false;
+        }
+        self.text.push(c);
+        true
+    }
+
+    /// Returns false if there was nothing to delete.
+    pub fn backspace(&mut self) -> bool {
+        self.text.pop().is_some()
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The text as it should be shown on the keyboard.
+    pub fn display_text(&self) -> String {
+        if self.password {
+            "*".repeat(self.text.chars().count())
+        } else {
+            self.text.clone()
+        }
+    }
+
+    /// Renders the keyboard to tightly packed sRGB RGBA8 pixels, starting at the top row.
+    pub fn render(&self) -> (xr::Extent2Di, Vec<u8>) {
+        raster::render(WIDTH, HEIGHT, |ctx| self.ui(ctx, &mut |_, _| {}))
+    }
+
+    /// The key at a point on the rendered keyboard, with the origin at the bottom left.
+    pub fn key_at(&self, uv: Vec2) -> Option<Key> {
+        let pos = raster::to_points(uv.x * WIDTH as f32, (1.0 - uv.y) * HEIGHT as f32);
+        let mut keys = Vec::new();
+        raster::layout(WIDTH, HEIGHT, |ctx| {
+            keys.clear();
+            self.ui(ctx, &mut |key, rect| keys.push((key, rect)));
+        });
+        keys.into_iter()
+            .find(|(_, rect)| rect.contains(pos))
+            .map(|(key, _)| key)
+    }
+
+    /// Lays out the keyboard, telling `on_key` where each key ended up.
+    fn ui(&self, ctx: &egui::Context, on_key: &mut impl FnMut(Key, egui::Rect)) {
+        egui::TopBottomPanel::top("text").show(ctx, |ui| {
+            ui.monospace(self.display_text());
+        });
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                for row in self.layout.rows() {
+                    ui.horizontal(|ui| {
+                        for key in row.chars() {
+                            on_key(Key::Char(key), ui.button(key.to_string()).rect);
+                        }
+                    });
+                }
+                ui.horizontal(|ui| {
+                    if self.keys().any(|key| key == ' ') {
+                        on_key(Key::Char(' '), ui.button("Space").rect);
+                    }
+                    if self.keys().any(|key| key == '\n') {
+                        on_key(Key::Char('\n'), ui.button("Enter").rect);
+                    }
+                    on_key(Key::Backspace, ui.button("Backspace").rect);
+                    on_key(Key::Done, ui.button("Done").rect);
+                });
+            });
+        });
+    }
+}
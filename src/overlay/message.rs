@@ -0,0 +1,41 @@
+use super::raster;
+use openxr as xr;
+
+/// Size of the rendered message, in pixels.
+const WIDTH: usize = 800;
+const HEIGHT: usize = 450;
+
+/// The contents of a message box requested through ShowMessageOverlay.
+#[derive(Debug)]
+pub struct Message {
+    pub text: String,
+    pub caption: String,
+    pub buttons: Vec<String>,
+}
+
+impl Message {
+    /// Renders the message box to tightly packed sRGB RGBA8 pixels, starting at the top row.
+    pub fn render(&self) -> (xr::Extent2Di, Vec<u8>) {
+        raster::render(WIDTH, HEIGHT, |ctx| self.ui(ctx))
+    }
+
+    fn ui(&self, ctx: &egui::Context) {
+        egui::TopBottomPanel::top("caption").show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.heading(&self.caption);
+            });
+        });
+        egui::TopBottomPanel::bottom("buttons").show(ctx, |ui| {
+            ui.horizontal_centered(|ui| {
+                for button in &self.buttons {
+                    let _ = ui.button(button);
+                }
+            });
+        });
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.label(&self.text);
+            });
+        });
+    }
+}
@@ -0,0 +1,222 @@
+use egui::epaint::{ClippedPrimitive, Primitive, Vertex};
+use egui::{ImageData, TextureId};
+use openxr as xr;
+use std::collections::HashMap;
+
+const PIXELS_PER_POINT: f32 = 2.0;
+
+/// Renders an egui UI to tightly packed sRGB RGBA8 pixels, starting at the top row. This is only
+/// meant for the handful of overlays we draw ourselves, so it's done on the CPU.
+pub fn render(
+    width: usize,
+    height: usize,
+    mut ui: impl FnMut(&egui::Context),
+) -> (xr::Extent2Di, Vec<u8>) {
+    let ctx = context();
+
+    // The first pass sets up the pixels per point and lays things out, the second one is
+    // the one that gets drawn.
+    let mut textures = Textures::default();
+    let first = ctx.run(input(width, height), &mut ui);
+    textures.update(first.textures_delta);
+    let output = ctx.run(input(width, height), &mut ui);
+    textures.update(output.textures_delta);
+
+    let mut canvas = Canvas {
+        width,
+        height,
+        pixels: vec![[0.0; 4]; width * height],
+    };
+    for ClippedPrimitive {
+        clip_rect,
+        primitive,
+    } in ctx.tessellate(output.shapes, output.pixels_per_point)
+    {
+        let Primitive::Mesh(mesh) = primitive else {
+            continue;
+        };
+        let Some(texture) = textures.0.get(&mesh.texture_id) else {
+            continue;
+        };
+        let clip = egui::Rect::from_min_max(
+            scale(clip_rect.min, output.pixels_per_point),
+            scale(clip_rect.max, output.pixels_per_point),
+        );
+        for tri in mesh.indices.chunks_exact(3) {
+            canvas.draw_triangle(
+                texture,
+                [0, 1, 2].map(|i| mesh.vertices[tri[i] as usize]),
+                output.pixels_per_point,
+                clip,
+            );
+        }
+    }
+
+    let pixels = canvas
+        .pixels
+        .into_iter()
+        .flat_map(|px| px.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8))
+        .collect();
+    (
+        xr::Extent2Di {
+            width: width as i32,
+            height: height as i32,
+        },
+        pixels,
+    )
+}
+
+/// Lays out an egui UI the same way [`render`] does, without drawing it. The last call to `ui`
+/// sees the final layout.
+pub fn layout(width: usize, height: usize, mut ui: impl FnMut(&egui::Context)) {
+    let ctx = context();
+    for _ in 0..2 {
+        let _ = ctx.run(input(width, height), &mut ui);
+    }
+}
+
+/// Converts a position in pixels, starting at the top left, to egui points.
+pub fn to_points(x: f32, y: f32) -> egui::Pos2 {
+    egui::pos2(x, y) / PIXELS_PER_POINT
+}
+
+fn context() -> egui::Context {
+    let ctx = egui::Context::default();
+    ctx.set_pixels_per_point(PIXELS_PER_POINT);
+    ctx
+}
+
+fn input(width: usize, height: usize) -> egui::RawInput {
+    egui::RawInput {
+        screen_rect: Some(egui::Rect::from_min_size(
+            egui::Pos2::ZERO,
+            egui::vec2(width as f32, height as f32) / PIXELS_PER_POINT,
+        )),
+        ..Default::default()
+    }
+}
+
+/// Premultiplied RGBA pixels being drawn to.
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<[f32; 4]>,
+}
+
+/// Textures egui asked us to create, as premultiplied RGBA.
+#[derive(Default)]
+struct Textures(HashMap<TextureId, Texture>);
+
+struct Texture {
+    width: usize,
+    height: usize,
+    texels: Vec<[f32; 4]>,
+}
+
+impl Textures {
+    fn update(&mut self, delta: egui::TexturesDelta) {
+        for (id, delta) in delta.set {
+            let (size, texels): ([usize; 2], Vec<[f32; 4]>) = match &delta.image {
+                ImageData::Color(image) => (
+                    image.size,
+                    image
+                        .pixels
+                        .iter()
+                        .map(|c| c.to_array().map(|c| c as f32 / 255.0))
+                        .collect(),
+                ),
+                // Font texels are white, with the glyph coverage as alpha.
+                ImageData::Font(image) => {
+                    (image.size, image.pixels.iter().map(|c| [*c; 4]).collect())
+                }
+            };
+            let [width, height] = size;
+            match delta.pos {
+                None => {
+                    self.0.insert(
+                        id,
+                        Texture {
+                            width,
+                            height,
+                            texels,
+                        },
+                    );
+                }
+                Some([x, y]) => {
+                    let Some(texture) = self.0.get_mut(&id) else {
+                        continue;
+                    };
+                    for (row, src) in texels.chunks_exact(width).enumerate() {
+                        let start = (y + row) * texture.width + x;
+                        texture.texels[start..start + width].copy_from_slice(src);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Texture {
+    fn sample(&self, uv: egui::Pos2) -> [f32; 4] {
+        let x = ((uv.x * self.width as f32) as usize).min(self.width - 1);
+        let y = ((uv.y * self.height as f32) as usize).min(self.height - 1);
+        self.texels[y * self.width + x]
+    }
+}
+
+fn scale(pos: egui::Pos2, factor: f32) -> egui::Pos2 {
+    (pos.to_vec2() * factor).to_pos2()
+}
+
+impl Canvas {
+    /// Blends a textured triangle over the canvas, using premultiplied alpha.
+    fn draw_triangle(
+        &mut self,
+        texture: &Texture,
+        vertices: [Vertex; 3],
+        pixels_per_point: f32,
+        clip: egui::Rect,
+    ) {
+        let [a, b, c] = vertices.map(|v| scale(v.pos, pixels_per_point));
+        let area = (b - a).x * (c - a).y - (b - a).y * (c - a).x;
+        if area.abs() < f32::EPSILON {
+            return;
+        }
+
+        let min_x = a.x.min(b.x).min(c.x).max(clip.min.x).max(0.0) as usize;
+        let min_y = a.y.min(b.y).min(c.y).max(clip.min.y).max(0.0) as usize;
+        let max_x = (a.x.max(b.x).max(c.x).min(clip.max.x).ceil() as usize).min(self.width);
+        let max_y = (a.y.max(b.y).max(c.y).min(clip.max.y).ceil() as usize).min(self.height);
+        let colors = vertices.map(|v| v.color.to_array().map(|c| c as f32 / 255.0));
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let p = egui::pos2(x as f32 + 0.5, y as f32 + 0.5);
+                let weights = [
+                    ((c - b).x * (p - b).y - (c - b).y * (p - b).x) / area,
+                    ((a - c).x * (p - c).y - (a - c).y * (p - c).x) / area,
+                    ((b - a).x * (p - a).y - (b - a).y * (p - a).x) / area,
+                ];
+                if weights.iter().any(|w| *w < 0.0) {
+                    continue;
+                }
+
+                let uv = vertices
+                    .iter()
+                    .zip(weights)
+                    .fold(egui::Pos2::ZERO, |uv, (v, w)| uv + v.uv.to_vec2() * w);
+                let texel = texture.sample(uv);
+                let dst = &mut self.pixels[y * self.width + x];
+                let src: [f32; 4] = std::array::from_fn(|i| {
+                    weights
+                        .iter()
+                        .zip(&colors)
+                        .map(|(w, color)| w * color[i])
+                        .sum::<f32>()
+                        * texel[i]
+                });
+                *dst = std::array::from_fn(|i| src[i] + dst[i] * (1.0 - src[3]));
+            }
+        }
+    }
+}
@@ -0,0 +1,3375 @@
+use super::*;
+use crate::compositor::FakeGraphicsData;
+use crate::graphics_backends::VulkanData;
+use keyboard::{Key, Keyboard, KeyboardLayout};
+use vr::EVROverlayError::*;
+use vr::{IVRCompositor028_Interface, IVROverlay027_Interface};
+
+/// How long the laser takes to update in tests
+const FRAME_TIME: Duration = Duration::from_millis(10);
+
+/// A transform that only moves things, without rotating them.
+fn translation(x: f32, y: f32, z: f32) -> vr::HmdMatrix34_t {
+    vr::HmdMatrix34_t {
+        m: [[1.0, 0.0, 0.0, x], [0.0, 1.0, 0.0, y], [0.0, 0.0, 1.0, z]],
+    }
+}
+
+struct Fixture {
+    overlays: Arc<OverlayMan>,
+    comp: Arc<Compositor>,
+    vk: Arc<VulkanData>,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        Self::with_xr(OpenXrData::new(&Injector::default()).unwrap())
+    }
+
+    fn with_xr(xr: OpenXrData<Compositor>) -> Self {
+        Self::with_options(xr, |_| {})
+    }
+
+    /// For options that are otherwise only read from the environment
+    fn with_options(xr: OpenXrData<Compositor>, configure: impl FnOnce(&mut OverlayMan)) -> Self {
+        let xr = Arc::new(xr);
+        let vk = Arc::new(VulkanData::new_temporary(&xr.instance, xr.system_id));
+        let comp = Arc::new(Compositor::new(xr.clone(), &Injector::default()));
+        xr.compositor.set(Arc::downgrade(&comp));
+        let mut overlays = OverlayMan::new(xr, &Injector::default());
+        configure(&mut overlays);
+        let overlays = Arc::new(overlays);
+        overlays.compositor.set(Arc::downgrade(&comp));
+        comp.overlays.set(Arc::downgrade(&overlays));
+        crate::init_logging();
+
+        Self { overlays, comp, vk }
+    }
+
+    #[track_caller]
+    fn create_overlay(&self, key: &CStr) -> vr::VROverlayHandle_t {
+        let mut handle = 0;
+        assert_eq!(
+            self.overlays
+                .CreateOverlay(key.as_ptr(), key.as_ptr(), &mut handle),
+            None
+        );
+        handle
+    }
+
+    fn wait_get_poses(&self) {
+        assert_eq!(
+            self.comp
+                .WaitGetPoses(std::ptr::null_mut(), 0, std::ptr::null_mut(), 0),
+            vr::EVRCompositorError::None
+        );
+    }
+
+    /// Overlays need a real session to have textures
+    fn start_real_session(&self) {
+        let handle = self.create_overlay(c"session_starter");
+        self.wait_get_poses();
+        assert_eq!(
+            self.overlays.SetOverlayTexture(handle, &self.texture()),
+            None
+        );
+    }
+
+    fn texture(&self) -> vr::Texture_t {
+        FakeGraphicsData::texture(&self.vk)
+    }
+
+    /// Returns the main and thumbnail handles.
+    #[track_caller]
+    fn create_dashboard_overlay(
+        &self,
+        key: &CStr,
+    ) -> (vr::VROverlayHandle_t, vr::VROverlayHandle_t) {
+        let (mut main, mut thumbnail) = (0, 0);
+        assert_eq!(
+            self.overlays.CreateDashboardOverlay(
+                key.as_ptr(),
+                c"Dashboard".as_ptr(),
+                &mut main,
+                &mut thumbnail
+            ),
+            None
+        );
+        (main, thumbnail)
+    }
+
+    /// Runs the laser for one frame, taking FRAME_TIME.
+    fn update_laser(
+        &self,
+        origin: vr::ETrackingUniverseOrigin,
+        controller: Option<(Hand, vr::HmdMatrix34_t)>,
+        pointer: PointerState,
+    ) {
+        self.overlays
+            .update_laser(origin, controller, pointer, FRAME_TIME);
+    }
+
+    fn layer_count(&self) -> usize {
+        let session = self.overlays.openxr.session_data.get();
+        self.overlays
+            .get_layers::<<FakeGraphicsData as GraphicsBackend>::Api>(&session, 1.0, 0)
+            .len()
+    }
+
+    #[track_caller]
+    fn overlay<R>(&self, handle: vr::VROverlayHandle_t, f: impl FnOnce(&Overlay) -> R) -> R {
+        let overlays = self.overlays.overlays.read().unwrap();
+        f(overlays
+            .get(OverlayKey::from(KeyData::from_ffi(handle)))
+            .expect("overlay should exist"))
+    }
+
+    #[track_caller]
+    fn overlay_mut<R>(
+        &self,
+        handle: vr::VROverlayHandle_t,
+        f: impl FnOnce(&mut Overlay) -> R,
+    ) -> R {
+        let mut overlays = self.overlays.overlays.write().unwrap();
+        f(overlays
+            .get_mut(OverlayKey::from(KeyData::from_ffi(handle)))
+            .expect("overlay should exist"))
+    }
+}
+
+#[test]
+fn texture_color_space() {
+    let f = Fixture::new();
+    let handle = f.create_overlay(c"color_space");
+
+    let mut space = vr::EColorSpace::Linear;
+    assert_eq!(
+        f.overlays.GetOverlayTextureColorSpace(handle, &mut space),
+        None
+    );
+    assert_eq!(space, vr::EColorSpace::Auto);
+
+    let mut texture = f.texture();
+    texture.eColorSpace = vr::EColorSpace::Gamma;
+    f.overlay(handle, |o| {
+        assert_eq!(o.color_space_for(&texture), vr::EColorSpace::Gamma)
+    });
+
+    assert_eq!(
+        f.overlays
+            .SetOverlayTextureColorSpace(handle, vr::EColorSpace::Linear),
+        None
+    );
+    assert_eq!(
+        f.overlays.GetOverlayTextureColorSpace(handle, &mut space),
+        None
+    );
+    assert_eq!(space, vr::EColorSpace::Linear);
+    f.overlay(handle, |o| {
+        assert_eq!(o.color_space_for(&texture), vr::EColorSpace::Linear)
+    });
+
+    assert_eq!(
+        f.overlays
+            .GetOverlayTextureColorSpace(handle, std::ptr::null_mut()),
+        InvalidParameter
+    );
+    assert_eq!(
+        f.overlays
+            .SetOverlayTextureColorSpace(0, vr::EColorSpace::Gamma),
+        UnknownOverlay
+    );
+}
+
+#[test]
+fn set_texture_with_color_space_override() {
+    let f = Fixture::new();
+    let handle = f.create_overlay(c"color_space_texture");
+    assert_eq!(
+        f.overlays
+            .SetOverlayTextureColorSpace(handle, vr::EColorSpace::Gamma),
+        None
+    );
+
+    f.wait_get_poses();
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+    f.overlay(handle, |o| assert!(o.rect.is_some()));
+}
+
+#[test]
+fn untextured_overlay_warns_once() {
+    let f = Fixture::new();
+    let handle = f.create_overlay(c"untextured");
+    assert_eq!(f.overlays.SetOverlayWidthInMeters(handle, 2.0), None);
+
+    // hidden overlays are not expected to have a texture
+    for _ in 0..UNTEXTURED_WARN_FRAMES {
+        assert_eq!(f.layer_count(), 0);
+    }
+    f.overlay(handle, |o| {
+        assert_eq!(o.untextured_frames.load(Ordering::Relaxed), 0)
+    });
+
+    assert_eq!(f.overlays.ShowOverlay(handle), None);
+    for _ in 0..UNTEXTURED_WARN_FRAMES - 1 {
+        assert_eq!(f.layer_count(), 0);
+    }
+    f.overlay(handle, |o| {
+        assert!(!o.warned_untextured.load(Ordering::Relaxed))
+    });
+
+    assert_eq!(f.layer_count(), 0);
+    f.overlay(handle, |o| {
+        assert!(o.warned_untextured.load(Ordering::Relaxed))
+    });
+
+    let mut overlays = f.overlays.overlays.write().unwrap();
+    let overlay = overlays
+        .get_mut(OverlayKey::from(KeyData::from_ffi(handle)))
+        .unwrap();
+    assert!((0..UNTEXTURED_WARN_FRAMES).all(|_| !overlay.note_untextured_frame()));
+}
+
+#[test]
+fn dashboard_overlay() {
+    let f = Fixture::new();
+    let (main, thumbnail) = f.create_dashboard_overlay(c"dashboard");
+    assert_ne!(main, thumbnail);
+
+    let mut found = 0;
+    assert_eq!(
+        f.overlays
+            .FindOverlay(c"dashboard.thumbnail".as_ptr(), &mut found),
+        None
+    );
+    assert_eq!(found, thumbnail);
+
+    f.wait_get_poses();
+    assert_eq!(f.overlays.SetOverlayTexture(main, &f.texture()), None);
+    assert_eq!(f.overlays.SetOverlayTexture(thumbnail, &f.texture()), None);
+
+    assert!(!f.overlays.IsDashboardVisible());
+    assert!(!f.overlays.IsActiveDashboardOverlay(main));
+    assert_eq!(f.layer_count(), 0);
+
+    f.overlays.ShowDashboard(c"dashboard".as_ptr());
+    assert!(f.overlays.IsDashboardVisible());
+    assert!(f.overlays.IsActiveDashboardOverlay(main));
+    assert!(!f.overlays.IsActiveDashboardOverlay(thumbnail));
+    assert_eq!(f.layer_count(), 1);
+
+    // Hiding other overlays leaves the dashboard up
+    assert_eq!(f.overlays.HideOverlay(thumbnail), None);
+    assert!(f.overlays.IsDashboardVisible());
+
+    // Hiding the one shown with it closes it
+    assert_eq!(f.overlays.HideOverlay(main), None);
+    assert!(!f.overlays.IsDashboardVisible());
+    assert!(!f.overlays.IsActiveDashboardOverlay(main));
+    assert_eq!(f.layer_count(), 0);
+
+    f.overlays.ShowDashboard(std::ptr::null());
+    assert!(f.overlays.IsActiveDashboardOverlay(main));
+    assert_eq!(f.layer_count(), 1);
+
+    // A key that only fits without the thumbnail suffix creates neither overlay.
+    let long_key =
+        CString::new("k".repeat(vr::k_unVROverlayMaxKeyLength as usize - ".thumbnail".len()))
+            .unwrap();
+    let (mut long_main, mut long_thumbnail) = (0, 0);
+    assert_eq!(
+        f.overlays.CreateDashboardOverlay(
+            long_key.as_ptr(),
+            c"long".as_ptr(),
+            &mut long_main,
+            &mut long_thumbnail
+        ),
+        KeyTooLong
+    );
+    assert_eq!(
+        f.overlays.FindOverlay(long_key.as_ptr(), &mut found),
+        UnknownOverlay
+    );
+}
+
+#[test]
+fn dashboard_scene_process() {
+    let f = Fixture::new();
+    let (main, thumbnail) = f.create_dashboard_overlay(c"dashboard");
+    let get_process = |handle| {
+        let mut pid = u32::MAX;
+        let err = f.overlays.GetDashboardOverlaySceneProcess(handle, &mut pid);
+        (err, pid)
+    };
+
+    assert_eq!(get_process(main), (None, 0));
+    assert_eq!(f.overlays.SetDashboardOverlaySceneProcess(main, 1234), None);
+    assert_eq!(get_process(main), (None, 1234));
+    assert_eq!(get_process(thumbnail), (None, 0));
+    assert_eq!(
+        f.overlays
+            .GetDashboardOverlaySceneProcess(main, std::ptr::null_mut()),
+        InvalidParameter
+    );
+
+    // Regular overlays don't belong to the dashboard.
+    let regular = f.create_overlay(c"regular");
+    assert_eq!(
+        f.overlays.SetDashboardOverlaySceneProcess(regular, 1234),
+        UnknownOverlay
+    );
+    assert_eq!(get_process(regular).0, UnknownOverlay);
+    assert_eq!(get_process(0xdead << 32).0, UnknownOverlay);
+
+    assert!(!f.overlays.IsActiveDashboardOverlay(main));
+    f.overlays.ShowDashboard(c"dashboard".as_ptr());
+    assert!(f.overlays.IsActiveDashboardOverlay(main));
+    assert!(!f.overlays.IsActiveDashboardOverlay(regular));
+}
+
+#[test]
+fn primary_dashboard_device() {
+    let f = Fixture::new();
+    let origin = vr::ETrackingUniverseOrigin::Standing;
+    let controller = translation(0.0, 0.0, 0.0);
+    let (main, _) = f.create_dashboard_overlay(c"dashboard");
+
+    f.update_laser(
+        origin,
+        Some((Hand::Left, controller)),
+        PointerState::default(),
+    );
+    // Hidden dashboards aren't driven by anything
+    assert_eq!(
+        f.overlays.GetPrimaryDashboardDevice(),
+        vr::k_unTrackedDeviceIndexInvalid
+    );
+
+    f.overlays.ShowDashboard(c"dashboard".as_ptr());
+    assert_eq!(f.overlays.GetPrimaryDashboardDevice(), Hand::Left as u32);
+
+    f.update_laser(origin, Option::None, PointerState::default());
+    assert_eq!(
+        f.overlays.GetPrimaryDashboardDevice(),
+        vr::k_unTrackedDeviceIndexInvalid
+    );
+}
+
+#[test]
+fn laser_haptic_vibration() {
+    use vr::IVRInput010_Interface;
+    let f = Fixture::new();
+    let input = Arc::new(Input::new(f.overlays.openxr.clone()));
+    f.overlays.openxr.input.set(Arc::downgrade(&input));
+    f.overlays.input.set(Arc::downgrade(&input));
+
+    // The laser vibrates through the app's vibration actions.
+    let manifest = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/input_data/actions.json\0"
+    );
+    assert_eq!(
+        input.SetActionManifestPath(manifest.as_ptr().cast()),
+        vr::EVRInputError::None
+    );
+    let mut vibration = 0;
+    assert_eq!(
+        input.GetActionHandle(c"/actions/set1/in/VibAct".as_ptr(), &mut vibration),
+        vr::EVRInputError::None
+    );
+    f.start_real_session();
+    let haptic = input
+        .haptic_action(vibration)
+        .expect("vibration action should be loaded");
+    let last_feedback = |hand| fakexr::last_haptic_feedback(haptic, hand);
+
+    let handle = f.create_overlay(c"haptics");
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+    assert_eq!(f.overlays.ShowOverlay(handle), None);
+    assert_eq!(
+        f.overlays
+            .SetOverlayInputMethod(handle, vr::VROverlayInputMethod::Mouse),
+        None
+    );
+    let vibrate = || {
+        f.overlays
+            .TriggerLaserMouseHapticVibration(handle, 0.5, 100.0, 0.75)
+    };
+    let laser_at = |x: f32| {
+        let controller = translation(x, 0.0, 0.0);
+        f.update_laser(
+            vr::ETrackingUniverseOrigin::Standing,
+            Some((Hand::Right, controller)),
+            PointerState::default(),
+        );
+    };
+
+    // Nothing is pointing at the overlay, so there's nothing to vibrate.
+    assert_eq!(vibrate(), None);
+    laser_at(5.0);
+    assert!(!f.overlays.IsHoverTargetOverlay(handle));
+    assert_eq!(vibrate(), None);
+    assert_eq!(last_feedback(fakexr::UserPath::RightHand), Option::None);
+
+    laser_at(0.0);
+    assert!(f.overlays.IsHoverTargetOverlay(handle));
+    assert_eq!(vibrate(), None);
+    assert_eq!(
+        last_feedback(fakexr::UserPath::RightHand),
+        Some(fakexr::HapticFeedback {
+            duration: xr::Duration::from_nanos(500_000_000),
+            frequency: 100.0,
+            amplitude: 0.75,
+        })
+    );
+    assert_eq!(last_feedback(fakexr::UserPath::LeftHand), Option::None);
+
+    assert_eq!(
+        f.overlays
+            .TriggerLaserMouseHapticVibration(0xdead << 32, 0.5, 100.0, 0.75),
+        UnknownOverlay
+    );
+}
+
+#[test]
+fn null_out_pointers() {
+    use std::ptr::null_mut;
+    let f = Fixture::new();
+    let handle = f.create_overlay(c"null_out");
+    let mut value = 0.0;
+
+    assert_eq!(
+        f.overlays.GetOverlayCurvature(handle, null_mut()),
+        InvalidParameter
+    );
+    assert_eq!(
+        f.overlays.GetOverlayWidthInMeters(handle, null_mut()),
+        InvalidParameter
+    );
+    assert_eq!(
+        f.overlays.GetOverlaySortOrder(handle, null_mut()),
+        InvalidParameter
+    );
+    assert_eq!(
+        f.overlays.GetOverlayAlpha(handle, null_mut()),
+        InvalidParameter
+    );
+    assert_eq!(
+        f.overlays.GetOverlayTexelAspect(handle, null_mut()),
+        InvalidParameter
+    );
+    assert_eq!(
+        f.overlays
+            .GetOverlayColor(handle, &mut value, &mut value, null_mut()),
+        InvalidParameter
+    );
+    assert_eq!(
+        f.overlays.GetOverlayFlags(handle, null_mut()),
+        InvalidParameter
+    );
+    assert_eq!(
+        f.overlays.GetOverlayFlag(
+            handle,
+            vr::VROverlayFlags::SortWithNonSceneOverlays,
+            null_mut()
+        ),
+        InvalidParameter
+    );
+    assert_eq!(
+        f.overlays.GetOverlayTextureBounds(handle, null_mut()),
+        InvalidParameter
+    );
+    assert_eq!(
+        f.overlays.GetOverlayTextureColorSpace(handle, null_mut()),
+        InvalidParameter
+    );
+    assert_eq!(
+        f.overlays.GetOverlayMouseScale(handle, null_mut()),
+        InvalidParameter
+    );
+    assert_eq!(
+        f.overlays.GetOverlayInputMethod(handle, null_mut()),
+        InvalidParameter
+    );
+    assert_eq!(
+        f.overlays
+            .GetOverlayTransformAbsolute(handle, null_mut(), null_mut()),
+        InvalidParameter
+    );
+    assert_eq!(
+        f.overlays
+            .GetOverlayAutoCurveDistanceRangeInMeters(handle, &mut value, null_mut()),
+        InvalidParameter
+    );
+    assert_eq!(
+        f.overlays.GetOverlayTexture(
+            handle,
+            null_mut(),
+            null_mut(),
+            null_mut(),
+            null_mut(),
+            null_mut(),
+            null_mut(),
+            null_mut(),
+            null_mut()
+        ),
+        InvalidParameter
+    );
+
+    // unknown handles are still reported as such
+    assert_eq!(
+        f.overlays.GetOverlayWidthInMeters(0xdead << 32, null_mut()),
+        UnknownOverlay
+    );
+}
+
+#[test]
+fn overlay_alpha_is_clamped() {
+    let mut xr = OpenXrData::new(&Injector::default()).unwrap();
+    xr.enabled_extensions.khr_composition_layer_color_scale_bias = true;
+    let f = Fixture::with_xr(xr);
+    let handle = f.create_overlay(c"alpha");
+    let get_alpha = || {
+        let mut alpha = 0.0;
+        assert_eq!(f.overlays.GetOverlayAlpha(handle, &mut alpha), None);
+        alpha
+    };
+
+    assert_eq!(f.overlays.SetOverlayAlpha(handle, 0.5), None);
+    assert_eq!(get_alpha(), 0.5);
+
+    assert_eq!(f.overlays.SetOverlayAlpha(handle, 2.0), None);
+    assert_eq!(get_alpha(), 1.0);
+    f.overlay(handle, |o| assert_eq!(o.alpha, Option::None));
+
+    assert_eq!(f.overlays.SetOverlayAlpha(handle, -1.0), None);
+    assert_eq!(get_alpha(), 0.0);
+
+    for invalid in [f32::NAN, f32::INFINITY] {
+        assert_eq!(
+            f.overlays.SetOverlayAlpha(handle, invalid),
+            InvalidParameter
+        );
+        assert_eq!(get_alpha(), 0.0);
+    }
+}
+
+#[test]
+fn overlay_color_tints_each_channel() {
+    let mut xr = OpenXrData::new(&Injector::default()).unwrap();
+    xr.enabled_extensions.khr_composition_layer_color_scale_bias = true;
+    let f = Fixture::with_xr(xr);
+    f.start_real_session();
+    let handle = f.create_overlay(c"tint");
+    let get_color = || {
+        let (mut r, mut g, mut b) = (0.0, 0.0, 0.0);
+        assert_eq!(
+            f.overlays.GetOverlayColor(handle, &mut r, &mut g, &mut b),
+            None
+        );
+        [r, g, b]
+    };
+    assert_eq!(get_color(), [1.0; 3]);
+
+    // a white glyph texture tinted red
+    let texture = f.texture();
+    FakeGraphicsData::set_texture_color(&texture, [0xff; 4]);
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &texture), None);
+    assert_eq!(f.overlays.ShowOverlay(handle), None);
+    assert_eq!(f.overlays.SetOverlayColor(handle, 1.0, 0.0, 0.0), None);
+    assert_eq!(f.overlays.SetOverlayAlpha(handle, 0.5), None);
+    assert_eq!(get_color(), [1.0, 0.0, 0.0]);
+
+    {
+        let session = f.overlays.openxr.session_data.get();
+        let layers = f
+            .overlays
+            .get_layers::<<FakeGraphicsData as GraphicsBackend>::Api>(&session, 1.0, 0);
+        assert_eq!(layers.len(), 1);
+        // color and alpha share the one chained struct
+        let bias = layers[0].color_bias_khr.as_deref().unwrap();
+        let scale = bias.color_scale;
+        assert_eq!([scale.r, scale.g, scale.b, scale.a], [1.0, 0.0, 0.0, 0.5]);
+        assert!(bias.next.is_null());
+    }
+
+    assert_eq!(
+        f.overlays.SetOverlayColor(handle, f32::NAN, 0.0, 0.0),
+        InvalidParameter
+    );
+    assert_eq!(f.overlays.SetOverlayColor(handle, 2.0, 1.0, 1.0), None);
+    assert_eq!(get_color(), [1.0; 3]);
+    f.overlay(handle, |o| assert_eq!(o.color, Option::None));
+}
+
+#[test]
+fn premultiplied_alpha_layer_flags() {
+    let mut xr = OpenXrData::new(&Injector::default()).unwrap();
+    xr.enabled_extensions.khr_composition_layer_color_scale_bias = true;
+    let f = Fixture::with_xr(xr);
+    f.start_real_session();
+    let handle = f.create_overlay(c"premultiplied");
+    let texture = f.texture();
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &texture), None);
+    assert_eq!(f.overlays.ShowOverlay(handle), None);
+    assert_eq!(f.overlays.SetOverlayAlpha(handle, 0.5), None);
+
+    let blend = xr::CompositionLayerFlags::BLEND_TEXTURE_SOURCE_ALPHA;
+    let straight = blend | xr::CompositionLayerFlags::UNPREMULTIPLIED_ALPHA;
+    // Ignoring the texture's alpha replaces it with the overlay's, through the bias.
+    for (premultiplied, ignore_alpha, flags, scale, bias_alpha) in [
+        (false, false, straight, [1.0, 1.0, 1.0, 0.5], 0.0),
+        (true, false, blend, [0.5, 0.5, 0.5, 0.5], 0.0),
+        (false, true, straight, [1.0, 1.0, 1.0, 0.0], 0.5),
+        (true, true, straight, [1.0, 1.0, 1.0, 0.0], 0.5),
+    ] {
+        for (flag, enabled) in [
+            (vr::VROverlayFlags::IsPremultiplied, premultiplied),
+            (vr::VROverlayFlags::IgnoreTextureAlpha, ignore_alpha),
+        ] {
+            assert_eq!(f.overlays.SetOverlayFlag(handle, flag, enabled), None);
+        }
+
+        let session = f.overlays.openxr.session_data.get();
+        let layers = f
+            .overlays
+            .get_layers::<<FakeGraphicsData as GraphicsBackend>::Api>(&session, 1.0, 0);
+        assert_eq!(layers.len(), 1);
+        let OverlayLayerInner::Quad(quad) = layers[0].layer.as_ref().unwrap() else {
+            panic!("expected a quad layer");
+        };
+        assert_eq!(
+            quad.as_raw().layer_flags,
+            flags,
+            "premultiplied: {premultiplied}, ignore alpha: {ignore_alpha}"
+        );
+        let scale_bias = layers[0].color_bias_khr.as_deref().unwrap();
+        let color = scale_bias.color_scale;
+        assert_eq!([color.r, color.g, color.b, color.a], scale);
+        assert_eq!(scale_bias.color_bias.a, bias_alpha);
+    }
+
+    // Fully opaque without an overlay alpha.
+    assert_eq!(f.overlays.SetOverlayAlpha(handle, 1.0), None);
+    let session = f.overlays.openxr.session_data.get();
+    let layers = f
+        .overlays
+        .get_layers::<<FakeGraphicsData as GraphicsBackend>::Api>(&session, 1.0, 0);
+    let OverlayLayerInner::Quad(quad) = layers[0].layer.as_ref().unwrap() else {
+        panic!("expected a quad layer");
+    };
+    assert_eq!(quad.as_raw().layer_flags, xr::CompositionLayerFlags::EMPTY);
+    assert!(layers[0].color_bias_khr.is_none());
+}
+
+#[test]
+fn overlay_texture_access() {
+    let f = Fixture::new();
+    let handle = f.create_overlay(c"texture_access");
+    f.overlay_mut(handle, |o| {
+        assert!(o.shared_texture().is_none());
+        assert_eq!(o.read_texture().err(), Some(InvalidTexture));
+    });
+
+    f.wait_get_poses();
+    let texture = f.texture();
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &texture), None);
+
+    f.overlay_mut(handle, |o| {
+        let shared = o.shared_texture().expect("texture should be shared");
+        assert_eq!(shared.eType, texture.eType);
+        assert_eq!(shared.handle, texture.handle);
+
+        let image = o.read_texture().expect("texture should be readable");
+        assert_eq!((image.width, image.height), (10, 10));
+        assert_eq!(image.pixels.len(), 10 * 10 * 4);
+    });
+}
+
+#[test]
+fn overlay_image_data() {
+    let f = Fixture::new();
+    let handle = f.create_overlay(c"image_data");
+    let mut pixels = [0u8; 2 * 2 * 4];
+    let get_image_data = |buffer: &mut [u8]| {
+        let (mut width, mut height) = (0, 0);
+        let err = f.overlays.GetOverlayImageData(
+            handle,
+            buffer.as_mut_ptr().cast(),
+            buffer.len() as u32,
+            &mut width,
+            &mut height,
+        );
+        (err, width, height)
+    };
+    assert_eq!(get_image_data(&mut pixels).0, InvalidTexture);
+
+    f.wait_get_poses();
+    FakeGraphicsData::set_swapchain_extent(2, 2);
+    let texture = f.texture();
+    FakeGraphicsData::set_texture_color(&texture, [0x10, 0x20, 0x30, 0x40]);
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &texture), None);
+    // Nothing is copied for reading until the app reads the overlay.
+    let copied = || {
+        f.overlay(handle, |o| {
+            let Some(SupportedBackend::Fake(backend)) = &o.compositor else {
+                panic!("overlay should be using the fake backend");
+            };
+            backend.snapshot(0).is_some()
+        })
+    };
+    assert!(!copied());
+
+    // The required size is reported even if the buffer can't hold it.
+    assert_eq!(get_image_data(&mut pixels[..4]), (InvalidParameter, 2, 2));
+    assert!(copied());
+
+    // What the app draws afterwards isn't shown until the texture is set again.
+    FakeGraphicsData::set_texture_color(&texture, [0xff; 4]);
+    assert_eq!(get_image_data(&mut pixels), (None, 2, 2));
+    assert_eq!(pixels, [0x10, 0x20, 0x30, 0x40].repeat(4).as_slice());
+
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &texture), None);
+    FakeGraphicsData::set_texture_color(&texture, [0x50; 4]);
+    assert_eq!(get_image_data(&mut pixels), (None, 2, 2));
+    assert_eq!(pixels, [0xff; 2 * 2 * 4]);
+
+    assert_eq!(
+        f.overlays.GetOverlayImageData(
+            0xdead << 32,
+            pixels.as_mut_ptr().cast(),
+            pixels.len() as u32,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        ),
+        UnknownOverlay
+    );
+}
+
+#[test]
+fn swapchain_failure_is_invalid_texture() {
+    let f = Fixture::new();
+    f.start_real_session();
+    let handle = f.create_overlay(c"swapchain_failure");
+    assert_eq!(f.overlays.ShowOverlay(handle), None);
+    let layers = f.layer_count();
+
+    let session = f.overlays.openxr.session_data.get().session.as_raw();
+    fakexr::set_swapchain_error(session, Some(xr::sys::Result::ERROR_RUNTIME_FAILURE));
+    assert_eq!(
+        f.overlays.SetOverlayTexture(handle, &f.texture()),
+        InvalidTexture
+    );
+    f.overlay(handle, |o| assert!(o.rect.is_none()));
+    assert_eq!(f.layer_count(), layers);
+
+    // The swapchain is created once the runtime cooperates again.
+    fakexr::set_swapchain_error(session, Option::None);
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+    f.overlay(handle, |o| assert!(o.rect.is_some()));
+    assert_eq!(f.layer_count(), layers + 1);
+}
+
+#[test]
+fn curvature_mode_width_animation() {
+    let mut xr = OpenXrData::new(&Injector::default()).unwrap();
+    xr.enabled_extensions.khr_composition_layer_cylinder = true;
+    let f = Fixture::with_xr(xr);
+
+    let cylinder_shape = |handle| {
+        f.overlay(handle, |o| {
+            let OverlayKind::Curved {
+                curvature,
+                base_width,
+            } = o.kind
+            else {
+                panic!("overlay should be curved");
+            };
+            o.curvature_mode
+                .cylinder_shape(o.width, base_width, curvature)
+        })
+    };
+
+    for mode in [CurvatureMode::ConstantAngle, CurvatureMode::ConstantRadius] {
+        let handle = f.create_overlay(&CString::new(format!("curved_{mode:?}")).unwrap());
+        assert_eq!(f.overlays.set_overlay_curvature_mode(handle, mode), None);
+        assert_eq!(f.overlays.SetOverlayWidthInMeters(handle, 1.0), None);
+        // Low enough that widening the overlay doesn't wrap it past the full circle
+        assert_eq!(f.overlays.SetOverlayCurvature(handle, 0.25), None);
+
+        let (start_radius, start_angle) = cylinder_shape(handle);
+        for width in [0.5, 2.0, 3.0] {
+            assert_eq!(f.overlays.SetOverlayWidthInMeters(handle, width), None);
+            let (radius, angle) = cylinder_shape(handle);
+            match mode {
+                CurvatureMode::ConstantAngle => {
+                    assert!((angle - start_angle).abs() < 1e-5, "{mode:?}");
+                    assert!((radius - start_radius * width).abs() < 1e-5, "{mode:?}");
+                }
+                CurvatureMode::ConstantRadius => {
+                    assert!((radius - start_radius).abs() < 1e-5, "{mode:?}");
+                    assert!((angle - start_angle * width).abs() < 1e-5, "{mode:?}");
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn cylinder_shape_is_bounded() {
+    for mode in [CurvatureMode::ConstantAngle, CurvatureMode::ConstantRadius] {
+        for (width, base_width, curvature) in [
+            (1.0, 1.0, 0.0001),
+            (1.0, 1.0, 0.0),
+            (10.0, 1.0, 1.0),
+            (1.0, 1.0, 1.0),
+        ] {
+            let (radius, angle) = mode.cylinder_shape(width, base_width, curvature);
+            let case = format!("{mode:?} width {width} curvature {curvature}");
+            assert!(
+                radius.is_finite() && radius > 0.0,
+                "{case}: radius {radius}"
+            );
+            assert!(radius <= MAX_CYLINDER_RADIUS, "{case}: radius {radius}");
+            assert!(
+                angle > 0.0 && angle <= 2.0 * PI + 1e-5,
+                "{case}: angle {angle}"
+            );
+            assert!((radius * angle - width).abs() < 1e-4, "{case}");
+        }
+    }
+}
+
+#[test]
+fn sort_order_is_non_negative() {
+    let f = Fixture::new();
+    let handle = f.create_overlay(c"sorted");
+    f.wait_get_poses();
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+    assert_eq!(f.overlays.SetOverlaySortOrder(handle, 5), None);
+
+    let textures = [f.texture(); 6];
+    assert_eq!(
+        f.comp
+            .SetSkyboxOverride(textures.as_ptr(), textures.len() as u32),
+        vr::EVRCompositorError::None
+    );
+    let skybox = f.overlays.skybox.read().unwrap()[0];
+
+    let mut order = u32::MAX;
+    assert_eq!(
+        f.overlays
+            .GetOverlaySortOrder(skybox.data().as_ffi(), &mut order),
+        None
+    );
+    assert_eq!(order, 0);
+    assert_eq!(f.overlays.GetOverlaySortOrder(handle, &mut order), None);
+    assert_eq!(order, 5);
+}
+
+#[test]
+fn skybox_sorts_below_max_sort_order() {
+    let mut xr = OpenXrData::new(&Injector::default()).unwrap();
+    xr.enabled_extensions.khr_composition_layer_cube = true;
+    let f = Fixture::with_xr(xr);
+    let handle = f.create_overlay(c"top");
+    f.wait_get_poses();
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+    assert_eq!(f.overlays.ShowOverlay(handle), None);
+    assert_eq!(f.overlays.SetOverlaySortOrder(handle, u32::MAX), None);
+
+    let textures = [f.texture(); 6];
+    assert_eq!(
+        f.comp
+            .SetSkyboxOverride(textures.as_ptr(), textures.len() as u32),
+        vr::EVRCompositorError::None
+    );
+
+    let mut order = 0;
+    assert_eq!(f.overlays.GetOverlaySortOrder(handle, &mut order), None);
+    assert_eq!(order, u32::MAX);
+
+    let session = f.overlays.openxr.session_data.get();
+    let layers = f
+        .overlays
+        .get_layers::<<FakeGraphicsData as GraphicsBackend>::Api>(&session, 1.0, 0);
+    assert_eq!(layers.len(), 2);
+    assert!(matches!(layers[0].layer, Some(OverlayLayerInner::Cube(_))));
+    assert!(matches!(layers[1].layer, Some(OverlayLayerInner::Quad(_))));
+}
+
+#[test]
+fn invalid_overlay_width() {
+    let f = Fixture::new();
+    let handle = f.create_overlay(c"width");
+    assert_eq!(f.overlays.SetOverlayWidthInMeters(handle, 2.0), None);
+
+    for width in [f32::NAN, f32::INFINITY, -1.0, 0.0] {
+        assert_eq!(
+            f.overlays.SetOverlayWidthInMeters(handle, width),
+            InvalidParameter,
+            "{width}"
+        );
+        f.overlay(handle, |o| assert_eq!(o.width, 2.0));
+    }
+
+    assert_eq!(f.overlays.SetOverlayWidthInMeters(handle, 1e-9), None);
+    f.overlay(handle, |o| assert_eq!(o.width, MIN_OVERLAY_WIDTH));
+}
+
+#[test]
+fn duplicate_overlay_key() {
+    let f = Fixture::new();
+    let handle = f.create_overlay(c"dupe");
+
+    let mut other = 0;
+    assert_eq!(
+        f.overlays
+            .CreateOverlay(c"dupe".as_ptr(), c"other".as_ptr(), &mut other),
+        KeyInUse
+    );
+    assert_eq!(other, 0);
+    assert_eq!(f.overlays.overlays.read().unwrap().len(), 1);
+
+    let mut found = 0;
+    assert_eq!(f.overlays.FindOverlay(c"dupe".as_ptr(), &mut found), None);
+    assert_eq!(found, handle);
+
+    // The key can be reused once the overlay is gone
+    assert_eq!(f.overlays.DestroyOverlay(handle), None);
+    f.create_overlay(c"dupe");
+}
+
+#[test]
+fn overlay_keys_ignore_case() {
+    let f = Fixture::new();
+    let handle = f.create_overlay(c"Foo");
+
+    let mut found = 0;
+    assert_eq!(f.overlays.FindOverlay(c"foo".as_ptr(), &mut found), None);
+    assert_eq!(found, handle);
+    let mut other = 0;
+    assert_eq!(
+        f.overlays
+            .CreateOverlay(c"FOO".as_ptr(), c"other".as_ptr(), &mut other),
+        KeyInUse
+    );
+    f.overlay(handle, |o| assert_eq!(o.key.as_c_str(), c"Foo"));
+
+    assert_eq!(f.overlays.DestroyOverlay(handle), None);
+    assert_eq!(
+        f.overlays.FindOverlay(c"foo".as_ptr(), &mut found),
+        UnknownOverlay
+    );
+
+    let longest = CString::new("k".repeat(vr::k_unVROverlayMaxKeyLength as usize - 1)).unwrap();
+    f.create_overlay(&longest);
+    let too_long = CString::new("k".repeat(vr::k_unVROverlayMaxKeyLength as usize)).unwrap();
+    assert_eq!(
+        f.overlays
+            .CreateOverlay(too_long.as_ptr(), c"long".as_ptr(), &mut other),
+        KeyTooLong
+    );
+}
+
+#[test]
+fn destroy_skybox_overlay() {
+    let f = Fixture::new();
+    f.start_real_session();
+
+    let textures = [f.texture(); 6];
+    assert_eq!(
+        f.comp
+            .SetSkyboxOverride(textures.as_ptr(), textures.len() as u32),
+        vr::EVRCompositorError::None
+    );
+    let skybox = f.overlays.skybox.read().unwrap()[0];
+    assert_eq!(f.overlays.DestroyOverlay(skybox.data().as_ffi()), None);
+    assert!(!f.overlays.skybox.read().unwrap().contains(&skybox));
+
+    f.overlays.clear_skybox();
+    assert!(f.overlays.skybox.read().unwrap().is_empty());
+}
+
+#[test]
+fn stale_layers_rejected() {
+    let f = Fixture::new();
+    let handle = f.create_overlay(c"stale");
+    f.wait_get_poses();
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+    assert_eq!(f.overlays.ShowOverlay(handle), None);
+
+    // Layers built for the current frame make it into the frame.
+    f.wait_get_poses();
+    f.comp.PostPresentHandoff();
+    let session = f.overlays.openxr.session_data.get();
+    assert_eq!(
+        fakexr::last_frame_layers(session.session.as_raw()),
+        [xr::StructureType::COMPOSITION_LAYER_QUAD]
+    );
+
+    let layers = f
+        .overlays
+        .get_layers::<<FakeGraphicsData as GraphicsBackend>::Api>(&session, 1.0, 1);
+    assert_eq!(layers.len(), 1);
+    assert_eq!(layers[0].frame_index, 1);
+    assert!(layers[0].is_for_frame(1));
+    // Held onto until the next frame, they're rejected.
+    assert!(!layers[0].is_for_frame(2));
+}
+
+#[test]
+fn set_alpha_twice() {
+    let mut layer = OverlayLayer::<<FakeGraphicsData as GraphicsBackend>::Api>::from(
+        OverlayLayerInner::Quad(xr::CompositionLayerQuad::new()),
+    );
+    layer.set_alpha(0.5);
+    layer.set_alpha(0.25);
+
+    let bias = layer.color_bias_khr.as_deref().unwrap();
+    assert_eq!(bias.color_scale.a, 0.25);
+    assert_eq!(
+        (bias.color_scale.r, bias.color_scale.g, bias.color_scale.b),
+        (1.0, 1.0, 1.0)
+    );
+    assert!(bias.next.is_null());
+
+    let Some(OverlayLayerInner::Quad(quad)) = &layer.layer else {
+        panic!("layer should be a quad");
+    };
+    assert_eq!(quad.as_raw().next, (bias as *const _).cast());
+}
+
+#[test]
+fn numeric_keyboard_layout() {
+    let mut keyboard = Keyboard::new(
+        KeyboardLayout::Numeric,
+        vr::EGamepadTextInputMode::Normal,
+        vr::EGamepadTextInputLineMode::MultipleLines,
+        8,
+        "4a2",
+    );
+    assert_eq!(keyboard.text(), "4a2");
+    assert!(keyboard.keys().all(|c| c.is_ascii_digit()));
+    assert_eq!(keyboard.keys().count(), 10);
+
+    for c in "1 b\n3".chars() {
+        keyboard.type_char(c);
+    }
+    assert_eq!(keyboard.text(), "4a213");
+}
+
+#[test]
+fn keyboard_keeps_existing_text() {
+    let mut keyboard = Keyboard::new(
+        KeyboardLayout::Qwerty,
+        vr::EGamepadTextInputMode::Normal,
+        vr::EGamepadTextInputLineMode::SingleLine,
+        16,
+        "jé.doe@mail-box.fr",
+    );
+    // Only cut down to the app's limit.
+    assert_eq!(keyboard.text(), "jé.doe@mail-box.");
+    assert!(!keyboard.type_char('x'));
+    assert!(keyboard.backspace());
+    assert!(!keyboard.type_char('@'));
+    assert!(keyboard.type_char('x'));
+    assert_eq!(keyboard.text(), "jé.doe@mail-boxx");
+}
+
+#[test]
+fn keyboard_modes() {
+    let mut keyboard = Keyboard::new(
+        KeyboardLayout::Azerty,
+        vr::EGamepadTextInputMode::Password,
+        vr::EGamepadTextInputLineMode::SingleLine,
+        4,
+        "",
+    );
+    assert!(keyboard.type_char('A'));
+    assert!(!keyboard.type_char('\n'));
+    assert!(keyboard.type_char(' '));
+    assert!(keyboard.type_char('z'));
+    assert!(keyboard.type_char('1'));
+    assert!(!keyboard.type_char('2'));
+    assert_eq!(keyboard.text(), "A z1");
+    assert_eq!(keyboard.display_text(), "****");
+}
+
+#[test]
+fn depth_sort_respects_ignoring_overlays() {
+    let layers = vec![
+        (0, Some(1.0), "near"),
+        (1, None, "fixed"),
+        (2, Some(5.0), "far"),
+        (3, Some(3.0), "middle"),
+    ];
+    assert_eq!(sort_layers(layers), ["far", "fixed", "middle", "near"]);
+
+    // without depth, only the sort order matters
+    let layers = vec![(2, None, "c"), (0, None, "a"), (1, None, "b")];
+    assert_eq!(sort_layers(layers), ["a", "b", "c"]);
+}
+
+#[test]
+fn depth_sort_measures_from_hmd() {
+    let f = Fixture::with_options(OpenXrData::new(&Injector::default()).unwrap(), |o| {
+        o.depth_sort = true
+    });
+    f.start_real_session();
+    for (key, z) in [(c"near", -1.0), (c"far", -3.0)] {
+        let handle = f.create_overlay(key);
+        let transform = translation(0.0, 0.0, z);
+        assert_eq!(
+            f.overlays.SetOverlayTransformAbsolute(
+                handle,
+                vr::ETrackingUniverseOrigin::Seated,
+                &transform
+            ),
+            None
+        );
+        assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+        assert_eq!(f.overlays.ShowOverlay(handle), None);
+    }
+
+    // The farthest overlay is drawn first.
+    let depths = || {
+        let session = f.overlays.openxr.session_data.get();
+        f.overlays
+            .get_layers::<<FakeGraphicsData as GraphicsBackend>::Api>(&session, 1.0, 0)
+            .iter()
+            .map(|layer| {
+                let Some(OverlayLayerInner::Quad(quad)) = &layer.layer else {
+                    panic!("expected a quad layer");
+                };
+                quad.as_raw().pose.position.z
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(depths(), [-3.0, -1.0]);
+
+    // Walking past both overlays turns the order around.
+    let session = f.overlays.openxr.session_data.get().session.as_raw();
+    fakexr::set_view_pose(
+        session,
+        xr::Posef {
+            position: xr::Vector3f {
+                x: 0.0,
+                y: 0.0,
+                z: -4.0,
+            },
+            orientation: xr::Quaternionf::IDENTITY,
+        },
+    );
+    assert_eq!(depths(), [-1.0, -3.0]);
+}
+
+#[test]
+fn sort_with_non_scene_overlays_ignores_depth_sort() {
+    let f = Fixture::new();
+    let handle = f.create_overlay(c"non_scene");
+    f.overlay(handle, |o| assert!(!o.ignores_depth_sort()));
+    assert_eq!(
+        f.overlays
+            .SetOverlayFlag(handle, vr::VROverlayFlags::SortWithNonSceneOverlays, true),
+        None
+    );
+    f.overlay(handle, |o| assert!(o.ignores_depth_sort()));
+    assert_eq!(
+        f.overlays
+            .SetOverlayFlag(handle, vr::VROverlayFlags::SortWithNonSceneOverlays, false),
+        None
+    );
+    f.overlay(handle, |o| assert!(!o.ignores_depth_sort()));
+}
+
+#[test]
+fn intersection_mask() {
+    let f = Fixture::new();
+    let handle = f.create_overlay(c"masked");
+    f.wait_get_poses();
+    let transform = translation(0.0, 0.0, -1.0);
+    assert_eq!(
+        f.overlays.SetOverlayTransformAbsolute(
+            handle,
+            vr::ETrackingUniverseOrigin::Standing,
+            &transform
+        ),
+        None
+    );
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+
+    let intersect = |x: f32, y: f32| {
+        let params = vr::VROverlayIntersectionParams_t {
+            vSource: vr::HmdVector3_t { v: [x, y, 0.0] },
+            vDirection: vr::HmdVector3_t {
+                v: [0.0, 0.0, -1.0],
+            },
+            eOrigin: vr::ETrackingUniverseOrigin::Standing,
+        };
+        let mut results = vr::VROverlayIntersectionResults_t::default();
+        f.overlays
+            .ComputeOverlayIntersection(handle, &params, &mut results)
+            .then_some(results)
+    };
+
+    let center = intersect(0.0, 0.0).expect("center ray should hit");
+    assert_eq!(center.vUVs.v, [0.5, 0.5]);
+    assert_eq!(center.vPoint.v, [0.0, 0.0, -1.0]);
+    assert_eq!(center.fDistance, 1.0);
+    let corner = intersect(0.45, 0.45).expect("unmasked corner ray should hit");
+    assert!(corner.vUVs.v[0] > 0.9 && corner.vUVs.v[1] > 0.9);
+    assert!(intersect(0.6, 0.0).is_none());
+
+    let rect = f.overlay(handle, |o| o.rect.unwrap());
+    let (width, height) = (rect.extent.width as f32, rect.extent.height as f32);
+    let mut circle = vr::VROverlayIntersectionMaskPrimitive_t {
+        m_nPrimitiveType: vr::EVROverlayIntersectionMaskPrimitiveType::Circle,
+        m_Primitive: vr::VROverlayIntersectionMaskPrimitive_Data_t {
+            m_Circle: vr::IntersectionMaskCircle_t {
+                m_flCenterX: width / 2.0,
+                m_flCenterY: height / 2.0,
+                m_flRadius: width / 2.0,
+            },
+        },
+    };
+    let size = std::mem::size_of::<vr::VROverlayIntersectionMaskPrimitive_t>() as u32;
+    assert_eq!(
+        f.overlays
+            .SetOverlayIntersectionMask(handle, &mut circle, 1, size - 1),
+        InvalidParameter
+    );
+    assert_eq!(
+        f.overlays
+            .SetOverlayIntersectionMask(handle, std::ptr::null_mut(), 1, size),
+        InvalidParameter
+    );
+    assert_eq!(
+        f.overlays
+            .SetOverlayIntersectionMask(handle, &mut circle, 1, size),
+        None
+    );
+    assert!(intersect(0.0, 0.0).is_some());
+    assert!(intersect(0.45, 0.45).is_none());
+
+    // Unknown primitive types leave the mask alone.
+    let mut unknown = circle;
+    unknown.m_nPrimitiveType = vr::EVROverlayIntersectionMaskPrimitiveType(7);
+    assert_eq!(
+        f.overlays
+            .SetOverlayIntersectionMask(handle, &mut unknown, 1, size),
+        BadMaskPrimitive
+    );
+    assert!(intersect(0.45, 0.45).is_none());
+
+    // An empty mask makes the whole overlay hittable again.
+    assert_eq!(
+        f.overlays
+            .SetOverlayIntersectionMask(handle, std::ptr::null_mut(), 0, size),
+        None
+    );
+    assert!(intersect(0.45, 0.45).is_some());
+}
+
+#[test]
+fn mouse_scale() {
+    let f = Fixture::new();
+    let handle = f.create_overlay(c"mouse");
+    let get_scale = || {
+        let mut scale = vr::HmdVector2_t::default();
+        assert_eq!(f.overlays.GetOverlayMouseScale(handle, &mut scale), None);
+        scale.v
+    };
+    assert_eq!(get_scale(), [1.0, 1.0]);
+
+    // Defaults to the texture size once there is one.
+    f.wait_get_poses();
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+    let rect = f.overlay(handle, |o| o.rect.unwrap());
+    assert_eq!(
+        get_scale(),
+        [rect.extent.width as f32, rect.extent.height as f32]
+    );
+
+    assert_eq!(
+        f.overlays.SetOverlayMouseScale(handle, std::ptr::null()),
+        InvalidParameter
+    );
+    assert_eq!(
+        f.overlays
+            .GetOverlayMouseScale(handle, std::ptr::null_mut()),
+        InvalidParameter
+    );
+    let scale = vr::HmdVector2_t {
+        v: [1920.0, 1080.0],
+    };
+    assert_eq!(f.overlays.SetOverlayMouseScale(handle, &scale), None);
+    assert_eq!(get_scale(), [1920.0, 1080.0]);
+    let zero = vr::HmdVector2_t { v: [0.0, 1080.0] };
+    assert_eq!(
+        f.overlays.SetOverlayMouseScale(handle, &zero),
+        InvalidParameter
+    );
+    assert_eq!(get_scale(), [1920.0, 1080.0]);
+    f.overlay(handle, |o| {
+        assert_eq!(o.uv_to_mouse(vec2(0.5, 0.25)), vec2(960.0, 270.0));
+    });
+}
+
+#[test]
+fn input_method() {
+    let f = Fixture::new();
+    let handle = f.create_overlay(c"input");
+    let get_method = || {
+        let mut method = vr::VROverlayInputMethod::Mouse;
+        assert_eq!(f.overlays.GetOverlayInputMethod(handle, &mut method), None);
+        method
+    };
+    assert_eq!(get_method(), vr::VROverlayInputMethod::None);
+    assert_eq!(
+        f.overlays
+            .SetOverlayInputMethod(handle, vr::VROverlayInputMethod::Mouse),
+        None
+    );
+    assert_eq!(get_method(), vr::VROverlayInputMethod::Mouse);
+    assert_eq!(
+        f.overlays
+            .GetOverlayInputMethod(handle, std::ptr::null_mut()),
+        InvalidParameter
+    );
+
+    // DualAnalog and values that were never valid
+    for method in [2, u32::MAX] {
+        assert_eq!(
+            f.overlays
+                .SetOverlayInputMethod(handle, vr::VROverlayInputMethod(method)),
+            InvalidParameter
+        );
+    }
+    assert_eq!(get_method(), vr::VROverlayInputMethod::Mouse);
+    assert_eq!(
+        f.overlays
+            .SetOverlayInputMethod(handle, vr::VROverlayInputMethod::None),
+        None
+    );
+    assert_eq!(get_method(), vr::VROverlayInputMethod::None);
+}
+
+#[test]
+fn laser_waits_for_mouse_overlays() {
+    let f = Fixture::new();
+    f.wait_get_poses();
+    let handle = f.create_overlay(c"mouse");
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+    assert!(!f.overlays.wants_laser());
+
+    // Overlays have to be visible and take mouse input.
+    assert_eq!(
+        f.overlays
+            .SetOverlayInputMethod(handle, vr::VROverlayInputMethod::Mouse),
+        None
+    );
+    assert!(!f.overlays.wants_laser());
+    assert_eq!(f.overlays.ShowOverlay(handle), None);
+    assert!(f.overlays.wants_laser());
+
+    // The laser still has to leave the overlay it's on.
+    let origin = vr::ETrackingUniverseOrigin::Standing;
+    let controller = translation(0.0, 0.0, 0.0);
+    f.update_laser(
+        origin,
+        Some((Hand::Right, controller)),
+        PointerState::default(),
+    );
+    assert!(f.overlays.IsHoverTargetOverlay(handle));
+    assert_eq!(f.overlays.HideOverlay(handle), None);
+    assert!(f.overlays.wants_laser());
+    f.update_laser(
+        origin,
+        Some((Hand::Right, controller)),
+        PointerState::default(),
+    );
+    assert!(!f.overlays.IsHoverTargetOverlay(handle));
+    assert!(!f.overlays.wants_laser());
+}
+
+#[test]
+fn hover_follows_laser() {
+    let f = Fixture::new();
+    f.wait_get_poses();
+    let origin = vr::ETrackingUniverseOrigin::Standing;
+    let [left, right] = [(c"left", -0.6), (c"right", 0.6)].map(|(key, x)| {
+        let handle = f.create_overlay(key);
+        assert_eq!(
+            f.overlays
+                .SetOverlayTransformAbsolute(handle, origin, &translation(x, 0.0, -1.0)),
+            None
+        );
+        assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+        assert_eq!(f.overlays.ShowOverlay(handle), None);
+        assert_eq!(
+            f.overlays
+                .SetOverlayInputMethod(handle, vr::VROverlayInputMethod::Mouse),
+            None
+        );
+        handle
+    });
+
+    // Focus changes are told to the overlay, not through IVRSystem.
+    let focus_events = |handle| {
+        let mut events = Vec::new();
+        let mut event = vr::VREvent_t::default();
+        let size = std::mem::size_of_val(&event) as u32;
+        while f.overlays.PollNextOverlayEvent(handle, &mut event, size) {
+            if event.eventType != vr::EVREventType::MouseMove as u32 {
+                assert_eq!(unsafe { event.data.overlay.overlayHandle }, handle);
+                events.push(event.eventType);
+            }
+        }
+        events
+    };
+    let laser_at = |x: f32| {
+        let controller = translation(x, 0.0, 0.0);
+        f.update_laser(
+            origin,
+            Some((Hand::Right, controller)),
+            PointerState::default(),
+        );
+    };
+    use vr::EVREventType::{FocusEnter, FocusLeave, OverlayFocusChanged};
+
+    laser_at(0.6);
+    assert!(f.overlays.IsHoverTargetOverlay(right));
+    assert!(!f.overlays.IsHoverTargetOverlay(left));
+    assert_eq!(
+        focus_events(right),
+        [FocusEnter as u32, OverlayFocusChanged as u32]
+    );
+    assert!(focus_events(left).is_empty());
+
+    // Staying on the same overlay doesn't change focus.
+    laser_at(0.5);
+    assert!(focus_events(right).is_empty());
+
+    laser_at(-0.6);
+    assert!(f.overlays.IsHoverTargetOverlay(left));
+    assert!(!f.overlays.IsHoverTargetOverlay(right));
+    assert_eq!(focus_events(right), [FocusLeave as u32]);
+    assert_eq!(
+        focus_events(left),
+        [FocusEnter as u32, OverlayFocusChanged as u32]
+    );
+
+    f.update_laser(origin, Option::None, PointerState::default());
+    assert!(!f.overlays.IsHoverTargetOverlay(left));
+    assert_eq!(focus_events(left), [FocusLeave as u32]);
+
+    let mut event = vr::VREvent_t::default();
+    let size = std::mem::size_of_val(&event) as u32;
+    assert!(!f.overlays.get_next_event(size, &mut event));
+}
+
+#[test]
+fn laser_mouse_events() {
+    let f = Fixture::new();
+    f.wait_get_poses();
+    let origin = vr::ETrackingUniverseOrigin::Standing;
+    let handle = f.create_overlay(c"mouse");
+    let transform = translation(0.0, 0.0, -1.0);
+    assert_eq!(
+        f.overlays
+            .SetOverlayTransformAbsolute(handle, origin, &transform),
+        None
+    );
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+    assert_eq!(f.overlays.ShowOverlay(handle), None);
+    let scale = vr::HmdVector2_t { v: [100.0, 100.0] };
+    assert_eq!(f.overlays.SetOverlayMouseScale(handle, &scale), None);
+    assert_eq!(
+        f.overlays
+            .SetOverlayInputMethod(handle, vr::VROverlayInputMethod::Mouse),
+        None
+    );
+
+    let laser = |x: f32, pressed: bool| {
+        let controller = translation(x, 0.0, 0.0);
+        let pointer = PointerState {
+            trigger: pressed,
+            ..Default::default()
+        };
+        f.update_laser(origin, Some((Hand::Right, controller)), pointer);
+    };
+    let drain = || {
+        let mut events = Vec::new();
+        let mut event = vr::VREvent_t::default();
+        let size = std::mem::size_of_val(&event) as u32;
+        while f.overlays.PollNextOverlayEvent(handle, &mut event, size) {
+            let ty = event.eventType;
+            let mouse = unsafe { event.data.mouse };
+            events.push(match ty {
+                t if t == vr::EVREventType::FocusEnter as u32
+                    || t == vr::EVREventType::FocusLeave as u32
+                    || t == vr::EVREventType::OverlayFocusChanged as u32 =>
+                {
+                    assert_eq!(unsafe { event.data.overlay.overlayHandle }, handle);
+                    (ty, Option::None)
+                }
+                _ => (ty, Some((mouse.x, mouse.y, mouse.button))),
+            });
+        }
+        events
+    };
+    use vr::EVREventType::{
+        FocusEnter, FocusLeave, MouseButtonDown, MouseButtonUp, MouseMove, OverlayFocusChanged,
+    };
+    let left = vr::EVRMouseButton::Left as u32;
+
+    laser(0.0, false);
+    assert_eq!(
+        drain(),
+        [
+            (FocusEnter as u32, Option::None),
+            (OverlayFocusChanged as u32, Option::None),
+            (MouseMove as u32, Some((50.0, 50.0, 0))),
+        ]
+    );
+
+    laser(0.0, true);
+    assert_eq!(
+        drain(),
+        [
+            (MouseMove as u32, Some((50.0, 50.0, 0))),
+            (MouseButtonDown as u32, Some((50.0, 50.0, left))),
+        ]
+    );
+
+    laser(0.25, true);
+    assert_eq!(drain(), [(MouseMove as u32, Some((75.0, 50.0, 0)))]);
+
+    // Leaving the overlay while pressed still releases the button where it was last seen.
+    laser(2.0, true);
+    assert_eq!(drain(), [(FocusLeave as u32, Option::None)]);
+    laser(2.0, false);
+    assert_eq!(drain(), [(MouseButtonUp as u32, Some((75.0, 50.0, left)))]);
+
+    // Overlays without mouse input don't get events.
+    assert_eq!(
+        f.overlays
+            .SetOverlayInputMethod(handle, vr::VROverlayInputMethod::None),
+        None
+    );
+    laser(0.0, true);
+    laser(0.0, false);
+    assert!(drain().is_empty());
+
+    // Apps that don't poll only get the latest position, and a bounded number of events.
+    assert_eq!(
+        f.overlays
+            .SetOverlayInputMethod(handle, vr::VROverlayInputMethod::Mouse),
+        None
+    );
+    laser(0.0, false);
+    laser(0.25, false);
+    assert_eq!(drain(), [(MouseMove as u32, Some((75.0, 50.0, 0)))]);
+    for _ in 0..MAX_OVERLAY_EVENTS {
+        laser(0.0, true);
+        laser(0.0, false);
+    }
+    assert_eq!(drain().len(), MAX_OVERLAY_EVENTS);
+}
+
+#[test]
+fn laser_scroll_events() {
+    let f = Fixture::new();
+    f.wait_get_poses();
+    let origin = vr::ETrackingUniverseOrigin::Standing;
+    let handle = f.create_overlay(c"scroll");
+    let transform = translation(0.0, 0.0, -1.0);
+    assert_eq!(
+        f.overlays
+            .SetOverlayTransformAbsolute(handle, origin, &transform),
+        None
+    );
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+    assert_eq!(f.overlays.ShowOverlay(handle), None);
+    assert_eq!(
+        f.overlays
+            .SetOverlayInputMethod(handle, vr::VROverlayInputMethod::Mouse),
+        None
+    );
+
+    let scroll_for = |x: f32, y: f32, frame_time| {
+        let controller = translation(0.0, 0.0, 0.0);
+        let pointer = PointerState {
+            trigger: false,
+            scroll: vec2(x, y),
+        };
+        f.overlays
+            .update_laser(origin, Some((Hand::Right, controller)), pointer, frame_time);
+    };
+    let scroll = |x: f32, y: f32| scroll_for(x, y, FRAME_TIME);
+    let drain_scrolls = || {
+        let mut scrolls = Vec::new();
+        let mut event = vr::VREvent_t::default();
+        let size = std::mem::size_of_val(&event) as u32;
+        while f.overlays.PollNextOverlayEvent(handle, &mut event, size) {
+            let ty = event.eventType;
+            if ty == vr::EVREventType::ScrollDiscrete as u32
+                || ty == vr::EVREventType::ScrollSmooth as u32
+            {
+                let scroll = unsafe { event.data.scroll };
+                scrolls.push((ty, scroll.xdelta, scroll.ydelta));
+            }
+        }
+        scrolls
+    };
+    let discrete = vr::EVREventType::ScrollDiscrete as u32;
+    let smooth = vr::EVREventType::ScrollSmooth as u32;
+
+    // Not opted in
+    scroll(0.0, 0.8);
+    assert!(drain_scrolls().is_empty());
+    scroll(0.0, 0.0);
+
+    assert_eq!(
+        f.overlays
+            .SetOverlayFlag(handle, vr::VROverlayFlags::SendVRDiscreteScrollEvents, true),
+        None
+    );
+    scroll(0.0, 0.8);
+    assert_eq!(drain_scrolls(), [(discrete, 0.0, 1.0)]);
+    // Holding the stick doesn't scroll again
+    scroll(0.0, 0.9);
+    assert!(drain_scrolls().is_empty());
+    scroll(0.0, 0.0);
+    scroll(0.0, -0.7);
+    assert_eq!(drain_scrolls(), [(discrete, 0.0, -1.0)]);
+
+    assert_eq!(
+        f.overlays.SetOverlayFlag(
+            handle,
+            vr::VROverlayFlags::SendVRDiscreteScrollEvents,
+            false
+        ),
+        None
+    );
+    assert_eq!(
+        f.overlays
+            .SetOverlayFlag(handle, vr::VROverlayFlags::SendVRSmoothScrollEvents, true),
+        None
+    );
+    // Smooth scrolls go by how long the stick was held, not how many frames that took.
+    let [short, long] = [10, 40].map(Duration::from_millis);
+    scroll_for(0.0, 0.25, short);
+    scroll_for(-0.5, 0.25, long);
+    let delta =
+        |stick: f32, frame_time: Duration| stick * SMOOTH_SCROLL_SPEED * frame_time.as_secs_f32();
+    assert_eq!(
+        drain_scrolls(),
+        [
+            (smooth, 0.0, delta(0.25, short)),
+            (smooth, delta(-0.5, long), delta(0.25, long))
+        ]
+    );
+}
+
+#[test]
+fn laser_with_action_manifest() {
+    use vr::IVRInput010_Interface;
+    let f = Fixture::new();
+    let input = Arc::new(Input::new(f.overlays.openxr.clone()));
+    f.overlays.openxr.input.set(Arc::downgrade(&input));
+    f.overlays.input.set(Arc::downgrade(&input));
+
+    let manifest = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/input_data/actions.json\0"
+    );
+    assert_eq!(
+        input.SetActionManifestPath(manifest.as_ptr().cast()),
+        vr::EVRInputError::None
+    );
+    let mut set1 = 0;
+    assert_eq!(
+        input.GetActionSetHandle(c"/actions/set1".as_ptr(), &mut set1),
+        vr::EVRInputError::None
+    );
+    f.start_real_session();
+
+    let origin = vr::ETrackingUniverseOrigin::Standing;
+    let handle = f.create_overlay(c"manifest_laser");
+    let transform = translation(0.0, 0.0, -1.0);
+    assert_eq!(
+        f.overlays
+            .SetOverlayTransformAbsolute(handle, origin, &transform),
+        None
+    );
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+    assert_eq!(f.overlays.ShowOverlay(handle), None);
+    assert_eq!(
+        f.overlays
+            .SetOverlayInputMethod(handle, vr::VROverlayInputMethod::Mouse),
+        None
+    );
+    assert_eq!(
+        f.overlays
+            .SetOverlayFlag(handle, vr::VROverlayFlags::SendVRDiscreteScrollEvents, true),
+        None
+    );
+
+    // The app only updates its own actions, which carries the laser's along.
+    let (trigger, scroll) = input
+        .legacy_pointer_actions()
+        .expect("legacy actions should be loaded with the manifest");
+    let point = |pressed: bool, scroll_y: f32| {
+        let hand = fakexr::UserPath::RightHand;
+        fakexr::set_action_state(trigger, pressed.into(), hand);
+        fakexr::set_action_state(scroll, fakexr::ActionState::Vector2(0.0, scroll_y), hand);
+        let mut active = vr::VRActiveActionSet_t {
+            ulActionSet: set1,
+            ..Default::default()
+        };
+        assert_eq!(
+            input.UpdateActionState(
+                &mut active,
+                std::mem::size_of::<vr::VRActiveActionSet_t>() as u32,
+                1
+            ),
+            vr::EVRInputError::None
+        );
+        f.update_laser(
+            origin,
+            Some((Hand::Right, translation(0.0, 0.0, 0.0))),
+            input.legacy_pointer_state(Hand::Right),
+        );
+    };
+    let drain = || {
+        let mut events = Vec::new();
+        let mut event = vr::VREvent_t::default();
+        let size = std::mem::size_of_val(&event) as u32;
+        while f.overlays.PollNextOverlayEvent(handle, &mut event, size) {
+            events.push(event.eventType);
+        }
+        events
+    };
+    use vr::EVREventType::{MouseButtonDown, MouseButtonUp, ScrollDiscrete};
+    let has_event = |events: &[u32], ty: vr::EVREventType| events.contains(&(ty as u32));
+
+    point(false, 0.0);
+    let events = drain();
+    assert!(!has_event(&events, MouseButtonDown));
+    assert!(!has_event(&events, ScrollDiscrete));
+
+    point(true, 0.0);
+    assert!(has_event(&drain(), MouseButtonDown));
+    point(false, 0.0);
+    assert!(has_event(&drain(), MouseButtonUp));
+
+    point(false, 0.8);
+    assert!(has_event(&drain(), ScrollDiscrete));
+}
+
+#[test]
+fn render_model() {
+    use vr::IVROverlay021On024;
+    let f = Fixture::new();
+    let handle = f.create_overlay(c"render_model");
+    let get = |handle| {
+        let mut name = [0 as c_char; 16];
+        let mut error = vr::EVROverlayError::RequestFailed;
+        let size = f.overlays.GetOverlayRenderModel(
+            handle,
+            name.as_mut_ptr(),
+            name.len() as u32,
+            std::ptr::null_mut(),
+            &mut error,
+        );
+        (size, error)
+    };
+    assert_eq!(get(handle), (0, None));
+    assert_eq!(get(0xdead << 32), (0, UnknownOverlay));
+
+    // Models can't be drawn, so setting one fails and the overlay keeps showing its texture.
+    let color = vr::HmdColor_t {
+        r: 1.0,
+        g: 0.5,
+        b: 0.25,
+        a: 1.0,
+    };
+    assert_eq!(
+        f.overlays
+            .SetOverlayRenderModel(handle, c"controller".as_ptr(), &color),
+        RequestFailed
+    );
+    assert_eq!(get(handle), (0, None));
+    f.wait_get_poses();
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+    assert_eq!(f.overlays.ShowOverlay(handle), None);
+    assert_eq!(f.layer_count(), 1);
+
+    // Clearing the model is fine
+    for name in [std::ptr::null(), c"".as_ptr()] {
+        assert_eq!(
+            f.overlays
+                .SetOverlayRenderModel(handle, name, std::ptr::null()),
+            None
+        );
+    }
+    assert_eq!(
+        f.overlays
+            .SetOverlayRenderModel(0xdead << 32, std::ptr::null(), std::ptr::null()),
+        UnknownOverlay
+    );
+}
+
+#[test]
+fn gamepad_focus_navigation() {
+    use vr::EOverlayDirection as Dir;
+    use vr::IVROverlay020On021;
+    let f = Fixture::new();
+    // a b
+    // c d
+    let [a, b, c, d] = [c"a", c"b", c"c", c"d"].map(|key| f.create_overlay(key));
+    for (direction, from, to) in [
+        (Dir::Right, a, b),
+        (Dir::Left, b, a),
+        (Dir::Down, a, c),
+        (Dir::Up, c, a),
+        (Dir::Right, c, d),
+        (Dir::Left, d, c),
+        (Dir::Down, b, d),
+        (Dir::Up, d, b),
+    ] {
+        assert_eq!(f.overlays.SetOverlayNeighbor(direction, from, to), None);
+    }
+    assert_eq!(
+        f.overlays.SetOverlayNeighbor(Dir::Up, a, 0xdead << 32),
+        UnknownOverlay
+    );
+    for direction in [Dir::Count, Dir(u32::MAX)] {
+        assert_eq!(
+            f.overlays.SetOverlayNeighbor(direction, a, b),
+            InvalidParameter
+        );
+        assert_eq!(
+            f.overlays.MoveGamepadFocusToNeighbor(direction, a),
+            InvalidParameter
+        );
+    }
+
+    assert_eq!(f.overlays.GetGamepadFocusOverlay(), 0);
+    assert_eq!(f.overlays.SetGamepadFocusOverlay(a), None);
+    for (direction, expected) in [
+        (Dir::Right, b),
+        (Dir::Down, d),
+        (Dir::Left, c),
+        (Dir::Up, a),
+    ] {
+        let from = f.overlays.GetGamepadFocusOverlay();
+        assert_eq!(f.overlays.MoveGamepadFocusToNeighbor(direction, from), None);
+        assert_eq!(f.overlays.GetGamepadFocusOverlay(), expected);
+    }
+    // a has nothing above it
+    assert_eq!(
+        f.overlays.MoveGamepadFocusToNeighbor(Dir::Up, a),
+        RequestFailed
+    );
+    assert_eq!(f.overlays.GetGamepadFocusOverlay(), a);
+
+    let events = |handle| {
+        let mut events = Vec::new();
+        let mut event = vr::VREvent_t::default();
+        let size = std::mem::size_of_val(&event) as u32;
+        while f.overlays.PollNextOverlayEvent(handle, &mut event, size) {
+            events.push(event.eventType);
+        }
+        events
+    };
+    let gained = vr::EVREventType::OverlayGamepadFocusGained as u32;
+    let lost = vr::EVREventType::OverlayGamepadFocusLost as u32;
+    assert_eq!(events(a), [gained, lost, gained]);
+    assert_eq!(events(b), [gained, lost]);
+
+    assert_eq!(f.overlays.SetGamepadFocusOverlay(0), None);
+    assert_eq!(f.overlays.GetGamepadFocusOverlay(), 0);
+    assert_eq!(events(a), [lost]);
+
+    // Destroying an overlay unlinks it and takes its focus away.
+    assert_eq!(f.overlays.SetGamepadFocusOverlay(b), None);
+    assert_eq!(f.overlays.DestroyOverlay(b), None);
+    assert_eq!(f.overlays.GetGamepadFocusOverlay(), 0);
+    assert_eq!(
+        f.overlays.MoveGamepadFocusToNeighbor(Dir::Right, a),
+        RequestFailed
+    );
+    f.overlay(d, |o| assert!(o.neighbors[Dir::Up.0 as usize].is_none()));
+}
+
+#[test]
+fn destroy_overlay_unlinks_it() {
+    let f = Fixture::new();
+    let target = f.create_overlay(c"target");
+    let cursor = f.create_overlay(c"cursor");
+    assert_eq!(f.overlays.SetOverlayCursor(target, cursor), None);
+    {
+        let mut laser = f.overlays.laser.lock().unwrap();
+        laser.hovered = Some(OverlayKey::from(KeyData::from_ffi(cursor)));
+        laser.pressed = Some((OverlayKey::from(KeyData::from_ffi(cursor)), Vec2::ZERO));
+    }
+    assert_eq!(f.overlays.DestroyOverlay(cursor), None);
+    f.overlay(target, |o| assert!(o.cursor.is_none()));
+    let laser = f.overlays.laser.lock().unwrap();
+    assert!(laser.hovered.is_none());
+    assert!(laser.pressed.is_none());
+    drop(laser);
+
+    let (main, _) = f.create_dashboard_overlay(c"dashboard");
+    f.overlays.ShowDashboard(c"dashboard".as_ptr());
+    assert!(f.overlays.IsActiveDashboardOverlay(main));
+    assert_eq!(f.overlays.DestroyOverlay(main), None);
+    assert!(f.overlays.dashboard.read().unwrap().active.is_none());
+    assert!(!f.overlays.IsDashboardVisible());
+}
+
+#[test]
+fn auto_curve_distance_range() {
+    use vr::IVROverlay020On021;
+    let f = Fixture::new();
+    let handle = f.create_overlay(c"auto_curve");
+    let get_range = || {
+        let (mut min, mut max) = (-1.0, -1.0);
+        assert_eq!(
+            f.overlays
+                .GetOverlayAutoCurveDistanceRangeInMeters(handle, &mut min, &mut max),
+            None
+        );
+        (min, max)
+    };
+    assert_eq!(get_range(), (0.0, 0.0));
+    // Without a range, curvature doesn't depend on distance.
+    f.overlay(handle, |o| assert_eq!(o.auto_curvature(0.5, 10.0), 0.5));
+
+    assert_eq!(
+        f.overlays
+            .SetOverlayAutoCurveDistanceRangeInMeters(handle, 3.0, 1.0),
+        InvalidParameter
+    );
+    assert_eq!(
+        f.overlays
+            .SetOverlayAutoCurveDistanceRangeInMeters(handle, 1.0, 3.0),
+        None
+    );
+    assert_eq!(get_range(), (1.0, 3.0));
+
+    f.overlay(handle, |o| {
+        for (distance, curvature) in [(0.5, 0.5), (1.0, 0.5), (2.0, 0.25), (3.0, 0.0), (5.0, 0.0)] {
+            assert_eq!(
+                o.auto_curvature(0.5, distance),
+                curvature,
+                "curvature at {distance}m"
+            );
+        }
+    });
+}
+
+#[test]
+fn overlay_cursor() {
+    let f = Fixture::new();
+    f.wait_get_poses();
+    let origin = vr::ETrackingUniverseOrigin::Standing;
+    let target = f.create_overlay(c"target");
+    let cursor = f.create_overlay(c"cursor");
+    let transform = translation(0.0, 0.0, -1.0);
+    assert_eq!(
+        f.overlays
+            .SetOverlayTransformAbsolute(target, origin, &transform),
+        None
+    );
+    assert_eq!(f.overlays.SetOverlayTexture(target, &f.texture()), None);
+    assert_eq!(f.overlays.ShowOverlay(target), None);
+
+    assert_eq!(
+        f.overlays.SetOverlayCursor(target, 0xdead << 32),
+        UnknownOverlay
+    );
+    assert_eq!(f.overlays.SetOverlayCursor(target, cursor), None);
+
+    let laser = |x: f32| {
+        let controller = translation(x, 0.0, 0.0);
+        f.update_laser(
+            origin,
+            Some((Hand::Right, controller)),
+            PointerState::default(),
+        );
+    };
+    let cursor_position = || {
+        f.overlay(cursor, |o| {
+            let (cursor_origin, transform) = o.placement().expect("cursor should be placed");
+            assert_eq!(cursor_origin, origin);
+            let pose: xr::Posef = transform.into();
+            vec3(pose.position.x, pose.position.y, pose.position.z)
+        })
+    };
+    let assert_near = |a: Vec3, b: Vec3| assert!(a.distance(b) < 1e-5, "{a} != {b}");
+
+    laser(0.25);
+    assert_near(cursor_position(), vec3(0.25, 0.0, -1.0 + CURSOR_OFFSET));
+    laser(-0.1);
+    assert_near(cursor_position(), vec3(-0.1, 0.0, -1.0 + CURSOR_OFFSET));
+
+    // Overrides pin the cursor no matter where the laser is, and are given in mouse coordinates.
+    let scale = vr::HmdVector2_t { v: [200.0, 100.0] };
+    assert_eq!(f.overlays.SetOverlayMouseScale(target, &scale), None);
+    let position = vr::HmdVector2_t { v: [150.0, 50.0] };
+    assert_eq!(
+        f.overlays
+            .SetOverlayCursorPositionOverride(target, &position),
+        None
+    );
+    laser(-0.4);
+    assert_near(cursor_position(), vec3(0.25, 0.0, -1.0 + CURSOR_OFFSET));
+
+    assert_eq!(f.overlays.ClearOverlayCursorPositionOverride(target), None);
+    laser(-0.4);
+    assert_near(cursor_position(), vec3(-0.4, 0.0, -1.0 + CURSOR_OFFSET));
+}
+
+#[test]
+fn letterbox_preserves_texture_aspect() {
+    let f = Fixture::new();
+    let handle = f.create_overlay(c"letterbox");
+    f.wait_get_poses();
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+    assert_eq!(f.overlays.ShowOverlay(handle), None);
+    // Texels twice as wide as they are tall make the overlay half as tall as the (square) texture.
+    assert_eq!(f.overlays.SetOverlayTexelAspect(handle, 2.0), None);
+
+    let quad_size = || {
+        let session = f.overlays.openxr.session_data.get();
+        let layers = f
+            .overlays
+            .get_layers::<<FakeGraphicsData as GraphicsBackend>::Api>(&session, 1.0, 0);
+        let Some(OverlayLayerInner::Quad(quad)) = &layers[0].layer else {
+            panic!("expected a quad layer");
+        };
+        let quad = quad.as_raw();
+        assert_eq!(quad.pose.position.z, -0.5);
+        assert_eq!(quad.pose.position.x, 0.0);
+        quad.size
+    };
+
+    let size = quad_size();
+    assert_eq!((size.width, size.height), (1.0, 0.5));
+
+    assert_eq!(
+        f.overlays
+            .set_overlay_content_fit(handle, ContentFit::Letterbox),
+        None
+    );
+    let size = quad_size();
+    assert_eq!((size.width, size.height), (0.5, 0.5));
+}
+
+#[test]
+fn per_eye_visibility() {
+    let f = Fixture::new();
+    let handle = f.create_overlay(c"per_eye");
+    f.wait_get_poses();
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+    assert_eq!(f.overlays.ShowOverlay(handle), None);
+
+    let views = || {
+        let session = f.overlays.openxr.session_data.get();
+        f.overlays
+            .get_layers::<<FakeGraphicsData as GraphicsBackend>::Api>(&session, 1.0, 0)
+            .iter()
+            .map(|layer| {
+                let Some(OverlayLayerInner::Quad(quad)) = &layer.layer else {
+                    panic!("expected a quad layer");
+                };
+                let quad = quad.as_raw();
+                (quad.eye_visibility, quad.sub_image.image_rect)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let full = views();
+    assert_eq!(full.len(), 1);
+    assert_eq!(full[0].0, xr::EyeVisibility::BOTH);
+    let rect = full[0].1;
+    let half_width = rect.extent.width / 2;
+
+    f.overlays
+        .overlays
+        .write()
+        .unwrap()
+        .get_mut(OverlayKey::from(KeyData::from_ffi(handle)))
+        .unwrap()
+        .eye_visibility = xr::EyeVisibility::LEFT;
+    let left = views();
+    assert_eq!(left.len(), 1);
+    assert_eq!(left[0].0, xr::EyeVisibility::LEFT);
+
+    assert_eq!(
+        f.overlays
+            .SetOverlayFlag(handle, vr::VROverlayFlags::SideBySide_Parallel, true),
+        None
+    );
+    let parallel = views();
+    assert_eq!(parallel.len(), 2);
+    assert_eq!(parallel[0].0, xr::EyeVisibility::LEFT);
+    assert_eq!(parallel[0].1.offset.x, rect.offset.x);
+    assert_eq!(parallel[0].1.extent.width, half_width);
+    assert_eq!(parallel[1].0, xr::EyeVisibility::RIGHT);
+    assert_eq!(parallel[1].1.offset.x, rect.offset.x + half_width);
+
+    assert_eq!(
+        f.overlays
+            .SetOverlayFlag(handle, vr::VROverlayFlags::SideBySide_Parallel, false),
+        None
+    );
+    assert_eq!(
+        f.overlays
+            .SetOverlayFlag(handle, vr::VROverlayFlags::SideBySide_Crossed, true),
+        None
+    );
+    let crossed = views();
+    assert_eq!(crossed.len(), 2);
+    assert_eq!(crossed[0].0, xr::EyeVisibility::LEFT);
+    assert_eq!(crossed[0].1.offset.x, rect.offset.x + half_width);
+    assert_eq!(crossed[1].0, xr::EyeVisibility::RIGHT);
+    assert_eq!(crossed[1].1.offset.x, rect.offset.x);
+}
+
+#[test]
+fn stereo_equirect_skybox() {
+    let mut xr = OpenXrData::new(&Injector::default()).unwrap();
+    xr.enabled_extensions.khr_composition_layer_equirect2 = true;
+    let f = Fixture::with_xr(xr);
+    f.start_real_session();
+
+    let right_vk = Arc::new(VulkanData::new_temporary(
+        &f.overlays.openxr.instance,
+        f.overlays.openxr.system_id,
+    ));
+    let textures = [f.texture(), FakeGraphicsData::texture(&right_vk)];
+    assert_eq!(
+        f.comp
+            .SetSkyboxOverride(textures.as_ptr(), textures.len() as u32),
+        vr::EVRCompositorError::None
+    );
+
+    let session = f.overlays.openxr.session_data.get();
+    let layers = f
+        .overlays
+        .get_layers::<<FakeGraphicsData as GraphicsBackend>::Api>(&session, 1.0, 0);
+    let eyes: Vec<_> = layers
+        .iter()
+        .map(|layer| {
+            let Some(OverlayLayerInner::Equirect2(equirect)) = &layer.layer else {
+                panic!("expected an equirect layer");
+            };
+            equirect.as_raw().eye_visibility
+        })
+        .collect();
+    assert_eq!(eyes, [xr::EyeVisibility::LEFT, xr::EyeVisibility::RIGHT]);
+
+    let skybox = f.overlays.skybox.read().unwrap();
+    let overlays = f.overlays.overlays.read().unwrap();
+    for (key, texture) in skybox.iter().zip(&textures) {
+        let shared = overlays[*key].shared_texture().unwrap();
+        assert_eq!(shared.handle, texture.handle);
+    }
+}
+
+#[test]
+fn cube_skybox() {
+    for cube_supported in [false, true] {
+        let mut xr = OpenXrData::new(&Injector::default()).unwrap();
+        xr.enabled_extensions.khr_composition_layer_cube = cube_supported;
+        let f = Fixture::with_xr(xr);
+        f.start_real_session();
+
+        let textures = [f.texture(); 6];
+        assert_eq!(
+            f.comp
+                .SetSkyboxOverride(textures.as_ptr(), textures.len() as u32),
+            vr::EVRCompositorError::None
+        );
+
+        let session = f.overlays.openxr.session_data.get();
+        let layers = f
+            .overlays
+            .get_layers::<<FakeGraphicsData as GraphicsBackend>::Api>(&session, 1.0, 0);
+        if cube_supported {
+            assert_eq!(layers.len(), 1);
+            assert!(matches!(layers[0].layer, Some(OverlayLayerInner::Cube(_))));
+        } else {
+            assert_eq!(layers.len(), 6);
+            assert!(layers
+                .iter()
+                .all(|l| matches!(l.layer, Some(OverlayLayerInner::Quad(_)))));
+        }
+    }
+}
+
+#[test]
+fn quad_skybox_face_orientation() {
+    let f = Fixture::new();
+    f.start_real_session();
+
+    let textures = [f.texture(); 6];
+    assert_eq!(
+        f.comp
+            .SetSkyboxOverride(textures.as_ptr(), textures.len() as u32),
+        vr::EVRCompositorError::None
+    );
+
+    // Which way each face is, and where the top left and top right corners of its image should
+    // be seen from the center, in OpenVR face order.
+    let faces = [
+        (Vec3::NEG_Z, vec3(-1.0, 1.0, -1.0), vec3(1.0, 1.0, -1.0)), // front
+        (Vec3::Z, vec3(1.0, 1.0, 1.0), vec3(-1.0, 1.0, 1.0)),       // back
+        (Vec3::NEG_X, vec3(-1.0, 1.0, 1.0), vec3(-1.0, 1.0, -1.0)), // left
+        (Vec3::X, vec3(1.0, 1.0, -1.0), vec3(1.0, 1.0, 1.0)),       // right
+        (Vec3::Y, vec3(-1.0, 1.0, 1.0), vec3(1.0, 1.0, 1.0)),       // top
+        (Vec3::NEG_Y, vec3(-1.0, -1.0, -1.0), vec3(1.0, -1.0, -1.0)), // bottom
+    ];
+    let skybox = f.overlays.skybox.read().unwrap();
+    let overlays = f.overlays.overlays.read().unwrap();
+    assert_eq!(skybox.len(), faces.len());
+    for (face, (key, (forward, top_left, top_right))) in skybox.iter().zip(faces).enumerate() {
+        let overlay = &overlays[*key];
+        // Aim slightly inside the corner, so the ray hits this face and not its neighbor.
+        let uv_at = |corner: Vec3| {
+            let direction = corner.lerp(forward, 0.01);
+            overlay
+                .intersect(Vec3::ZERO, direction.normalize())
+                .unwrap_or_else(|| panic!("face {face} should be hit"))
+                .uv
+        };
+        // UVs start from the bottom left.
+        let uv = uv_at(top_left);
+        assert!(uv.x < 0.05 && uv.y > 0.95, "face {face} top left: {uv}");
+        let uv = uv_at(top_right);
+        assert!(uv.x > 0.95 && uv.y > 0.95, "face {face} top right: {uv}");
+    }
+}
+
+#[test]
+fn skybox_follows_tracking_space() {
+    for equirect_supported in [false, true] {
+        let mut xr = OpenXrData::new(&Injector::default()).unwrap();
+        xr.enabled_extensions.khr_composition_layer_equirect2 = equirect_supported;
+        let f = Fixture::with_xr(xr);
+        f.start_real_session();
+
+        let texture = f.texture();
+        assert_eq!(
+            f.comp.SetSkyboxOverride(&texture, 1),
+            vr::EVRCompositorError::None
+        );
+
+        for origin in [
+            vr::ETrackingUniverseOrigin::Standing,
+            vr::ETrackingUniverseOrigin::Seated,
+            vr::ETrackingUniverseOrigin::Standing,
+        ] {
+            f.comp.SetTrackingSpace(origin);
+            let session = f.overlays.openxr.session_data.get();
+            let layers = f
+                .overlays
+                .get_layers::<<FakeGraphicsData as GraphicsBackend>::Api>(&session, 1.0, 0);
+            assert!(!layers.is_empty());
+            let expected = session.get_space_for_origin(origin).as_raw();
+            for layer in &layers {
+                let space = match layer.layer.as_ref().unwrap() {
+                    OverlayLayerInner::Quad(quad) => quad.as_raw().space,
+                    OverlayLayerInner::Equirect2(equirect) => equirect.as_raw().space,
+                    _ => panic!("expected a quad or equirect layer"),
+                };
+                assert_eq!(
+                    space, expected,
+                    "equirect: {equirect_supported}, origin: {origin:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn equirect_skybox_fallback() {
+    for equirect_supported in [false, true] {
+        let mut xr = OpenXrData::new(&Injector::default()).unwrap();
+        xr.enabled_extensions.khr_composition_layer_equirect2 = equirect_supported;
+        let f = Fixture::with_xr(xr);
+        f.start_real_session();
+
+        let texture = f.texture();
+        assert_eq!(
+            f.comp.SetSkyboxOverride(&texture, 1),
+            vr::EVRCompositorError::None
+        );
+
+        let session = f.overlays.openxr.session_data.get();
+        let layers = f
+            .overlays
+            .get_layers::<<FakeGraphicsData as GraphicsBackend>::Api>(&session, 1.0, 0);
+        if equirect_supported {
+            assert_eq!(layers.len(), 1);
+            assert!(matches!(
+                layers[0].layer,
+                Some(OverlayLayerInner::Equirect2(_))
+            ));
+        } else {
+            assert_eq!(layers.len(), 4);
+            assert!(layers
+                .iter()
+                .all(|l| matches!(l.layer, Some(OverlayLayerInner::Quad(_)))));
+
+            // the strips should cover the whole horizon
+            let skybox = f.overlays.skybox.read().unwrap();
+            let overlays = f.overlays.overlays.read().unwrap();
+            let mut u: Vec<_> = skybox
+                .iter()
+                .map(|key| (overlays[*key].bounds.uMin, overlays[*key].bounds.uMax))
+                .collect();
+            u.sort_by(|a, b| a.0.total_cmp(&b.0));
+            assert_eq!(u, [(0.0, 0.25), (0.25, 0.5), (0.5, 0.75), (0.75, 1.0)]);
+        }
+    }
+}
+
+#[test]
+fn multisampled_overlay_swapchain() {
+    let f = Fixture::new();
+    f.start_real_session();
+
+    FakeGraphicsData::set_texture_sample_count(4);
+    let handle = f.create_overlay(c"msaa");
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+    let rect = f.overlay(handle, |o| o.rect.unwrap());
+
+    {
+        let session = f.overlays.openxr.session_data.get();
+        let swapchains = session.overlay_data.swapchains.lock().unwrap();
+        let Ok(swapchains): Result<&SwapchainMap<<FakeGraphicsData as GraphicsBackend>::Api>, _> =
+            swapchains.as_ref().unwrap().try_into()
+        else {
+            panic!("overlays should be using the fake backend");
+        };
+        // the texture gets resolved into a single sampled swapchain of the same size
+        let key = OverlayKey::from(KeyData::from_ffi(handle));
+        let info = &swapchains[key].info;
+        assert_eq!(info.sample_count, 1);
+        assert_eq!(
+            [info.width, info.height],
+            [rect.extent.width as u32, rect.extent.height as u32]
+        );
+    }
+
+    assert_eq!(f.layer_count(), 0);
+    assert_eq!(f.overlays.ShowOverlay(handle), None);
+    assert_eq!(f.layer_count(), 1);
+}
+
+#[test]
+fn array_overlay_texture_layer() {
+    let f = Fixture::new();
+    f.start_real_session();
+
+    let handle = f.create_overlay(c"array");
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+    assert_eq!(FakeGraphicsData::last_overlay_texture_layer(), 0);
+
+    assert_eq!(f.overlays.set_overlay_texture_layer(handle, 1), None);
+    assert_eq!(FakeGraphicsData::last_overlay_texture_layer(), 0);
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+    assert_eq!(FakeGraphicsData::last_overlay_texture_layer(), 1);
+
+    assert_eq!(
+        f.overlays.set_overlay_texture_layer(0xdead << 32, 1),
+        UnknownOverlay
+    );
+}
+
+#[test]
+fn overlay_settings() {
+    use crate::settings::Settings;
+    use vr::IVRSettings003_Interface;
+    let f = Fixture::new();
+    f.start_real_session();
+    let settings = Settings::new(f.overlays.openxr.clone(), &Injector::default());
+    settings.overlays.set(Arc::downgrade(&f.overlays));
+    let handle = f.create_overlay(c"settings");
+
+    let set_layer = |section: &CStr, layer| {
+        let mut err = vr::EVRSettingsError::None;
+        settings.SetInt32(section.as_ptr(), c"textureLayer".as_ptr(), layer, &mut err);
+        err
+    };
+    assert_eq!(
+        set_layer(c"xrizer.overlay.settings", 1),
+        vr::EVRSettingsError::None
+    );
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+    assert_eq!(FakeGraphicsData::last_overlay_texture_layer(), 1);
+
+    assert_eq!(
+        set_layer(c"xrizer.overlay.settings", -1),
+        vr::EVRSettingsError::WriteFailed
+    );
+    assert_eq!(
+        set_layer(c"xrizer.overlay.missing", 1),
+        vr::EVRSettingsError::WriteFailed
+    );
+
+    let set_curvature_mode = |mode: &CStr| {
+        let mut err = vr::EVRSettingsError::None;
+        settings.SetString(
+            c"xrizer.overlay.settings".as_ptr(),
+            c"curvatureMode".as_ptr(),
+            mode.as_ptr(),
+            &mut err,
+        );
+        err
+    };
+    f.overlay(handle, |o| {
+        assert_eq!(o.curvature_mode, CurvatureMode::ConstantAngle)
+    });
+    assert_eq!(
+        set_curvature_mode(c"constant_radius"),
+        vr::EVRSettingsError::None
+    );
+    f.overlay(handle, |o| {
+        assert_eq!(o.curvature_mode, CurvatureMode::ConstantRadius)
+    });
+    assert_eq!(
+        set_curvature_mode(c"bendy"),
+        vr::EVRSettingsError::WriteFailed
+    );
+    f.overlay(handle, |o| {
+        assert_eq!(o.curvature_mode, CurvatureMode::ConstantRadius)
+    });
+
+    for (letterbox, fit) in [(true, ContentFit::Letterbox), (false, ContentFit::Stretch)] {
+        let mut err = vr::EVRSettingsError::None;
+        settings.SetBool(
+            c"xrizer.overlay.settings".as_ptr(),
+            c"letterbox".as_ptr(),
+            letterbox,
+            &mut err,
+        );
+        assert_eq!(err, vr::EVRSettingsError::None);
+        f.overlay(handle, |o| assert_eq!(o.fit, fit));
+    }
+}
+
+#[test]
+fn unchanged_overlay_texture_is_copied_again() {
+    let f = Fixture::new();
+    f.start_real_session();
+
+    let handle = f.create_overlay(c"static");
+    let copies = FakeGraphicsData::overlay_copies();
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+    assert_eq!(FakeGraphicsData::overlay_copies(), copies + 1);
+    // Apps render into the same texture again before setting it.
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+    assert_eq!(FakeGraphicsData::overlay_copies(), copies + 2);
+    f.overlay(handle, |o| assert!(o.rect.is_some()));
+}
+
+#[test]
+fn recenter_moves_seated_overlays() {
+    let f = Fixture::new();
+    f.start_real_session();
+    let handle = f.create_overlay(c"recenter");
+    let transform = translation(0.0, 0.0, -1.0);
+    assert_eq!(
+        f.overlays.SetOverlayTransformAbsolute(
+            handle,
+            vr::ETrackingUniverseOrigin::Seated,
+            &transform
+        ),
+        None
+    );
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+    assert_eq!(f.overlays.ShowOverlay(handle), None);
+
+    // The HMD is turned to the left and tilted down.
+    let head_position = vec3(1.0, 1.6, 0.5);
+    let yaw = Quat::from_rotation_y(PI / 2.0);
+    let head_rotation = yaw * Quat::from_rotation_x(-0.3);
+    let session = f.overlays.openxr.session_data.get().session.as_raw();
+    fakexr::set_view_pose(
+        session,
+        xr::Posef {
+            position: xr::Vector3f {
+                x: head_position.x,
+                y: head_position.y,
+                z: head_position.z,
+            },
+            orientation: xr::Quaternionf {
+                x: head_rotation.x,
+                y: head_rotation.y,
+                z: head_rotation.z,
+                w: head_rotation.w,
+            },
+        },
+    );
+
+    // Where the overlay ends up, seen from the HMD
+    let position_from_head = || {
+        let session = f.overlays.openxr.session_data.get();
+        let layers = f
+            .overlays
+            .get_layers::<<FakeGraphicsData as GraphicsBackend>::Api>(&session, 1.0, 0);
+        let Some(OverlayLayerInner::Quad(quad)) = &layers[0].layer else {
+            panic!("expected a quad layer");
+        };
+        let quad = quad.as_raw();
+        let space = session.get_space_for_origin(vr::ETrackingUniverseOrigin::Seated);
+        assert_eq!(quad.space, space.as_raw());
+        let origin = space
+            .locate(&session.view_space, f.overlays.openxr.display_time.get())
+            .unwrap()
+            .pose;
+        let rotation = Quat::from_xyzw(
+            origin.orientation.x,
+            origin.orientation.y,
+            origin.orientation.z,
+            origin.orientation.w,
+        );
+        let position = quad.pose.position;
+        vec3(origin.position.x, origin.position.y, origin.position.z)
+            + rotation * vec3(position.x, position.y, position.z)
+    };
+
+    let world = vec3(0.0, 0.0, -1.0);
+    let before = position_from_head();
+    assert!(before.abs_diff_eq(head_rotation.inverse() * (world - head_position), 1e-5));
+
+    f.overlays
+        .openxr
+        .reset_tracking_space(vr::ETrackingUniverseOrigin::Seated);
+    // Recentering only takes the HMD's yaw into account, so the overlay is a meter in front of
+    // where the HMD faces, at its height.
+    let after = position_from_head();
+    let expected = head_rotation.inverse() * (yaw * world);
+    assert!(after.abs_diff_eq(expected, 1e-5), "{after} != {expected}");
+}
+
+#[test]
+fn overlay_origins_resolve_to_distinct_spaces() {
+    let f = Fixture::new();
+    f.start_real_session();
+    // Recentering the standing space sets it apart from the raw one.
+    let session = f.overlays.openxr.session_data.get().session.as_raw();
+    fakexr::set_view_pose(
+        session,
+        xr::Posef {
+            position: xr::Vector3f {
+                x: 1.0,
+                y: 1.6,
+                z: 0.0,
+            },
+            orientation: xr::Quaternionf::IDENTITY,
+        },
+    );
+    f.overlays
+        .openxr
+        .reset_tracking_space(vr::ETrackingUniverseOrigin::Standing);
+
+    let origins = [
+        vr::ETrackingUniverseOrigin::Seated,
+        vr::ETrackingUniverseOrigin::Standing,
+        vr::ETrackingUniverseOrigin::RawAndUncalibrated,
+    ];
+    let transform = translation(0.0, 0.0, -1.0);
+    for (i, origin) in origins.into_iter().enumerate() {
+        let handle = f.create_overlay(&CString::new(format!("origin{i}")).unwrap());
+        assert_eq!(
+            f.overlays
+                .SetOverlayTransformAbsolute(handle, origin, &transform),
+            None
+        );
+        assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+        assert_eq!(f.overlays.ShowOverlay(handle), None);
+    }
+
+    let session = f.overlays.openxr.session_data.get();
+    let layers = f
+        .overlays
+        .get_layers::<<FakeGraphicsData as GraphicsBackend>::Api>(&session, 1.0, 0);
+    let mut spaces: Vec<_> = layers
+        .iter()
+        .map(|layer| {
+            let Some(OverlayLayerInner::Quad(quad)) = &layer.layer else {
+                panic!("expected a quad layer");
+            };
+            quad.as_raw().space.into_raw()
+        })
+        .collect();
+    for origin in origins {
+        let space = session.get_space_for_origin(origin).as_raw().into_raw();
+        assert!(spaces.contains(&space), "no layer in the {origin:?} space");
+    }
+    spaces.sort();
+    spaces.dedup();
+    assert_eq!(spaces.len(), origins.len());
+
+    // The raw space isn't affected by recentering, so it's 1m to the side of the standing one.
+    let raw_from_standing = session
+        .get_space_for_origin(vr::ETrackingUniverseOrigin::RawAndUncalibrated)
+        .locate(
+            session.get_space_for_origin(vr::ETrackingUniverseOrigin::Standing),
+            f.overlays.openxr.display_time.get(),
+        )
+        .unwrap()
+        .pose
+        .position;
+    assert_eq!(
+        [
+            raw_from_standing.x,
+            raw_from_standing.y,
+            raw_from_standing.z
+        ],
+        [-1.0, -1.6, 0.0]
+    );
+}
+
+#[test]
+fn get_layers_reuses_spaces() {
+    let f = Fixture::new();
+    f.start_real_session();
+
+    for i in 0..100 {
+        let key = CString::new(format!("overlay{i}")).unwrap();
+        let handle = f.create_overlay(&key);
+        let origin = if i % 2 == 0 {
+            vr::ETrackingUniverseOrigin::Seated
+        } else {
+            vr::ETrackingUniverseOrigin::Standing
+        };
+        let transform = translation(0.0, 0.0, -1.0);
+        assert_eq!(
+            f.overlays
+                .SetOverlayTransformAbsolute(handle, origin, &transform),
+            None
+        );
+        assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+        assert_eq!(f.overlays.ShowOverlay(handle), None);
+    }
+
+    let session = f.overlays.openxr.session_data.get().session.as_raw();
+    let created = fakexr::reference_spaces_created(session);
+    for _ in 0..10 {
+        assert_eq!(f.layer_count(), 100);
+    }
+    assert_eq!(fakexr::reference_spaces_created(session), created);
+}
+
+#[test]
+fn get_layers_allows_concurrent_readers() {
+    let f = Fixture::new();
+    f.start_real_session();
+
+    let handle = f.create_overlay(c"shared");
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+    assert_eq!(f.overlays.ShowOverlay(handle), None);
+
+    let (held_tx, held_rx) = std::sync::mpsc::channel();
+    let (done_tx, done_rx) = std::sync::mpsc::channel();
+    std::thread::scope(|s| {
+        let reader = s.spawn(|| {
+            let _overlays = f.overlays.overlays.read().unwrap();
+            held_tx.send(()).unwrap();
+            // Times out if get_layers is waiting for this lock to be released
+            done_rx
+                .recv_timeout(std::time::Duration::from_secs(5))
+                .is_ok()
+        });
+
+        held_rx.recv().unwrap();
+        assert_eq!(f.layer_count(), 1);
+        let _ = done_tx.send(());
+        assert!(reader.join().unwrap(), "get_layers blocked on a reader");
+    });
+}
+
+#[test]
+fn mismatched_texture_api() {
+    let f = Fixture::new();
+    f.start_real_session();
+
+    let handle = f.create_overlay(c"mismatch");
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+
+    let gl_texture = vr::Texture_t {
+        eType: vr::ETextureType::OpenGL,
+        ..f.texture()
+    };
+    assert_eq!(
+        f.overlays.SetOverlayTexture(handle, &gl_texture),
+        InvalidTexture
+    );
+    // the previous texture is still shown
+    f.overlay(handle, |o| assert!(o.rect.is_some()));
+
+    // layers requested for another API are skipped instead of crashing the app
+    let session = f.overlays.openxr.session_data.get();
+    assert!(f
+        .overlays
+        .get_layers::<xr::OpenGL>(&session, 1.0, 0)
+        .is_empty());
+}
+
+#[test]
+fn interop_texture() {
+    let f = Fixture::new();
+    f.start_real_session();
+    {
+        let session = f.overlays.openxr.session_data.get();
+        assert!(needs_interop(vr::ETextureType::OpenGL, &session));
+        assert!(!needs_interop(vr::ETextureType::Vulkan, &session));
+    }
+
+    let handle = f.create_overlay(c"interop");
+    let texture = f.texture();
+    // Reading back GL textures needs a GL context, so fake textures stand in for the API the app
+    // draws with.
+    FakeGraphicsData::set_foreign_textures(true);
+
+    let copies = FakeGraphicsData::overlay_copies();
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &texture), None);
+    assert_eq!(f.overlays.ShowOverlay(handle), None);
+    // the texture's pixels were uploaded with a backend for the session, not copied
+    assert_eq!(FakeGraphicsData::overlay_copies(), copies);
+    f.overlay(handle, |o| {
+        assert!(o.interop.is_some());
+        assert!(o.compositor.is_some());
+        assert!(o.rect.is_some());
+    });
+    assert_eq!(f.layer_count(), 1);
+
+    // the overlay keeps reading back textures from the same API
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &texture), None);
+    let gl_texture = vr::Texture_t {
+        eType: vr::ETextureType::OpenGL,
+        ..texture
+    };
+    assert_eq!(
+        f.overlays.SetOverlayTexture(handle, &gl_texture),
+        InvalidTexture
+    );
+}
+
+#[test]
+fn get_overlay_texture() {
+    let f = Fixture::new();
+    f.start_real_session();
+
+    let handle = f.create_overlay(c"native");
+    let get_texture = |native: &mut *mut c_void, width: &mut u32, height: &mut u32| {
+        let mut api = vr::ETextureType::Invalid;
+        let ret = f.overlays.GetOverlayTexture(
+            handle,
+            native,
+            std::ptr::null_mut(),
+            width,
+            height,
+            std::ptr::null_mut(),
+            &mut api,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        );
+        (ret, api)
+    };
+
+    let (mut native, mut width, mut height) = (std::ptr::null_mut(), 0, 0);
+    assert_eq!(
+        get_texture(&mut native, &mut width, &mut height).0,
+        InvalidTexture
+    );
+
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+    assert_eq!(
+        get_texture(&mut native, &mut width, &mut height),
+        (None, vr::ETextureType::Reserved)
+    );
+    assert!(!native.is_null());
+    assert_eq!((width, height), (10, 10));
+}
+
+#[test]
+fn message_overlay_lifecycle() {
+    let f = Fixture::new();
+    let show = || {
+        f.overlays.ShowMessageOverlay(
+            c"Discard changes?".as_ptr(),
+            c"Confirm".as_ptr(),
+            c"Yes".as_ptr(),
+            c"No".as_ptr(),
+            std::ptr::null(),
+            std::ptr::null(),
+        )
+    };
+    assert_eq!(
+        show(),
+        vr::VRMessageOverlayResponse::CouldntFindOrCreateClientOverlay
+    );
+
+    f.start_real_session();
+    let count = || f.overlays.overlays.read().unwrap().len();
+    let before = count();
+    assert_eq!(f.layer_count(), 0);
+
+    assert_eq!(
+        show(),
+        vr::VRMessageOverlayResponse::CouldntFindSystemOverlay
+    );
+    assert_eq!(count(), before + 1);
+    assert_eq!(f.layer_count(), 1);
+
+    // Showing another message replaces the current one
+    assert_eq!(
+        show(),
+        vr::VRMessageOverlayResponse::CouldntFindSystemOverlay
+    );
+    assert_eq!(count(), before + 1);
+
+    f.overlays.CloseMessageOverlay();
+    assert_eq!(count(), before);
+    assert_eq!(f.layer_count(), 0);
+    // Nothing left to close
+    f.overlays.CloseMessageOverlay();
+    assert_eq!(count(), before);
+}
+
+#[test]
+fn message_renders_opaque_text() {
+    let message = message::Message {
+        text: "Discard changes?".into(),
+        caption: "Confirm".into(),
+        buttons: vec!["Yes".into(), "No".into()],
+    };
+    let (extent, pixels) = message.render();
+    assert_eq!(
+        pixels.len(),
+        extent.width as usize * extent.height as usize * 4
+    );
+    let pixels: Vec<&[u8]> = pixels.chunks_exact(4).collect();
+    assert!(pixels.iter().all(|px| px[3] == 0xff));
+    // Text shows up as pixels that differ from the background
+    assert!(pixels.iter().any(|px| *px != pixels[0]));
+}
+
+#[test]
+fn virtual_keyboard() {
+    let f = Fixture::new();
+    f.start_real_session();
+    let count = || f.overlays.overlays.read().unwrap().len();
+    let before = count();
+    let show = || {
+        f.overlays.ShowKeyboard(
+            vr::EGamepadTextInputMode::Normal,
+            vr::EGamepadTextInputLineMode::SingleLine,
+            0,
+            c"Name".as_ptr(),
+            10,
+            c"hi".as_ptr(),
+            42,
+        )
+    };
+    assert_eq!(show(), None);
+    assert_eq!(count(), before + 1);
+    // The keyboard is drawn as soon as it's shown
+    assert_eq!(f.layer_count(), 1);
+    assert_eq!(show(), KeyboardAlreadyInUse);
+
+    f.overlays.press_key(Key::Char('a'));
+    f.overlays.press_key(Key::Char('!'));
+    f.overlays.press_key(Key::Backspace);
+    f.overlays.press_key(Key::Char('b'));
+    f.overlays.press_key(Key::Done);
+    assert_eq!(count(), before);
+    assert_eq!(f.layer_count(), 0);
+
+    let mut text = [0 as c_char; 16];
+    assert_eq!(
+        f.overlays
+            .GetKeyboardText(text.as_mut_ptr(), text.len() as u32),
+        4
+    );
+    assert_eq!(unsafe { CStr::from_ptr(text.as_ptr()) }, c"hib");
+
+    let next_event = || {
+        let mut event = vr::VREvent_t::default();
+        let size = std::mem::size_of_val(&event) as u32;
+        f.overlays.get_next_event(size, &mut event).then_some(event)
+    };
+    for (ty, input) in [
+        (vr::EVREventType::KeyboardCharInput, c"a"),
+        (vr::EVREventType::KeyboardCharInput, c"\x08"),
+        (vr::EVREventType::KeyboardCharInput, c"b"),
+        (vr::EVREventType::KeyboardDone, c""),
+    ] {
+        let event = next_event().expect("missing keyboard event");
+        assert_eq!(event.eventType, ty as u32);
+        let keyboard = unsafe { event.data.keyboard };
+        assert_eq!(
+            unsafe { CStr::from_ptr(keyboard.cNewInput.as_ptr()) },
+            input
+        );
+        assert_eq!(keyboard.uUserValue, 42);
+        assert_eq!(keyboard.overlayHandle, 0);
+    }
+    assert!(next_event().is_none());
+
+    // A keyboard for an overlay can be shown again once the last one is done
+    let handle = f.create_overlay(c"text_field");
+    assert_eq!(
+        f.overlays.ShowKeyboardForOverlay(
+            handle,
+            vr::EGamepadTextInputMode::Password,
+            vr::EGamepadTextInputLineMode::SingleLine,
+            0,
+            std::ptr::null(),
+            10,
+            std::ptr::null(),
+            7,
+        ),
+        None
+    );
+    assert_eq!(count(), before + 2);
+    f.overlays.HideKeyboard();
+    assert_eq!(count(), before + 1);
+
+    let event = next_event().expect("missing close event");
+    assert_eq!(event.eventType, vr::EVREventType::KeyboardClosed as u32);
+    let keyboard = unsafe { event.data.keyboard };
+    assert_eq!(keyboard.uUserValue, 7);
+    assert_eq!(keyboard.overlayHandle, handle);
+}
+
+#[test]
+fn laser_types_on_keyboard() {
+    let f = Fixture::new();
+    f.start_real_session();
+    assert_eq!(
+        f.overlays.ShowKeyboard(
+            vr::EGamepadTextInputMode::Normal,
+            vr::EGamepadTextInputLineMode::SingleLine,
+            0,
+            c"".as_ptr(),
+            10,
+            c"".as_ptr(),
+            0,
+        ),
+        None
+    );
+    let keyboard_overlay = || {
+        f.overlays
+            .keyboard
+            .lock()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .overlay
+    };
+    let handle = keyboard_overlay().unwrap().data().as_ffi();
+    let size = f.overlay(handle, |o| o.quad_size(o.rect.unwrap()));
+
+    let keyboard = Keyboard::new(
+        KeyboardLayout::configured(),
+        vr::EGamepadTextInputMode::Normal,
+        vr::EGamepadTextInputLineMode::SingleLine,
+        10,
+        "",
+    );
+    let uv_of = |wanted: Key| {
+        (0..100)
+            .flat_map(|y| (0..100).map(move |x| vec2(x as f32 + 0.5, y as f32 + 0.5) / 100.0))
+            .find(|uv| keyboard.key_at(*uv) == Some(wanted))
+            .expect("key not found on keyboard")
+    };
+    // The keyboard faces the seated origin, so a laser pointing straight ahead hits it.
+    let click = |key: Key| {
+        let uv = uv_of(key);
+        let x = KEYBOARD_POSITION.x + (uv.x - 0.5) * size.width;
+        let y = KEYBOARD_POSITION.y + (uv.y - 0.5) * size.height;
+        let controller = translation(x, y, 0.0);
+        for trigger in [true, false] {
+            f.update_laser(
+                vr::ETrackingUniverseOrigin::Seated,
+                Some((Hand::Right, controller)),
+                PointerState {
+                    trigger,
+                    ..Default::default()
+                },
+            );
+        }
+    };
+    let text = || {
+        let mut text = [0 as c_char; 16];
+        f.overlays
+            .GetKeyboardText(text.as_mut_ptr(), text.len() as u32);
+        unsafe { CStr::from_ptr(text.as_ptr()) }.to_owned()
+    };
+
+    click(Key::Char('1'));
+    click(Key::Char('2'));
+    click(Key::Backspace);
+    assert_eq!(text(), c"1".to_owned());
+    assert!(keyboard_overlay().is_some());
+
+    click(Key::Done);
+    assert!(keyboard_overlay().is_none());
+    assert_eq!(text(), c"1".to_owned());
+}
+
+#[test]
+fn session_restart_clears_textures() {
+    let f = Fixture::new();
+    f.start_real_session();
+    let handle = f.create_overlay(c"restart");
+    let resubmitted = f.create_overlay(c"resubmitted");
+    for overlay in [handle, resubmitted] {
+        assert_eq!(f.overlays.ShowOverlay(overlay), None);
+        assert_eq!(f.overlays.SetOverlayTexture(overlay, &f.texture()), None);
+    }
+    assert_eq!(f.layer_count(), 2);
+
+    f.comp.openxr.restart_session();
+    assert!(f
+        .overlays
+        .openxr
+        .session_data
+        .get()
+        .overlay_data
+        .swapchains
+        .lock()
+        .unwrap()
+        .is_none());
+    assert_eq!(f.layer_count(), 0);
+    // Textures set after the restart are already in the new session.
+    assert_eq!(
+        f.overlays.SetOverlayTexture(resubmitted, &f.texture()),
+        None
+    );
+
+    // The old texture may be gone, so the overlay waits for the app to set it again.
+    f.wait_get_poses();
+    f.overlay(handle, |o| {
+        assert!(o.rect.is_none());
+        assert!(o.shared_texture().is_none());
+    });
+    f.overlay(resubmitted, |o| assert!(o.rect.is_some()));
+    assert_eq!(f.layer_count(), 1);
+
+    assert_eq!(f.overlays.SetOverlayTexture(handle, &f.texture()), None);
+    assert_eq!(f.layer_count(), 2);
+}
+
+#[test]
+fn overlay_limit() {
+    let f = Fixture::new();
+    f.start_real_session();
+    // The skybox is made of overlays too, which shouldn't take up the app's slots.
+    let texture = f.texture();
+    assert_eq!(
+        f.comp.SetSkyboxOverride(&texture, 1),
+        vr::EVRCompositorError::None
+    );
+    assert!(!f.overlays.skybox.read().unwrap().is_empty());
+
+    let handles: Vec<_> = (0..f.overlays.max_overlays)
+        .map(|i| f.create_overlay(&CString::new(format!("limit{i}")).unwrap()))
+        .collect();
+
+    let mut handle = 0;
+    assert_eq!(
+        f.overlays.CreateOverlay(
+            c"one_too_many".as_ptr(),
+            c"one_too_many".as_ptr(),
+            &mut handle
+        ),
+        OverlayLimitExceeded
+    );
+    let (mut main, mut thumbnail) = (0, 0);
+    assert_eq!(
+        f.overlays.CreateDashboardOverlay(
+            c"dashboard".as_ptr(),
+            c"dashboard".as_ptr(),
+            &mut main,
+            &mut thumbnail
+        ),
+        OverlayLimitExceeded
+    );
+
+    assert_eq!(f.overlays.DestroyOverlay(handles[0]), None);
+    f.create_overlay(c"one_too_many");
+}
+
+#[test]
+fn overlay_flags_mask() {
+    let f = Fixture::new();
+    let handle = f.create_overlay(c"flags");
+    let mut mask = u32::MAX;
+    assert_eq!(f.overlays.GetOverlayFlags(handle, &mut mask), None);
+    assert_eq!(mask, 0);
+
+    let set = [
+        vr::VROverlayFlags::SideBySide_Parallel,
+        vr::VROverlayFlags::SendVRDiscreteScrollEvents,
+        vr::VROverlayFlags::SortWithNonSceneOverlays,
+    ];
+    for flag in set {
+        assert_eq!(f.overlays.SetOverlayFlag(handle, flag, true), None);
+    }
+    assert_eq!(
+        f.overlays.SetOverlayFlag(
+            handle,
+            vr::VROverlayFlags::SendVRDiscreteScrollEvents,
+            false
+        ),
+        None
+    );
+    assert_eq!(f.overlays.GetOverlayFlags(handle, &mut mask), None);
+    assert_eq!(
+        mask,
+        vr::VROverlayFlags::SideBySide_Parallel as u32
+            | vr::VROverlayFlags::SortWithNonSceneOverlays as u32
+    );
+
+    for flag in set {
+        let mut enabled = false;
+        assert_eq!(f.overlays.GetOverlayFlag(handle, flag, &mut enabled), None);
+        assert_eq!(enabled, mask & flag as u32 != 0);
+    }
+
+    assert_eq!(
+        f.overlays.GetOverlayFlags(handle, std::ptr::null_mut()),
+        InvalidParameter
+    );
+    assert_eq!(
+        f.overlays.GetOverlayFlag(
+            handle,
+            vr::VROverlayFlags::SideBySide_Parallel,
+            std::ptr::null_mut()
+        ),
+        InvalidParameter
+    );
+    assert_eq!(
+        f.overlays.GetOverlayFlags(0xdead << 32, &mut mask),
+        UnknownOverlay
+    );
+}
+
+#[track_caller]
+fn assert_matrix_eq(a: vr::HmdMatrix34_t, b: vr::HmdMatrix34_t) {
+    for (row_a, row_b) in a.m.iter().zip(&b.m) {
+        for (a, b) in row_a.iter().zip(row_b) {
+            assert!((a - b).abs() < 1e-6, "{:?} != {:?}", a, b);
+        }
+    }
+}
+
+#[test]
+fn transform_round_trip() {
+    let pose = xr::Posef {
+        position: xr::Vector3f {
+            x: 0.5,
+            y: -1.0,
+            z: 2.0,
+        },
+        orientation: {
+            let q = Quat::from_euler(glam::EulerRot::YXZ, 0.3, -0.7, 1.2);
+            xr::Quaternionf {
+                x: q.x,
+                y: q.y,
+                z: q.z,
+                w: q.w,
+            }
+        },
+    };
+    let matrix = vr::HmdMatrix34_t::from(pose);
+    let back = xr::Posef::from(matrix);
+    assert_eq!(
+        [back.position.x, back.position.y, back.position.z],
+        [0.5, -1.0, 2.0]
+    );
+    assert_matrix_eq(back.into(), matrix);
+
+    let f = Fixture::new();
+    let handle = f.create_overlay(c"round_trip");
+    let mut origin = vr::ETrackingUniverseOrigin::RawAndUncalibrated;
+    let mut transform = vr::HmdMatrix34_t::default();
+    assert_eq!(
+        f.overlays.SetOverlayTransformAbsolute(
+            handle,
+            vr::ETrackingUniverseOrigin::Seated,
+            &matrix
+        ),
+        None
+    );
+    assert_eq!(
+        f.overlays
+            .GetOverlayTransformAbsolute(handle, &mut origin, &mut transform),
+        None
+    );
+    assert_eq!(origin, vr::ETrackingUniverseOrigin::Seated);
+    assert_matrix_eq(transform, matrix);
+
+    // A scaled rotation turns into a non-normalized quaternion. The getter reports the
+    // normalized transform that's actually rendered, not the app's input.
+    let mut scaled = matrix;
+    for row in &mut scaled.m {
+        for value in &mut row[..3] {
+            *value *= 2.0;
+        }
+    }
+    let raw = xr::Posef::from(scaled).orientation;
+    assert!((Quat::from_xyzw(raw.x, raw.y, raw.z, raw.w).length() - 1.0).abs() > 0.1);
+    assert_eq!(
+        f.overlays.SetOverlayTransformAbsolute(
+            handle,
+            vr::ETrackingUniverseOrigin::Standing,
+            &scaled
+        ),
+        None
+    );
+    assert_eq!(
+        f.overlays
+            .GetOverlayTransformAbsolute(handle, &mut origin, &mut transform),
+        None
+    );
+    assert_eq!(origin, vr::ETrackingUniverseOrigin::Standing);
+    f.overlay(handle, |o| assert_matrix_eq(o.pose().into(), transform));
+    for row in &transform.m {
+        let length = Vec3::from_slice(&row[..3]).length();
+        assert!(
+            (length - 1.0).abs() < 1e-5,
+            "{transform:?} isn't a rotation"
+        );
+    }
+    assert_eq!(
+        [transform.m[0][3], transform.m[1][3], transform.m[2][3]],
+        [0.5, -1.0, 2.0]
+    );
+
+    assert_eq!(
+        f.overlays
+            .GetOverlayTransformAbsolute(handle, &mut origin, std::ptr::null_mut()),
+        InvalidParameter
+    );
+    assert_eq!(
+        f.overlays
+            .GetOverlayTransformAbsolute(0xdead << 32, &mut origin, &mut transform),
+        UnknownOverlay
+    );
+}
+
+#[test]
+fn default_overlay_pose() {
+    assert_eq!(parse_overlay_distance(" 1.25 "), Some(1.25));
+    assert!(parse_overlay_distance("0").is_none());
+    assert!(parse_overlay_distance("-1").is_none());
+    assert!(parse_overlay_distance("inf").is_none());
+    assert!(parse_overlay_distance("far").is_none());
+
+    let f = Fixture::new();
+    let handle = f.create_overlay(c"default_pose");
+    f.overlay(handle, |o| {
+        let pose = o.pose();
+        assert_eq!(pose.position.z, -*OVERLAY_DISTANCE);
+        assert_eq!((pose.position.x, pose.position.y), (0.0, 0.0));
+    });
+
+    let transform = translation(0.25, 0.0, -2.0);
+    assert_eq!(
+        f.overlays.SetOverlayTransformAbsolute(
+            handle,
+            vr::ETrackingUniverseOrigin::Standing,
+            &transform
+        ),
+        None
+    );
+    f.overlay(handle, |o| {
+        let pose = o.pose();
+        assert_eq!((pose.position.x, pose.position.z), (0.25, -2.0));
+    });
+}
+
+#[test]
+fn partial_equirect_coverage() {
+    assert_eq!(
+        EquirectCoverage::parse("360, 90, -90"),
+        Some(EquirectCoverage::default())
+    );
+    assert!(EquirectCoverage::parse("180,45").is_none());
+    assert!(EquirectCoverage::parse("180,45,-45,0").is_none());
+    assert!(EquirectCoverage::parse("400,45,-45").is_none());
+    assert!(EquirectCoverage::parse("180,-45,45").is_none());
+
+    let mut xr = OpenXrData::new(&Injector::default()).unwrap();
+    xr.enabled_extensions.khr_composition_layer_equirect2 = true;
+    let f = Fixture::with_xr(xr);
+    f.start_real_session();
+    let texture = f.texture();
+    assert_eq!(
+        f.comp.SetSkyboxOverride(&texture, 1),
+        vr::EVRCompositorError::None
+    );
+
+    let angles = || {
+        let session = f.overlays.openxr.session_data.get();
+        let layers = f
+            .overlays
+            .get_layers::<<FakeGraphicsData as GraphicsBackend>::Api>(&session, 1.0, 0);
+        let Some(OverlayLayerInner::Equirect2(equirect)) = &layers[0].layer else {
+            panic!("expected an equirect layer");
+        };
+        let raw = equirect.as_raw();
+        (
+            raw.central_horizontal_angle,
+            raw.upper_vertical_angle,
+            raw.lower_vertical_angle,
+        )
+    };
+    assert_eq!(angles(), (2.0 * PI, 0.5 * PI, -0.5 * PI));
+
+    let settings = crate::settings::Settings::new(f.overlays.openxr.clone(), &Injector::default());
+    settings.overlays.set(Arc::downgrade(&f.overlays));
+    let set_coverage = |coverage: &CStr| {
+        let mut err = vr::EVRSettingsError::None;
+        vr::IVRSettings003_Interface::SetString(
+            &settings,
+            c"xrizer.skybox".as_ptr(),
+            c"coverage".as_ptr(),
+            coverage.as_ptr(),
+            &mut err,
+        );
+        err
+    };
+    assert_eq!(set_coverage(c"180,45,-30"), vr::EVRSettingsError::None);
+    let expected = (
+        180f32.to_radians(),
+        45f32.to_radians(),
+        (-30f32).to_radians(),
+    );
+    assert_eq!(angles(), expected);
+    assert_eq!(
+        set_coverage(c"180,-45,45"),
+        vr::EVRSettingsError::WriteFailed
+    );
+    assert_eq!(angles(), expected);
+
+    // Later skyboxes keep the coverage.
+    assert_eq!(
+        f.comp.SetSkyboxOverride(&texture, 1),
+        vr::EVRCompositorError::None
+    );
+    assert_eq!(angles(), expected);
+}
@@ -1,29 +1,319 @@
+mod panorama;
+mod png;
+
+use crate::{
+    clientcore::{Injected, Injector},
+    compositor::{Compositor, EyeImage, ScreenshotEyes},
+    graphics_backends::flip_rows,
+    system::System,
+};
+use log::{debug, info, warn};
 use openvr as vr;
+use openxr as xr;
+use std::collections::{HashMap, VecDeque};
+use std::ffi::{CStr, CString};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
 
-#[derive(Default, macros::InterfaceImpl)]
+#[derive(macros::InterfaceImpl)]
 #[interface = "IVRScreenshots"]
 #[versions(001)]
 pub struct Screenshots {
     vtables: Vtables,
+    compositor: Injected<Compositor>,
+    system: Injected<System>,
+    next_handle: AtomicU32,
+    /// Screenshot types the app has said it will take itself
+    hooked_types: Mutex<Vec<vr::EVRScreenshotType>>,
+    requests: Mutex<HashMap<vr::ScreenshotHandle_t, ScreenshotRequest>>,
+    events: Mutex<VecDeque<ScreenshotEvent>>,
+}
+
+/// A screenshot the app has been asked to take through RequestScreenshot.
+struct ScreenshotRequest {
+    ty: vr::EVRScreenshotType,
+    preview_filename: CString,
+    vr_filename: Option<CString>,
+}
+
+struct ScreenshotEvent {
+    ty: vr::EVREventType,
+    data: vr::VREvent_Screenshot_t,
+}
+
+impl Screenshots {
+    pub fn new(injector: &Injector) -> Self {
+        Self {
+            vtables: Default::default(),
+            compositor: injector.inject(),
+            system: injector.inject(),
+            // 0 is k_unScreenshotHandleInvalid
+            next_handle: AtomicU32::new(1),
+            hooked_types: Mutex::default(),
+            requests: Mutex::default(),
+            events: Mutex::default(),
+        }
+    }
+
+    fn new_handle(&self) -> vr::ScreenshotHandle_t {
+        self.next_handle.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn queue_event(
+        &self,
+        ty: vr::EVREventType,
+        handle: vr::ScreenshotHandle_t,
+        screenshot_type: vr::EVRScreenshotType,
+    ) {
+        self.events.lock().unwrap().push_back(ScreenshotEvent {
+            ty,
+            data: vr::VREvent_Screenshot_t {
+                handle,
+                type_: screenshot_type as u32,
+            },
+        });
+    }
+
+    /// Writes the next submitted eye textures out as a stereo or stereo panorama screenshot,
+    /// once the app has submitted them.
+    fn capture(
+        &self,
+        ty: vr::EVRScreenshotType,
+        preview_filename: &CStr,
+        vr_filename: &CStr,
+    ) -> Result<(), vr::EVRScreenshotError> {
+        let fovs = self.eye_fovs();
+        let preview_filename = preview_filename.to_owned();
+        let vr_filename = vr_filename.to_owned();
+        let write = move |eyes: ScreenshotEyes| {
+            let eyes = eyes.map(upright);
+            // Failures have already been logged, and there's nobody left to report them to.
+            let _ = write_screenshot(ty, &eyes, fovs, &preview_filename, &vr_filename);
+        };
+        self.compositor
+            .get()
+            .ok_or(vr::EVRCompositorError::RequestFailed)
+            .and_then(|comp| comp.capture_next_eyes(write))
+            .map_err(|_| {
+                warn!("Can't take screenshot: app isn't submitting frames");
+                vr::EVRScreenshotError::RequestFailed
+            })
+    }
+
+    fn eye_fovs(&self) -> [xr::Fovf; 2] {
+        let Some(system) = self.system.get() else {
+            // Close enough to most headsets.
+            const FOV: f32 = std::f32::consts::FRAC_PI_4;
+            return [xr::Fovf {
+                angle_left: -FOV,
+                angle_right: FOV,
+                angle_up: FOV,
+                angle_down: -FOV,
+            }; 2];
+        };
+        [vr::EVREye::Left, vr::EVREye::Right].map(|eye| system.eye_fov(eye))
+    }
+
+    pub fn get_next_event(&self, size: u32, out: *mut vr::VREvent_t) -> bool {
+        const FUNC: &str = "get_next_event";
+        if out.is_null() {
+            warn!("{FUNC}: Got null event pointer.");
+            return false;
+        }
+
+        let mut events = self.events.lock().unwrap();
+        if events.is_empty() {
+            return false;
+        }
+
+        const MIN_SCREENSHOT_EVENT_SIZE: usize = std::mem::offset_of!(vr::VREvent_t, data)
+            + std::mem::size_of::<vr::VREvent_Screenshot_t>();
+        if size < MIN_SCREENSHOT_EVENT_SIZE as u32 {
+            warn!("{FUNC}: Provided event struct size ({size}) is smaller than required ({MIN_SCREENSHOT_EVENT_SIZE}).");
+            return false;
+        }
+
+        let event = events.pop_front().unwrap();
+        // See Input::get_next_event - VREvent_t may be smaller than ours.
+        unsafe {
+            (&raw mut (*out).eventType).write(event.ty as u32);
+            (&raw mut (*out).trackedDeviceIndex).write(vr::k_unTrackedDeviceIndex_Hmd);
+            (&raw mut (*out).eventAgeSeconds).write(0.0);
+            (&raw mut (*out).data.screenshot).write(event.data);
+        }
+        true
+    }
+}
+
+/// Screenshot filenames are passed without an extension.
+fn screenshot_path(name: &CStr) -> PathBuf {
+    PathBuf::from(name.to_string_lossy().into_owned()).with_extension("png")
+}
+
+fn write_png(
+    name: &CStr,
+    width: u32,
+    height: u32,
+    pixels: &[u8],
+) -> Result<(), vr::EVRScreenshotError> {
+    let path = screenshot_path(name);
+    std::fs::write(&path, png::encode_rgba(width, height, pixels)).map_err(|e| {
+        warn!("Failed to write screenshot to {}: {e}", path.display());
+        vr::EVRScreenshotError::RequestFailed
+    })?;
+    info!("Wrote screenshot to {}", path.display());
+    Ok(())
+}
+
+/// An eye image with its rows starting at the top left, however it was submitted.
+fn upright(eye: EyeImage) -> (xr::Extent2Di, Vec<u8>) {
+    let EyeImage {
+        extent,
+        mut pixels,
+        bounds,
+        texture_type,
+    } = eye;
+    // GL reads start at the bottom row, and bounds can flip the image either way on top of that.
+    if (texture_type == vr::ETextureType::OpenGL) != bounds.vertically_flipped() {
+        pixels = flip_rows(&pixels, extent.width as usize);
+    }
+    if bounds.horizontally_flipped() {
+        for row in pixels.chunks_exact_mut(extent.width as usize * 4) {
+            row.reverse();
+            row.chunks_exact_mut(4).for_each(<[u8]>::reverse);
+        }
+    }
+    (extent, pixels)
+}
+
+/// Writes the preview, which is just the left eye, and the VR image holding both eyes.
+fn write_screenshot(
+    ty: vr::EVRScreenshotType,
+    eyes: &[(xr::Extent2Di, Vec<u8>); 2],
+    fovs: [xr::Fovf; 2],
+    preview_filename: &CStr,
+    vr_filename: &CStr,
+) -> Result<(), vr::EVRScreenshotError> {
+    let (left_extent, left) = &eyes[0];
+    let (width, height, stereo) = if ty == vr::EVRScreenshotType::StereoPanorama {
+        panorama::stereo_panorama(eyes, fovs)
+    } else {
+        side_by_side(eyes)
+    };
+    write_png(
+        preview_filename,
+        left_extent.width as u32,
+        left_extent.height as u32,
+        left,
+    )?;
+    write_png(vr_filename, width, height, &stereo)
+}
+
+/// Places the left and right eye images next to each other, padding the shorter one.
+fn side_by_side(
+    [(left_extent, left), (right_extent, right)]: &[(xr::Extent2Di, Vec<u8>); 2],
+) -> (u32, u32, Vec<u8>) {
+    let left_row = left_extent.width as usize * 4;
+    let right_row = right_extent.width as usize * 4;
+    let height = left_extent.height.max(right_extent.height) as usize;
+
+    let mut pixels = Vec::with_capacity((left_row + right_row) * height);
+    for y in 0..height {
+        for (row_len, eye) in [(left_row, left), (right_row, right)] {
+            match eye.get(y * row_len..(y + 1) * row_len) {
+                Some(row) => pixels.extend_from_slice(row),
+                None => pixels.resize(pixels.len() + row_len, 0),
+            }
+        }
+    }
+
+    let width = (left_extent.width + right_extent.width) as u32;
+    (width, height as u32, pixels)
 }
 
 impl vr::IVRScreenshots001_Interface for Screenshots {
     fn SubmitScreenshot(
         &self,
-        _: vr::ScreenshotHandle_t,
-        _: vr::EVRScreenshotType,
-        _: *const std::os::raw::c_char,
-        _: *const std::os::raw::c_char,
+        handle: vr::ScreenshotHandle_t,
+        ty: vr::EVRScreenshotType,
+        source_preview_filename: *const std::os::raw::c_char,
+        source_vr_filename: *const std::os::raw::c_char,
     ) -> vr::EVRScreenshotError {
-        vr::EVRScreenshotError::IncompatibleVersion
+        if source_preview_filename.is_null() {
+            return vr::EVRScreenshotError::RequestFailed;
+        }
+
+        // An invalid handle means the app took this screenshot on its own, so there's nowhere
+        // for it to go.
+        if handle == vr::k_unScreenshotHandleInvalid {
+            info!("App submitted unrequested screenshot, ignoring");
+            return vr::EVRScreenshotError::None;
+        }
+
+        let mut requests = self.requests.lock().unwrap();
+        let Some(request) = requests.get(&handle) else {
+            warn!("App submitted unknown screenshot handle {handle}");
+            return vr::EVRScreenshotError::NotFound;
+        };
+        if request.ty != ty {
+            warn!(
+                "App submitted {ty:?} screenshot for {:?} request {handle}",
+                request.ty
+            );
+            return vr::EVRScreenshotError::RequestFailed;
+        }
+
+        let sources = [
+            Some(unsafe { CStr::from_ptr(source_preview_filename) }),
+            (!source_vr_filename.is_null()).then(|| unsafe { CStr::from_ptr(source_vr_filename) }),
+        ];
+        let destinations = [
+            Some(&request.preview_filename),
+            request.vr_filename.as_ref(),
+        ];
+        for (source, destination) in sources.into_iter().zip(destinations) {
+            let (Some(source), Some(destination)) = (source, destination) else {
+                continue;
+            };
+            let source = PathBuf::from(source.to_string_lossy().into_owned());
+            let destination = screenshot_path(destination);
+            if source == destination {
+                continue;
+            }
+            if let Err(e) = std::fs::copy(&source, &destination) {
+                warn!(
+                    "Failed to copy screenshot from {} to {}: {e}",
+                    source.display(),
+                    destination.display()
+                );
+                return vr::EVRScreenshotError::RequestFailed;
+            }
+        }
+
+        requests.remove(&handle);
+        info!("Screenshot {handle} submitted");
+        vr::EVRScreenshotError::None
     }
     fn TakeStereoScreenshot(
         &self,
-        _: *mut vr::ScreenshotHandle_t,
-        _: *const std::os::raw::c_char,
-        _: *const std::os::raw::c_char,
+        handle: *mut vr::ScreenshotHandle_t,
+        preview_filename: *const std::os::raw::c_char,
+        vr_filename: *const std::os::raw::c_char,
     ) -> vr::EVRScreenshotError {
-        vr::EVRScreenshotError::IncompatibleVersion
+        if preview_filename.is_null() || vr_filename.is_null() {
+            return vr::EVRScreenshotError::RequestFailed;
+        }
+        let preview_filename = unsafe { CStr::from_ptr(preview_filename) };
+        let vr_filename = unsafe { CStr::from_ptr(vr_filename) };
+        if let Err(e) = self.capture(vr::EVRScreenshotType::Stereo, preview_filename, vr_filename) {
+            return e;
+        }
+
+        if !handle.is_null() {
+            unsafe { handle.write(self.new_handle()) };
+        }
+        vr::EVRScreenshotError::None
     }
     fn UpdateScreenshotProgress(
         &self,
@@ -34,16 +324,46 @@ impl vr::IVRScreenshots001_Interface for Screenshots {
     }
     fn GetScreenshotPropertyFilename(
         &self,
-        _: vr::ScreenshotHandle_t,
-        _: vr::EVRScreenshotPropertyFilenames,
-        _: *mut std::os::raw::c_char,
-        _: u32,
+        handle: vr::ScreenshotHandle_t,
+        filename_type: vr::EVRScreenshotPropertyFilenames,
+        filename: *mut std::os::raw::c_char,
+        size: u32,
         err: *mut vr::EVRScreenshotError,
     ) -> u32 {
-        if !err.is_null() {
-            unsafe { *err = vr::EVRScreenshotError::IncompatibleVersion };
+        let set_err = |e| {
+            if let Some(err) = unsafe { err.as_mut() } {
+                *err = e;
+            }
+        };
+
+        let requests = self.requests.lock().unwrap();
+        let Some(request) = requests.get(&handle) else {
+            set_err(vr::EVRScreenshotError::NotFound);
+            return 0;
+        };
+        let data = match filename_type {
+            vr::EVRScreenshotPropertyFilenames::Preview => Some(&request.preview_filename),
+            vr::EVRScreenshotPropertyFilenames::VR => request.vr_filename.as_ref(),
+        };
+        // Mono screenshots have no VR image.
+        let Some(data) = data else {
+            set_err(vr::EVRScreenshotError::NotFound);
+            return 0;
+        };
+
+        let data = data.to_bytes_with_nul();
+        set_err(vr::EVRScreenshotError::None);
+        // A null buffer is just a query for the required size.
+        if !filename.is_null() {
+            if (size as usize) < data.len() {
+                set_err(vr::EVRScreenshotError::BufferTooSmall);
+            } else {
+                let buf = unsafe { std::slice::from_raw_parts_mut(filename.cast(), data.len()) };
+                buf.copy_from_slice(data);
+            }
         }
-        0
+
+        data.len() as u32
     }
     fn GetScreenshotPropertyType(
         &self,
@@ -57,18 +377,497 @@ impl vr::IVRScreenshots001_Interface for Screenshots {
     }
     fn HookScreenshot(
         &self,
-        _: *const vr::EVRScreenshotType,
-        _: std::os::raw::c_int,
+        types: *const vr::EVRScreenshotType,
+        count: std::os::raw::c_int,
     ) -> vr::EVRScreenshotError {
+        let Ok(count) = usize::try_from(count) else {
+            return vr::EVRScreenshotError::RequestFailed;
+        };
+        let types: &[vr::EVRScreenshotType] = if count == 0 {
+            &[]
+        } else if types.is_null() {
+            return vr::EVRScreenshotError::RequestFailed;
+        } else {
+            unsafe { std::slice::from_raw_parts(types, count) }
+        };
+
+        debug!("App hooked screenshot types {types:?}");
+        *self.hooked_types.lock().unwrap() = types.to_vec();
         vr::EVRScreenshotError::None
     }
     fn RequestScreenshot(
         &self,
-        _: *mut vr::ScreenshotHandle_t,
-        _: vr::EVRScreenshotType,
-        _: *const std::os::raw::c_char,
-        _: *const std::os::raw::c_char,
+        handle: *mut vr::ScreenshotHandle_t,
+        ty: vr::EVRScreenshotType,
+        preview_filename: *const std::os::raw::c_char,
+        vr_filename: *const std::os::raw::c_char,
     ) -> vr::EVRScreenshotError {
-        vr::EVRScreenshotError::IncompatibleVersion
+        // The VR filename is ignored for mono screenshots, so it may be null.
+        if handle.is_null()
+            || preview_filename.is_null()
+            || (vr_filename.is_null() && ty != vr::EVRScreenshotType::Mono)
+        {
+            return vr::EVRScreenshotError::RequestFailed;
+        }
+
+        // Stereo screenshots always work - if the app doesn't want to take them itself,
+        // we capture them from the submitted eye textures.
+        if !self.hooked_types.lock().unwrap().contains(&ty) {
+            if !matches!(
+                ty,
+                vr::EVRScreenshotType::Stereo | vr::EVRScreenshotType::StereoPanorama
+            ) {
+                warn!("Can't take {ty:?} screenshot: app hasn't hooked it");
+                return vr::EVRScreenshotError::RequestFailed;
+            }
+            let preview_filename = unsafe { CStr::from_ptr(preview_filename) };
+            let vr_filename = unsafe { CStr::from_ptr(vr_filename) };
+            if let Err(e) = self.capture(ty, preview_filename, vr_filename) {
+                return e;
+            }
+            unsafe { handle.write(self.new_handle()) };
+            return vr::EVRScreenshotError::None;
+        }
+
+        let request = ScreenshotRequest {
+            ty,
+            preview_filename: unsafe { CStr::from_ptr(preview_filename) }.into(),
+            vr_filename: (!vr_filename.is_null() && ty != vr::EVRScreenshotType::Mono)
+                .then(|| unsafe { CStr::from_ptr(vr_filename) }.into()),
+        };
+
+        let new_handle = self.new_handle();
+        debug!("Requesting {ty:?} screenshot {new_handle}");
+        self.requests.lock().unwrap().insert(new_handle, request);
+        self.queue_event(vr::EVREventType::RequestScreenshot, new_handle, ty);
+        unsafe { handle.write(new_handle) };
+        vr::EVRScreenshotError::None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compositor::FakeGraphicsData;
+    use crate::graphics_backends::VulkanData;
+    use crate::openxr_data::OpenXrData;
+    use std::ffi::CString;
+    use std::path::Path;
+    use std::sync::Arc;
+    use vr::EVRScreenshotError::*;
+    use vr::{IVRCompositor028_Interface, IVRScreenshots001_Interface};
+
+    struct Fixture {
+        screenshots: Arc<Screenshots>,
+        comp: Arc<Compositor>,
+        xr: Arc<OpenXrData<Compositor>>,
+        vk: Arc<VulkanData>,
+        dir: PathBuf,
+    }
+
+    impl Fixture {
+        fn new(name: &str) -> Self {
+            let xr = Arc::new(OpenXrData::new(&Injector::default()).unwrap());
+            let vk = Arc::new(VulkanData::new_temporary(&xr.instance, xr.system_id));
+            let comp = Arc::new(Compositor::new(xr.clone(), &Injector::default()));
+            xr.compositor.set(Arc::downgrade(&comp));
+            let screenshots = Arc::new(Screenshots::new(&Injector::default()));
+            screenshots.compositor.set(Arc::downgrade(&comp));
+            crate::init_logging();
+
+            let dir = std::env::temp_dir()
+                .join(format!("xrizer_screenshots_{name}_{}", std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+
+            Self {
+                screenshots,
+                comp,
+                xr,
+                vk,
+                dir,
+            }
+        }
+
+        fn filename(&self, name: &str) -> CString {
+            CString::new(self.dir.join(name).to_str().unwrap()).unwrap()
+        }
+
+        fn submit_frame(&self, left: &vr::Texture_t, right: &vr::Texture_t) {
+            assert_eq!(
+                self.comp
+                    .WaitGetPoses(std::ptr::null_mut(), 0, std::ptr::null_mut(), 0),
+                vr::EVRCompositorError::None
+            );
+            for (eye, texture) in [(vr::EVREye::Left, left), (vr::EVREye::Right, right)] {
+                assert_eq!(
+                    self.comp
+                        .Submit(eye, texture, std::ptr::null(), vr::EVRSubmitFlags::Default),
+                    vr::EVRCompositorError::None
+                );
+            }
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    #[track_caller]
+    fn read_png(path: &Path) -> (u32, u32, Vec<u8>) {
+        png::decode_rgba(&std::fs::read(path).expect("screenshot should be written"))
+    }
+
+    #[test]
+    fn stereo_screenshot() {
+        let f = Fixture::new("stereo");
+        let preview = f.filename("preview");
+        let vr_image = f.filename("vr");
+
+        let mut handle = 0;
+        assert_eq!(
+            f.screenshots
+                .TakeStereoScreenshot(&mut handle, preview.as_ptr(), vr_image.as_ptr()),
+            RequestFailed
+        );
+
+        const LEFT: [u8; 4] = [0xff, 0, 0, 0xff];
+        const RIGHT: [u8; 4] = [0, 0, 0xff, 0xff];
+        let right_vk = Arc::new(VulkanData::new_temporary(&f.xr.instance, f.xr.system_id));
+        let left_texture = FakeGraphicsData::texture(&f.vk);
+        let right_texture = FakeGraphicsData::texture(&right_vk);
+        f.submit_frame(&left_texture, &right_texture);
+
+        assert_eq!(
+            f.screenshots
+                .TakeStereoScreenshot(&mut handle, preview.as_ptr(), vr_image.as_ptr()),
+            None
+        );
+        assert_ne!(handle, 0);
+        // one screenshot at a time
+        assert_eq!(
+            f.screenshots
+                .TakeStereoScreenshot(&mut handle, preview.as_ptr(), vr_image.as_ptr()),
+            RequestFailed
+        );
+        assert!(!f.dir.join("vr.png").exists());
+
+        // the screenshot is of the next frame
+        FakeGraphicsData::set_texture_color(&left_texture, LEFT);
+        FakeGraphicsData::set_texture_color(&right_texture, RIGHT);
+        f.submit_frame(&left_texture, &right_texture);
+
+        let (width, height, pixels) = read_png(&f.dir.join("preview.png"));
+        assert!(width > 0 && height > 0);
+        assert!(pixels.chunks_exact(4).all(|p| p == LEFT));
+
+        let (stereo_width, stereo_height, pixels) = read_png(&f.dir.join("vr.png"));
+        assert_eq!((stereo_width, stereo_height), (width * 2, height));
+        for row in pixels.chunks_exact(stereo_width as usize * 4) {
+            let (left, right) = row.split_at(width as usize * 4);
+            assert!(left.chunks_exact(4).all(|p| p == LEFT));
+            assert!(right.chunks_exact(4).all(|p| p == RIGHT));
+        }
+    }
+
+    #[test]
+    fn flipped_bounds_screenshot() {
+        let f = Fixture::new("flipped");
+        let preview = f.filename("preview");
+        let vr_image = f.filename("vr");
+
+        let texture = FakeGraphicsData::texture(&f.vk);
+        f.submit_frame(&texture, &texture);
+        let mut handle = 0;
+        assert_eq!(
+            f.screenshots
+                .TakeStereoScreenshot(&mut handle, preview.as_ptr(), vr_image.as_ptr()),
+            None
+        );
+
+        // The left eye is mirrored and the right one upside down, so the first pixel read back
+        // from each ends up in the top right and bottom left of them.
+        const FIRST: [u8; 4] = [0, 0xff, 0, 0xff];
+        FakeGraphicsData::set_first_pixel_color(FIRST);
+        let bounds = [
+            vr::VRTextureBounds_t {
+                uMin: 1.0,
+                vMin: 0.0,
+                uMax: 0.0,
+                vMax: 1.0,
+            },
+            vr::VRTextureBounds_t {
+                uMin: 0.0,
+                vMin: 1.0,
+                uMax: 1.0,
+                vMax: 0.0,
+            },
+        ];
+        assert_eq!(
+            f.comp
+                .WaitGetPoses(std::ptr::null_mut(), 0, std::ptr::null_mut(), 0),
+            vr::EVRCompositorError::None
+        );
+        for (eye, bounds) in [vr::EVREye::Left, vr::EVREye::Right]
+            .into_iter()
+            .zip(&bounds)
+        {
+            assert_eq!(
+                f.comp
+                    .Submit(eye, &texture, bounds, vr::EVRSubmitFlags::Default),
+                vr::EVRCompositorError::None
+            );
+        }
+
+        let (width, height, pixels) = read_png(&f.dir.join("vr.png"));
+        let eye_width = width / 2;
+        let marked: Vec<_> = pixels
+            .chunks_exact(4)
+            .enumerate()
+            .filter(|(_, pixel)| *pixel == FIRST)
+            .map(|(i, _)| (i as u32 % width, i as u32 / width))
+            .collect();
+        assert_eq!(marked, [(eye_width - 1, 0), (eye_width, height - 1)]);
+
+        let (_, _, preview_pixels) = read_png(&f.dir.join("preview.png"));
+        let first_row = &preview_pixels[..eye_width as usize * 4];
+        assert_eq!(first_row.chunks_exact(4).last().unwrap(), FIRST);
+    }
+
+    #[test]
+    fn request_then_submit() {
+        let f = Fixture::new("request");
+        let preview = f.filename("preview");
+        let vr_image = f.filename("vr");
+        let hooked = [vr::EVRScreenshotType::Stereo];
+        assert_eq!(f.screenshots.HookScreenshot(hooked.as_ptr(), 1), None);
+
+        let mut handle = 0;
+        assert_eq!(
+            f.screenshots.RequestScreenshot(
+                &mut handle,
+                vr::EVRScreenshotType::Stereo,
+                preview.as_ptr(),
+                vr_image.as_ptr()
+            ),
+            None
+        );
+        assert_ne!(handle, 0);
+
+        let mut event = vr::VREvent_t::default();
+        let size = std::mem::size_of_val(&event) as u32;
+        assert!(f.screenshots.get_next_event(size, &mut event));
+        assert_eq!(event.eventType, vr::EVREventType::RequestScreenshot as u32);
+        let data = unsafe { event.data.screenshot };
+        assert_eq!(data.handle, handle);
+        assert_eq!(data.type_, vr::EVRScreenshotType::Stereo as u32);
+        assert!(!f.screenshots.get_next_event(size, &mut event));
+
+        // the app renders its screenshot somewhere else
+        let source_preview = f.dir.join("source_preview.png");
+        let source_vr = f.dir.join("source_vr.png");
+        std::fs::write(&source_preview, b"preview").unwrap();
+        std::fs::write(&source_vr, b"vr").unwrap();
+        let source_preview = CString::new(source_preview.to_str().unwrap()).unwrap();
+        let source_vr = CString::new(source_vr.to_str().unwrap()).unwrap();
+
+        let submit = |handle, ty| {
+            f.screenshots
+                .SubmitScreenshot(handle, ty, source_preview.as_ptr(), source_vr.as_ptr())
+        };
+        assert_eq!(submit(handle + 1, vr::EVRScreenshotType::Stereo), NotFound);
+        assert_eq!(
+            submit(handle, vr::EVRScreenshotType::Cubemap),
+            RequestFailed
+        );
+        assert_eq!(submit(handle, vr::EVRScreenshotType::Stereo), None);
+
+        assert_eq!(
+            std::fs::read(f.dir.join("preview.png")).unwrap(),
+            b"preview"
+        );
+        assert_eq!(std::fs::read(f.dir.join("vr.png")).unwrap(), b"vr");
+
+        // requests can only be submitted once
+        assert_eq!(submit(handle, vr::EVRScreenshotType::Stereo), NotFound);
+    }
+
+    #[test]
+    fn hooked_screenshot_routing() {
+        let f = Fixture::new("hooked");
+        let preview = f.filename("preview");
+        let vr_image = f.filename("vr");
+
+        assert_eq!(
+            f.screenshots.HookScreenshot(std::ptr::null(), 2),
+            RequestFailed
+        );
+        assert_eq!(
+            f.screenshots.HookScreenshot(std::ptr::null(), -1),
+            RequestFailed
+        );
+
+        let hooked = [vr::EVRScreenshotType::Mono, vr::EVRScreenshotType::Cubemap];
+        assert_eq!(
+            f.screenshots
+                .HookScreenshot(hooked.as_ptr(), hooked.len() as i32),
+            None
+        );
+
+        let mut event = vr::VREvent_t::default();
+        let size = std::mem::size_of_val(&event) as u32;
+        let request = |ty| {
+            let mut handle = 0;
+            let err = f.screenshots.RequestScreenshot(
+                &mut handle,
+                ty,
+                preview.as_ptr(),
+                vr_image.as_ptr(),
+            );
+            (err, handle)
+        };
+
+        // hooked types go to the app
+        for ty in hooked {
+            let (err, handle) = request(ty);
+            assert_eq!(err, None);
+            assert!(f.screenshots.get_next_event(size, &mut event));
+            assert_eq!(event.eventType, vr::EVREventType::RequestScreenshot as u32);
+            let data = unsafe { event.data.screenshot };
+            assert_eq!((data.handle, data.type_), (handle, ty as u32));
+        }
+
+        // unhooked stereo screenshots are captured directly, which fails without any frames
+        let (err, _) = request(vr::EVRScreenshotType::Stereo);
+        assert_eq!(err, RequestFailed);
+        assert!(!f.screenshots.get_next_event(size, &mut event));
+
+        // other unhooked types aren't supported
+        let (err, _) = request(vr::EVRScreenshotType::MonoPanorama);
+        assert_eq!(err, RequestFailed);
+        assert!(!f.screenshots.get_next_event(size, &mut event));
+    }
+
+    #[test]
+    fn screenshot_filenames() {
+        let f = Fixture::new("filenames");
+        let preview = f.filename("preview");
+        let vr_image = f.filename("vr");
+        let hooked = [vr::EVRScreenshotType::Stereo, vr::EVRScreenshotType::Mono];
+        assert_eq!(
+            f.screenshots
+                .HookScreenshot(hooked.as_ptr(), hooked.len() as i32),
+            None
+        );
+
+        let mut handle = 0;
+        assert_eq!(
+            f.screenshots.RequestScreenshot(
+                &mut handle,
+                vr::EVRScreenshotType::Stereo,
+                preview.as_ptr(),
+                vr_image.as_ptr()
+            ),
+            None
+        );
+
+        let get = |handle, ty, buf: &mut [std::os::raw::c_char]| {
+            let mut err = IncompatibleVersion;
+            let ptr = if buf.is_empty() {
+                std::ptr::null_mut()
+            } else {
+                buf.as_mut_ptr()
+            };
+            let len = f.screenshots.GetScreenshotPropertyFilename(
+                handle,
+                ty,
+                ptr,
+                buf.len() as u32,
+                &mut err,
+            );
+            (len, err)
+        };
+
+        for (ty, expected) in [
+            (vr::EVRScreenshotPropertyFilenames::Preview, &preview),
+            (vr::EVRScreenshotPropertyFilenames::VR, &vr_image),
+        ] {
+            let len = expected.as_bytes_with_nul().len() as u32;
+            assert_eq!(get(handle, ty, &mut []), (len, None));
+            assert_eq!(get(handle, ty, &mut [0; 2]), (len, BufferTooSmall));
+
+            let mut buf = vec![0; len as usize];
+            assert_eq!(get(handle, ty, &mut buf), (len, None));
+            let name = unsafe { CStr::from_ptr(buf.as_ptr()) };
+            assert_eq!(name, expected.as_c_str());
+        }
+
+        assert_eq!(
+            get(
+                handle + 1,
+                vr::EVRScreenshotPropertyFilenames::Preview,
+                &mut []
+            ),
+            (0, NotFound)
+        );
+
+        // mono screenshots don't have a VR filename
+        assert_eq!(
+            f.screenshots.RequestScreenshot(
+                &mut handle,
+                vr::EVRScreenshotType::Mono,
+                preview.as_ptr(),
+                std::ptr::null()
+            ),
+            None
+        );
+        assert_eq!(
+            get(handle, vr::EVRScreenshotPropertyFilenames::VR, &mut []),
+            (0, NotFound)
+        );
+    }
+
+    #[test]
+    fn stereo_panorama_screenshot() {
+        let f = Fixture::new("panorama");
+        let preview = f.filename("preview");
+        let vr_image = f.filename("vr");
+
+        const LEFT: [u8; 4] = [0xff, 0, 0, 0xff];
+        const RIGHT: [u8; 4] = [0, 0, 0xff, 0xff];
+        let right_vk = Arc::new(VulkanData::new_temporary(&f.xr.instance, f.xr.system_id));
+        let left_texture = FakeGraphicsData::texture(&f.vk);
+        let right_texture = FakeGraphicsData::texture(&right_vk);
+        FakeGraphicsData::set_texture_color(&left_texture, LEFT);
+        FakeGraphicsData::set_texture_color(&right_texture, RIGHT);
+        f.submit_frame(&left_texture, &right_texture);
+
+        let mut handle = 0;
+        assert_eq!(
+            f.screenshots.RequestScreenshot(
+                &mut handle,
+                vr::EVRScreenshotType::StereoPanorama,
+                preview.as_ptr(),
+                vr_image.as_ptr()
+            ),
+            None
+        );
+        f.submit_frame(&left_texture, &right_texture);
+
+        let (width, height, pixels) = read_png(&f.dir.join("vr.png"));
+        // two 2:1 equirects stacked on top of each other
+        assert_eq!(width, height);
+        let pixel = |x: u32, y: u32| {
+            let idx = (y * width + x) as usize * 4;
+            &pixels[idx..idx + 4]
+        };
+
+        // straight ahead is the center of each band, and straight behind is the edge
+        let band = height / 2;
+        assert_eq!(pixel(width / 2, band / 2), LEFT);
+        assert_eq!(pixel(width / 2, band + band / 2), RIGHT);
+        assert_eq!(pixel(0, band / 2), [0, 0, 0, 0xff]);
+        assert_eq!(pixel(0, band + band / 2), [0, 0, 0, 0xff]);
     }
 }
@@ -0,0 +1,80 @@
+//! Approximates stereo panoramas from a single frame. We can't re-render the scene in every
+//! direction, so each eye's image is projected into its part of an equirect panorama and the
+//! rest of the sphere is left black.
+
+use openxr as xr;
+use std::f32::consts::{PI, TAU};
+
+const BLACK: [u8; 4] = [0, 0, 0, 0xff];
+/// Keeps the panorama a sane size for very high resolution eye textures.
+const MAX_WIDTH: u32 = 8192;
+
+/// Builds an over/under stereo panorama with the left eye on top, as OpenVR expects.
+/// Each eye is a 2:1 equirect, so the resulting image is square.
+pub fn stereo_panorama(
+    eyes: &[(xr::Extent2Di, Vec<u8>); 2],
+    fovs: [xr::Fovf; 2],
+) -> (u32, u32, Vec<u8>) {
+    // Keep the horizontal resolution the eye textures were rendered at.
+    let pixels_per_radian = eyes
+        .iter()
+        .zip(&fovs)
+        .map(|((extent, _), fov)| extent.width as f32 / (fov.angle_right - fov.angle_left))
+        .fold(0.0, f32::max);
+    let width = ((pixels_per_radian * TAU) as u32).clamp(2, MAX_WIDTH) & !1;
+    let height = width / 2;
+
+    let mut pixels = Vec::with_capacity(width as usize * height as usize * 2 * 4);
+    for (eye, fov) in eyes.iter().zip(fovs) {
+        project_eye(eye, fov, width, height, &mut pixels);
+    }
+    (width, height * 2, pixels)
+}
+
+fn project_eye(
+    (extent, eye): &(xr::Extent2Di, Vec<u8>),
+    fov: xr::Fovf,
+    width: u32,
+    height: u32,
+    out: &mut Vec<u8>,
+) {
+    let [left, right, up, down] = [
+        fov.angle_left,
+        fov.angle_right,
+        fov.angle_up,
+        fov.angle_down,
+    ]
+    .map(f32::tan);
+    let (eye_width, eye_height) = (extent.width as usize, extent.height as usize);
+
+    for y in 0..height {
+        // latitude, from straight up to straight down
+        let lat = PI / 2.0 - (y as f32 + 0.5) / height as f32 * PI;
+        for x in 0..width {
+            // longitude, with 0 being straight ahead (-Z)
+            let lon = (x as f32 + 0.5) / width as f32 * TAU - PI;
+            let dir = [lat.cos() * lon.sin(), lat.sin(), -lat.cos() * lon.cos()];
+
+            let pixel = (dir[2] < 0.0)
+                .then(|| {
+                    let u = (dir[0] / -dir[2] - left) / (right - left);
+                    // image rows go from top to bottom
+                    let v = (up - dir[1] / -dir[2]) / (up - down);
+                    sample(eye, eye_width, eye_height, u, v)
+                })
+                .flatten()
+                .unwrap_or(&BLACK);
+            out.extend_from_slice(pixel);
+        }
+    }
+}
+
+fn sample(pixels: &[u8], width: usize, height: usize, u: f32, v: f32) -> Option<&[u8]> {
+    if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+        return None;
+    }
+    let x = (u * width as f32) as usize;
+    let y = (v * height as f32) as usize;
+    let idx = (y * width + x) * 4;
+    pixels.get(idx..idx + 4)
+}
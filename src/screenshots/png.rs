@@ -0,0 +1,146 @@
+//! A minimal PNG encoder. Screenshots are written rarely enough that we don't bother compressing
+//! them - the image data is stored in uncompressed deflate blocks.
+
+/// Encodes tightly packed RGBA8 pixels as a PNG.
+pub fn encode_rgba(width: u32, height: u32, pixels: &[u8]) -> Vec<u8> {
+    assert_eq!(pixels.len(), width as usize * height as usize * 4);
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+
+    let mut header = Vec::with_capacity(13);
+    header.extend(width.to_be_bytes());
+    header.extend(height.to_be_bytes());
+    // 8 bits per channel, RGBA, default compression/filter, no interlacing
+    header.extend([8, 6, 0, 0, 0]);
+    write_chunk(&mut png, b"IHDR", &header);
+
+    let mut scanlines = Vec::with_capacity(pixels.len() + height as usize);
+    for row in pixels.chunks_exact(width as usize * 4) {
+        // filter type None
+        scanlines.push(0);
+        scanlines.extend_from_slice(row);
+    }
+    write_chunk(&mut png, b"IDAT", &zlib_stored(&scanlines));
+    write_chunk(&mut png, b"IEND", &[]);
+
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, ty: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend(ty);
+    png.extend(data);
+    let crc = crc32(&png[start..]);
+    png.extend(crc.to_be_bytes());
+}
+
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = u16::MAX as usize;
+
+    // deflate, 32K window, no preset dictionary, fastest compression
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        // an empty stream still needs a final block
+        out.extend([1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        out.push(last as u8);
+        out.extend(len.to_le_bytes());
+        out.extend((!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = u32::MAX;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb88320 & mask);
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + *byte as u32) % MOD;
+        b = (b + a) % MOD;
+    }
+    (b << 16) | a
+}
+
+/// Decodes a PNG produced by [`encode_rgba`], returning its dimensions and RGBA8 pixels.
+#[cfg(test)]
+pub fn decode_rgba(png: &[u8]) -> (u32, u32, Vec<u8>) {
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    let mut rest = &png[8..];
+    let (mut width, mut height, mut zlib) = (0, 0, Vec::new());
+    while !rest.is_empty() {
+        let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+        let (ty, data) = (&rest[4..8], &rest[8..8 + len]);
+        let crc = u32::from_be_bytes(rest[8 + len..12 + len].try_into().unwrap());
+        assert_eq!(crc32(&rest[4..8 + len]), crc, "bad crc for {ty:?}");
+        match ty {
+            b"IHDR" => {
+                width = u32::from_be_bytes(data[..4].try_into().unwrap());
+                height = u32::from_be_bytes(data[4..8].try_into().unwrap());
+                assert_eq!(&data[8..], [8, 6, 0, 0, 0]);
+            }
+            b"IDAT" => zlib.extend_from_slice(data),
+            _ => {}
+        }
+        rest = &rest[12 + len..];
+    }
+
+    let mut scanlines = Vec::new();
+    let mut blocks = &zlib[2..zlib.len() - 4];
+    while !blocks.is_empty() {
+        let len = u16::from_le_bytes([blocks[1], blocks[2]]) as usize;
+        scanlines.extend_from_slice(&blocks[5..5 + len]);
+        blocks = &blocks[5 + len..];
+    }
+    assert_eq!(
+        u32::from_be_bytes(zlib[zlib.len() - 4..].try_into().unwrap()),
+        adler32(&scanlines)
+    );
+
+    let pixels = scanlines
+        .chunks_exact(width as usize * 4 + 1)
+        .flat_map(|row| {
+            assert_eq!(row[0], 0, "unexpected filter type");
+            &row[1..]
+        })
+        .copied()
+        .collect();
+    (width, height, pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"IEND"), 0xae426082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+    }
+
+    #[test]
+    fn round_trip() {
+        // big enough to need multiple deflate blocks
+        let (width, height) = (200, 100);
+        let pixels: Vec<u8> = (0..width * height * 4).map(|i| i as u8).collect();
+        let png = encode_rgba(width, height, &pixels);
+        assert_eq!(decode_rgba(&png), (width, height, pixels));
+    }
+}
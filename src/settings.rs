@@ -1,14 +1,68 @@
+use crate::{
+    clientcore::{Injected, Injector},
+    openxr_data::RealOpenXrData,
+    overlay::{ContentFit, CurvatureMode, EquirectCoverage, OverlayMan},
+};
 use log::debug;
 use openvr as vr;
-use openvr::EVRSettingsError;
-use std::ffi::CStr;
+use openvr::{EVRSettingsError, IVROverlay027_Interface};
+use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
+use std::sync::Arc;
 
-#[derive(Default, macros::InterfaceImpl)]
+#[derive(macros::InterfaceImpl)]
 #[interface = "IVRSettings"]
 #[versions(003, 002)]
 pub struct Settings {
     vtables: Vtables,
+    openxr: Arc<RealOpenXrData>,
+    pub(crate) overlays: Injected<OverlayMan>,
+}
+
+impl Settings {
+    pub fn new(openxr: Arc<RealOpenXrData>, injector: &Injector) -> Self {
+        Self {
+            vtables: Default::default(),
+            openxr,
+            overlays: injector.inject(),
+        }
+    }
+
+    /// Applies a setting to the overlay with the given key.
+    fn set_overlay_setting(
+        &self,
+        overlay_key: &str,
+        set: impl FnOnce(&OverlayMan, vr::VROverlayHandle_t) -> vr::EVROverlayError,
+    ) -> EVRSettingsError {
+        let Some(overlays) = self.overlays.get() else {
+            return EVRSettingsError::WriteFailed;
+        };
+        let Ok(overlay_key) = CString::new(overlay_key) else {
+            return EVRSettingsError::WriteFailed;
+        };
+        let mut handle = 0;
+        let mut err = overlays.FindOverlay(overlay_key.as_ptr(), &mut handle);
+        if err == vr::EVROverlayError::None {
+            err = set(&overlays, handle);
+        }
+        if err != vr::EVROverlayError::None {
+            debug!("Failed to set setting on overlay {overlay_key:?}: {err:?}");
+            return EVRSettingsError::WriteFailed;
+        }
+        EVRSettingsError::None
+    }
+}
+
+/// Settings of a single overlay, which OpenVR has no calls for, are kept in a section named with
+/// this prefix followed by the overlay's key.
+const OVERLAY_SECTION_PREFIX: &str = "xrizer.overlay.";
+/// Settings of the skybox set through IVRCompositor::SetSkyboxOverride
+const SKYBOX_SECTION: &str = "xrizer.skybox";
+
+/// k_pch_SteamVR_Section/k_pch_SteamVR_PreferredRefreshRate, which we route to
+/// XR_FB_display_refresh_rate.
+fn is_refresh_rate_setting(section: &str, key: &str) -> bool {
+    section == "steamvr" && key == "preferredRefreshRate"
 }
 
 impl vr::IVRSettings003_Interface for Settings {
@@ -37,8 +91,21 @@ impl vr::IVRSettings003_Interface for Settings {
         let section = unsafe { CStr::from_ptr(section) }.to_string_lossy();
         let key = unsafe { CStr::from_ptr(settings_key) }.to_string_lossy();
         debug!("Setting bool on {section}/{key} to {value}");
+        let mut result = EVRSettingsError::None;
+        if let Some(overlay_key) = section.strip_prefix(OVERLAY_SECTION_PREFIX) {
+            if key == "letterbox" {
+                let fit = if value {
+                    ContentFit::Letterbox
+                } else {
+                    ContentFit::Stretch
+                };
+                result = self.set_overlay_setting(overlay_key, |overlays, handle| {
+                    overlays.set_overlay_content_fit(handle, fit)
+                });
+            }
+        }
         if let Some(error) = unsafe { error.as_mut() } {
-            *error = EVRSettingsError::None;
+            *error = result;
         }
     }
 
@@ -52,8 +119,21 @@ impl vr::IVRSettings003_Interface for Settings {
         let section = unsafe { CStr::from_ptr(section) }.to_string_lossy();
         let key = unsafe { CStr::from_ptr(settings_key) }.to_string_lossy();
         debug!("Setting int on {section}/{key} to {value}");
+        let mut result = EVRSettingsError::None;
+        if is_refresh_rate_setting(&section, &key) {
+            self.openxr.request_display_refresh_rate(value as f32);
+        } else if let Some(overlay_key) = section.strip_prefix(OVERLAY_SECTION_PREFIX) {
+            if key == "textureLayer" {
+                result = match u32::try_from(value) {
+                    Ok(layer) => self.set_overlay_setting(overlay_key, |overlays, handle| {
+                        overlays.set_overlay_texture_layer(handle, layer)
+                    }),
+                    Err(_) => EVRSettingsError::WriteFailed,
+                };
+            }
+        }
         if let Some(error) = unsafe { error.as_mut() } {
-            *error = EVRSettingsError::None;
+            *error = result;
         }
     }
 
@@ -67,6 +147,9 @@ impl vr::IVRSettings003_Interface for Settings {
         let section = unsafe { CStr::from_ptr(section) }.to_string_lossy();
         let key = unsafe { CStr::from_ptr(settings_key) }.to_string_lossy();
         debug!("Setting float on {section}/{key} to {value}");
+        if is_refresh_rate_setting(&section, &key) {
+            self.openxr.request_display_refresh_rate(value);
+        }
         if let Some(error) = unsafe { error.as_mut() } {
             *error = EVRSettingsError::None;
         }
@@ -83,8 +166,32 @@ impl vr::IVRSettings003_Interface for Settings {
         let key = unsafe { CStr::from_ptr(settings_key) }.to_string_lossy();
         let value = unsafe { CStr::from_ptr(value) }.to_string_lossy();
         debug!("Setting string on {section}/{key} to {value}");
+        let mut result = EVRSettingsError::None;
+        if let Some(overlay_key) = section.strip_prefix(OVERLAY_SECTION_PREFIX) {
+            if key == "curvatureMode" {
+                let mode = match value.as_ref() {
+                    "constant_angle" => Some(CurvatureMode::ConstantAngle),
+                    "constant_radius" => Some(CurvatureMode::ConstantRadius),
+                    _ => None,
+                };
+                result = match mode {
+                    Some(mode) => self.set_overlay_setting(overlay_key, |overlays, handle| {
+                        overlays.set_overlay_curvature_mode(handle, mode)
+                    }),
+                    None => EVRSettingsError::WriteFailed,
+                };
+            }
+        } else if section == SKYBOX_SECTION && key == "coverage" {
+            result = match (EquirectCoverage::parse(&value), self.overlays.get()) {
+                (Some(coverage), Some(overlays)) => {
+                    overlays.set_skybox_coverage(coverage);
+                    EVRSettingsError::None
+                }
+                _ => EVRSettingsError::WriteFailed,
+            };
+        }
         if let Some(error) = unsafe { error.as_mut() } {
-            *error = EVRSettingsError::None;
+            *error = result;
         }
     }
 
@@ -115,6 +222,9 @@ impl vr::IVRSettings003_Interface for Settings {
             *error = EVRSettingsError::None;
         }
         debug!("Getting int on {section}/{key}");
+        if is_refresh_rate_setting(&section, &key) {
+            return self.openxr.display_refresh_rate().unwrap_or(0.0).round() as i32;
+        }
         0
     }
 
@@ -130,6 +240,9 @@ impl vr::IVRSettings003_Interface for Settings {
             *error = EVRSettingsError::None;
         }
         debug!("Getting float on {section}/{key}");
+        if is_refresh_rate_setting(&section, &key) {
+            return self.openxr.display_refresh_rate().unwrap_or(0.0);
+        }
         0.0
     }
 
@@ -2,6 +2,8 @@ use crate::{
     clientcore::{Injected, Injector},
     input::Input,
     openxr_data::{Hand, RealOpenXrData, SessionData},
+    overlay::OverlayMan,
+    screenshots::Screenshots,
     tracy_span,
 };
 use glam::{Mat3, Quat, Vec3};
@@ -73,11 +75,52 @@ impl ViewCache {
 pub struct System {
     openxr: Arc<RealOpenXrData>, // We don't need to test session restarting.
     input: Injected<Input<crate::compositor::Compositor>>,
+    screenshots: Injected<Screenshots>,
+    overlays: Injected<OverlayMan>,
     vtables: Vtables,
     last_connected_hands: ConnectedHands,
     views: Mutex<ViewCache>,
 }
 
+/// Converts an OpenXR visibility mask into the flat vertex list OpenVR expects (a triangle list,
+/// or the loop itself for line loops), with coordinates scaled by the raw projection
+/// (`[left, right, top, bottom]`).
+fn hidden_area_vertices(
+    vertices: &[xr::Vector2f],
+    indices: &[u32],
+    [left, right, top, bottom]: [f32; 4],
+    triangles: bool,
+) -> Vec<vr::HmdVector2_t> {
+    let vertex = |i: u32| {
+        let Some(v) = vertices.get(i as usize) else {
+            warn!(
+                "visibility mask index {i} out of range ({})",
+                vertices.len()
+            );
+            return None;
+        };
+
+        // It is unclear to me why this scaling is necessary, but OpenComposite does it and
+        // it seems to get games to use the mask correctly.
+        let x_scaled = (v.x - left) / (right - left);
+        let y_scaled = (v.y - top) / (bottom - top);
+        Some(vr::HmdVector2_t {
+            v: [x_scaled, y_scaled],
+        })
+    };
+    // A bad index drops its whole triangle, so the triangles after it stay intact.
+    indices
+        .chunks_exact(if triangles { 3 } else { 1 })
+        .filter_map(|primitive| {
+            primitive
+                .iter()
+                .map(|&i| vertex(i))
+                .collect::<Option<Vec<_>>>()
+        })
+        .flatten()
+        .collect()
+}
+
 mod log_tags {
     pub const TRACKED_PROP: &str = "tracked_property";
 }
@@ -87,6 +130,8 @@ impl System {
         Self {
             openxr,
             input: injector.inject(),
+            screenshots: injector.inject(),
+            overlays: injector.inject(),
             vtables: Default::default(),
             last_connected_hands: Default::default(),
             views: Mutex::default(),
@@ -112,6 +157,15 @@ impl System {
         let mut views = self.views.lock().unwrap();
         views.get_views(&session, self.openxr.display_time.get(), ty)
     }
+
+    pub fn eye_fov(&self, eye: vr::EVREye) -> xr::Fovf {
+        let ty = self
+            .openxr
+            .session_data
+            .get()
+            .current_origin_as_reference_space();
+        self.get_views(ty).views[eye as usize].fov
+    }
 }
 
 impl vr::IVRSystem022_Interface for System {
@@ -161,19 +215,14 @@ impl vr::IVRSystem022_Interface for System {
         top: *mut f32,
         bottom: *mut f32,
     ) {
-        let ty = self
-            .openxr
-            .session_data
-            .get()
-            .current_origin_as_reference_space();
-        let view = self.get_views(ty).views[eye as usize];
+        let fov = self.eye_fov(eye);
 
         // Top and bottom are flipped, for some reason
         unsafe {
-            *left = view.fov.angle_left.tan();
-            *right = view.fov.angle_right.tan();
-            *bottom = view.fov.angle_up.tan();
-            *top = view.fov.angle_down.tan();
+            *left = fov.angle_left.tan();
+            *right = fov.angle_right.tan();
+            *bottom = fov.angle_up.tan();
+            *top = fov.angle_down.tan();
         }
     }
     fn ComputeDistortion(
@@ -303,39 +352,36 @@ impl vr::IVRSystem022_Interface for System {
         };
 
         let session_data = self.openxr.session_data.get();
-        let mask = session_data
-            .session
-            .get_visibility_mask_khr(
-                xr::ViewConfigurationType::PRIMARY_STEREO,
-                eye as u32,
-                mask_ty,
-            )
-            .unwrap();
+        let mask = match session_data.session.get_visibility_mask_khr(
+            xr::ViewConfigurationType::PRIMARY_STEREO,
+            eye as u32,
+            mask_ty,
+        ) {
+            Ok(mask) => mask,
+            Err(e) => {
+                warn!("Failed to get visibility mask for {eye:?}: {e}");
+                return Default::default();
+            }
+        };
 
         trace!("openxr mask: {:#?} {:#?}", mask.indices, mask.vertices);
 
-        let [mut left, mut right, mut top, mut bottom] = [0.0; 4];
-        self.GetProjectionRaw(eye, &mut left, &mut right, &mut top, &mut bottom);
-
-        // convert from indices + vertices to just vertices
-        let vertices: Vec<_> = mask
-            .indices
-            .into_iter()
-            .map(|i| {
-                let v = mask.vertices[i as usize];
-
-                // It is unclear to me why this scaling is necessary, but OpenComposite does it and
-                // it seems to get games to use the mask correctly.
-                let x_scaled = (v.x - left) / (right - left);
-                let y_scaled = (v.y - top) / (bottom - top);
-                vr::HmdVector2_t {
-                    v: [x_scaled, y_scaled],
-                }
-            })
-            .collect();
+        let mut fov = [0.0; 4];
+        let [left, right, top, bottom] = &mut fov;
+        self.GetProjectionRaw(eye, left, right, top, bottom);
 
+        let vertices = hidden_area_vertices(
+            &mask.vertices,
+            &mask.indices,
+            fov,
+            ty != vr::EHiddenAreaMeshType::LineLoop,
+        );
         trace!("vertices: {vertices:#?}");
-        let count = vertices.len() / 3;
+        // For line loops, unTriangleCount is actually the vertex count.
+        let count = match ty {
+            vr::EHiddenAreaMeshType::LineLoop => vertices.len(),
+            _ => vertices.len() / 3,
+        };
         // XXX: what are we supposed to do here? pVertexData is a random pointer and there's no
         // clear way for the application to deallocate it
         // fortunately it seems like applications don't call this often, so this leakage isn't a
@@ -403,7 +449,7 @@ impl vr::IVRSystem022_Interface for System {
             }
         }
 
-        self.input.get().is_some_and(|input| {
+        let got_input_event = self.input.get().is_some_and(|input| {
             let got_event = input.get_next_event(size, event);
             if got_event && !pose.is_null() {
                 unsafe {
@@ -416,7 +462,17 @@ impl vr::IVRSystem022_Interface for System {
                 }
             }
             got_event
-        })
+        });
+
+        got_input_event
+            || self
+                .screenshots
+                .get()
+                .is_some_and(|screenshots| screenshots.get_next_event(size, event))
+            || self
+                .overlays
+                .get()
+                .is_some_and(|overlays| overlays.get_next_event(size, event))
     }
 
     fn PollNextEvent(&self, event: *mut vr::VREvent_t, size: u32) -> bool {
@@ -606,7 +662,9 @@ impl vr::IVRSystem022_Interface for System {
                 let views = self.get_views(xr::ReferenceSpaceType::VIEW).views;
                 views[1].pose.position.x - views[0].pose.position.x
             }
-            vr::ETrackedDeviceProperty::DisplayFrequency_Float => 90.0,
+            vr::ETrackedDeviceProperty::DisplayFrequency_Float => {
+                self.openxr.display_refresh_rate().unwrap_or(90.0)
+            }
             _ => {
                 if let Some(error) = unsafe { error.as_mut() } {
                     *error = vr::ETrackedPropertyError::UnknownProperty;
@@ -780,6 +838,8 @@ impl vr::IVRSystem021On022 for System {
     fn ResetSeatedZeroPose(&self) {
         self.openxr
             .reset_tracking_space(vr::ETrackingUniverseOrigin::Seated);
+        // The cached views were located in the old seated space.
+        self.reset_views();
     }
 }
 
@@ -976,4 +1036,42 @@ mod tests {
         test_prop(vr::ETrackedDeviceProperty::ManufacturerName_String);
         test_prop(vr::ETrackedDeviceProperty::ControllerType_String);
     }
+
+    #[test]
+    fn hidden_area_mesh_conversion() {
+        // A quad split into two triangles, sharing two vertices.
+        let vertices = [
+            xr::Vector2f { x: -1.0, y: 1.0 },
+            xr::Vector2f { x: 1.0, y: 1.0 },
+            xr::Vector2f { x: 1.0, y: -1.0 },
+            xr::Vector2f { x: -1.0, y: -1.0 },
+        ];
+        let indices = [0, 1, 2, 0, 2, 3];
+
+        let fov = [-1.0, 1.0, 1.0, -1.0];
+        let mesh = hidden_area_vertices(&vertices, &indices, fov, true);
+        assert_eq!(mesh.len() / 3, 2);
+        let mesh: Vec<_> = mesh.iter().map(|v| v.v).collect();
+        assert_eq!(
+            mesh,
+            [
+                [0.0, 0.0],
+                [1.0, 0.0],
+                [1.0, 1.0],
+                [0.0, 0.0],
+                [1.0, 1.0],
+                [0.0, 1.0],
+            ]
+        );
+
+        // Bogus indices drop their triangle rather than panicking or shifting the next one.
+        let mesh = hidden_area_vertices(&vertices, &[0, 1, 7, 0, 2, 3], fov, true);
+        let mesh: Vec<_> = mesh.iter().map(|v| v.v).collect();
+        assert_eq!(mesh, [[0.0, 0.0], [1.0, 1.0], [0.0, 1.0]]);
+
+        // Line loops only lose the bad vertex.
+        let mesh = hidden_area_vertices(&vertices, &[0, 7, 1], fov, false);
+        let mesh: Vec<_> = mesh.iter().map(|v| v.v).collect();
+        assert_eq!(mesh, [[0.0, 0.0], [1.0, 0.0]]);
+    }
 }
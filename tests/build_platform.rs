@@ -0,0 +1,25 @@
+#[path = "../build/platform.rs"]
+mod platform;
+use platform::platform_for;
+
+#[test]
+fn supported_platforms() {
+    for (os, arch, expected) in [
+        ("windows", "x86", ("vrclient", "bin/")),
+        ("windows", "x86_64", ("vrclient_x64", "bin/")),
+        ("linux", "x86", ("vrclient", "bin/")),
+        ("linux", "x86_64", ("vrclient", "bin/linux64/")),
+        ("linux", "aarch64", ("vrclient", "bin/linuxarm64/")),
+        ("macos", "x86_64", ("vrclient", "bin/osx64/")),
+        ("macos", "aarch64", ("vrclient", "bin/osx64/")),
+    ] {
+        assert_eq!(platform_for(os, arch), Ok(expected), "{os} ({arch})");
+    }
+}
+
+#[test]
+fn unsupported_platform() {
+    let err = platform_for("linux", "riscv64").unwrap_err();
+    assert!(err.contains("riscv64"), "{err}");
+    assert!(platform_for("android", "aarch64").is_err());
+}
@@ -1,7 +1,10 @@
-use nanoserde::DeJson;
+use nanoserde::{DeJson, DeJsonErr, DeJsonState, DeJsonTok, SerJson, SerJsonState};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
 use std::io::{BufRead, BufReader};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::str::Chars;
 
 // https://doc.rust-lang.org/cargo/reference/external-tools.html#json-messages
 #[derive(DeJson)]
@@ -16,6 +19,38 @@ struct ArtifactTarget {
     crate_types: Vec<String>,
 }
 
+impl Artifact {
+    /// Picks the shared library out of the artifact's files, which may also include import
+    /// libraries, debug info or versioned names.
+    fn library_path(&self) -> Result<&str, String> {
+        use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+        let expected = format!("{DLL_PREFIX}{}{DLL_SUFFIX}", self.target.name);
+        let file_name = |path: &str| {
+            Path::new(path)
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default()
+                .to_owned()
+        };
+
+        self.filenames
+            .iter()
+            .find(|path| file_name(path) == expected)
+            .or_else(|| {
+                self.filenames
+                    .iter()
+                    .find(|path| file_name(path).ends_with(DLL_SUFFIX))
+            })
+            .map(String::as_str)
+            .ok_or_else(|| {
+                format!(
+                    "Couldn't find {expected} among the build outputs: {:?}",
+                    self.filenames
+                )
+            })
+    }
+}
+
 #[derive(DeJson, Debug)]
 struct BuildScriptExecution {
     env: Vec<[String; 2]>,
@@ -28,7 +63,109 @@ enum Message {
     Unknown,
 }
 
+/// What we need to know from cargo's build output.
+#[derive(Default, Debug)]
+struct BuildOutput {
+    lib_path: Option<String>,
+    platform_dir: Option<String>,
+    vrclient_name: Option<String>,
+}
+
+impl BuildOutput {
+    /// Takes in a line of cargo's JSON output. Lines that can't be parsed are skipped, since
+    /// cargo may emit messages we don't know about.
+    fn process_line(&mut self, line: &str) -> Result<(), String> {
+        let msg = match Message::deserialize_json(line) {
+            Ok(msg) => msg,
+            Err(e) => {
+                eprintln!(
+                    "Skipping unexpected cargo message ({e}): {}",
+                    line.trim_end()
+                );
+                return Ok(());
+            }
+        };
+
+        match msg {
+            Message::CompilerArtifact(a) => {
+                let target = &a.target;
+                if target.name == "xrizer" && target.crate_types.contains(&"cdylib".into()) {
+                    self.lib_path = Some(a.library_path()?.to_owned());
+                }
+            }
+            Message::BuildScriptExecuted(b) => {
+                if !b.package_id.contains("xrizer#") && !b.package_id.contains("xrizer@") {
+                    return Ok(());
+                }
+                for [name, value] in b.env {
+                    match name.as_str() {
+                        "XRIZER_OPENVR_PLATFORM_DIR" => self.platform_dir = Some(value),
+                        "XRIZER_OPENVR_VRCLIENT_NAME" => self.vrclient_name = Some(value),
+                        _ => {}
+                    }
+                }
+            }
+            Message::Unknown => {}
+        }
+        Ok(())
+    }
+}
+
+/// Overrides where the runtime directory tree is created, instead of next to the built library.
+const OUT_DIR_FLAG: &str = "--xrizer-out";
+const OUT_DIR_ENV: &str = "XRIZER_XBUILD_OUT";
+/// Registers the built runtime in openvrpaths.vrpath.
+const REGISTER_FLAG: &str = "--xrizer-register";
+/// Removes the runtime directory tree instead of creating it.
+const CLEAN_FLAG: &str = "--xrizer-clean";
+const CLEAN_ENV: &str = "XRIZER_XBUILD_CLEAN";
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    out_dir: Option<PathBuf>,
+    register: bool,
+    clean: bool,
+    cargo_args: Vec<OsString>,
+}
+
+/// Splits our own flags from the ones meant for cargo.
+fn parse_args(args: impl IntoIterator<Item = OsString>) -> Options {
+    let mut options = Options {
+        out_dir: None,
+        register: false,
+        clean: false,
+        cargo_args: Vec::new(),
+    };
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == OUT_DIR_FLAG {
+            options.out_dir = args.next().map(PathBuf::from);
+        } else if let Some(dir) = arg
+            .to_str()
+            .and_then(|arg| arg.strip_prefix(OUT_DIR_FLAG)?.strip_prefix('='))
+        {
+            options.out_dir = Some(dir.into());
+        } else if arg == REGISTER_FLAG {
+            options.register = true;
+        } else if arg == CLEAN_FLAG {
+            options.clean = true;
+        } else {
+            options.cargo_args.push(arg);
+        }
+    }
+    options
+}
+
 fn main() {
+    let Options {
+        out_dir,
+        register,
+        clean,
+        cargo_args,
+    } = parse_args(std::env::args_os().skip(1));
+    let out_dir = out_dir.or_else(|| std::env::var_os(OUT_DIR_ENV).map(PathBuf::from));
+    let clean = clean || std::env::var_os(CLEAN_ENV).is_some_and(|v| v == "1");
+
     let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".into());
     let mut cmd = Command::new(cargo)
         .args([
@@ -38,7 +175,7 @@ fn main() {
             "-p",
             "xrizer",
         ])
-        .args(std::env::args_os().skip(1))
+        .args(cargo_args)
         .stdout(Stdio::piped())
         .spawn()
         .expect("Failed to call cargo");
@@ -46,88 +183,294 @@ fn main() {
     let stdout = cmd.stdout.take().unwrap();
     let mut stdout = BufReader::new(stdout);
 
-    let mut lib_path: Option<String> = None;
-    let mut platform_dir: Option<String> = None;
-    let mut vrclient_name: Option<String> = None;
+    let mut output = BuildOutput::default();
     let mut line = String::new();
 
     while stdout.read_line(&mut line).expect("Failed to read line") > 0 {
-        let msg = Message::deserialize_json(&line).unwrap();
+        if let Err(e) = output.process_line(&line) {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
         line.clear();
+    }
 
-        match msg {
-            Message::CompilerArtifact(a) => {
-                let target = a.target;
-                if !(target.name == "xrizer" && target.crate_types.contains(&"cdylib".into())) {
-                    continue;
-                }
+    if !cmd.wait().expect("waiting for build failed").success() {
+        std::process::exit(1);
+    }
+    let Some(lib_path) = output.lib_path.map(PathBuf::from) else {
+        eprintln!("cargo didn't report building the xrizer library");
+        std::process::exit(1);
+    };
+    let platform_dir = output
+        .platform_dir
+        .expect("openvr platform directory should be known");
+    let vrclient_name = output.vrclient_name.expect("vrclient name should be known");
 
-                lib_path = Some(
-                    a.filenames
-                        .into_iter()
-                        .find(|p| p.ends_with(".so"))
-                        .unwrap(),
-                )
-            }
-            Message::BuildScriptExecuted(b) => {
-                if !b.package_id.contains("xrizer#") && !b.package_id.contains("xrizer@") {
-                    continue;
-                }
-                for [name, value] in b.env {
-                    match name.as_str() {
-                        "XRIZER_OPENVR_PLATFORM_DIR" => platform_dir = Some(value),
-                        "XRIZER_OPENVR_VRCLIENT_NAME" => vrclient_name = Some(value),
-                        _ => {}
-                    }
-                }
-            }
-            Message::Unknown => {}
+    let root = out_dir.unwrap_or_else(|| lib_path.parent().unwrap().to_owned());
+    if clean {
+        if let Err(e) = clean_runtime_tree(&root, &lib_path, &platform_dir, &vrclient_name) {
+            eprintln!("{e}");
+            std::process::exit(1);
         }
+        return;
     }
 
-    if !cmd.wait().expect("waiting for build failed").success() {
+    if let Err(e) = create_runtime_tree(&root, &lib_path, &platform_dir, &vrclient_name) {
+        eprintln!("{e}");
         std::process::exit(1);
     }
-    let lib_path = PathBuf::from(lib_path.expect("lib path missing"));
-    let platform_dir = platform_dir.expect("openvr platform directory should be known");
-    let vrclient_name = vrclient_name.expect("vrclient name should be known");
-
-    let parent = lib_path.parent().unwrap();
-    let platform_path = parent.join(platform_dir);
-    match std::fs::create_dir_all(&platform_path) {
-        Ok(_) => (),
-        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => (),
-        err => {
-            eprintln!("Failed to create directory '{platform_path:?}': {err:?}");
+
+    if register {
+        if let Err(e) = register_runtime(&root) {
+            eprintln!("Failed to register xrizer in openvrpaths.vrpath: {e}");
             std::process::exit(1);
         }
     }
+}
 
-    let vrclient_path = platform_path.join(vrclient_name).with_extension(
-        lib_path
-            .extension()
-            .expect("build shared library should have an extension"),
-    );
-    match std::os::unix::fs::symlink(&lib_path, vrclient_path) {
-        Ok(_) => (),
-        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => (),
-        err => {
-            eprintln!("Failed to create vrclient symlink: {err:?}");
-            std::process::exit(1);
+/// openvrpaths.vrpath, as written by vrpathreg. Only the runtimes are touched, everything else
+/// (including keys other tools added) is written back as it was.
+type VrPaths = BTreeMap<String, JsonValue>;
+
+/// Any JSON value, kept as parsed so it can be written back unchanged.
+#[derive(Clone, Debug, PartialEq)]
+enum JsonValue {
+    Null,
+    Bool(bool),
+    U64(u64),
+    I64(i64),
+    F64(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(BTreeMap<String, JsonValue>),
+}
+
+impl DeJson for JsonValue {
+    fn de_json(s: &mut DeJsonState, i: &mut Chars) -> Result<Self, DeJsonErr> {
+        let value = match s.tok.clone() {
+            DeJsonTok::CurlyOpen => return BTreeMap::de_json(s, i).map(Self::Object),
+            DeJsonTok::BlockOpen => return Vec::de_json(s, i).map(Self::Array),
+            DeJsonTok::Null => Self::Null,
+            DeJsonTok::Bool(b) => Self::Bool(b),
+            DeJsonTok::U64(n) => Self::U64(n),
+            DeJsonTok::I64(n) => Self::I64(n),
+            DeJsonTok::F64(n) => Self::F64(n),
+            DeJsonTok::Str => Self::String(s.as_string()?),
+            _ => return Err(s.err_token("value")),
+        };
+        s.next_tok(i)?;
+        Ok(value)
+    }
+}
+
+impl SerJson for JsonValue {
+    fn ser_json(&self, d: usize, s: &mut SerJsonState) {
+        match self {
+            Self::Null => s.out.push_str("null"),
+            Self::Bool(b) => b.ser_json(d, s),
+            Self::U64(n) => n.ser_json(d, s),
+            Self::I64(n) => n.ser_json(d, s),
+            Self::F64(n) => n.ser_json(d, s),
+            Self::String(string) => string.ser_json(d, s),
+            Self::Array(values) => values.ser_json(d, s),
+            Self::Object(values) => values.ser_json(d, s),
         }
     }
+}
 
-    // This file seems to prevent Steam from overwriting xrizer as a runtime path in the openvrpaths.
-    let version = parent.join("bin/version.txt");
-    match std::fs::File::create(version) {
-        Ok(_) => (),
-        err => {
-            eprintln!("Failed to create bin/linux64 directory: {err:?}");
-            std::process::exit(1);
+fn vrpath_file() -> Result<PathBuf, String> {
+    #[cfg(windows)]
+    let config = std::env::var_os("LOCALAPPDATA").map(PathBuf::from);
+    #[cfg(not(windows))]
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")));
+
+    config
+        .map(|config| config.join("openvr/openvrpaths.vrpath"))
+        .ok_or_else(|| "couldn't find the config directory".into())
+}
+
+fn register_runtime(root: &Path) -> Result<(), String> {
+    let root = std::fs::canonicalize(root).map_err(|e| format!("{root:?}: {e}"))?;
+    let root = root
+        .to_str()
+        .ok_or_else(|| format!("{root:?} is not valid UTF-8"))?;
+    let path = vrpath_file()?;
+    let existing = match std::fs::read_to_string(&path) {
+        Ok(contents) => Some(contents),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(format!("{path:?}: {e}")),
+    };
+
+    let vrpath = merge_vrpath(existing.as_deref(), root)?;
+    std::fs::create_dir_all(path.parent().unwrap()).map_err(|e| format!("{path:?}: {e}"))?;
+    std::fs::write(&path, vrpath).map_err(|e| format!("{path:?}: {e}"))?;
+    println!("Registered {root} as the OpenVR runtime in {path:?}");
+    Ok(())
+}
+
+/// Makes `runtime` the active runtime in the given vrpath file contents, keeping everything else.
+fn merge_vrpath(existing: Option<&str>, runtime: &str) -> Result<String, String> {
+    let mut paths = match existing {
+        Some(existing) => VrPaths::deserialize_json(existing)
+            .map_err(|e| format!("couldn't parse existing file: {e}"))?,
+        None => VrPaths::from([
+            ("jsonid".into(), JsonValue::String("vrpathreg".into())),
+            ("version".into(), JsonValue::U64(1)),
+        ]),
+    };
+
+    // The first runtime is the active one.
+    let runtimes = paths.entry("runtime".into()).or_insert(JsonValue::Null);
+    if !matches!(runtimes, JsonValue::Array(_)) {
+        *runtimes = JsonValue::Array(Vec::new());
+    }
+    let JsonValue::Array(runtimes) = runtimes else {
+        unreachable!()
+    };
+    let runtime = JsonValue::String(runtime.into());
+    runtimes.retain(|r| *r != runtime);
+    runtimes.insert(0, runtime);
+
+    Ok(paths.serialize_json())
+}
+
+/// Lays out the runtime directory tree under `root`, with the vrclient library pointing at the
+/// built one.
+fn create_runtime_tree(
+    root: &Path,
+    lib_path: &Path,
+    platform_dir: &str,
+    vrclient_name: &str,
+) -> Result<(), String> {
+    let platform_path = root.join(platform_dir);
+    std::fs::create_dir_all(&platform_path)
+        .map_err(|e| format!("Failed to create directory {platform_path:?}: {e}"))?;
+
+    let vrclient_path = vrclient_path(root, lib_path, platform_dir, vrclient_name);
+    link_vrclient(lib_path, &vrclient_path)
+        .map_err(|e| format!("Failed to create vrclient at {vrclient_path:?}: {e}"))?;
+
+    // This file seems to prevent Steam from overwriting xrizer as a runtime path in the
+    // openvrpaths, which only happens on Linux.
+    if lib_path.extension().is_some_and(|ext| ext == "so") {
+        let version = root.join("bin/version.txt");
+        std::fs::File::create(&version)
+            .map_err(|e| format!("Failed to create {version:?}: {e}"))?;
+    }
+
+    Ok(())
+}
+
+/// Removes what [`create_runtime_tree`] made under `root`, leaving anything else alone.
+fn clean_runtime_tree(
+    root: &Path,
+    lib_path: &Path,
+    platform_dir: &str,
+    vrclient_name: &str,
+) -> Result<(), String> {
+    let vrclient_path = vrclient_path(root, lib_path, platform_dir, vrclient_name);
+    match std::fs::symlink_metadata(&vrclient_path) {
+        Ok(meta) if is_linked_vrclient(lib_path, &vrclient_path, &meta) => {
+            unlink_vrclient(&vrclient_path)
+                .map_err(|e| format!("Failed to remove {vrclient_path:?}: {e}"))?
         }
+        Ok(_) => eprintln!("Not removing {vrclient_path:?}, since xbuild didn't create it"),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(format!("{vrclient_path:?}: {e}")),
+    }
+
+    // We only ever create this file empty.
+    let version = root.join("bin/version.txt");
+    if std::fs::metadata(&version).is_ok_and(|meta| meta.is_file() && meta.len() == 0) {
+        std::fs::remove_file(&version).map_err(|e| format!("Failed to remove {version:?}: {e}"))?;
+    }
+
+    // Only empty directories can be removed, so this stops at the first one with other files.
+    let platform_path = root.join(platform_dir);
+    for dir in platform_path.ancestors().take_while(|dir| *dir != root) {
+        if std::fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Where the runtime's vrclient library should be, given the runtime root and the built library.
+fn vrclient_path(root: &Path, lib_path: &Path, platform_dir: &str, vrclient_name: &str) -> PathBuf {
+    root.join(platform_dir).join(vrclient_name).with_extension(
+        lib_path
+            .extension()
+            .expect("build shared library should have an extension"),
+    )
+}
+
+#[cfg(unix)]
+fn link_vrclient(lib_path: &Path, vrclient_path: &Path) -> std::io::Result<()> {
+    match std::os::unix::fs::symlink(lib_path, vrclient_path) {
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(()),
+        res => res,
     }
 }
 
+/// Symlinks need special privileges on Windows, so the library is copied instead. A hash of the
+/// copy is stored next to it, so cleaning can tell it apart from a library someone else put there.
+#[cfg(windows)]
+fn link_vrclient(lib_path: &Path, vrclient_path: &Path) -> std::io::Result<()> {
+    std::fs::copy(lib_path, vrclient_path)?;
+    let hash = file_hash(vrclient_path)?;
+    std::fs::write(copy_marker_path(vrclient_path), hash.to_string())
+}
+
+/// Only a link to our own library counts, links someone pointed elsewhere are left alone.
+#[cfg(unix)]
+fn is_linked_vrclient(lib_path: &Path, vrclient_path: &Path, meta: &std::fs::Metadata) -> bool {
+    meta.is_symlink() && std::fs::read_link(vrclient_path).is_ok_and(|target| target == lib_path)
+}
+
+#[cfg(windows)]
+fn is_linked_vrclient(_lib_path: &Path, vrclient_path: &Path, meta: &std::fs::Metadata) -> bool {
+    let Some(hash) = std::fs::read_to_string(copy_marker_path(vrclient_path))
+        .ok()
+        .and_then(|hash| hash.trim().parse::<u64>().ok())
+    else {
+        return false;
+    };
+    meta.is_file() && file_hash(vrclient_path).is_ok_and(|actual| actual == hash)
+}
+
+#[cfg(unix)]
+fn unlink_vrclient(vrclient_path: &Path) -> std::io::Result<()> {
+    std::fs::remove_file(vrclient_path)
+}
+
+#[cfg(windows)]
+fn unlink_vrclient(vrclient_path: &Path) -> std::io::Result<()> {
+    std::fs::remove_file(vrclient_path)?;
+    std::fs::remove_file(copy_marker_path(vrclient_path))
+}
+
+/// Where the hash of a copied vrclient library is kept.
+#[cfg(windows)]
+fn copy_marker_path(vrclient_path: &Path) -> PathBuf {
+    let mut path = vrclient_path.as_os_str().to_owned();
+    path.push(".xbuild");
+    path.into()
+}
+
+/// FNV-1a of a file's contents. This only has to stay the same between builds of xbuild, which
+/// std's hashers don't promise.
+#[cfg(windows)]
+fn file_hash(path: &Path) -> std::io::Result<u64> {
+    Ok(std::fs::read(path)?
+        .iter()
+        .fold(0xcbf29ce484222325, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        }))
+}
+
 impl DeJson for Message {
     fn de_json(
         state: &mut nanoserde::DeJsonState,
@@ -143,15 +486,307 @@ impl DeJson for Message {
         match reason.as_str() {
             "compiler-artifact" => {
                 let fixed: String = ['{', state.cur].into_iter().chain(input).collect();
-                let msg = Artifact::deserialize_json(&fixed).unwrap();
+                let msg = Artifact::deserialize_json(&fixed)?;
                 Ok(Self::CompilerArtifact(msg))
             }
             "build-script-executed" => {
                 let fixed: String = ['{', state.cur].into_iter().chain(input).collect();
-                let msg = BuildScriptExecution::deserialize_json(&fixed).unwrap();
+                let msg = BuildScriptExecution::deserialize_json(&fixed)?;
                 Ok(Self::BuildScriptExecuted(msg))
             }
             _ => Ok(Self::Unknown),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn artifact(filenames: &[&str]) -> Artifact {
+        Artifact {
+            target: ArtifactTarget {
+                name: "xrizer".into(),
+                crate_types: vec!["cdylib".into()],
+            },
+            filenames: filenames.iter().map(|&name| name.into()).collect(),
+        }
+    }
+
+    #[test]
+    fn library_among_other_artifacts() {
+        use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+        let lib = format!("target/debug/{DLL_PREFIX}xrizer{DLL_SUFFIX}");
+        let other = format!("target/debug/{DLL_PREFIX}xrizer_helper{DLL_SUFFIX}");
+        let a = artifact(&[
+            "target/debug/xrizer.pdb",
+            &other,
+            &lib,
+            "target/debug/xrizer.d",
+        ]);
+        assert_eq!(a.library_path(), Ok(lib.as_str()));
+
+        // fall back to any shared library
+        let a = artifact(&["target/debug/xrizer.pdb", &other]);
+        assert_eq!(a.library_path(), Ok(other.as_str()));
+    }
+
+    #[test]
+    fn missing_library() {
+        let a = artifact(&["target/debug/xrizer.pdb", "target/debug/xrizer.d"]);
+        let err = a.library_path().unwrap_err();
+        assert!(
+            err.contains("xrizer.pdb") && err.contains("xrizer.d"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn windows_vrclient_path() {
+        let root = Path::new("target").join("debug");
+        let lib = root.join("xrizer.dll");
+        assert_eq!(
+            vrclient_path(&root, &lib, "bin/", "vrclient_x64"),
+            Path::new("target/debug/bin/vrclient_x64.dll")
+        );
+    }
+
+    #[test]
+    fn linux_vrclient_path() {
+        let lib = Path::new("target/debug/libxrizer.so");
+        assert_eq!(
+            vrclient_path(lib.parent().unwrap(), lib, "bin/linux64/", "vrclient"),
+            Path::new("target/debug/bin/linux64/vrclient.so")
+        );
+    }
+
+    #[test]
+    fn macos_vrclient_path() {
+        let lib = Path::new("target/debug/libxrizer.dylib");
+        assert_eq!(
+            vrclient_path(lib.parent().unwrap(), lib, "bin/osx64/", "vrclient"),
+            Path::new("target/debug/bin/osx64/vrclient.dylib")
+        );
+    }
+
+    #[test]
+    fn malformed_messages_are_skipped() {
+        use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+        let lib = format!("/xrizer/target/debug/{DLL_PREFIX}xrizer{DLL_SUFFIX}");
+        let mut output = BuildOutput::default();
+        for line in [
+            "warning: not json at all",
+            r#"{"reason":"compiler-artifact","target":{"name":"xrizer","crate_types":"#,
+            r#"{"reason":"build-script-executed","package_id":42}"#,
+        ] {
+            assert_eq!(output.process_line(line), Ok(()));
+        }
+        assert!(output.lib_path.is_none());
+
+        let artifact = format!(
+            r#"{{"reason":"compiler-artifact","package_id":"path+file:///xrizer#0.1.0","target":{{"name":"xrizer","kind":["cdylib"],"crate_types":["cdylib"]}},"filenames":["{lib}"],"fresh":false}}"#
+        );
+        assert_eq!(output.process_line(&artifact), Ok(()));
+        assert_eq!(output.lib_path.as_deref(), Some(lib.as_str()));
+    }
+
+    /// An empty directory for a test to create files in.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("xbuild-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn linux_runtime_tree() {
+        let root = temp_dir("linux_runtime_tree");
+        let lib = root.join("libxrizer.so");
+        std::fs::write(&lib, "").unwrap();
+
+        create_runtime_tree(&root, &lib, "bin/linux64/", "vrclient").unwrap();
+        assert!(root.join("bin/linux64/vrclient.so").exists());
+        assert!(root.join("bin/version.txt").exists());
+
+        // Running again over an existing tree is fine.
+        create_runtime_tree(&root, &lib, "bin/linux64/", "vrclient").unwrap();
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn windows_runtime_tree() {
+        let root = temp_dir("windows_runtime_tree");
+        let lib = root.join("xrizer.dll");
+        std::fs::write(&lib, "").unwrap();
+
+        create_runtime_tree(&root, &lib, "bin/", "vrclient_x64").unwrap();
+        assert!(root.join("bin/vrclient_x64.dll").exists());
+        assert!(!root.join("bin/version.txt").exists());
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn clean_keeps_replaced_vrclient() {
+        let root = temp_dir("clean_replaced_vrclient");
+        let lib = root.join("xrizer.dll");
+        std::fs::write(&lib, "xrizer").unwrap();
+        create_runtime_tree(&root, &lib, "bin/", "vrclient_x64").unwrap();
+
+        let vrclient = root.join("bin/vrclient_x64.dll");
+        std::fs::write(&vrclient, "someone else's vrclient").unwrap();
+        clean_runtime_tree(&root, &lib, "bin/", "vrclient_x64").unwrap();
+        assert!(vrclient.exists());
+
+        std::fs::copy(&lib, &vrclient).unwrap();
+        clean_runtime_tree(&root, &lib, "bin/", "vrclient_x64").unwrap();
+        assert!(!root.join("bin").exists());
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn clean_runtime_tree_removes_only_our_files() {
+        let root = temp_dir("clean_runtime_tree");
+        let lib = root.join("libxrizer.so");
+        std::fs::write(&lib, "").unwrap();
+        create_runtime_tree(&root, &lib, "bin/linux64/", "vrclient").unwrap();
+
+        clean_runtime_tree(&root, &lib, "bin/linux64/", "vrclient").unwrap();
+        assert!(lib.exists());
+        assert!(!root.join("bin").exists());
+
+        // Files that aren't ours keep their directories around.
+        create_runtime_tree(&root, &lib, "bin/linux64/", "vrclient").unwrap();
+        let other = root.join("bin/other.txt");
+        std::fs::write(&other, "keep me").unwrap();
+        clean_runtime_tree(&root, &lib, "bin/linux64/", "vrclient").unwrap();
+        assert!(!root.join("bin/linux64").exists());
+        assert!(!root.join("bin/version.txt").exists());
+        assert!(other.exists());
+
+        // A vrclient that isn't a link to our library is left alone.
+        #[cfg(unix)]
+        {
+            std::fs::create_dir_all(root.join("bin/linux64")).unwrap();
+            let vrclient = root.join("bin/linux64/vrclient.so");
+            std::fs::write(&vrclient, "").unwrap();
+            clean_runtime_tree(&root, &lib, "bin/linux64/", "vrclient").unwrap();
+            assert!(vrclient.exists());
+
+            // Neither is a link someone pointed at another library.
+            let other_lib = root.join("libother.so");
+            std::fs::write(&other_lib, "").unwrap();
+            std::fs::remove_file(&vrclient).unwrap();
+            std::os::unix::fs::symlink(&other_lib, &vrclient).unwrap();
+            clean_runtime_tree(&root, &lib, "bin/linux64/", "vrclient").unwrap();
+            assert_eq!(std::fs::read_link(&vrclient).unwrap(), other_lib);
+        }
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn own_flags_are_not_forwarded() {
+        let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+        assert_eq!(
+            parse_args(args(&["--release", "--xrizer-out", "/opt/xrizer", "-v"])),
+            Options {
+                out_dir: Some("/opt/xrizer".into()),
+                register: false,
+                clean: false,
+                cargo_args: args(&["--release", "-v"]),
+            }
+        );
+        assert_eq!(
+            parse_args(args(&["--xrizer-out=/opt/xrizer", "--xrizer-register"])),
+            Options {
+                out_dir: Some("/opt/xrizer".into()),
+                register: true,
+                clean: false,
+                cargo_args: args(&[]),
+            }
+        );
+        assert_eq!(
+            parse_args(args(&["--release"])),
+            Options {
+                out_dir: None,
+                register: false,
+                clean: false,
+                cargo_args: args(&["--release"]),
+            }
+        );
+    }
+
+    #[test]
+    fn clean_flag() {
+        let args = ["--xrizer-clean", "--release"].map(OsString::from);
+        assert_eq!(
+            parse_args(args),
+            Options {
+                out_dir: None,
+                register: false,
+                clean: true,
+                cargo_args: vec!["--release".into()],
+            }
+        );
+    }
+
+    #[test]
+    fn merge_existing_vrpath() {
+        let existing = r#"{
+            "config": ["/home/user/.local/share/Steam/config"],
+            "external_drivers": null,
+            "jsonid": "vrpathreg",
+            "log": ["/home/user/.local/share/Steam/logs"],
+            "runtime": [
+                "/home/user/.local/share/Steam/steamapps/common/SteamVR",
+                "/home/user/xrizer/target/debug"
+            ],
+            "some_tool": {"enabled": true, "offset": -1.5, "ids": [1, -2]},
+            "version": 1
+        }"#;
+        let merged = merge_vrpath(Some(existing), "/home/user/xrizer/target/debug").unwrap();
+        let mut merged = VrPaths::deserialize_json(&merged).unwrap();
+        let string = |s: &str| JsonValue::String(s.into());
+        assert_eq!(
+            merged.remove("runtime").unwrap(),
+            JsonValue::Array(vec![
+                string("/home/user/xrizer/target/debug"),
+                string("/home/user/.local/share/Steam/steamapps/common/SteamVR")
+            ])
+        );
+
+        // Everything else is left alone
+        let mut existing = VrPaths::deserialize_json(existing).unwrap();
+        existing.remove("runtime");
+        assert_eq!(merged, existing);
+        assert_eq!(
+            merged["some_tool"],
+            JsonValue::Object(BTreeMap::from([
+                ("enabled".into(), JsonValue::Bool(true)),
+                ("offset".into(), JsonValue::F64(-1.5)),
+                (
+                    "ids".into(),
+                    JsonValue::Array(vec![JsonValue::U64(1), JsonValue::I64(-2)])
+                ),
+            ]))
+        );
+        assert_eq!(merged["version"], JsonValue::U64(1));
+    }
+
+    #[test]
+    fn new_vrpath() {
+        let merged = merge_vrpath(None, "/opt/xrizer").unwrap();
+        let merged = VrPaths::deserialize_json(&merged).unwrap();
+        assert_eq!(
+            merged["runtime"],
+            JsonValue::Array(vec![JsonValue::String("/opt/xrizer".into())])
+        );
+        assert_eq!(merged["jsonid"], JsonValue::String("vrpathreg".into()));
+    }
+
+    #[test]
+    fn malformed_vrpath() {
+        assert!(merge_vrpath(Some("{ not json"), "/opt/xrizer").is_err());
+    }
+}